@@ -0,0 +1,132 @@
+//! Generates a synthetic SS14-like project tree for the benchmarks.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const COMPONENTS: usize = 100;
+pub const PROTOTYPE_FILES: usize = 100;
+pub const PROTOTYPES_PER_FILE: usize = 10;
+
+const ENTITY_PROTOTYPE: &str = r#"namespace Robust.Shared.Prototypes;
+
+[Prototype("entity")]
+public sealed partial class EntityPrototype : IPrototype, IInheritingPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+
+    [ParentDataField(typeof(AbstractPrototypeIdArraySerializer<EntityPrototype>))]
+    public string[]? Parents { get; private set; }
+
+    [DataField("name")]
+    public string? SetName;
+
+    [DataField("description")]
+    public string? SetDesc;
+
+    [DataField("suffix")]
+    public string? SetSuffix;
+
+    [DataField("components")]
+    public ComponentRegistry Components = new();
+}
+"#;
+
+/// Writes the fixture to a temporary folder (once) and returns its root.
+pub fn project() -> PathBuf {
+    let root = std::env::temp_dir().join("robust-lsp-bench");
+    if root.join("SpaceStation14.sln").exists() {
+        return root;
+    }
+
+    write(&root.join("SpaceStation14.sln"), "");
+    write(
+        &root.join("RobustToolbox/Robust.Shared/Prototypes/EntityPrototype.cs"),
+        ENTITY_PROTOTYPE,
+    );
+
+    for i in 0..COMPONENTS {
+        write(
+            &root.join(format!("Content.Shared/Bench/Bench{i}Component.cs")),
+            &component(i),
+        );
+    }
+
+    for i in 0..PROTOTYPE_FILES {
+        write(
+            &root.join(format!("Resources/Prototypes/Entities/bench_{i}.yml")),
+            &prototypes(i),
+        );
+        write(
+            &root.join(format!("Resources/Locale/en-US/bench_{i}.ftl")),
+            &locales(i),
+        );
+    }
+
+    root
+}
+
+/// Source of one of the generated prototype files, used for completion benchmarks.
+pub fn prototype_file(root: &Path, i: usize) -> String {
+    fs::read_to_string(root.join(format!("Resources/Prototypes/Entities/bench_{i}.yml"))).unwrap()
+}
+
+fn component(i: usize) -> String {
+    format!(
+        r#"namespace Content.Shared.Bench;
+
+[RegisterComponent]
+public sealed partial class Bench{i}Component : Component
+{{
+    [DataField]
+    public int Value = {i};
+
+    [DataField("spawn")]
+    public EntProtoId Spawn;
+
+    [DataField]
+    public LocId Label;
+
+    [DataField]
+    public bool Enabled;
+
+    public float NotAField;
+}}
+"#
+    )
+}
+
+fn prototypes(file: usize) -> String {
+    let mut src = String::new();
+    for i in 0..PROTOTYPES_PER_FILE {
+        let id = file * PROTOTYPES_PER_FILE + i;
+        let parent = if i == 0 {
+            String::new()
+        } else {
+            format!("  parent: BenchEntity{}\n", id - 1)
+        };
+
+        src.push_str(&format!(
+            "- type: entity\n  id: BenchEntity{id}\n{parent}  name: bench entity {id}\n  components:\n  - type: Bench{}\n    value: {id}\n    label: bench-label-{id}\n  - type: Bench{}\n    enabled: true\n\n",
+            id % COMPONENTS,
+            (id + 1) % COMPONENTS,
+        ));
+    }
+    src
+}
+
+fn locales(file: usize) -> String {
+    (0..PROTOTYPES_PER_FILE)
+        .map(|i| {
+            let id = file * PROTOTYPES_PER_FILE + i;
+            format!("bench-label-{id} = Bench label {{ $count }}\n")
+        })
+        .collect()
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
@@ -0,0 +1,119 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use robust_lsp::{
+    backend::Context,
+    cli::index_project,
+    completion::{yml::YamlCompletion, Completion},
+    parse::structs::csharp::ReflectionManager,
+};
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tower_lsp::lsp_types::Position;
+
+mod fixture;
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn indexed(rt: &Runtime) -> Arc<Context> {
+    let root = fixture::project();
+    rt.block_on(index_project(&root)).unwrap()
+}
+
+fn project_parser(c: &mut Criterion) {
+    let rt = runtime();
+    let root = fixture::project();
+
+    let mut group = c.benchmark_group("ProjectParser");
+    group.sample_size(10);
+    group.bench_function("parse", |b| {
+        b.to_async(&rt).iter(|| index_project(&root));
+    });
+    group.finish();
+}
+
+fn reflection_manager(c: &mut Criterion) {
+    let rt = runtime();
+    let context = indexed(&rt);
+    let reflection = ReflectionManager::new(&context);
+
+    let mut group = c.benchmark_group("ReflectionManager");
+    group.bench_function("get_prototype_by_name", |b| {
+        b.to_async(&rt)
+            .iter(|| reflection.get_prototype_by_name("entity"));
+    });
+    group.bench_function("get_component_by_name", |b| {
+        b.to_async(&rt)
+            .iter(|| reflection.get_component_by_name("Bench42"));
+    });
+    group.bench_function("get_fields", |b| {
+        let component = rt
+            .block_on(reflection.get_component_by_name("Bench42"))
+            .unwrap();
+        b.to_async(&rt).iter(|| reflection.get_fields(&component));
+    });
+    group.finish();
+}
+
+fn yaml_completion(c: &mut Criterion) {
+    let rt = runtime();
+    let context = indexed(&rt);
+    let root = fixture::project();
+    let rope = Rope::from_str(&fixture::prototype_file(&root, 0));
+    let path = root.join("Resources/Prototypes/Entities/bench_0.yml");
+
+    let mut group = c.benchmark_group("YamlCompletion");
+    // `- type: entity` of the first prototype.
+    group.bench_function("prototype kind", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let completion = YamlCompletion::new(
+                    context.clone(),
+                    Position::new(0, 9),
+                    &rope,
+                    root.clone(),
+                    path.clone(),
+                );
+                completion.completion()
+            })
+        });
+    });
+    // `- type: Bench0` inside `components`.
+    group.bench_function("component name", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let completion = YamlCompletion::new(
+                    context.clone(),
+                    Position::new(4, 12),
+                    &rope,
+                    root.clone(),
+                    path.clone(),
+                );
+                completion.completion()
+            })
+        });
+    });
+    // `label: bench-label-0` is a LocId field.
+    group.bench_function("locale value", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let completion = YamlCompletion::new(
+                    context.clone(),
+                    Position::new(6, 15),
+                    &rope,
+                    root.clone(),
+                    path.clone(),
+                );
+                completion.completion()
+            })
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, project_parser, reflection_manager, yaml_completion);
+criterion_main!(benches);
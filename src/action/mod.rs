@@ -0,0 +1,7 @@
+pub mod yaml;
+
+pub type CodeActionResult = Option<tower_lsp::lsp_types::CodeActionResponse>;
+
+pub trait CodeAction {
+    fn code_action(&self) -> CodeActionResult;
+}
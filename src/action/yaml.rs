@@ -0,0 +1,406 @@
+use super::{CodeAction, CodeActionResult};
+use crate::{
+    backend::Context,
+    diagnostic::yaml::{
+        tab_indented_lines, ID_CASE, MISSING_DEPENDENCY, MISSING_REQUIRED, RENAMED, TAB_INDENTATION,
+    },
+    parse::structs::yaml::YamlPrototype,
+    rename::{yaml::YamlRename, Rename},
+};
+use ropey::Rope;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{
+    self, CodeActionKind, CodeActionOrCommand, Diagnostic, NumberOrString, Position, Range,
+    TextEdit, Url, WorkspaceEdit,
+};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+pub struct YamlCodeAction {
+    context: Arc<Context>,
+    uri: Url,
+    range: Range,
+    diagnostics: Vec<Diagnostic>,
+    /// The snapshot of the opened documents, renaming an id reads the prototype files from it.
+    opened: HashMap<Url, Rope>,
+    src: String,
+    tree: Tree,
+}
+
+impl CodeAction for YamlCodeAction {
+    fn code_action(&self) -> CodeActionResult {
+        let start_point = Point::new(
+            self.range.start.line as usize,
+            self.range.start.character as usize,
+        );
+        let end_point = Point::new(
+            self.range.end.line as usize,
+            self.range.end.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let mapping_pair_node = root_node
+            .named_descendant_for_point_range(start_point, end_point)
+            .and_then(|node| find_ancestor(node, "block_mapping_pair"));
+
+        let mut actions = vec![];
+        let mut tab_indentations = vec![];
+        for diagnostic in &self.diagnostics {
+            let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                continue;
+            };
+            let action = match code.as_str() {
+                MISSING_REQUIRED => self.add_required_fields(diagnostic),
+                RENAMED => self.rename_to_new_name(diagnostic),
+                MISSING_DEPENDENCY => self.add_dependency(diagnostic),
+                ID_CASE => self.rename_id(diagnostic),
+                // The tabs of every line are replaced by a single action.
+                TAB_INDENTATION => {
+                    tab_indentations.push(diagnostic.clone());
+                    None
+                }
+                _ => None,
+            };
+            actions.extend(action);
+        }
+        actions.extend(self.tabs_to_spaces(tab_indentations));
+        actions.extend(mapping_pair_node.and_then(|n| self.sort_parents(n)));
+
+        let actions = actions
+            .into_iter()
+            .map(CodeActionOrCommand::CodeAction)
+            .collect::<Vec<_>>();
+
+        if actions.is_empty() {
+            None
+        } else {
+            Some(actions)
+        }
+    }
+}
+
+impl YamlCodeAction {
+    pub fn new(
+        context: Arc<Context>,
+        uri: Url,
+        range: Range,
+        diagnostics: Vec<Diagnostic>,
+        rope: &Rope,
+        opened: HashMap<Url, Rope>,
+    ) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            uri,
+            range,
+            diagnostics,
+            opened,
+            src,
+            tree,
+        }
+    }
+
+    /// Sorts the `parent` list, removes duplicates and parents that are
+    /// already inherited through the other ones.
+    fn sort_parents(&self, mapping_pair_node: Node) -> Option<lsp_types::CodeAction> {
+        let key = mapping_pair_node
+            .child_by_field_name("key")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        if key != "parent" {
+            return None;
+        }
+
+        let value_node = mapping_pair_node.child_by_field_name("value")?;
+        let flow_sequence = value_node.named_child(0)?;
+        if flow_sequence.kind() != "flow_sequence" {
+            return None;
+        }
+
+        let proto_name = self
+            .get_field(&mapping_pair_node.parent()?, "type")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+
+        let mut cursor = flow_sequence.walk();
+        let parents = flow_sequence
+            .named_children(&mut cursor)
+            .filter_map(|n| n.utf8_text(self.src.as_bytes()).ok())
+            .map(|p| p.trim_matches(|c| c == '"' || c == '\'').to_owned())
+            .collect::<Vec<_>>();
+
+        let mut sorted = parents.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        let ancestors = sorted
+            .iter()
+            .map(|p| (p.clone(), self.get_ancestors(proto_name, p)))
+            .collect::<HashMap<_, _>>();
+        let implied = sorted
+            .iter()
+            .filter(|p| {
+                ancestors
+                    .iter()
+                    .any(|(other, ancestors)| other != *p && ancestors.contains(*p))
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+        sorted.retain(|p| !implied.contains(p));
+
+        // Parents that inherit each other in a loop would otherwise remove all entries.
+        if sorted.is_empty() || sorted == parents {
+            return None;
+        }
+
+        let edit = TextEdit {
+            range: Range::new(
+                Position::new(
+                    value_node.start_position().row as u32,
+                    value_node.start_position().column as u32,
+                ),
+                Position::new(
+                    value_node.end_position().row as u32,
+                    value_node.end_position().column as u32,
+                ),
+            ),
+            new_text: format!("[{}]", sorted.join(", ")),
+        };
+
+        Some(lsp_types::CodeAction {
+            title: "Sort and deduplicate parents".to_owned(),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), vec![edit])])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Appends the required fields reported as missing to the end of the mapping.
+    fn add_required_fields(&self, diagnostic: &Diagnostic) -> Option<lsp_types::CodeAction> {
+        let fields = diagnostic
+            .data
+            .as_ref()?
+            .get("fields")?
+            .as_array()?
+            .iter()
+            .filter_map(|f| f.as_str())
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return None;
+        }
+
+        let point = Point::new(
+            diagnostic.range.start.line as usize,
+            diagnostic.range.start.character as usize,
+        );
+        let node = self
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)?;
+        let block_mapping = find_ancestor(node, "block_mapping")?;
+        let last_pair = block_mapping.named_child(block_mapping.named_child_count() - 1)?;
+
+        let indent = " ".repeat(block_mapping.start_position().column);
+        let position = Position::new(
+            last_pair.end_position().row as u32,
+            last_pair.end_position().column as u32,
+        );
+        let edit = TextEdit {
+            range: Range::new(position, position),
+            new_text: fields.iter().map(|f| format!("\n{indent}{f}: ")).collect(),
+        };
+
+        let title = match fields.as_slice() {
+            [field] => format!("Add required field `{field}`"),
+            _ => "Add required fields".to_owned(),
+        };
+
+        Some(lsp_types::CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), vec![edit])])),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// Replaces the old name of a component or a field with the new one.
+    fn rename_to_new_name(&self, diagnostic: &Diagnostic) -> Option<lsp_types::CodeAction> {
+        let new_name = diagnostic.data.as_ref()?.get("newName")?.as_str()?;
+
+        let edit = TextEdit {
+            range: diagnostic.range,
+            new_text: new_name.to_owned(),
+        };
+
+        Some(lsp_types::CodeAction {
+            title: format!("Rename to `{new_name}`"),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), vec![edit])])),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// Renames the id to the one written in the convention of its kind,
+    /// along with its locale keys like the rename request does.
+    fn rename_id(&self, diagnostic: &Diagnostic) -> Option<lsp_types::CodeAction> {
+        let new_name = diagnostic.data.as_ref()?.get("newName")?.as_str()?;
+
+        let rename = YamlRename::new(
+            self.context.clone(),
+            self.uri.clone(),
+            diagnostic.range.start,
+            &Rope::from_str(&self.src),
+            self.opened.clone(),
+        );
+
+        Some(lsp_types::CodeAction {
+            title: format!("Rename to `{new_name}`"),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(rename.rename(new_name)?),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// Adds the missing component right after the one that depends on it.
+    fn add_dependency(&self, diagnostic: &Diagnostic) -> Option<lsp_types::CodeAction> {
+        let component = diagnostic.data.as_ref()?.get("component")?.as_str()?;
+
+        let point = Point::new(
+            diagnostic.range.start.line as usize,
+            diagnostic.range.start.character as usize,
+        );
+        let node = self
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)?;
+        let item = find_ancestor(node, "block_sequence_item")?;
+
+        // The last item of a sequence ends on the next line, after the line break.
+        let end = self.src[..item.end_byte()].trim_end();
+        let line_start = end.rfind('\n').map_or(0, |i| i + 1);
+        let position = Position::new(
+            end.matches('\n').count() as u32,
+            (end.len() - line_start) as u32,
+        );
+
+        let indent = " ".repeat(item.start_position().column);
+        let edit = TextEdit {
+            range: Range::new(position, position),
+            new_text: format!("\n{indent}- type: {component}"),
+        };
+
+        Some(lsp_types::CodeAction {
+            title: format!("Add component `{component}`"),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), vec![edit])])),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// Indents every line of the file that has tabs with two spaces per tab,
+    /// the usual indentation of the prototypes.
+    fn tabs_to_spaces(&self, diagnostics: Vec<Diagnostic>) -> Option<lsp_types::CodeAction> {
+        if diagnostics.is_empty() {
+            return None;
+        }
+
+        let edits = tab_indented_lines(&self.src)
+            .map(|(line, indent)| TextEdit {
+                range: Range::new(
+                    Position::new(line, 0),
+                    Position::new(line, indent.len() as u32),
+                ),
+                new_text: indent.replace('\t', "  "),
+            })
+            .collect();
+
+        Some(lsp_types::CodeAction {
+            title: "Convert the tab indentation to spaces".to_owned(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(diagnostics),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(self.uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    fn get_ancestors(&self, proto_name: &str, id: &str) -> HashSet<String> {
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+
+        let mut ancestors = HashSet::new();
+        let mut stack = vec![id.to_owned()];
+        while let Some(id) = stack.pop() {
+            let Some(proto) = lock.get(&YamlPrototype::dummy(proto_name, &id)) else {
+                continue;
+            };
+
+            for parent in proto.parents.iter() {
+                if ancestors.insert(parent.clone()) {
+                    stack.push(parent.clone());
+                }
+            }
+        }
+
+        ancestors
+    }
+
+    fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
+        if node.kind() != "block_mapping" {
+            return None;
+        }
+
+        for i in 0..node.named_child_count() {
+            let field_node = node.named_child(i)?;
+            let key = field_node
+                .child_by_field_name("key")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?
+                .to_owned();
+
+            if key == name {
+                return Some(field_node);
+            }
+        }
+        None
+    }
+}
+
+fn find_ancestor<'a>(node: Node<'a>, kind: &str) -> Option<Node<'a>> {
+    let mut node = node;
+    while node.kind() != kind {
+        node = node.parent()?;
+    }
+    Some(node)
+}
@@ -1,66 +1,915 @@
 use crate::{
+    action::{yaml::YamlCodeAction, CodeAction},
+    cli,
     completion::{yml::YamlCompletion, Completion},
+    config::{Config, PrototypesConfig},
+    diagnostic::{rules::apply_rules, yaml::YamlDiagnostics, Diagnostics},
+    encoding::{ConvertPositions, Direction, PositionConverter, PositionEncoding},
+    folding::{yaml::YamlFoldingRange, FoldingRange},
     goto::{yml::YamlGotoDefinition, GotoDefinition},
+    graph::{PrototypeGraph, PrototypeGraphParams, PROTOTYPE_GRAPH_METHOD},
     hint::{yaml::YamlInlayHint, InlayHint},
+    hover::{yaml::YamlHover, Hover},
+    implementation::{yaml::YamlImplementation, Implementation},
+    lens::{yaml::YamlCodeLens, CodeLens},
+    logging,
     parse::{
         common::Index,
-        csharp, fluent,
-        structs::{csharp::CsharpClass, fluent::FluentKey, yaml::YamlPrototype},
-        yaml, FileGroup, ParseResult, ProjectParser,
+        file_groups,
+        structs::{
+            csharp::{set_relaxed_prototypes, CsharpClass, CsharpUsageIndex, InheritanceGraph},
+            fluent::{FluentKey, FluentKeyIndex},
+            intern,
+            json::RsiMetaCache,
+            yaml::{is_prototype_id, YamlPrototype},
+        },
+        yaml, ParseCancellation, ParseResult, ProjectParser,
     },
-    utils::check_project_compliance,
+    picker::{EntityPage, EntityPickerParams, ENTITY_PICKER_METHOD},
+    references::{yaml::YamlReferences, References},
+    rename::{yaml::YamlRename, Rename},
+    schema::json::JsonSchemaExporter,
+    semantic::{self, yaml::YamlSemanticTokens, SemanticTokens},
+    status::{LastError, ServerStatus, StatusNotification, STATUS_METHOD},
+    timings::{RequestTimer, Timings},
+    type_definition::{yaml::YamlTypeDefinition, TypeDefinition},
+    utils::{
+        check_project_compliance, document_path, is_project_root, recover_in_time, PROJECT_MARKERS,
+    },
+    vfs::SharedFileSystem,
+};
+#[cfg(feature = "csharp")]
+use crate::{
+    completion::csharp::CsharpCompletion, diagnostic::csharp::CsharpDiagnostics,
+    hint::csharp::CsharpInlayHint, lens::csharp::CsharpCodeLens, parse::csharp,
+};
+#[cfg(feature = "fluent")]
+use crate::{
+    completion::fluent::FluentCompletion,
+    diagnostic::fluent::FluentDiagnostics,
+    goto::fluent::FluentGotoDefinition,
+    locale::UnusedLocaleKeys,
+    parse::{fluent, structs::fluent::is_identifier},
+    references::fluent::FluentReferences,
+    rename::fluent::FluentRename,
+    semantic::fluent::{FluentDocument, FluentSemanticTokens},
 };
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use ropey::Rope;
+use serde_json::{json, Value};
 use std::{
     collections::{HashMap, HashSet},
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, task::JoinHandle};
 use tower_lsp::{
     jsonrpc::{Error, Result},
     lsp_types::{
-        CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
-        DidOpenTextDocumentParams, DidSaveTextDocumentParams, GotoDefinitionParams,
-        GotoDefinitionResponse, InitializeParams, InitializeResult, InitializedParams,
-        InlayHintParams, MessageType, OneOf::Left, ServerCapabilities, TextDocumentSyncCapability,
-        TextDocumentSyncKind, Url,
+        notification::{DidChangeWatchedFiles, Notification, WorkDoneProgressCancel},
+        request::{
+            GotoImplementationParams, GotoImplementationResponse, GotoTypeDefinitionParams,
+            GotoTypeDefinitionResponse,
+        },
+        ClientCapabilities, CodeActionParams, CodeActionProviderCapability, CodeActionResponse,
+        CodeLensOptions, CodeLensParams, CompletionOptions, CompletionParams, CompletionResponse,
+        DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+        DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams, DidSaveTextDocumentParams, ExecuteCommandOptions,
+        ExecuteCommandParams, FileChangeType, FileSystemWatcher, FoldingRangeParams,
+        FoldingRangeProviderCapability, GlobPattern, GotoDefinitionParams, GotoDefinitionResponse,
+        HoverParams, HoverProviderCapability, ImplementationProviderCapability, InitializeParams,
+        InitializeResult, InitializedParams, InlayHintParams, Location, MessageType,
+        NumberOrString,
+        OneOf::{Left, Right},
+        PrepareRenameResponse, ReferenceParams, Registration, RenameOptions, RenameParams,
+        SaveOptions, SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensParams,
+        SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult,
+        SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentPositionParams,
+        TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+        TextDocumentSyncSaveOptions, TypeDefinitionProviderCapability, Url, WatchKind,
+        WorkDoneProgressCancelParams, WorkspaceEdit,
     },
-    Client, LanguageServer,
+    Client, ClientSocket, LanguageServer, LspService,
 };
-use tracing::instrument;
-use tree_sitter::Tree;
+use tracing::{field::Empty, instrument};
+use tree_sitter::{InputEdit, Point, Tree};
 
-pub(crate) type FluentLocales = Arc<RwLock<HashSet<FluentKey>>>;
-pub(crate) type CsharpClasses = Arc<RwLock<HashSet<CsharpClass>>>;
-pub(crate) type YamlPrototypes = Arc<RwLock<HashSet<YamlPrototype>>>;
-pub(crate) type ParsedFiles = Arc<RwLock<HashMap<PathBuf, Tree>>>;
+/// How long the changed documents stay idle before they are indexed again.
+const REPARSE_DELAY: std::time::Duration = std::time::Duration::from_millis(300);
+const EXPORT_SCHEMA_COMMAND: &str = "robust-lsp.exportSchema";
+const SET_PROJECT_ROOT_COMMAND: &str = "robust-lsp.setProjectRoot";
+const DIFF_PROTOTYPES_COMMAND: &str = "robust-lsp.diffPrototypes";
+const INDEX_STATS_COMMAND: &str = "robust-lsp.indexStats";
+#[cfg(feature = "fluent")]
+const UNUSED_LOCALE_KEYS_COMMAND: &str = "robust-lsp.unusedLocaleKeys";
+const WATCHED_FILES_REGISTRATION: &str = "robust-lsp.watchedFiles";
+/// How often the status is sent to the client, see `StatusNotification`.
+const STATUS_INTERVAL: Duration = Duration::from_secs(30);
+
+pub type FluentLocales = Arc<RwLock<FluentKeyIndex>>;
+pub type CsharpClasses = Arc<RwLock<HashSet<CsharpClass>>>;
+pub type CsharpInheritors = Arc<RwLock<InheritanceGraph>>;
+pub type CsharpUsages = Arc<RwLock<CsharpUsageIndex>>;
+pub type YamlPrototypes = Arc<RwLock<HashSet<YamlPrototype>>>;
+/// How many prototypes of a kind use each value of a field, keyed by the kind and the path
+/// of the field, e.g. `entity` and `suffix`.
+pub type UsedValues = Arc<RwLock<HashMap<(String, String), Arc<HashMap<String, usize>>>>>;
+pub type ParsedFiles = Arc<RwLock<HashMap<PathBuf, Tree>>>;
+pub type OpenedFiles = Arc<RwLock<HashMap<Url, Rope>>>;
 
 #[derive(Default)]
-pub(crate) struct Context {
-    pub(crate) parsed_files: ParsedFiles,
-    pub(crate) classes: CsharpClasses,
-    pub(crate) prototypes: YamlPrototypes,
-    pub(crate) locales: FluentLocales,
+pub struct Context {
+    pub parsed_files: ParsedFiles,
+    /// The texts of the documents opened in the client, which may differ from the files.
+    pub opened_files: OpenedFiles,
+    pub classes: CsharpClasses,
+    pub inheritors: CsharpInheritors,
+    pub usages: CsharpUsages,
+    /// The locale keys passed to `Loc.GetString` in the C# code.
+    pub locale_usages: CsharpUsages,
+    pub prototypes: YamlPrototypes,
+    /// Counted on the first completion of the field and dropped once the prototypes change.
+    pub used_values: UsedValues,
+    pub locales: FluentLocales,
+    pub config: Arc<RwLock<Config>>,
+    pub rsi_meta: Arc<RsiMetaCache>,
+    /// Where the providers read the resources from, e.g. RSIs and audio files.
+    pub fs: SharedFileSystem,
+    pub client_capabilities: Arc<RwLock<ClientCapabilities>>,
+    /// The last failure of a provider, reported in the status.
+    pub last_error: LastError,
+}
+
+impl Context {
+    /// The text of the file, the one of the opened document if the snapshot of the opened
+    /// documents has it. The providers get the snapshot rather than lock the opened files,
+    /// which a change may be waiting to write.
+    pub fn read_document(&self, opened: &HashMap<Url, Rope>, path: &Path) -> Option<String> {
+        let uri = Url::from_file_path(path).ok()?;
+        if let Some(rope) = opened.get(&uri) {
+            return Some(rope.to_string());
+        }
+        self.fs.read_to_string(path).ok()
+    }
+
+    /// Adds the classes to the index, the classes with the same name are replaced.
+    pub async fn extend_classes(&self, classes: Vec<CsharpClass>) {
+        let mut lock = self.classes.write().await;
+        let mut inheritors = self.inheritors.write().await;
+
+        for class in classes {
+            insert_class(&mut lock, &mut inheritors, class);
+        }
+    }
+
+    /// Replaces the classes defined in the file with the freshly parsed ones.
+    pub async fn replace_classes(&self, path: &Path, classes: Vec<CsharpClass>) {
+        let mut lock = self.classes.write().await;
+        let mut inheritors = self.inheritors.write().await;
+        let stale = lock
+            .par_iter()
+            .filter(|c| c.declarations().any(|d| d.index().0 == path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for class in stale {
+            inheritors.remove(&class);
+            lock.remove(&class);
+            if !classes.contains(&class) {
+                tracing::info!("Remove class: {}", class.name);
+            }
+
+            // The declarations of a partial class in the other files stay.
+            let declarations = class.into_declarations().into_iter();
+            let declarations = declarations.filter(|d| d.index().0 != path).collect();
+            if let Some(class) = CsharpClass::merge(declarations) {
+                inheritors.insert(&class);
+                lock.insert(class);
+            }
+        }
+
+        for class in classes {
+            tracing::info!("New/changed class: {}", class.name);
+            insert_class(&mut lock, &mut inheritors, class);
+        }
+
+        // The strings of the replaced and removed classes may no longer be used.
+        intern::prune();
+    }
+
+    /// Replaces the locale keys defined in the file with the freshly parsed ones.
+    pub async fn replace_locales(&self, path: &Path, keys: Vec<FluentKey>) {
+        let mut lock = self.locales.write().await;
+        let removed = lock.remove_file(path);
+
+        for key in removed.iter().filter(|k| !keys.contains(k)) {
+            tracing::info!("Remove locale key: {}", key.key);
+        }
+
+        for key in keys {
+            tracing::info!("New/changed locale key: {}", key.key);
+            lock.insert(key);
+        }
+    }
+
+    /// Drops everything indexed from the file, or from the files of the folder,
+    /// e.g. once it is deleted.
+    pub async fn remove_files(&self, path: &Path) {
+        let mut files = HashSet::new();
+        files.extend(
+            self.classes
+                .read()
+                .await
+                .iter()
+                .flat_map(|c| c.declarations().map(|d| d.index().0.clone())),
+        );
+        files.extend(
+            self.prototypes
+                .read()
+                .await
+                .iter()
+                .flat_map(|p| p.definitions().map(|d| d.index().0.clone())),
+        );
+        files.extend(
+            self.locales
+                .read()
+                .await
+                .iter()
+                .map(|k| k.index().0.clone()),
+        );
+        files.retain(|file| file.starts_with(path));
+
+        for file in files {
+            tracing::info!("Remove the entries of {}", file.display());
+            self.replace_classes(&file, vec![]).await;
+            self.usages.write().await.replace(&file, vec![]);
+            self.locale_usages.write().await.replace(&file, vec![]);
+            self.replace_prototypes(&file, vec![]).await;
+            self.replace_locales(&file, vec![]).await;
+            self.parsed_files.write().await.remove(&file);
+        }
+    }
+
+    /// Adds the prototypes to the index, a prototype defined several times is
+    /// represented by the definition from the highest-priority override root.
+    pub async fn extend_prototypes(&self, prototypes: Vec<YamlPrototype>) {
+        let config = self.config.read().await.prototypes.clone();
+        let mut lock = self.prototypes.write().await;
+        self.used_values.write().await.clear();
+
+        for proto in prototypes {
+            insert_prototype(&mut lock, &config, proto);
+        }
+    }
+
+    /// Replaces the prototypes defined in the file with the freshly parsed ones.
+    pub async fn replace_prototypes(&self, path: &Path, prototypes: Vec<YamlPrototype>) {
+        let config = self.config.read().await.prototypes.clone();
+        let mut lock = self.prototypes.write().await;
+        self.used_values.write().await.clear();
+        let stale = lock
+            .par_iter()
+            .filter(|p| p.definitions().any(|d| d.index().0 == path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for proto in stale {
+            lock.remove(&proto);
+            if !prototypes.contains(&proto) {
+                tracing::info!("Remove prototype: {} with id {}", proto.prototype, proto.id);
+            }
+
+            let definitions = unshadow(proto).into_iter().filter(|d| d.index().0 != path);
+            if let Some(proto) = shadow(definitions.collect(), &config) {
+                lock.insert(proto);
+            }
+        }
+
+        for proto in prototypes {
+            tracing::info!(
+                "New/changed prototype: {} with id {}",
+                proto.prototype,
+                proto.id
+            );
+            insert_prototype(&mut lock, &config, proto);
+        }
+    }
+
+    /// Picks the representative definitions again after the override roots have changed.
+    pub async fn reorder_prototypes(&self) {
+        let config = self.config.read().await.prototypes.clone();
+        let mut lock = self.prototypes.write().await;
+        self.used_values.write().await.clear();
+
+        for proto in std::mem::take(&mut *lock) {
+            if let Some(proto) = shadow(unshadow(proto), &config) {
+                lock.insert(proto);
+            }
+        }
+    }
+}
+
+fn insert_class(
+    classes: &mut HashSet<CsharpClass>,
+    inheritors: &mut InheritanceGraph,
+    class: CsharpClass,
+) {
+    // Another declaration of a partial class is merged with the known ones,
+    // a class declared again in the same file or elsewhere replaces them.
+    let mut declarations = match classes.take(&class) {
+        Some(old) => {
+            inheritors.remove(&old);
+            old.into_declarations()
+                .into_iter()
+                .filter(|d| d.index().0 != class.index().0 && d.is_partial_of(&class))
+                .collect()
+        }
+        None => vec![],
+    };
+    declarations.push(class);
+
+    if let Some(class) = CsharpClass::merge(declarations) {
+        inheritors.insert(&class);
+        classes.insert(class);
+    }
+}
+
+fn insert_prototype(
+    prototypes: &mut HashSet<YamlPrototype>,
+    config: &PrototypesConfig,
+    proto: YamlPrototype,
+) {
+    // A definition from the same file is a newer version of the same definition.
+    let mut definitions = prototypes
+        .take(&proto)
+        .map(unshadow)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|d| d.index().0 != proto.index().0)
+        .collect::<Vec<_>>();
+    definitions.push(proto);
+
+    if let Some(proto) = shadow(definitions, config) {
+        prototypes.insert(proto);
+    }
+}
+
+/// Makes the highest-priority definition shadow the others,
+/// the definitions with the same priority keep their order.
+fn shadow(mut definitions: Vec<YamlPrototype>, config: &PrototypesConfig) -> Option<YamlPrototype> {
+    definitions.sort_by_cached_key(|d| config.priority(&d.index().0));
+
+    let mut definitions = definitions.into_iter();
+    let mut proto = definitions.next()?;
+    proto.shadowed = definitions.collect();
+    Some(proto)
+}
+
+fn unshadow(mut proto: YamlPrototype) -> Vec<YamlPrototype> {
+    let shadowed = std::mem::take(&mut proto.shadowed);
+    std::iter::once(proto).chain(shadowed).collect()
+}
+
+/// The path passed to a command either as a path or as a file uri.
+fn argument_path(argument: &str) -> Option<PathBuf> {
+    match Url::parse(argument) {
+        Ok(uri) if uri.scheme() == "file" => uri.to_file_path().ok(),
+        _ => Some(PathBuf::from(argument)),
+    }
+}
+
+/// Drops the version of the document once it is parsed, unless it has changed again meanwhile.
+async fn forget_version(versions: &RwLock<HashMap<Url, i32>>, uri: &Url, version: i32) {
+    let mut lock = versions.write().await;
+    if lock.get(uri) == Some(&version) {
+        lock.remove(uri);
+    }
+}
+
+/// Parses the text of the file from scratch, the trees of the YAML and C# files are kept.
+fn parse_tree(path: &Path, text: &str) -> Option<Tree> {
+    match path.extension()?.to_str()? {
+        "yml" | "yaml" => yaml::parse_tree(text, None),
+        #[cfg(feature = "csharp")]
+        "cs" => csharp::parse_tree(text, None),
+        _ => None,
+    }
+}
+
+/// The byte offset and the point of the char in the rope, as the tree-sitter edits count them.
+fn rope_point(rope: &Rope, char_idx: usize) -> (usize, Point) {
+    let char_idx = char_idx.min(rope.len_chars());
+    let byte = rope.char_to_byte(char_idx);
+    let row = rope.char_to_line(char_idx);
+    (byte, Point::new(row, byte - rope.line_to_byte(row)))
+}
+
+/// Explains why the server is idle and how to point it at the project.
+fn idle_message() -> String {
+    let expected = PROJECT_MARKERS
+        .iter()
+        .map(|m| format!("`{m}`"))
+        .collect::<Vec<_>>()
+        .join(" or ");
+    format!(
+        "The workspace is not a Space Station 14 project, {expected} is expected in its root. \
+         Run the `{SET_PROJECT_ROOT_COMMAND}` command with the root of the project to index it."
+    )
 }
 
-pub(crate) struct Backend {
+pub struct Backend {
     client: Arc<Client>,
-    opened_files: RwLock<HashMap<Url, Rope>>,
+    /// The languages the documents without an extension were opened in, e.g. untitled ones.
+    document_languages: RwLock<HashMap<Url, String>>,
+    /// The latest versions of the changed Fluent and YAML files, used to debounce re-parsing.
+    reparse_versions: Arc<RwLock<HashMap<Url, i32>>>,
+    /// The opened Fluent files parsed for the semantic tokens, dropped once they change.
+    #[cfg(feature = "fluent")]
+    fluent_documents: RwLock<HashMap<Url, Arc<FluentDocument>>>,
     context: Arc<Context>,
     root_uri: Arc<RwLock<Option<Url>>>,
+    /// How the client counts the columns, negotiated on initialization.
+    position_encoding: RwLock<PositionEncoding>,
+    /// Stops the running parsing when its progress is cancelled in the client,
+    /// replaced at the start of every run.
+    parse_cancellation: RwLock<ParseCancellation>,
+    /// Whether the project has been indexed, reported in the status.
+    indexed: Arc<AtomicBool>,
+    /// Sends the status periodically, aborted on shutdown.
+    status_task: RwLock<Option<JoinHandle<()>>>,
+    /// The timings of the requests served, reported by the `robust-lsp.indexStats` command.
+    timings: Timings,
 }
 
 impl Backend {
-    pub(crate) fn new(client: Client) -> Self {
+    pub fn new(client: Client) -> Self {
+        Self::with_file_system(client, SharedFileSystem::default())
+    }
+
+    /// Makes the providers read the resources through the file system, e.g. from memory.
+    pub fn with_file_system(client: Client, fs: SharedFileSystem) -> Self {
         Self {
             client: Arc::new(client),
-            opened_files: Default::default(),
-            context: Default::default(),
+            document_languages: Default::default(),
+            reparse_versions: Default::default(),
+            #[cfg(feature = "fluent")]
+            fluent_documents: Default::default(),
+            context: Arc::new(Context {
+                fs,
+                ..Default::default()
+            }),
             root_uri: Default::default(),
+            position_encoding: Default::default(),
+            parse_cancellation: Default::default(),
+            indexed: Default::default(),
+            status_task: Default::default(),
+            timings: Default::default(),
+        }
+    }
+
+    /// Builds the service with the custom requests registered, the socket carries
+    /// the messages to the client. Serving it over stdio or any other transport is up to the caller.
+    pub fn service() -> (LspService<Self>, ClientSocket) {
+        Self::service_with(SharedFileSystem::default())
+    }
+
+    pub fn service_with(fs: SharedFileSystem) -> (LspService<Self>, ClientSocket) {
+        LspService::build(|client| Self::with_file_system(client, fs))
+            .custom_method(PROTOTYPE_GRAPH_METHOD, Self::prototype_graph)
+            .custom_method(ENTITY_PICKER_METHOD, Self::entities)
+            .custom_method(STATUS_METHOD, Self::status)
+            .custom_method(
+                WorkDoneProgressCancel::METHOD,
+                Self::work_done_progress_cancel,
+            )
+            .finish()
+    }
+
+    /// Handles the `robust-lsp/entities` request.
+    #[instrument(skip(self))]
+    pub async fn entities(&self, params: EntityPickerParams) -> Result<EntityPage> {
+        let config = self.context.config.read().await;
+        let lock = self.context.prototypes.read().await;
+        Ok(EntityPage::new(&lock, &config, &params))
+    }
+
+    /// Handles the `robust-lsp/status` request, the status is also sent as `$/robustLsp/status`.
+    pub async fn status(&self) -> Result<ServerStatus> {
+        let pending = self.reparse_versions.read().await.len();
+        let indexed = self.indexed.load(Ordering::Relaxed);
+        Ok(ServerStatus::new(&self.context, indexed, pending).await)
+    }
+
+    /// Sends the status every `STATUS_INTERVAL`, so that the client can tell the server is alive.
+    async fn report_status(&self) {
+        let (client, context) = (self.client.clone(), self.context.clone());
+        let (versions, indexed) = (self.reparse_versions.clone(), self.indexed.clone());
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(STATUS_INTERVAL).await;
+                let pending = versions.read().await.len();
+                let status =
+                    ServerStatus::new(&context, indexed.load(Ordering::Relaxed), pending).await;
+                client.send_notification::<StatusNotification>(status).await;
+            }
+        });
+        *self.status_task.write().await = Some(task);
+    }
+
+    /// Handles the `window/workDoneProgress/cancel` notification, which `tower-lsp` doesn't route.
+    /// Cancelling the progress of any file group stops the whole parsing, the progress
+    /// of a previous run is ignored.
+    pub async fn work_done_progress_cancel(&self, params: WorkDoneProgressCancelParams) {
+        let cancellation = self.parse_cancellation.read().await.clone();
+        match params.token {
+            NumberOrString::String(token) if cancellation.owns_progress(&token) => {
+                tracing::info!("Parsing is cancelled by the client.");
+                cancellation.cancel();
+            }
+            token => tracing::trace!("Unknown progress is cancelled: {token:?}"),
+        }
+    }
+
+    /// Handles the `robust-lsp/prototypeGraph` request.
+    #[instrument(skip(self))]
+    pub async fn prototype_graph(
+        &self,
+        params: PrototypeGraphParams,
+    ) -> Result<Option<PrototypeGraph>> {
+        let lock = self.context.prototypes.read().await;
+        let mut graph = PrototypeGraph::new(&lock, &params.prototype, &params.id);
+        drop(lock);
+
+        // The locations of the nodes name their own files, so any uri does.
+        if let (Some(graph), Some(root)) = (&mut graph, self.root_uri.read().await.clone()) {
+            self.convert_positions(&root, &mut graph.nodes, Direction::ToClient)
+                .await;
+        }
+        Ok(graph)
+    }
+
+    /// Collects the semantic tokens of the opened file, of the whole file or only of the range.
+    async fn semantic_tokens(
+        &self,
+        uri: &Url,
+        range: Option<tower_lsp::lsp_types::Range>,
+    ) -> Option<tower_lsp::lsp_types::SemanticTokens> {
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let timeout = self.provider_timeout().await;
+        let mut tokens = match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "Semantic tokens",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            None,
+                            move || YamlSemanticTokens::new(context, &rope).semantic_tokens(range),
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => match self.fluent_document(uri).await {
+                Some(document) => {
+                    recover_in_time(
+                        "Semantic tokens",
+                        &self.context.last_error,
+                        timeout,
+                        uri,
+                        None,
+                        move || FluentSemanticTokens::new(document).semantic_tokens(range),
+                    )
+                    .await
+                }
+                None => {
+                    tracing::trace!("File wasn't cached.");
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut tokens, Direction::ToClient)
+            .await;
+        tokens
+    }
+
+    /// The parsed opened Fluent file, it is parsed again only after it changes.
+    #[cfg(feature = "fluent")]
+    async fn fluent_document(&self, uri: &Url) -> Option<Arc<FluentDocument>> {
+        if let Some(document) = self.fluent_documents.read().await.get(uri) {
+            return Some(document.clone());
+        }
+
+        let rope = self.context.opened_files.read().await.get(uri).cloned()?;
+        let document = Arc::new(FluentDocument::parse(&rope));
+        self.fluent_documents
+            .write()
+            .await
+            .insert(uri.clone(), document.clone());
+        Some(document)
+    }
+
+    /// Whether the YAML file is one of the prototype files set in the settings.
+    /// The documents that aren't files, e.g. untitled ones, always are.
+    async fn is_prototype_file(&self, uri: &Url) -> bool {
+        let config = self.context.config.read().await;
+        uri.scheme() != "file" || config.prototypes.is_prototype_file(&document_path(uri))
+    }
+
+    /// The path of the document whose extension is told by its language when its uri has none,
+    /// e.g. `untitled:Untitled-1` opened as YAML is served as `Untitled-1.yml`.
+    async fn document_file(&self, uri: &Url) -> PathBuf {
+        let mut path = document_path(uri);
+        if path.extension().is_none() {
+            let languages = self.document_languages.read().await;
+            if let Some("yaml") = languages.get(uri).map(String::as_str) {
+                path.set_extension("yml");
+            }
+        }
+        path
+    }
+
+    /// How long the providers other than completion may run, see `recover_in_time`.
+    async fn provider_timeout(&self) -> Option<Duration> {
+        self.context.config.read().await.timeouts.providers()
+    }
+
+    /// Converts the positions of the value between the encoding of the client
+    /// and the byte columns the providers count.
+    async fn convert_positions(
+        &self,
+        uri: &Url,
+        value: &mut impl ConvertPositions,
+        direction: Direction,
+    ) {
+        let encoding = *self.position_encoding.read().await;
+        if encoding == PositionEncoding::Utf8 {
+            return;
+        }
+
+        let opened = self.context.opened_files.read().await;
+        let mut converter = PositionConverter::new(encoding, direction, &opened, &*self.context.fs);
+        value.convert(uri, &mut converter);
+    }
+
+    async fn apply_config(&self, mut config: Config) {
+        tracing::trace!("Applying settings: {config:?}");
+        logging::reconfigure(config.log.level, config.log.format);
+        set_relaxed_prototypes(config.prototypes.relaxed);
+
+        if let Some(root) = self.root_uri.read().await.as_ref() {
+            if let Ok(root) = root.to_file_path() {
+                for override_root in config.prototypes.override_roots.iter_mut() {
+                    *override_root = root.join(&*override_root);
+                }
+                config.prototypes.compile_files(&root);
+            }
+        }
+
+        let mut lock = self.context.config.write().await;
+        let reorder = lock.prototypes.override_roots != config.prototypes.override_roots;
+        let refilter = lock.prototypes.include != config.prototypes.include
+            || lock.prototypes.exclude != config.prototypes.exclude;
+        let prototypes = config.prototypes.clone();
+        *lock = config;
+        drop(lock);
+
+        if reorder {
+            self.context.reorder_prototypes().await;
+        }
+        if refilter {
+            // The files included since then are indexed once they are saved.
+            let excluded = self
+                .context
+                .prototypes
+                .read()
+                .await
+                .iter()
+                .flat_map(|p| p.definitions().map(|d| d.index().0.clone()))
+                .filter(|file| !prototypes.is_prototype_file(file))
+                .collect::<HashSet<_>>();
+            for file in excluded {
+                self.context.replace_prototypes(&file, vec![]).await;
+            }
         }
     }
+
+    /// Asks the client to report deleted files, so that their entries are dropped
+    /// from the index. Folders are watched too, as their files aren't reported one by one.
+    async fn watch_deleted_files(&self) {
+        let supported = self
+            .context
+            .client_capabilities
+            .read()
+            .await
+            .workspace
+            .as_ref()
+            .and_then(|w| w.did_change_watched_files)
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false);
+        if !supported {
+            return;
+        }
+
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/*".to_owned()),
+                kind: Some(WatchKind::Delete),
+            }],
+        };
+        let registration = Registration {
+            id: WATCHED_FILES_REGISTRATION.to_owned(),
+            method: DidChangeWatchedFiles::METHOD.to_owned(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            tracing::warn!("Failed to watch the deleted files: {err}");
+        }
+    }
+
+    async fn index_project(&self, uri: Url) {
+        // A cancelled run doesn't stop the next ones, e.g. when the root is set again.
+        let cancellation = {
+            let mut lock = self.parse_cancellation.write().await;
+            *lock = lock.next();
+            lock.clone()
+        };
+        self.indexed.store(false, Ordering::Relaxed);
+
+        let parser = ProjectParser::new(uri, self.context.clone(), Some(self.client.clone()))
+            .with_cancellation(cancellation.clone());
+        parser.parse(file_groups()).await;
+        // The index of a cancelled run is partial, so the project isn't reported as indexed.
+        self.indexed
+            .store(!cancellation.is_cancelled(), Ordering::Relaxed);
+        if let Ok(status) = self.status().await {
+            self.client
+                .send_notification::<StatusNotification>(status)
+                .await;
+        }
+
+        if cancellation.is_cancelled() {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    "Parsing has been cancelled, only the files parsed by then are indexed.",
+                )
+                .await;
+        }
+    }
+
+    /// Re-parses the keys of a changed Fluent file once the typing stops.
+    #[cfg(feature = "fluent")]
+    async fn schedule_fluent_reparse(&self, uri: Url, version: i32, rope: Rope) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+
+        self.reparse_versions
+            .write()
+            .await
+            .insert(uri.clone(), version);
+
+        let versions = self.reparse_versions.clone();
+        let context = self.context.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(REPARSE_DELAY).await;
+            if versions.read().await.get(&uri) != Some(&version) {
+                return;
+            }
+
+            let keys = fluent::get_keys(&path, &rope.to_string());
+            context.replace_locales(&path, keys).await;
+            forget_version(&versions, &uri, version).await;
+        });
+    }
+
+    /// Indexes the prototypes of the changed YAML file once it stays idle, the tree edited
+    /// along the way is parsed again incrementally. The version is recorded by `did_change`
+    /// before it edits the tree.
+    fn schedule_yaml_reparse(&self, uri: Url, version: i32, rope: Rope) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+
+        let versions = self.reparse_versions.clone();
+        let context = self.context.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(REPARSE_DELAY).await;
+
+            let src = rope.to_string();
+            let tree = {
+                let mut trees = context.parsed_files.write().await;
+                // A later change has already edited the kept tree to match a newer text.
+                if versions.read().await.get(&uri) != Some(&version) {
+                    return;
+                }
+                yaml::reparse_kept(&mut trees, &path, &src)
+            };
+
+            let prototypes = match tree.map(|tree| yaml::get_prototypes(&tree, &src, &path)) {
+                Some(Ok(ParseResult::YamlPrototypes(prototypes))) => prototypes,
+                _ => vec![],
+            };
+            context.replace_prototypes(&path, prototypes).await;
+            forget_version(&versions, &uri, version).await;
+        });
+    }
+
+    #[instrument(skip_all, fields(uri = %uri, elapsed_ms = Empty, results = Empty))]
+    async fn publish_diagnostics(&self, uri: Url) {
+        let timer = RequestTimer::start(&self.timings, "diagnostics");
+        let path = self.document_file(&uri).await;
+        let extension = path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let opened = self.context.opened_files.read().await;
+        let Some(rope) = opened.get(&uri) else {
+            return;
+        };
+
+        let (context, rope) = (self.context.clone(), rope.clone());
+        drop(opened);
+
+        let timeout = self.provider_timeout().await;
+        let mut diagnostics = match extension {
+            // The excluded files are cleared of the diagnostics published before.
+            "yml" | "yaml" if !self.is_prototype_file(&uri).await => vec![],
+            "yml" | "yaml" => {
+                recover_in_time(
+                    "Diagnostics",
+                    &self.context.last_error,
+                    timeout,
+                    &uri,
+                    None,
+                    move || YamlDiagnostics::new(context, path, &rope).diagnostics(),
+                )
+                .await
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                recover_in_time(
+                    "Diagnostics",
+                    &self.context.last_error,
+                    timeout,
+                    &uri,
+                    None,
+                    move || FluentDiagnostics::new(context, path, &rope).diagnostics(),
+                )
+                .await
+            }
+            #[cfg(feature = "csharp")]
+            "cs" => {
+                recover_in_time(
+                    "Diagnostics",
+                    &self.context.last_error,
+                    timeout,
+                    &uri,
+                    None,
+                    move || CsharpDiagnostics::new(context, path).diagnostics(),
+                )
+                .await
+            }
+            _ => return,
+        };
+        let rules = self.context.config.read().await.diagnostics.rules.clone();
+        apply_rules(&mut diagnostics, &rules);
+        timer.finish(diagnostics.len());
+        self.convert_positions(&uri, &mut diagnostics, Direction::ToClient)
+            .await;
+
+        tracing::trace!("Publishing {} diagnostics.", diagnostics.len());
+        self.client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
+    }
 }
 
 #[tower_lsp::async_trait]
@@ -68,27 +917,83 @@ impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         tracing::info!("Server is initializing...");
 
-        if !check_project_compliance(&params) {
-            return Err(Error::request_cancelled());
+        // Outside of a project the server stays idle until the root is set by the command.
+        match params.root_uri.clone() {
+            Some(root_uri) if check_project_compliance(&params) => {
+                self.root_uri.write().await.replace(root_uri);
+            }
+            root_uri => tracing::warn!("{root_uri:?} is not a Space Station 14 project."),
         }
 
-        self.root_uri
-            .write()
-            .await
-            .replace(params.root_uri.unwrap());
+        let position_encoding = PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|g| g.position_encodings.as_deref()),
+        );
+        *self.position_encoding.write().await = position_encoding;
+        *self.context.client_capabilities.write().await = params.capabilities;
+
+        if let Some(config) = params.initialization_options.and_then(Config::from_value) {
+            self.apply_config(config).await;
+        }
 
         Ok(InitializeResult {
             server_info: None,
             capabilities: ServerCapabilities {
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::INCREMENTAL,
+                position_encoding: Some(position_encoding.kind()),
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::INCREMENTAL),
+                        will_save: Some(true),
+                        save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                            include_text: Some(true),
+                        })),
+                        ..Default::default()
+                    },
                 )),
                 completion_provider: Some(CompletionOptions {
-                    trigger_characters: Some(vec![" ".to_string()]),
+                    trigger_characters: Some(vec![" ".to_string(), "<".to_string()]),
                     ..Default::default()
                 }),
                 definition_provider: Some(Left(true)),
                 inlay_hint_provider: Some(Left(true)),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic::legend(),
+                            range: Some(true),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                references_provider: Some(Left(true)),
+                rename_provider: Some(Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        EXPORT_SCHEMA_COMMAND.to_owned(),
+                        SET_PROJECT_ROOT_COMMAND.to_owned(),
+                        DIFF_PROTOTYPES_COMMAND.to_owned(),
+                        INDEX_STATS_COMMAND.to_owned(),
+                        #[cfg(feature = "fluent")]
+                        UNUSED_LOCALE_KEYS_COMMAND.to_owned(),
+                    ],
+                    work_done_progress_options: Default::default(),
+                }),
                 ..Default::default()
             },
         })
@@ -99,86 +1004,91 @@ impl LanguageServer for Backend {
             .log_message(MessageType::INFO, "server initialized!")
             .await;
 
-        // I'm shocked by this myself O_O
-        let uri = self.root_uri.read().await.clone().unwrap().clone();
-
-        let csharp_parser = Arc::new(csharp::parse);
-        let csharp_dispatcher = Arc::new(csharp::dispatch);
-        let matchers = vec![
-            FileGroup::new(
-                "C# prototypes",
-                "*Prototype.cs",
-                csharp_parser.clone(),
-                csharp_dispatcher.clone(),
-            ),
-            FileGroup::new(
-                "C# components",
-                "*Component.cs",
-                csharp_parser.clone(),
-                csharp_dispatcher.clone(),
-            ),
-            FileGroup::new(
-                "others C# files",
-                "*.cs",
-                csharp_parser.clone(),
-                csharp_dispatcher.clone(),
-            ),
-            FileGroup::new(
-                "fluent files",
-                "*.ftl",
-                Arc::new(fluent::parse),
-                Arc::new(fluent::dispatch),
-            ),
-            FileGroup::new(
-                "yaml files",
-                "**/Prototypes/**/*.{yml,yaml}",
-                Arc::new(yaml::parse),
-                Arc::new(yaml::dispatch),
-            ),
-        ];
-
-        let parser = ProjectParser::new(uri, self.context.clone(), self.client.clone());
-        parser.parse(matchers).await;
-    }
+        self.watch_deleted_files().await;
+        self.report_status().await;
 
-    #[instrument(skip_all, fields(uri = %params.text_document.uri))]
-    async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let Ok(path) = params.text_document.uri.to_file_path() else {
+        let Some(uri) = self.root_uri.read().await.clone() else {
+            self.client
+                .show_message(MessageType::WARNING, idle_message())
+                .await;
             return;
         };
 
-        if !path.is_file() {
-            return;
+        self.index_project(uri).await;
+    }
+
+    #[instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        // The text comes from the client, so untitled and unsaved documents are served too.
+        let rope = Rope::from_str(&params.text_document.text);
+        if document_path(&params.text_document.uri)
+            .extension()
+            .is_none()
+        {
+            self.document_languages.write().await.insert(
+                params.text_document.uri.clone(),
+                params.text_document.language_id.clone(),
+            );
         }
+        self.context
+            .opened_files
+            .write()
+            .await
+            .insert(params.text_document.uri.clone(), rope);
+        #[cfg(feature = "fluent")]
+        self.fluent_documents
+            .write()
+            .await
+            .remove(&params.text_document.uri);
+        tracing::trace!("Document has been cached.");
 
-        match std::fs::File::open(&path) {
-            Ok(handler) => {
-                let rope = Rope::from_reader(handler).unwrap();
-                self.opened_files
-                    .write()
-                    .await
-                    .insert(params.text_document.uri, rope);
-                tracing::trace!("Document has been cached.");
-            }
-            Err(err) => {
-                tracing::trace!("File can't be opened: {}", err);
+        // The tree of an opened file is kept to be edited and parsed again incrementally.
+        if let Ok(path) = params.text_document.uri.to_file_path() {
+            if let Some(tree) = parse_tree(&path, &params.text_document.text) {
+                self.context.parsed_files.write().await.insert(path, tree);
             }
         }
+
+        self.publish_diagnostics(params.text_document.uri).await;
     }
 
     #[instrument(skip_all, fields(uri = %params.text_document.uri))]
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
-        let mut lock = self.opened_files.write().await;
-        let found_rope = lock.get_mut(&params.text_document.uri);
+        let uri = params.text_document.uri;
+        let encoding = *self.position_encoding.read().await;
+        let file = self.document_file(&uri).await;
+        let is_yaml = matches!(
+            file.extension().and_then(|e| e.to_str()),
+            Some("yml" | "yaml")
+        );
+        let reparse = is_yaml && self.is_prototype_file(&uri).await;
+        let mut lock = self.context.opened_files.write().await;
+        let found_rope = lock.get_mut(&uri);
 
         match found_rope {
             Some(rope) => {
+                // The version is recorded before the kept tree is edited, so that a pending
+                // re-parse of an older text doesn't replace the edited tree.
+                if reparse {
+                    self.reparse_versions
+                        .write()
+                        .await
+                        .insert(uri.clone(), params.text_document.version);
+                }
+
+                // The edits are applied to the kept tree as they come, it is parsed again when needed.
+                let mut trees = self.context.parsed_files.write().await;
+                let mut tree = match uri.to_file_path() {
+                    Ok(path) => trees.get_mut(&path),
+                    _ => None,
+                };
+
                 for change in params.content_changes {
                     if let Some(range) = change.range {
-                        let start_idx = rope.line_to_char(range.start.line as usize)
-                            + range.start.character as usize;
-                        let end_idx = rope.line_to_char(range.end.line as usize)
-                            + range.end.character as usize;
+                        let start_idx = encoding.char_index(rope, range.start);
+                        let end_idx = encoding.char_index(rope, range.end);
+                        let (start_byte, start_position) = rope_point(rope, start_idx);
+                        let (old_end_byte, old_end_position) = rope_point(rope, end_idx);
 
                         if let Err(err) = rope.try_remove(start_idx..end_idx) {
                             tracing::warn!("Failed to remove text from document: {}.", err);
@@ -187,9 +1097,38 @@ impl LanguageServer for Backend {
                             tracing::warn!("Failed to insert text into document: {}.", err);
                         }
 
+                        if let Some(tree) = tree.as_deref_mut() {
+                            let new_end_idx = start_idx + change.text.chars().count();
+                            let (new_end_byte, new_end_position) = rope_point(rope, new_end_idx);
+                            tree.edit(&InputEdit {
+                                start_byte,
+                                old_end_byte,
+                                new_end_byte,
+                                start_position,
+                                old_end_position,
+                                new_end_position,
+                            });
+                        }
+
                         tracing::trace!("Document has been changed.");
                     }
                 }
+                drop(trees);
+
+                if reparse {
+                    self.schedule_yaml_reparse(
+                        uri.clone(),
+                        params.text_document.version,
+                        rope.clone(),
+                    );
+                }
+
+                #[cfg(feature = "fluent")]
+                if uri.path().ends_with(".ftl") {
+                    self.fluent_documents.write().await.remove(&uri);
+                    self.schedule_fluent_reparse(uri, params.text_document.version, rope.clone())
+                        .await;
+                }
             }
             None => {
                 tracing::warn!("File wasn't cached.");
@@ -197,12 +1136,53 @@ impl LanguageServer for Backend {
         }
     }
 
-    async fn did_save(&self, params: DidSaveTextDocumentParams) {
-        let path = match params.text_document.uri.to_file_path() {
-            Ok(p) => p,
-            Err(_) => {
-                tracing::warn!(
-                    "Failed to convert uri to path: {}.",
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        // A pending re-parse is dropped along with the kept tree.
+        self.reparse_versions.write().await.remove(&uri);
+        self.context.opened_files.write().await.remove(&uri);
+        self.document_languages.write().await.remove(&uri);
+        #[cfg(feature = "fluent")]
+        self.fluent_documents.write().await.remove(&uri);
+        if let Ok(path) = uri.to_file_path() {
+            self.context.parsed_files.write().await.remove(&path);
+        }
+        tracing::trace!("Document has been closed.");
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        if let Some(config) = Config::from_value(params.settings) {
+            self.apply_config(config).await;
+        }
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        for change in params.changes {
+            if change.typ != FileChangeType::DELETED {
+                continue;
+            }
+
+            if let Ok(path) = change.uri.to_file_path() {
+                tracing::trace!("{} has been deleted.", path.display());
+                self.context.remove_files(&path).await;
+            }
+        }
+    }
+
+    async fn will_save(&self, params: tower_lsp::lsp_types::WillSaveTextDocumentParams) {
+        // A pending re-parse is dropped, the saved text is parsed on save.
+        self.reparse_versions
+            .write()
+            .await
+            .remove(&params.text_document.uri);
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let path = match params.text_document.uri.to_file_path() {
+            Ok(p) => p,
+            Err(_) => {
+                tracing::warn!(
+                    "Failed to convert uri to path: {}.",
                     params.text_document.uri
                 );
                 return;
@@ -214,166 +1194,601 @@ impl LanguageServer for Backend {
             .to_str()
             .unwrap_or_default();
 
+        // The saved text is parsed rather than the file, which may change again by the time
+        // it is read. Without the text the opened buffer already matches the saved file.
+        let uri = &params.text_document.uri;
+        let rope = match params.text {
+            Some(text) => {
+                let rope = Rope::from_str(&text);
+                if let Some(opened) = self.context.opened_files.write().await.get_mut(uri) {
+                    // The kept tree was edited along the buffer, not the saved text.
+                    if *opened != rope {
+                        if let Some(tree) = parse_tree(&path, &text) {
+                            self.context
+                                .parsed_files
+                                .write()
+                                .await
+                                .insert(path.clone(), tree);
+                        }
+                    }
+                    *opened = rope.clone();
+                }
+                #[cfg(feature = "fluent")]
+                self.fluent_documents.write().await.remove(uri);
+                Some(rope)
+            }
+            None => self.context.opened_files.read().await.get(uri).cloned(),
+        };
+        let parsed_files = self.context.parsed_files.clone();
+
         match ext {
+            #[cfg(feature = "csharp")]
             "cs" => {
-                let result = csharp::parse(path.clone(), self.context.parsed_files.clone()).await;
+                let result = match rope {
+                    Some(rope) => csharp::parse_rope(path.clone(), rope, parsed_files).await,
+                    None => csharp::parse(path.clone(), parsed_files).await,
+                };
                 match result {
                     Ok(result) => {
-                        let ParseResult::Csharp(parsed_classes) = result else {
+                        let ParseResult::Csharp(parsed_classes, usages, locale_usages) = result
+                        else {
                             tracing::warn!("Failed to parse C# prototypes while saving file.");
                             return;
                         };
 
-                        let mut lock = self.context.classes.write().await;
-                        let diff = lock
-                            .par_iter()
-                            .filter(|c| c.index().0 == path)
-                            .filter(|c| !parsed_classes.contains(c))
-                            .cloned()
-                            .collect::<Vec<_>>();
-
-                        for class in parsed_classes {
-                            tracing::info!("New/changed class: {}", class.name);
-                            lock.insert(class);
-                        }
-                        for class in diff {
-                            tracing::info!("Remove class: {}", class.name);
-                            lock.remove(&class);
-                        }
+                        self.context.replace_classes(&path, parsed_classes).await;
+                        self.context.usages.write().await.replace(&path, usages);
+                        self.context
+                            .locale_usages
+                            .write()
+                            .await
+                            .replace(&path, locale_usages);
                     }
                     Err(_) => {
+                        // The classes of a file that can't be parsed anymore are no longer there.
                         tracing::warn!("Failed to parse the file {}", path.display());
-                        return;
+                        self.context.remove_files(&path).await;
                     }
                 }
+
+                self.publish_diagnostics(params.text_document.uri).await;
+            }
+            "yml" | "yaml" if !self.is_prototype_file(uri).await => {
+                self.publish_diagnostics(params.text_document.uri).await;
             }
             "yml" | "yaml" => {
-                let result = yaml::parse(path.clone(), self.context.parsed_files.clone()).await;
+                let result = match rope {
+                    Some(rope) => yaml::parse_rope(path.clone(), rope, parsed_files).await,
+                    None => yaml::parse(path.clone(), parsed_files).await,
+                };
                 match result {
                     Ok(result) => {
                         let ParseResult::YamlPrototypes(parsed_prototypes) = result else {
                             tracing::warn!("Failed to parse YAML prototypes while saving file.");
                             return;
                         };
-                        let mut lock = self.context.prototypes.write().await;
-                        let diff = lock
-                            .par_iter()
-                            .filter(|p| p.index().0 == path)
-                            .filter(|p| !parsed_prototypes.contains(p))
-                            .cloned()
-                            .collect::<Vec<_>>();
-
-                        for proto in parsed_prototypes {
-                            tracing::info!(
-                                "New/changed prototype: {} with id {}",
-                                proto.prototype,
-                                proto.id
-                            );
-                            lock.insert(proto);
-                        }
+                        self.context
+                            .replace_prototypes(&path, parsed_prototypes)
+                            .await;
+                    }
+                    Err(_) => {
+                        // E.g. a file that can't be read, its prototypes are no longer there.
+                        tracing::warn!("Failed to parse the file {}", path.display());
+                        self.context.remove_files(&path).await;
+                    }
+                }
 
-                        for proto in diff {
-                            tracing::info!(
-                                "Remove prototype: {} with id {}",
-                                proto.prototype,
-                                proto.id
-                            );
-                            lock.remove(&proto);
-                        }
+                self.publish_diagnostics(params.text_document.uri).await;
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                let result = match rope {
+                    Some(rope) => Ok(ParseResult::Fluent(fluent::get_keys(
+                        &path,
+                        &rope.to_string(),
+                    ))),
+                    None => fluent::parse(path.clone(), parsed_files).await,
+                };
+                match result {
+                    Ok(result) => {
+                        let ParseResult::Fluent(parsed_keys) = result else {
+                            tracing::warn!("Failed to parse Fluent keys while saving file.");
+                            return;
+                        };
+                        self.context.replace_locales(&path, parsed_keys).await;
                     }
                     Err(_) => {
                         tracing::warn!("Failed to parse the file {}", path.display());
-                        return;
+                        self.context.remove_files(&path).await;
                     }
                 }
+
+                self.publish_diagnostics(params.text_document.uri).await;
             }
             _ => {}
         }
     }
 
     #[rustfmt::skip]
-    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+    #[instrument(skip_all, fields(uri = %params.text_document_position.text_document.uri, elapsed_ms = Empty, results = Empty))]
+    async fn completion(&self, mut params: CompletionParams) -> Result<Option<CompletionResponse>> {
         tracing::trace!("Completion request has been received.");
+        let timer = RequestTimer::start(&self.timings, "completion");
+
+        let uri = &params.text_document_position.text_document.uri;
+        self.convert_positions(uri, &mut params.text_document_position.position, Direction::FromClient)
+            .await;
 
-        let file = params.text_document_position.text_document.uri.to_file_path().unwrap_or_default();
+        let file = self.document_file(uri).await;
         let extension = file.extension().unwrap_or_default().to_str().unwrap_or_default();
 
-        let root_path = self.root_uri.read().await.clone().unwrap().to_file_path().unwrap_or_default();
+        let root_path = self.root_uri.read().await.as_ref().and_then(|uri| uri.to_file_path().ok()).unwrap_or_default();
+        let position = params.text_document_position.position;
+        let timeout = self.context.config.read().await.timeouts.completion();
 
-        match extension {
+        let mut response = match extension {
+            "yml" | "yaml" if !self.is_prototype_file(uri).await => None,
             "yml" | "yaml" => {
-                let opened = self.opened_files.read().await;
-                let rope = opened.get(&params.text_document_position.text_document.uri);
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time("Completion", &self.context.last_error, timeout, uri, Some(position), move || {
+                            let completion = YamlCompletion::new(context, position, &rope, root_path, file);
+                            completion.completion()
+                        }).await
+                    },
+                    None => None
+                }
+            },
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
 
                 match rope {
                     Some(rope) => {
-                        let completion = YamlCompletion::new(self.context.clone(), params.text_document_position.position, rope, root_path);
-                        Ok(completion.completion())
+                        let context = self.context.clone();
+                        recover_in_time("Completion", &self.context.last_error, timeout, uri, Some(position), move || {
+                            let completion = FluentCompletion::new(context, position, &rope);
+                            completion.completion()
+                        }).await
                     },
-                    None => Ok(None)
+                    None => None
+                }
+            },
+            #[cfg(feature = "csharp")]
+            "cs" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time("Completion", &self.context.last_error, timeout, uri, Some(position), move || {
+                            let completion = CsharpCompletion::new(context, position, &rope);
+                            completion.completion()
+                        }).await
+                    },
+                    None => None
                 }
             },
             _ => {
                 tracing::trace!("File extension is not supported.");
-                Ok(None)
+                None
             }
-        }
+        };
+
+        timer.finish(match &response {
+            Some(CompletionResponse::Array(items)) => items.len(),
+            Some(CompletionResponse::List(list)) => list.items.len(),
+            None => 0,
+        });
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
     }
 
     #[rustfmt::skip]
+    #[instrument(skip_all, fields(uri = %params.text_document_position_params.text_document.uri, elapsed_ms = Empty, results = Empty))]
     async fn goto_definition(
         &self,
-        params: GotoDefinitionParams,
+        mut params: GotoDefinitionParams,
     ) -> Result<Option<GotoDefinitionResponse>> {
         tracing::trace!("Goto definition request has been received.");
+        let timer = RequestTimer::start(&self.timings, "gotoDefinition");
 
-        let file = params.text_document_position_params.text_document.uri.to_file_path().unwrap_or_default();
+        let uri = &params.text_document_position_params.text_document.uri;
+        self.convert_positions(uri, &mut params.text_document_position_params.position, Direction::FromClient)
+            .await;
+
+        let file = self.document_file(&params.text_document_position_params.text_document.uri).await;
         let extension = file.extension().unwrap_or_default().to_str().unwrap_or_default();
+        let position = params.text_document_position_params.position;
+        let timeout = self.provider_timeout().await;
 
-        match extension {
+        let mut response = match extension {
             "yml" | "yaml" => {
-                let opened = self.opened_files.read().await;
-                let rope = opened.get(&params.text_document_position_params.text_document.uri);
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
 
                 match rope {
                     Some(rope) => {
-                        let definition = YamlGotoDefinition::new(self.context.clone(), params.text_document_position_params.position, rope);
-                        Ok(definition.goto_definition())
+                        let context = self.context.clone();
+                        recover_in_time("Goto definition", &self.context.last_error, timeout, uri, Some(position), move || {
+                            let definition = YamlGotoDefinition::new(context, position, &rope);
+                            definition.goto_definition()
+                        }).await
                     }
                     None => {
                         tracing::trace!("File wasn't cached.");
-                        Ok(None)
+                        None
                     }
                 }
             }
-            _ => Ok(None)
-        }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        recover_in_time("Goto definition", &self.context.last_error, timeout, uri, Some(position), move || {
+                            let definition = FluentGotoDefinition::new(context, document, position, &rope);
+                            definition.goto_definition()
+                        }).await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None
+        };
+
+        timer.finish(match &response {
+            Some(GotoDefinitionResponse::Scalar(_)) => 1,
+            Some(GotoDefinitionResponse::Array(locations)) => locations.len(),
+            Some(GotoDefinitionResponse::Link(links)) => links.len(),
+            None => 0,
+        });
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn code_lens(
+        &self,
+        params: CodeLensParams,
+    ) -> Result<Option<Vec<tower_lsp::lsp_types::CodeLens>>> {
+        tracing::trace!("Code lens request has been received.");
+
+        let uri = &params.text_document.uri;
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let timeout = self.provider_timeout().await;
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        recover_in_time(
+                            "Code lens",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            None,
+                            move || YamlCodeLens::new(context, document, &rope).code_lens(),
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "csharp")]
+            "cs" => {
+                let context = self.context.clone();
+                recover_in_time(
+                    "Code lens",
+                    &self.context.last_error,
+                    timeout,
+                    uri,
+                    None,
+                    move || CsharpCodeLens::new(context, file).code_lens(),
+                )
+                .await
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
     }
 
     async fn inlay_hint(
         &self,
-        params: InlayHintParams,
+        mut params: InlayHintParams,
     ) -> Result<Option<Vec<tower_lsp::lsp_types::InlayHint>>> {
         tracing::trace!("Inlay hint request has been received.");
 
-        let file = params.text_document.uri.to_file_path().unwrap_or_default();
+        let uri = &params.text_document.uri;
+        self.convert_positions(uri, &mut params.range, Direction::FromClient)
+            .await;
+
+        let file = self.document_file(&params.text_document.uri).await;
         let extension = file
             .extension()
             .unwrap_or_default()
             .to_str()
             .unwrap_or_default();
 
-        match extension {
+        let range = params.range;
+        let timeout = self.provider_timeout().await;
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "Inlay hint",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            None,
+                            move || YamlInlayHint::new(context, range, &rope).inlay_hint(),
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "csharp")]
+            "cs" => {
+                let context = self.context.clone();
+                recover_in_time(
+                    "Inlay hint",
+                    &self.context.last_error,
+                    timeout,
+                    uri,
+                    None,
+                    move || CsharpInlayHint::new(context, file, range).inlay_hint(),
+                )
+                .await
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    #[instrument(skip_all, fields(
+        uri = %params.text_document_position_params.text_document.uri,
+        elapsed_ms = Empty,
+        results = Empty,
+    ))]
+    async fn hover(&self, mut params: HoverParams) -> Result<Option<tower_lsp::lsp_types::Hover>> {
+        tracing::trace!("Hover request has been received.");
+        let timer = RequestTimer::start(&self.timings, "hover");
+
+        let uri = &params.text_document_position_params.text_document.uri;
+        self.convert_positions(
+            uri,
+            &mut params.text_document_position_params.position,
+            Direction::FromClient,
+        )
+        .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+        let root_path = self
+            .root_uri
+            .read()
+            .await
+            .as_ref()
+            .and_then(|uri| uri.to_file_path().ok())
+            .unwrap_or_default();
+
+        let position = params.text_document_position_params.position;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "Hover",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || YamlHover::new(context, position, &rope, root_path).hover(),
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        timer.finish(response.iter().count());
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn goto_implementation(
+        &self,
+        mut params: GotoImplementationParams,
+    ) -> Result<Option<GotoImplementationResponse>> {
+        tracing::trace!("Goto implementation request has been received.");
+
+        let uri = &params.text_document_position_params.text_document.uri;
+        self.convert_positions(
+            uri,
+            &mut params.text_document_position_params.position,
+            Direction::FromClient,
+        )
+        .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let position = params.text_document_position_params.position;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "Implementation",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                YamlImplementation::new(context, position, &rope).implementation()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn goto_type_definition(
+        &self,
+        mut params: GotoTypeDefinitionParams,
+    ) -> Result<Option<GotoTypeDefinitionResponse>> {
+        tracing::trace!("Goto type definition request has been received.");
+
+        let uri = &params.text_document_position_params.text_document.uri;
+        self.convert_positions(
+            uri,
+            &mut params.text_document_position_params.position,
+            Direction::FromClient,
+        )
+        .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let position = params.text_document_position_params.position;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
             "yml" | "yaml" => {
-                let opened = self.opened_files.read().await;
-                let rope = opened.get(&params.text_document.uri);
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
 
                 match rope {
                     Some(rope) => {
-                        let hint =
-                            YamlInlayHint::new(self.context.classes.clone(), params.range, rope);
-                        Ok(hint.inlay_hint())
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "Type definition",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                YamlTypeDefinition::new(context, position, &rope).type_definition()
+                            },
+                        )
+                        .await
                     }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn folding_range(
+        &self,
+        params: FoldingRangeParams,
+    ) -> Result<Option<Vec<tower_lsp::lsp_types::FoldingRange>>> {
+        tracing::trace!("Folding range request has been received.");
+
+        let uri = &params.text_document.uri;
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let timeout = self.provider_timeout().await;
+        match extension {
+            "yml" | "yaml" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => Ok(recover_in_time(
+                        "Folding range",
+                        &self.context.last_error,
+                        timeout,
+                        uri,
+                        None,
+                        move || YamlFoldingRange::new(&rope).folding_range(),
+                    )
+                    .await),
                     None => {
                         tracing::trace!("File wasn't cached.");
                         Ok(None)
@@ -384,7 +1799,471 @@ impl LanguageServer for Backend {
         }
     }
 
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        tracing::trace!("Semantic tokens request has been received.");
+
+        let tokens = self.semantic_tokens(&params.text_document.uri, None).await;
+        Ok(tokens.map(SemanticTokensResult::Tokens))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        tracing::trace!("Semantic tokens range request has been received.");
+
+        let tokens = self
+            .semantic_tokens(&params.text_document.uri, Some(params.range))
+            .await;
+        Ok(tokens.map(SemanticTokensRangeResult::Tokens))
+    }
+
+    async fn references(&self, mut params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        tracing::trace!("References request has been received.");
+
+        let uri = &params.text_document_position.text_document.uri;
+        self.convert_positions(
+            uri,
+            &mut params.text_document_position.position,
+            Direction::FromClient,
+        )
+        .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let position = params.text_document_position.position;
+        let include_declaration = params.context.include_declaration;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                // The provider reads the other documents from the snapshot, the lock isn't held
+                // while it runs so that a change waiting to write doesn't block both.
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let context = self.context.clone();
+                        recover_in_time(
+                            "References",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                let references = YamlReferences::new(
+                                    context,
+                                    position,
+                                    include_declaration,
+                                    &rope,
+                                    opened,
+                                );
+                                references.references()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                let rope = self.context.opened_files.read().await.get(uri).cloned();
+
+                match rope {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        recover_in_time(
+                            "References",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                let references = FluentReferences::new(
+                                    context,
+                                    document,
+                                    position,
+                                    include_declaration,
+                                    &rope,
+                                );
+                                references.references()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn prepare_rename(
+        &self,
+        mut params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        tracing::trace!("Prepare rename request has been received.");
+
+        let uri = &params.text_document.uri;
+        self.convert_positions(uri, &mut params.position, Direction::FromClient)
+            .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let position = params.position;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        recover_in_time(
+                            "Prepare rename",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                YamlRename::new(context, document, position, &rope, opened)
+                                    .prepare_rename()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        recover_in_time(
+                            "Prepare rename",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                FluentRename::new(context, document, position, &rope, opened)
+                                    .prepare_rename()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // The client shows the reason instead of trying to rename whatever is under the cursor.
+        if response.is_none() {
+            return Err(Error::invalid_params(
+                "Only prototype ids and Fluent messages and terms can be renamed.",
+            ));
+        }
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn rename(&self, mut params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        tracing::trace!("Rename request has been received.");
+
+        let uri = &params.text_document_position.text_document.uri;
+        self.convert_positions(
+            uri,
+            &mut params.text_document_position.position,
+            Direction::FromClient,
+        )
+        .await;
+
+        let file = self.document_file(uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let position = params.text_document_position.position;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                if !is_prototype_id(&params.new_name) {
+                    return Err(Error::invalid_params(format!(
+                        "`{}` isn't a valid prototype id.",
+                        params.new_name
+                    )));
+                }
+                // The provider reads the other documents from the snapshot, the lock isn't held
+                // while it runs so that a change waiting to write doesn't block both.
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        let new_name = params.new_name.clone();
+                        recover_in_time(
+                            "Rename",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                YamlRename::new(context, document, position, &rope, opened)
+                                    .rename(&new_name)
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "fluent")]
+            "ftl" => {
+                if !is_identifier(&params.new_name) {
+                    return Err(Error::invalid_params(format!(
+                        "`{}` isn't a valid Fluent identifier.",
+                        params.new_name
+                    )));
+                }
+                // The provider reads the other documents from the snapshot, see the YAML branch.
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        let new_name = params.new_name.clone();
+                        recover_in_time(
+                            "Rename",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            Some(position),
+                            move || {
+                                FluentRename::new(context, document, position, &rope, opened)
+                                    .rename(&new_name)
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn code_action(
+        &self,
+        mut params: CodeActionParams,
+    ) -> Result<Option<CodeActionResponse>> {
+        tracing::trace!("Code action request has been received.");
+
+        let uri = &params.text_document.uri;
+        self.convert_positions(uri, &mut params.range, Direction::FromClient)
+            .await;
+        self.convert_positions(uri, &mut params.context.diagnostics, Direction::FromClient)
+            .await;
+
+        let file = self.document_file(&params.text_document.uri).await;
+        let extension = file
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let range = params.range;
+        let timeout = self.provider_timeout().await;
+
+        let mut response = match extension {
+            "yml" | "yaml" => {
+                // Renaming an id reads the other documents from the snapshot, see `references`.
+                let opened = self.context.opened_files.read().await.clone();
+
+                match opened.get(uri).cloned() {
+                    Some(rope) => {
+                        let (context, document) = (self.context.clone(), uri.clone());
+                        let diagnostics = params.context.diagnostics.clone();
+                        recover_in_time(
+                            "Code action",
+                            &self.context.last_error,
+                            timeout,
+                            uri,
+                            None,
+                            move || {
+                                YamlCodeAction::new(
+                                    context,
+                                    document,
+                                    range,
+                                    diagnostics,
+                                    &rope,
+                                    opened,
+                                )
+                                .code_action()
+                            },
+                        )
+                        .await
+                    }
+                    None => {
+                        tracing::trace!("File wasn't cached.");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.convert_positions(uri, &mut response, Direction::ToClient)
+            .await;
+        Ok(response)
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        tracing::trace!("Execute command request has been received.");
+
+        match params.command.as_str() {
+            EXPORT_SCHEMA_COMMAND => {
+                let schema = JsonSchemaExporter::new(self.context.clone()).export().await;
+
+                // The schema is written to the file if the path is passed as the first argument.
+                if let Some(path) = params.arguments.first().and_then(Value::as_str) {
+                    let content = serde_json::to_string_pretty(&schema).unwrap_or_default();
+                    if let Err(err) = self.context.fs.write(Path::new(path), content.as_bytes()) {
+                        tracing::warn!("Failed to write the schema to {path}: {err}");
+                        return Err(Error::internal_error());
+                    }
+                }
+
+                Ok(Some(schema))
+            }
+            SET_PROJECT_ROOT_COMMAND => {
+                // The root is passed either as a path or as a file uri.
+                let Some(root) = params.arguments.first().and_then(Value::as_str) else {
+                    return Err(Error::invalid_params("The root of the project is missing."));
+                };
+                let Some(uri) = argument_path(root)
+                    .filter(|p| is_project_root(p))
+                    .and_then(|p| Url::from_directory_path(p).ok())
+                else {
+                    return Err(Error::invalid_params(format!(
+                        "{root} is not the root of a Space Station 14 project."
+                    )));
+                };
+
+                // The entries of the previous root don't belong to the project.
+                let previous = self.root_uri.write().await.replace(uri.clone());
+                if let Some(path) = previous.and_then(|u| u.to_file_path().ok()) {
+                    self.context.remove_files(&path).await;
+                }
+
+                tracing::info!("The project root is set to {uri}.");
+                self.index_project(uri).await;
+                Ok(None)
+            }
+            DIFF_PROTOTYPES_COMMAND => {
+                // The snapshots are passed as paths or file uris, the old one first.
+                let (Some(old), Some(new)) = (
+                    params.arguments.first().and_then(Value::as_str),
+                    params.arguments.get(1).and_then(Value::as_str),
+                ) else {
+                    return Err(Error::invalid_params(
+                        "The old and the new snapshots are missing.",
+                    ));
+                };
+                let (Some(old_path), Some(new_path)) = (argument_path(old), argument_path(new))
+                else {
+                    return Err(Error::invalid_params(
+                        "The snapshots must be paths or file uris.",
+                    ));
+                };
+
+                let Some(diff) = cli::diff_prototypes(&old_path, &new_path).await else {
+                    return Err(Error::invalid_params(format!(
+                        "Failed to read the snapshots {old} and {new}."
+                    )));
+                };
+                Ok(serde_json::to_value(diff).ok())
+            }
+            INDEX_STATS_COMMAND => {
+                let classes = self.context.classes.read().await;
+                let stats = json!({
+                    "classes": classes.len(),
+                    "fields": classes.iter().map(|c| c.fields.len()).sum::<usize>(),
+                    "prototypes": self.context.prototypes.read().await.len(),
+                    "localeKeys": self.context.locales.read().await.len(),
+                    "strings": intern::stats(),
+                    "timings": self.timings.summary(),
+                });
+
+                Ok(Some(stats))
+            }
+            #[cfg(feature = "fluent")]
+            UNUSED_LOCALE_KEYS_COMMAND => {
+                let mut keys = UnusedLocaleKeys::new(self.context.clone()).find().await;
+                for key in keys.iter_mut() {
+                    let uri = key.location.uri.clone();
+                    self.convert_positions(&uri, key, Direction::ToClient).await;
+                }
+
+                Ok(serde_json::to_value(keys).ok())
+            }
+            command => Err(Error::invalid_params(format!("Unknown command: {command}"))),
+        }
+    }
+
     async fn shutdown(&self) -> Result<()> {
+        if let Some(task) = self.status_task.write().await.take() {
+            task.abort();
+        }
         Ok(())
     }
 }
@@ -0,0 +1,402 @@
+use crate::{
+    backend::Context,
+    diagnostic::{yaml::YamlDiagnostics, Diagnostics},
+    parse::{
+        common::{DefinitionIndex, Index},
+        file_groups, prototype_file_group,
+        structs::{fluent::FluentKeyKind, yaml::YamlPrototype},
+        FileGroup, ProjectParser,
+    },
+};
+use ropey::Rope;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+    sync::Arc,
+    time::Instant,
+};
+use tower_lsp::lsp_types::{DiagnosticSeverity, NumberOrString, Position, Range, Url};
+use tree_sitter::Parser;
+
+/// Indexes the project without an LSP client and returns the filled context.
+pub async fn index_project(root: &Path) -> Option<Arc<Context>> {
+    index_files(root, file_groups()).await
+}
+
+async fn index_files(root: &Path, groups: Vec<FileGroup>) -> Option<Arc<Context>> {
+    let root = root.canonicalize().ok()?;
+    let uri = Url::from_directory_path(root).ok()?;
+
+    let context = Arc::new(Context::default());
+    let parser = ProjectParser::new(uri, context.clone(), None);
+    parser.parse(groups).await;
+
+    Some(context)
+}
+
+/// The prototypes added, removed and changed between two snapshots of a project.
+#[derive(Debug, Default, Serialize)]
+pub struct PrototypeDiff {
+    pub added: Vec<PrototypeId>,
+    pub removed: Vec<PrototypeId>,
+    pub changed: Vec<PrototypeId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct PrototypeId {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+}
+
+impl From<&YamlPrototype> for PrototypeId {
+    fn from(proto: &YamlPrototype) -> Self {
+        Self {
+            kind: proto.prototype.clone(),
+            id: proto.id.clone(),
+        }
+    }
+}
+
+/// Parses the prototypes of both snapshots, e.g. two checkouts of the repository,
+/// and compares them by their type and id. A prototype is changed when the text of its
+/// definition differs, besides the blank lines, the comments and the trailing spaces,
+/// moving it to another file doesn't change it.
+pub async fn diff_prototypes(old: &Path, new: &Path) -> Option<PrototypeDiff> {
+    let old = index_files(old, vec![prototype_file_group()]).await?;
+    let new = index_files(new, vec![prototype_file_group()]).await?;
+    let old = definitions(&old).await;
+    let new = definitions(&new).await;
+
+    let mut diff = PrototypeDiff::default();
+    for (id, text) in &new {
+        match old.get(id) {
+            None => diff.added.push(id.clone()),
+            Some(old_text) if old_text != text => diff.changed.push(id.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|id| !new.contains_key(*id))
+        .cloned()
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    Some(diff)
+}
+
+/// Prints the difference of the prototypes of two snapshots as JSON
+/// and returns the process exit code.
+pub async fn print_prototype_diff(old: &Path, new: &Path) -> i32 {
+    let Some(diff) = diff_prototypes(old, new).await else {
+        eprintln!("Failed to index {} and {}", old.display(), new.display());
+        return 2;
+    };
+
+    println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+    0
+}
+
+/// The normalized text of the definitions of the indexed prototypes.
+async fn definitions(context: &Context) -> HashMap<PrototypeId, String> {
+    let prototypes = context.prototypes.read().await;
+    let mut files = HashMap::<&Path, Vec<&YamlPrototype>>::new();
+    for proto in prototypes.iter() {
+        files.entry(&proto.index().0).or_default().push(proto);
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_yaml::language()).unwrap();
+
+    let mut definitions = HashMap::new();
+    for (path, protos) in files {
+        let Ok(src) = context.fs.read_to_string(path) else {
+            tracing::warn!("Failed to read the file {}", path.display());
+            continue;
+        };
+        let Some(tree) = parser.parse(&src, None) else {
+            continue;
+        };
+
+        for proto in protos {
+            let Some(range) = proto.index().1 else {
+                continue;
+            };
+            let mut node = tree
+                .root_node()
+                .descendant_for_byte_range(range.start_byte, range.end_byte);
+            while let Some(n) = node.filter(|n| n.kind() != "block_sequence_item") {
+                node = n.parent();
+            }
+            let Some(text) = node.and_then(|n| n.utf8_text(src.as_bytes()).ok()) else {
+                continue;
+            };
+
+            let text = text
+                .lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty() && !line.trim_start().starts_with('#'))
+                .collect::<Vec<_>>()
+                .join("\n");
+            definitions.insert(PrototypeId::from(proto), text);
+        }
+    }
+
+    definitions
+}
+
+pub async fn bench_parse(root: &Path) {
+    let start = Instant::now();
+    let Some(context) = index_project(root).await else {
+        eprintln!("Failed to index {}", root.display());
+        return;
+    };
+    let elapsed = start.elapsed();
+
+    println!("Indexed {} in {:.2?}", root.display(), elapsed);
+    println!("  classes:    {}", context.classes.read().await.len());
+    println!("  prototypes: {}", context.prototypes.read().await.len());
+    println!("  locales:    {}", context.locales.read().await.len());
+}
+
+#[derive(Serialize)]
+struct Finding {
+    file: String,
+    range: Range,
+    severity: &'static str,
+    code: Option<String>,
+    message: String,
+}
+
+/// The path of the file relative to the root with `/` separators, so that the JSON
+/// is the same on every platform, e.g. `Content.Shared/Tools/ToolComponent.cs`.
+fn relative_path(root: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    path.to_string_lossy().replace(MAIN_SEPARATOR, "/")
+}
+
+/// Runs the diagnostics passes over every prototype file of the project,
+/// prints the findings as JSON and returns the process exit code.
+pub async fn check(root: &Path) -> i32 {
+    let Some(context) = index_project(root).await else {
+        eprintln!("Failed to index {}", root.display());
+        return 2;
+    };
+
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut findings = vec![];
+
+    for path in prototype_files(&root) {
+        let rope = match context.fs.read_to_string(&path) {
+            Ok(text) => Rope::from_str(&text),
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        let diagnostics = YamlDiagnostics::new(context.clone(), path.clone(), &rope).diagnostics();
+        findings.extend(diagnostics.into_iter().map(|d| Finding {
+            file: relative_path(&root, &path),
+            range: d.range,
+            severity: match d.severity {
+                Some(DiagnosticSeverity::ERROR) => "error",
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                Some(DiagnosticSeverity::INFORMATION) => "information",
+                _ => "hint",
+            },
+            code: match d.code {
+                Some(NumberOrString::String(code)) => Some(code),
+                Some(NumberOrString::Number(code)) => Some(code.to_string()),
+                None => None,
+            },
+            message: d.message,
+        }));
+    }
+
+    println!("{}", serde_json::to_string_pretty(&findings).unwrap());
+
+    if findings.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+/// The index as it is written by `robust-lsp index`, for the scripts that don't speak LSP.
+#[derive(Serialize)]
+struct IndexDump {
+    classes: Vec<ClassEntry>,
+    prototypes: Vec<PrototypeEntry>,
+    locales: Vec<LocaleEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClassEntry {
+    name: String,
+    base: Vec<String>,
+    attributes: Vec<String>,
+    fields: Vec<FieldEntry>,
+    #[serde(flatten)]
+    location: Option<EntryLocation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldEntry {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    /// The name of the field in YAML, only for the `[DataField]`s.
+    yaml_name: Option<String>,
+    #[serde(flatten)]
+    location: Option<EntryLocation>,
+}
+
+#[derive(Serialize)]
+struct PrototypeEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+    parents: Vec<String>,
+    components: Vec<String>,
+    #[serde(flatten)]
+    location: Option<EntryLocation>,
+}
+
+#[derive(Serialize)]
+struct LocaleEntry {
+    key: String,
+    term: bool,
+    locale: String,
+    text: String,
+    #[serde(flatten)]
+    location: Option<EntryLocation>,
+}
+
+#[derive(Serialize)]
+struct EntryLocation {
+    file: String,
+    range: Option<Range>,
+}
+
+/// Indexes the project and writes the classes, prototypes and locale keys
+/// with their locations as JSON, to the output file or to stdout.
+pub async fn export_index(root: &Path, output: Option<&Path>) -> i32 {
+    let Some(context) = index_project(root).await else {
+        eprintln!("Failed to index {}", root.display());
+        return 2;
+    };
+
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let location = |index: &DefinitionIndex| {
+        let DefinitionIndex(path, range) = index;
+        (!path.as_os_str().is_empty()).then(|| EntryLocation {
+            file: relative_path(&root, path),
+            range: range.map(|r| {
+                Range::new(
+                    Position::new(r.start_point.row as u32, r.start_point.column as u32),
+                    Position::new(r.end_point.row as u32, r.end_point.column as u32),
+                )
+            }),
+        })
+    };
+
+    let mut classes = context
+        .classes
+        .read()
+        .await
+        .iter()
+        .map(|class| ClassEntry {
+            name: class.name.clone(),
+            base: class.base.clone(),
+            attributes: class
+                .attributes
+                .attributes
+                .iter()
+                .map(|a| a.name.to_string())
+                .collect(),
+            fields: class
+                .fields
+                .iter()
+                .map(|field| FieldEntry {
+                    name: field.name.clone(),
+                    type_name: field.type_name.to_string(),
+                    yaml_name: field
+                        .attributes
+                        .contains("DataField")
+                        .then(|| field.get_data_field_name()),
+                    location: location(field.index()),
+                })
+                .collect(),
+            location: location(class.index()),
+        })
+        .collect::<Vec<_>>();
+    classes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut prototypes = context
+        .prototypes
+        .read()
+        .await
+        .iter()
+        .map(|proto| PrototypeEntry {
+            kind: proto.prototype.clone(),
+            id: proto.id.clone(),
+            parents: proto.parents.clone(),
+            components: proto.components.clone(),
+            location: location(proto.index()),
+        })
+        .collect::<Vec<_>>();
+    prototypes.sort_by(|a, b| (&a.kind, &a.id).cmp(&(&b.kind, &b.id)));
+
+    let mut locales = context
+        .locales
+        .read()
+        .await
+        .iter()
+        .map(|key| LocaleEntry {
+            key: key.key.clone(),
+            term: key.kind == FluentKeyKind::Term,
+            locale: key.locale.clone(),
+            text: key.value.clone(),
+            location: location(key.index()),
+        })
+        .collect::<Vec<_>>();
+    locales.sort_by(|a, b| (&a.locale, &a.key, a.term).cmp(&(&b.locale, &b.key, b.term)));
+
+    let dump = IndexDump {
+        classes,
+        prototypes,
+        locales,
+    };
+    let json = serde_json::to_string_pretty(&dump).unwrap();
+    match output {
+        Some(output) => match std::fs::write(output, json) {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("Failed to write {}: {err}", output.display());
+                2
+            }
+        },
+        None => {
+            println!("{json}");
+            0
+        }
+    }
+}
+
+fn prototype_files(root: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(root.join("Resources/Prototypes"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|e| e.into_path())
+        .filter(|p| {
+            p.is_file() && matches!(p.extension().and_then(|e| e.to_str()), Some("yml" | "yaml"))
+        })
+        .collect()
+}
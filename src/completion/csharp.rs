@@ -0,0 +1,136 @@
+use super::{Completion, CompletionResult};
+use crate::{
+    backend::Context,
+    parse::{csharp::COMPONENT_APIS, structs::csharp::Component},
+    utils::line_before,
+};
+use rayon::prelude::*;
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionList, CompletionResponse, CompletionTextEdit,
+    Position, Range, TextEdit,
+};
+
+/// Completes component class names in the type arguments of entity system APIs,
+/// e.g. `TryComp<|>`, so that quick edits don't need a C# language server.
+pub struct CsharpCompletion {
+    context: Arc<Context>,
+    position: Position,
+    src: String,
+}
+
+impl Completion for CsharpCompletion {
+    fn completion(&self) -> CompletionResult {
+        let before = line_before(&self.src, self.position)?;
+
+        let word = before
+            .rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        let (api, argument) = generic_api(&before[..before.len() - word.len()])?;
+        // Only the first argument of an event subscription is a component.
+        if api == "SubscribeLocalEvent" && argument > 0 {
+            return None;
+        }
+
+        let start = Position::new(
+            self.position.line,
+            self.position.character - word.len() as u32,
+        );
+        let range = Range::new(start, self.position);
+        let prefix = word.to_lowercase();
+
+        let lock = block_in_place(|| self.context.classes.blocking_read());
+        let mut names = lock
+            .par_iter()
+            .filter(|c| Component::try_from(*c).is_ok() || c.name.ends_with("Component"))
+            .filter(|c| c.name.to_lowercase().contains(&prefix))
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+        drop(lock);
+
+        names.sort_by(|a, b| {
+            let a_prefix = a.to_lowercase().starts_with(&prefix);
+            let b_prefix = b.to_lowercase().starts_with(&prefix);
+            b_prefix
+                .cmp(&a_prefix)
+                .then(a.len().cmp(&b.len()))
+                .then_with(|| a.cmp(b))
+        });
+        names.dedup();
+
+        let limit = block_in_place(|| self.context.config.blocking_read())
+            .completion
+            .max_items;
+        names.truncate(limit);
+
+        let items = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::CLASS),
+                detail: Some(format!("{api}<T>")),
+                sort_text: Some(format!("{i:04}")),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    new_text: name,
+                    range,
+                })),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        tracing::trace!("Items found: {}", items.len());
+
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        }))
+    }
+}
+
+impl CsharpCompletion {
+    pub fn new(context: Arc<Context>, position: Position, rope: &Rope) -> Self {
+        Self {
+            context,
+            position,
+            src: rope.to_string(),
+        }
+    }
+}
+
+/// Finds the entity system API whose type argument list is open at the end of the text,
+/// e.g. `TryComp` and `0` for `if (TryComp<`, with the index of the argument being typed.
+fn generic_api(text: &str) -> Option<(&str, usize)> {
+    let mut argument = 0;
+    let mut rest = text.trim_end();
+    loop {
+        if let Some(before) = rest.strip_suffix('<') {
+            rest = before.trim_end();
+            break;
+        }
+
+        // The previous arguments must be plain type names, e.g. `Foo, Bar.Baz,`.
+        rest = rest.strip_suffix(',')?.trim_end();
+        let name_start = name_start(rest, |c| c.is_alphanumeric() || c == '_' || c == '.');
+        if name_start == rest.len() {
+            return None;
+        }
+        rest = rest[..name_start].trim_end();
+        argument += 1;
+    }
+
+    let api = &rest[name_start(rest, |c| c.is_alphanumeric() || c == '_')..];
+    COMPONENT_APIS.contains(&api).then_some((api, argument))
+}
+
+/// The byte index of the name at the end of the text, right after the last character that
+/// isn't a part of it. The character may take more than a byte, e.g. a combining mark.
+fn name_start(text: &str, is_name: impl Fn(char) -> bool) -> usize {
+    text.char_indices()
+        .rev()
+        .find(|&(_, c)| !is_name(c))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
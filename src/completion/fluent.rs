@@ -0,0 +1,221 @@
+use super::{Completion, CompletionResult};
+use crate::{
+    backend::Context,
+    completion::matcher::Matcher,
+    parse::structs::fluent::{builtin_function, is_identifier, FluentKeyKind, BUILTIN_FUNCTIONS},
+    utils::line_before,
+};
+use rayon::prelude::*;
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionList, CompletionResponse, CompletionTextEdit,
+    Documentation, Position, Range, TextEdit,
+};
+
+pub struct FluentCompletion {
+    context: Arc<Context>,
+    position: Position,
+    src: String,
+}
+
+impl Completion for FluentCompletion {
+    fn completion(&self) -> CompletionResult {
+        let before = line_before(&self.src, self.position)?;
+
+        // A key typed at the start of the line, e.g. `ent-Crow`.
+        if is_identifier(before) {
+            return self.entity_keys_completion(before);
+        }
+
+        // Terms and functions can only be referenced inside a placeable of a value,
+        // so the cursor must be after an unclosed `{`.
+        let placeable = before.rfind('{')?;
+        if before[placeable..].contains('}') {
+            return None;
+        }
+
+        // The arguments of an unclosed call, e.g. `{ NUMBER($count, mini`.
+        let inside = &before[placeable + 1..];
+        if let Some(call) = inside.rfind('(').filter(|i| !inside[*i..].contains(')')) {
+            return self.named_arguments_completion(&inside[..call], &inside[call + 1..]);
+        }
+
+        let word = before
+            .rsplit(|c: char| c.is_whitespace() || c == '{')
+            .next()
+            .unwrap_or_default();
+
+        let start = Position::new(
+            self.position.line,
+            self.position.character - word.len() as u32,
+        );
+        let range = Range::new(start, self.position);
+
+        let mut items = match word.chars().next() {
+            None => [self.functions(word, range), self.terms(word, range)].concat(),
+            Some('-') => self.terms(word, range),
+            Some(c) if c.is_ascii_uppercase() => self.functions(word, range),
+            _ => return None,
+        };
+
+        let limit = block_in_place(|| self.context.config.blocking_read())
+            .completion
+            .max_items;
+        items.truncate(limit);
+
+        tracing::trace!("Items found: {}", items.len());
+
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        }))
+    }
+}
+
+impl FluentCompletion {
+    pub fn new(context: Arc<Context>, position: Position, rope: &Rope) -> Self {
+        Self {
+            context,
+            position,
+            src: rope.to_string(),
+        }
+    }
+
+    /// Offers the keys named after the entity ids when the typed key has the prefix
+    /// of `diagnostics.entityLocale`, e.g. `ent-Crowbar` for `ent-Cr`.
+    fn entity_keys_completion(&self, typed: &str) -> CompletionResult {
+        let prefix = block_in_place(|| self.context.config.blocking_read())
+            .diagnostics
+            .entity_locale
+            .prefix
+            .clone();
+        let typed_id = typed.strip_prefix(prefix.as_str())?;
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let ids = lock
+            .par_iter()
+            // Abstract entities are never spawned, so they aren't named.
+            .filter(|p| p.prototype == "entity" && !p.is_abstract())
+            .map(|p| p.id.as_str())
+            .collect::<Vec<_>>();
+
+        let range = Range::new(Position::new(self.position.line, 0), self.position);
+        let items = Matcher::from_context(&self.context)
+            .rank(typed_id, ids, |id| *id)
+            .into_iter()
+            .map(|id| {
+                let key = format!("{prefix}{id}");
+                CompletionItem {
+                    label: key.clone(),
+                    kind: Some(CompletionItemKind::CONSTANT),
+                    detail: Some("entity".to_owned()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        new_text: key,
+                        range,
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        }))
+    }
+
+    fn terms(&self, word: &str, range: Range) -> Vec<CompletionItem> {
+        let prefix = word.trim_start_matches('-');
+
+        let lock = block_in_place(|| self.context.locales.blocking_read());
+        let mut items = lock
+            .names(FluentKeyKind::Term)
+            .filter(|key| key.starts_with(prefix))
+            .map(|key| {
+                let label = format!("-{key}");
+                CompletionItem {
+                    label: label.clone(),
+                    kind: Some(CompletionItemKind::CONSTANT),
+                    detail: Some("term".to_owned()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        new_text: label,
+                        range,
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        items.sort_by(|a, b| a.label.cmp(&b.label));
+        items
+    }
+
+    fn functions(&self, word: &str, range: Range) -> Vec<CompletionItem> {
+        BUILTIN_FUNCTIONS
+            .iter()
+            .filter(|f| f.name.starts_with(word))
+            .map(|f| CompletionItem {
+                label: f.name.to_owned(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(f.signature.to_owned()),
+                documentation: Some(Documentation::String(f.documentation.to_owned())),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    new_text: f.name.to_owned(),
+                    range,
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Offers the named arguments of a built-in function that aren't specified yet,
+    /// `callee` is the text of the placeable before the `(` and `args` the text after it.
+    fn named_arguments_completion(&self, callee: &str, args: &str) -> CompletionResult {
+        let name = callee.split_whitespace().next_back()?;
+        let function = builtin_function(name)?;
+
+        let word = args
+            .rsplit(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or_default();
+        // Values are typed after the name of the argument, positional ones are variables.
+        if !word.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+
+        let specified = args
+            .split(',')
+            .filter_map(|arg| arg.split_once(':'))
+            .map(|(name, _)| name.trim())
+            .collect::<Vec<_>>();
+
+        let start = Position::new(
+            self.position.line,
+            self.position.character - word.len() as u32,
+        );
+        let range = Range::new(start, self.position);
+
+        let items = function
+            .named_arguments
+            .iter()
+            .filter(|arg| arg.starts_with(word) && !specified.contains(arg))
+            .map(|arg| CompletionItem {
+                label: arg.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(function.signature.to_owned()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    new_text: format!("{arg}: "),
+                    range,
+                })),
+                ..Default::default()
+            })
+            .collect();
+
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        }))
+    }
+}
@@ -0,0 +1,200 @@
+use crate::backend::Context;
+use serde::Deserialize;
+use std::path::Path;
+use tokio::task::block_in_place;
+
+/// Similarity required for a Jaro-Winkler match that is neither a prefix nor a substring.
+const JARO_WINKLER_THRESHOLD: f64 = 0.8;
+
+/// The score added to a candidate defined in the folder of the edited file,
+/// less the farther it is. Enough to reorder close matches, not to put a worse kind
+/// of match, e.g. a substring, before a prefix.
+const PROXIMITY_WEIGHT: f64 = 0.5;
+
+/// How the typed text is matched against the completion candidates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchAlgorithm {
+    /// Candidates that start with the text.
+    Prefix,
+    /// Candidates that contain the text, the ones that start with it first.
+    Substring,
+    /// Prefixes and substrings first, then candidates similar by Jaro-Winkler.
+    #[default]
+    JaroWinkler,
+    /// Candidates that contain the characters of the text in order, like skim and fzf,
+    /// e.g. `BaIt` matches `BaseItem`.
+    Skim,
+}
+
+/// Matches and ranks completion candidates against the typed text
+/// with the algorithm and the number of items from the completion settings.
+#[derive(Debug, Clone, Copy)]
+pub struct Matcher {
+    algorithm: MatchAlgorithm,
+    limit: usize,
+}
+
+impl Matcher {
+    pub fn new(algorithm: MatchAlgorithm, limit: usize) -> Self {
+        Self { algorithm, limit }
+    }
+
+    pub fn from_context(context: &Context) -> Self {
+        let config = block_in_place(|| context.config.blocking_read());
+        Self::new(config.completion.matcher, config.completion.max_items)
+    }
+
+    /// The maximum number of items in a completion list.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Scores how well the candidate matches the text, the higher the better,
+    /// `None` if it doesn't match at all. An empty text matches everything equally.
+    pub fn score(&self, text: &str, candidate: &str) -> Option<f64> {
+        if text.is_empty() {
+            return Some(0.0);
+        }
+
+        let (lower_text, lower_candidate) = (text.to_lowercase(), candidate.to_lowercase());
+        let coverage = text.chars().count() as f64 / candidate.chars().count().max(1) as f64;
+
+        match self.algorithm {
+            MatchAlgorithm::Prefix => lower_candidate.starts_with(&lower_text).then_some(coverage),
+            MatchAlgorithm::Substring => {
+                if lower_candidate.starts_with(&lower_text) {
+                    Some(1.0 + coverage)
+                } else if lower_candidate.contains(&lower_text) {
+                    Some(coverage)
+                } else {
+                    None
+                }
+            }
+            MatchAlgorithm::JaroWinkler => {
+                let score = strsim::jaro_winkler(text, candidate);
+                if lower_candidate.starts_with(&lower_text) {
+                    Some(2.0 + score)
+                } else if lower_candidate.contains(&lower_text) {
+                    Some(1.0 + score)
+                } else if score > JARO_WINKLER_THRESHOLD {
+                    Some(score)
+                } else {
+                    None
+                }
+            }
+            MatchAlgorithm::Skim => skim_score(&lower_text, candidate),
+        }
+    }
+
+    /// Scores the candidate like [`Self::score`], adding how close the file it is defined in
+    /// is to the edited one, mappers mostly reference the content next to what they edit.
+    pub fn score_near(
+        &self,
+        text: &str,
+        candidate: &str,
+        origin: &Path,
+        path: &Path,
+    ) -> Option<f64> {
+        Some(self.score(text, candidate)? + PROXIMITY_WEIGHT * proximity(origin, path))
+    }
+
+    /// Keeps the items whose key matches the text, the best matches first
+    /// and the equal ones by the key, cut to the limit.
+    pub fn rank<T>(
+        &self,
+        text: &str,
+        items: impl IntoIterator<Item = T>,
+        key: impl Fn(&T) -> &str,
+    ) -> Vec<T> {
+        self.rank_by(items, |item| self.score(text, key(item)), &key)
+    }
+
+    /// Ranks the items like [`Self::rank`], the ones defined closer to the origin file first
+    /// among similar matches, see [`Self::score_near`].
+    pub fn rank_near<T>(
+        &self,
+        text: &str,
+        origin: &Path,
+        items: impl IntoIterator<Item = T>,
+        key: impl Fn(&T) -> &str,
+        path: impl Fn(&T) -> &Path,
+    ) -> Vec<T> {
+        self.rank_by(
+            items,
+            |item| self.score_near(text, key(item), origin, path(item)),
+            &key,
+        )
+    }
+
+    fn rank_by<T>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        score: impl Fn(&T) -> Option<f64>,
+        key: impl Fn(&T) -> &str,
+    ) -> Vec<T> {
+        let mut scored = items
+            .into_iter()
+            .filter_map(|item| Some((score(&item)?, item)))
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.total_cmp(a_score).then_with(|| key(a).cmp(key(b)))
+        });
+        scored.truncate(self.limit);
+
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// From 1 for a file in the same folder as the origin down to 0, halving with every folder
+/// between them, e.g. `Entities/Objects/Tools` is two folders from `Entities/Objects/Misc`.
+fn proximity(origin: &Path, path: &Path) -> f64 {
+    let (Some(origin), Some(path)) = (origin.parent(), path.parent()) else {
+        return 0.0;
+    };
+    let common = origin
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return 0.0;
+    }
+
+    let distance = origin.components().count() + path.components().count() - 2 * common;
+    0.5_f64.powi(distance as i32)
+}
+
+/// Finds the characters of the text in the candidate in order, rewarding the ones
+/// that follow each other or start a word, e.g. `I` of `BaseItem` or of `base_item`.
+fn skim_score(lower_text: &str, candidate: &str) -> Option<f64> {
+    let chars = candidate.chars().collect::<Vec<_>>();
+
+    let mut score = 0.0;
+    let mut last_match = None;
+    let mut position = 0;
+    for ch in lower_text.chars() {
+        let found = (position..chars.len()).find(|&i| chars[i].to_lowercase().eq([ch]))?;
+
+        let starts_word = found == 0
+            || !chars[found - 1].is_alphanumeric()
+            || (chars[found].is_uppercase() && chars[found - 1].is_lowercase());
+
+        score += 1.0;
+        if starts_word {
+            score += 2.0;
+        }
+        match last_match {
+            Some(last) if last + 1 == found => score += 1.5,
+            Some(last) => score -= 0.1 * (found - last - 1) as f64,
+            None => score -= 0.1 * found as f64,
+        }
+
+        last_match = Some(found);
+        position = found + 1;
+    }
+
+    // Shorter candidates are closer to what is typed.
+    Some(score - 0.01 * chars.len() as f64)
+}
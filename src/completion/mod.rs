@@ -1,3 +1,8 @@
+#[cfg(feature = "csharp")]
+pub mod csharp;
+#[cfg(feature = "fluent")]
+pub mod fluent;
+pub mod matcher;
 pub mod yml;
 
 pub(self) type CompletionResult = Option<tower_lsp::lsp_types::CompletionResponse>;
@@ -1,25 +1,36 @@
 use super::{Completion, CompletionResult};
 use crate::{
     backend::Context,
-    parse::structs::{
-        csharp::{Component, CsharpClassField, Prototype, ReflectionManager},
-        json::RsiMeta,
+    completion::matcher::Matcher,
+    parse::{
+        common::Index,
+        structs::{
+            csharp::{Component, CsharpClass, CsharpClassField, Prototype, ReflectionManager},
+            fluent::FluentKeyKind,
+            yaml::YamlPrototype,
+        },
     },
-    utils::{block, get_columns},
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::{block, get_columns, resource_path, RESOURCE_PATH_SEPARATORS},
 };
 use rayon::prelude::*;
 use ropey::Rope;
-use std::{fs, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use stringcase::camel_case;
 use tokio::task::block_in_place;
 use tower_lsp::lsp_types::{
     self, CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionList,
-    CompletionResponse, CompletionTextEdit, Position, Range, TextEdit,
+    CompletionResponse, CompletionTextEdit, InsertTextFormat, Position, Range, TextEdit,
 };
-use tracing::instrument;
 use tree_sitter::{Node, Parser, Point, Tree};
 
 const SPRITES_RES_PATH: &str = "Resources/Textures/";
+/// Prototype fields whose values are offered from the ones already used in other prototypes.
+static METADATA_FIELDS: &[&str] = &["suffix", "categories", "placement.mode"];
 
 pub struct YamlCompletion {
     context: Arc<Context>,
@@ -27,10 +38,20 @@ pub struct YamlCompletion {
     src: String,
     tree: Tree,
     root_path: PathBuf,
+    /// The edited file, the prototypes defined close to it are offered first.
+    path: PathBuf,
 }
 
 impl Completion for YamlCompletion {
     fn completion(&self) -> CompletionResult {
+        // Tags are looked up at the cursor itself, the whole line would find the mapping pair.
+        if let Some(tag) = self.find_tag() {
+            return self.tag_completion(tag);
+        }
+        if let Some(result) = self.new_file_completion() {
+            return Some(result);
+        }
+
         let (start_col, end_col) = get_columns(self.position, &self.src);
         let start_point = Point::new(self.position.line as usize, start_col);
         let end_point = Point::new(self.position.line as usize, end_col);
@@ -45,7 +66,7 @@ impl Completion for YamlCompletion {
             tracing::trace!("Found node: {node:#?}");
             if node.kind() == "string_scalar" {
                 for _ in 0..3 {
-                    node = node.parent().unwrap();
+                    node = node.parent()?;
                 }
             }
             if node.kind() == "ERROR" {
@@ -78,7 +99,13 @@ impl Completion for YamlCompletion {
 }
 
 impl YamlCompletion {
-    pub fn new(context: Arc<Context>, position: Position, src: &Rope, root_path: PathBuf) -> Self {
+    pub fn new(
+        context: Arc<Context>,
+        position: Position,
+        src: &Rope,
+        root_path: PathBuf,
+        path: PathBuf,
+    ) -> Self {
         let src = src.to_string();
 
         let mut parser = Parser::new();
@@ -91,6 +118,7 @@ impl YamlCompletion {
             src,
             tree,
             root_path,
+            path,
         }
     }
 
@@ -113,6 +141,19 @@ impl YamlCompletion {
         Some(node)
     }
 
+    fn find_tag(&self) -> Option<Node<'_>> {
+        let point = Point::new(
+            self.position.line as usize,
+            (self.position.character as usize).saturating_sub(1),
+        );
+        let node = self
+            .tree
+            .root_node()
+            .named_descendant_for_point_range(point, point)?;
+
+        (node.kind() == "tag").then_some(node)
+    }
+
     fn find_block_mapping<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
         debug_assert_eq!(node.kind(), "block_sequence");
 
@@ -146,20 +187,6 @@ impl YamlCompletion {
         }
     }
 
-    fn get_nesting(&self, node: &Node) -> usize {
-        let mut nest = 0;
-
-        let mut parent = node.parent();
-        while let Some(node) = parent {
-            if node.kind() == "block_node" {
-                nest += 1;
-            }
-            parent = node.parent();
-        }
-
-        nest
-    }
-
     fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
         debug_assert_eq!(node.kind(), "block_mapping");
 
@@ -226,7 +253,11 @@ impl YamlCompletion {
     }
 
     fn get_specified_parents(&self, node: &Node) -> Option<Vec<&str>> {
-        debug_assert!(node.kind() == "block_mapping_pair" || node.kind() == "flow_sequence");
+        debug_assert!(
+            node.kind() == "block_mapping_pair"
+                || node.kind() == "flow_sequence"
+                || node.kind() == "block_sequence"
+        );
 
         let mut parents = Vec::new();
         match node.kind() {
@@ -244,6 +275,15 @@ impl YamlCompletion {
                 }
                 Some(parents)
             }
+            "block_sequence" => {
+                for i in 0..node.named_child_count() {
+                    let item = node.named_child(i).unwrap();
+                    if let Some(value_node) = item.named_child(0) {
+                        parents.push(value_node.utf8_text(self.src.as_bytes()).ok()?);
+                    }
+                }
+                Some(parents)
+            }
             _ => None,
         }
     }
@@ -251,45 +291,63 @@ impl YamlCompletion {
     fn block_sequence_item(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_sequence_item");
 
-        if self.get_nesting(&node) > 4 {
-            None
-        } else {
-            Some(CompletionResponse::Array(vec![CompletionItem {
-                label: "type".to_owned(),
-                kind: Some(CompletionItemKind::FIELD),
-                detail: Some("string".to_owned()),
-                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                    range: {
-                        let position = Position::new(
-                            self.position.line,
-                            node.start_position().column as u32 + 2,
-                        );
-                        lsp_types::Range {
-                            start: position,
-                            end: position,
-                        }
-                    },
-                    new_text: format!("type:"),
-                })),
-                ..Default::default()
-            }]))
+        if let Some(result) = self.metadata_completion(node) {
+            return Some(result);
+        }
+        if let Some(result) = self.prototype_parents_completion(node) {
+            return Some(result);
+        }
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        match resolver.resolve(node.parent()?)? {
+            SchemaType::Prototypes | SchemaType::ComponentRegistry => {
+                Some(CompletionResponse::Array(vec![CompletionItem {
+                    label: "type".to_owned(),
+                    kind: Some(CompletionItemKind::FIELD),
+                    detail: Some("string".to_owned()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range: {
+                            let position = Position::new(
+                                self.position.line,
+                                node.start_position().column as u32 + 2,
+                            );
+                            lsp_types::Range {
+                                start: position,
+                                end: position,
+                            }
+                        },
+                        new_text: format!("type:"),
+                    })),
+                    ..Default::default()
+                }]))
+            }
+            _ => None,
         }
     }
 
     fn block_mapping_pair(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let nest = self.get_nesting(&node);
         let key_node = node.child_by_field_name("key")?;
         let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+
+        if let Some(result) = self.metadata_completion(node) {
+            return Some(result);
+        }
 
         if key_name == "type" {
-            match nest {
-                2 => return self.prototype_completion(node, key_node),
-                4 => return self.components_completion(node, key_node),
+            match resolver.resolve_container(node)? {
+                SchemaType::Prototypes => self.prototype_completion(node, key_node),
+                SchemaType::ComponentRegistry => self.components_completion(node, key_node),
                 _ => None,
             }
-        } else if key_name == "parent" && nest == 2 {
+        } else if key_name == "parent"
+            && matches!(
+                resolver.resolve(node.parent()?),
+                Some(SchemaType::Prototype(_))
+            )
+        {
             self.prototype_parents_completion(node)
         } else {
             self.object_field_type_completion(node)
@@ -299,21 +357,29 @@ impl YamlCompletion {
     fn block_mapping(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping");
 
-        if self.get_nesting(&node) > 2 {
-            self.component_fields_completion(node)
-        } else {
-            self.prototype_fields_completion(node)
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        match resolver.resolve(node)? {
+            SchemaType::Prototype(proto) => self.prototype_fields_completion(node, &proto),
+            SchemaType::Component(comp) => self.data_fields_completion(node, &comp),
+            SchemaType::DataDefinition(class) => self.data_fields_completion(node, &class),
+            SchemaType::Dictionary(key_type, _) => {
+                let prototype_name = resolver.resolve_key_prototype(node, &key_type)?;
+                self.dictionary_keys_completion(node, prototype_name)
+            }
+            _ => None,
         }
     }
 
     fn flow_node(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "flow_node");
-        self.prototype_parents_completion(node)
+        self.metadata_completion(node)
+            .or_else(|| self.prototype_parents_completion(node))
     }
 
     fn flow_sequence(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "flow_sequence");
-        self.prototype_parents_completion(node)
+        self.metadata_completion(node)
+            .or_else(|| self.prototype_parents_completion(node))
     }
 
     fn object_field_type_completion(&self, node: Node) -> CompletionResult {
@@ -321,47 +387,106 @@ impl YamlCompletion {
 
         let key_node = node.child_by_field_name("key")?;
         let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-        let mapping_node = node.parent()?;
-        let obj_name = self.get_object_name(&mapping_node)?;
-        let reflection = ReflectionManager::new(self.context.classes.clone());
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let owner = resolver.resolve(node.parent()?)?;
+        let reflection = ReflectionManager::new(&self.context);
 
-        match self.get_nesting(&node) {
-            2 => self.prototype_field_type_completion(node, reflection, obj_name, key_name),
-            4 => self.component_field_type_completion(node, reflection, obj_name, key_name),
+        if let SchemaType::Dictionary(_, type_name) = &owner {
+            let field = CsharpClassField::new_empty(key_name, type_name);
+            return self.field_type_completion(node, field, reflection);
+        }
+
+        let class = owner.class()?;
+        let field = block(|| reflection.get_fields(class))
+            .into_iter()
+            .find(|f| f.get_data_field_name() == key_name)?;
+
+        let comp_name = match &owner {
+            SchemaType::Component(comp) => Some(comp.get_component_name()),
             _ => None,
+        };
+
+        match (comp_name.as_deref(), field.get_data_field_name().as_str()) {
+            (Some("Sprite" | "Icon"), "sprite") => self.sprite_field_type_completion(node),
+            (Some("Sprite"), "state") => self.state_field_type_completion(node),
+            (Some("Construction"), "graph") => self.construction_graph_completion(node),
+            (Some("Construction"), "node") => self.construction_node_completion(node),
+            _ => self.field_type_completion(node, field, reflection),
         }
     }
 
-    #[instrument(skip_all, ret)]
-    fn component_field_type_completion(
-        &self,
-        node: Node,
-        reflection: ReflectionManager,
-        object_name: &str,
-        key_name: &str,
-    ) -> CompletionResult {
+    /// Offers the construction graphs, the field is often a plain string
+    /// checked by a custom serializer rather than a `ProtoId`.
+    fn construction_graph_completion(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let comp = block(|| reflection.get_component_by_name(object_name))?;
-        let field = block(|| reflection.get_fields(&comp))
+        let value = match node.child_by_field_name("value") {
+            Some(value_node) => value_node.utf8_text(self.src.as_bytes()).ok()?,
+            None => "",
+        };
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let graphs = lock
+            .par_iter()
+            .filter(|p| p.prototype == "constructionGraph")
+            .collect::<Vec<_>>();
+
+        let items = Matcher::from_context(&self.context)
+            .rank_near(
+                value,
+                &self.path,
+                graphs,
+                |p| &p.id,
+                |p| self.definition_path(p),
+            )
             .into_iter()
-            .find(|f| f.get_data_field_name() == key_name)?;
+            .map(|p| self.prototype_item(p, "constructionGraph"))
+            .collect::<Vec<_>>();
 
-        match (
-            comp.get_component_name().as_str(),
-            field.get_data_field_name().as_str(),
-        ) {
-            ("Sprite" | "Icon", "sprite") => self.sprite_field_type_completion(node),
-            ("Sprite", "state") => self.state_field_type_completion(node),
-            _ => self.field_type_completion(node, field, reflection),
-        }
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// Offers the nodes of the construction graph set in the `graph` field next to it.
+    fn construction_node_completion(&self, node: Node) -> CompletionResult {
+        debug_assert_eq!(node.kind(), "block_mapping_pair");
+
+        let graph = self
+            .get_field(&node.parent()?, "graph")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        let value = match node.child_by_field_name("value") {
+            Some(value_node) => value_node.utf8_text(self.src.as_bytes()).ok()?,
+            None => "",
+        };
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let nodes = lock
+            .get(&YamlPrototype::dummy("constructionGraph", graph))?
+            .graph_nodes()
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        let items = Matcher::from_context(&self.context)
+            .rank(value, nodes, |node| *node)
+            .into_iter()
+            .map(|node| CompletionItem {
+                label: node.to_owned(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some(graph.to_owned()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        Some(CompletionResponse::Array(items))
     }
 
     fn state_field_type_completion(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let sprites_folder = self.root_path.join(SPRITES_RES_PATH);
-        if !sprites_folder.exists() {
+        let sprites_folder = resource_path(&self.root_path, SPRITES_RES_PATH);
+        if !self.context.fs.exists(&sprites_folder) {
             return None;
         }
 
@@ -370,13 +495,14 @@ impl YamlCompletion {
             .child_by_field_name("value")?;
         let sprite_path = sprite_node.utf8_text(self.src.as_bytes()).ok()?;
 
+        let sprite_path = sprite_path.trim_end_matches(RESOURCE_PATH_SEPARATORS);
         if !sprite_path.ends_with(".rsi") {
             tracing::trace!("sprite path does not end with .rsi");
             return None;
         }
 
-        let path = sprites_folder.join(sprite_path);
-        if !path.exists() || !path.is_dir() {
+        let path = resource_path(&sprites_folder, sprite_path);
+        if !self.context.fs.is_dir(&path) {
             tracing::trace!("{path:?} does not exist");
             return None;
         }
@@ -384,18 +510,12 @@ impl YamlCompletion {
         let rsi_name = path.file_name()?.to_string_lossy().into_owned();
         let meta_path = path.join("meta.json");
 
-        if !meta_path.exists() || !meta_path.is_file() {
+        if !self.context.fs.is_file(&meta_path) {
             tracing::trace!("{meta_path:?} does not exist");
             return None;
         }
 
-        let meta: RsiMeta = match serde_json::from_reader(fs::File::open(&meta_path).ok()?) {
-            Ok(meta) => meta,
-            Err(err) => {
-                tracing::error!("Failed to read {meta_path:?}: {err}");
-                return None;
-            }
-        };
+        let meta = self.context.rsi_meta.get(&*self.context.fs, &meta_path)?;
 
         let map = |s: String| CompletionItem {
             label: s,
@@ -407,23 +527,19 @@ impl YamlCompletion {
         let states = match node.child_by_field_name("value") {
             Some(value_node) => {
                 let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                let mut states = meta
-                    .states
-                    .into_iter()
-                    .map(|s| (strsim::jaro_winkler(value, &s.name), s.name))
-                    .filter(|(diff, _)| *diff > 0.6)
-                    .map(|(diff, s)| (diff, map(s)))
-                    .collect::<Vec<_>>();
+                let matcher = Matcher::from_context(&self.context);
 
-                states.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                states.reverse();
-
-                states.into_iter().map(|(_, s)| s).collect::<Vec<_>>()
+                matcher
+                    .rank(value, meta.states.clone(), |s| &s.name)
+                    .into_iter()
+                    .map(|s| map(s.name))
+                    .collect::<Vec<_>>()
             }
             None => {
                 let states = meta
                     .states
-                    .into_iter()
+                    .iter()
+                    .cloned()
                     .map(|s| map(s.name))
                     .collect::<Vec<_>>();
 
@@ -441,32 +557,37 @@ impl YamlCompletion {
     fn sprite_field_type_completion(&self, node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let sprites_folder = self.root_path.join(SPRITES_RES_PATH);
-        if !sprites_folder.exists() {
+        let sprites_folder = resource_path(&self.root_path, SPRITES_RES_PATH);
+        if !self.context.fs.exists(&sprites_folder) {
             return None;
         }
 
         let paths = match node.child_by_field_name("value") {
             Some(value_node) => {
                 let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                if value.ends_with('/') {
-                    let path = sprites_folder.join(value);
-                    if !path.exists() || !path.is_dir() {
+                if value.ends_with(RESOURCE_PATH_SEPARATORS) {
+                    let path = resource_path(&sprites_folder, value);
+                    if !self.context.fs.is_dir(&path) {
                         tracing::trace!("{path:?} does not exist");
                         return None;
                     }
 
-                    let last = value.split('/').filter(|s| !s.is_empty()).last()?;
+                    let last = value
+                        .split(RESOURCE_PATH_SEPARATORS)
+                        .filter(|s| !s.is_empty())
+                        .last()?;
                     if last.ends_with(".rsi") {
                         tracing::trace!("{last} ends with .rsi");
                         return None;
                     }
 
-                    let paths = fs::read_dir(path)
+                    let paths = self
+                        .context
+                        .fs
+                        .read_dir(&path)
                         .ok()?
-                        .filter_map(Result::ok)
-                        .map(|f| {
-                            let path = f.path();
+                        .into_iter()
+                        .map(|path| {
                             let name = path
                                 .file_name()
                                 .unwrap_or_default()
@@ -489,7 +610,7 @@ impl YamlCompletion {
                     paths
                 } else {
                     let parts = value
-                        .split('/')
+                        .split(RESOURCE_PATH_SEPARATORS)
                         .filter(|s| !s.is_empty())
                         .collect::<Vec<_>>();
                     let last = parts.last()?.to_owned();
@@ -503,62 +624,53 @@ impl YamlCompletion {
                     let sprites_path = if parts_count == 1 {
                         sprites_folder
                     } else {
-                        sprites_folder
-                            .join(parts.into_iter().take(parts_count - 1).collect::<PathBuf>())
+                        resource_path(&sprites_folder, &parts[..parts_count - 1].join("/"))
                     };
-                    if !sprites_path.exists() || !sprites_path.is_dir() {
+                    if !self.context.fs.is_dir(&sprites_path) {
                         tracing::trace!("{sprites_path:?} does not exist");
                         return None;
                     }
 
-                    let mut paths = fs::read_dir(sprites_path)
+                    let names = self
+                        .context
+                        .fs
+                        .read_dir(&sprites_path)
                         .ok()?
-                        .filter_map(Result::ok)
-                        .map(|f| {
-                            let path = f.path();
-                            let name = path
-                                .file_name()
+                        .into_iter()
+                        .map(|path| {
+                            path.file_name()
                                 .unwrap_or_default()
                                 .to_string_lossy()
-                                .into_owned();
-                            (strsim::jaro_winkler(last, &name), name)
-                        })
-                        .filter(|(diff, _)| *diff > 0.6)
-                        .map(|(diff, name)| {
+                                .into_owned()
+                        });
+
+                    Matcher::from_context(&self.context)
+                        .rank(last, names, |name| name.as_str())
+                        .into_iter()
+                        .map(|name| {
                             let is_rsi = name.ends_with(".rsi");
-                            (
-                                diff,
-                                CompletionItem {
-                                    label: name.clone(),
-                                    kind: Some(if is_rsi {
-                                        CompletionItemKind::FILE
-                                    } else {
-                                        CompletionItemKind::FOLDER
-                                    }),
-                                    insert_text: Some(if is_rsi {
-                                        name
-                                    } else {
-                                        format!("{name}/")
-                                    }),
-                                    ..Default::default()
-                                },
-                            )
+                            CompletionItem {
+                                label: name.clone(),
+                                kind: Some(if is_rsi {
+                                    CompletionItemKind::FILE
+                                } else {
+                                    CompletionItemKind::FOLDER
+                                }),
+                                insert_text: Some(if is_rsi { name } else { format!("{name}/") }),
+                                ..Default::default()
+                            }
                         })
-                        .collect::<Vec<_>>();
-
-                    paths.sort_by_key(|p| (p.0 * 100.0) as u32);
-                    paths.reverse();
-                    paths.truncate(100);
-
-                    paths.into_iter().map(|(_, p)| p).collect::<Vec<_>>()
+                        .collect::<Vec<_>>()
                 }
             }
             None => {
-                let paths = fs::read_dir(sprites_folder)
+                let paths = self
+                    .context
+                    .fs
+                    .read_dir(&sprites_folder)
                     .ok()?
-                    .filter_map(Result::ok)
-                    .map(|f| {
-                        let path = f.path();
+                    .into_iter()
+                    .map(|path| {
                         let name = path
                             .file_name()
                             .unwrap_or_default()
@@ -575,7 +687,7 @@ impl YamlCompletion {
                         } else {
                             CompletionItem {
                                 label: name.clone(),
-                                kind: Some(if path.is_dir() {
+                                kind: Some(if self.context.fs.is_dir(&path) {
                                     CompletionItemKind::FOLDER
                                 } else {
                                     CompletionItemKind::FILE
@@ -601,23 +713,6 @@ impl YamlCompletion {
         }
     }
 
-    fn prototype_field_type_completion(
-        &self,
-        node: Node,
-        reflection: ReflectionManager,
-        object_name: &str,
-        key_name: &str,
-    ) -> CompletionResult {
-        debug_assert_eq!(node.kind(), "block_mapping_pair");
-
-        let prototype = block(|| reflection.get_prototype_by_name(object_name))?;
-        let field = block(|| reflection.get_fields(&prototype))
-            .into_iter()
-            .find(|f| f.get_data_field_name() == key_name)?;
-
-        self.field_type_completion(node, field, reflection)
-    }
-
     fn field_type_completion(
         &self,
         node: Node,
@@ -635,157 +730,103 @@ impl YamlCompletion {
                     ..Default::default()
                 })
                 .collect::<Vec<_>>(),
-            "EntProtoId" => {
-                let lock = tokio::task::block_in_place(|| self.context.prototypes.blocking_read());
-                let entity_prototypes = lock.par_iter().filter(|p| p.prototype == "entity");
-
-                let prototypes = match node.child_by_field_name("value") {
-                    Some(value_node) => {
-                        let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                        let mut prototypes = entity_prototypes
-                            .map(|p| (strsim::jaro_winkler(value, p.id.as_str()), p))
-                            .filter(|(similarity, _)| *similarity > 0.6)
-                            .map(|(d, p)| {
-                                (
-                                    d,
-                                    CompletionItem {
-                                        label: p.id.clone(),
-                                        kind: Some(CompletionItemKind::CLASS),
-                                        detail: Some("entity".to_owned()),
-                                        ..Default::default()
-                                    },
-                                )
-                            })
-                            .collect::<Vec<_>>();
-
-                        prototypes.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                        prototypes.reverse();
-                        prototypes.truncate(100);
-
-                        prototypes.into_iter().map(|(_, p)| p).collect::<Vec<_>>()
-                    }
-                    None => {
-                        let mut prototypes = entity_prototypes
-                            .map(|p| CompletionItem {
-                                label: p.id.clone(),
-                                kind: Some(CompletionItemKind::CLASS),
-                                detail: Some("entity".to_owned()),
-                                ..Default::default()
-                            })
-                            .collect::<Vec<_>>();
-                        prototypes.truncate(100);
-
-                        prototypes
-                    }
+            type_name @ ("EntProtoId" | "ReagentId") => {
+                let prototype_name = match type_name {
+                    "EntProtoId" => "entity",
+                    _ => "reagent",
+                };
+                let value = match node.child_by_field_name("value") {
+                    Some(value_node) => value_node.utf8_text(self.src.as_bytes()).ok()?,
+                    None => "",
                 };
 
-                prototypes
+                let lock = tokio::task::block_in_place(|| self.context.prototypes.blocking_read());
+                let prototypes = lock
+                    .par_iter()
+                    .filter(|p| p.prototype == prototype_name)
+                    .collect::<Vec<_>>();
+
+                Matcher::from_context(&self.context)
+                    .rank_near(
+                        value,
+                        &self.path,
+                        prototypes,
+                        |p| &p.id,
+                        |p| self.definition_path(p),
+                    )
+                    .into_iter()
+                    .map(|p| self.prototype_item(p, prototype_name))
+                    .collect::<Vec<_>>()
             }
             value if value.starts_with("ProtoId<") => {
                 let inner = value.trim_start_matches("ProtoId<").trim_end_matches('>');
                 let prototype = block(|| reflection.get_prototype_by_name(inner))?;
                 let prototype_name = camel_case(&prototype.get_prototype_name());
 
-                let lock = tokio::task::block_in_place(|| self.context.prototypes.blocking_read());
-                let filtered_prototypes = lock.par_iter().filter(|p| p.prototype == prototype_name);
-
-                let map = |l: String| CompletionItem {
-                    label: l,
-                    kind: Some(CompletionItemKind::CLASS),
-                    detail: Some(prototype_name.clone()),
-                    ..Default::default()
+                let value = match node.child_by_field_name("value") {
+                    Some(value_node) => value_node.utf8_text(self.src.as_bytes()).ok()?,
+                    None => "",
                 };
 
-                let prototypes = match node.child_by_field_name("value") {
-                    Some(value_node) => {
-                        let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                        let mut prototypes = filtered_prototypes
-                            .map(|p| (strsim::jaro_winkler(value, &p.id), p))
-                            .filter(|(diff, _)| *diff > 0.6)
-                            .map(|(d, p)| (d, map(p.id.clone())))
-                            .collect::<Vec<_>>();
-
-                        prototypes.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                        prototypes.reverse();
-                        prototypes.truncate(100);
-
-                        prototypes.into_iter().map(|(_, p)| p).collect::<Vec<_>>()
-                    }
-                    None => {
-                        let mut prototypes = filtered_prototypes
-                            .map(|p| map(p.id.clone()))
-                            .collect::<Vec<_>>();
-
-                        prototypes.truncate(100);
-                        prototypes
-                    }
-                };
+                let lock = tokio::task::block_in_place(|| self.context.prototypes.blocking_read());
+                let prototypes = lock
+                    .par_iter()
+                    .filter(|p| p.prototype == prototype_name)
+                    .collect::<Vec<_>>();
 
-                prototypes
+                Matcher::from_context(&self.context)
+                    .rank_near(
+                        value,
+                        &self.path,
+                        prototypes,
+                        |p| &p.id,
+                        |p| self.definition_path(p),
+                    )
+                    .into_iter()
+                    .map(|p| self.prototype_item(p, &prototype_name))
+                    .collect::<Vec<_>>()
             }
             "LocId" => {
-                let lock = block_in_place(|| self.context.locales.blocking_read());
-                let map = |key: String, range: Option<Range>| CompletionItem {
-                    label: key.clone(),
-                    kind: Some(CompletionItemKind::VALUE),
-                    detail: Some("locale".to_owned()),
-                    text_edit: if let Some(range) = range {
-                        Some(CompletionTextEdit::Edit(TextEdit {
-                            new_text: key.clone(),
-                            range,
-                        }))
-                    } else {
-                        None
-                    },
-                    ..Default::default()
+                let value_node = node.child_by_field_name("value");
+                let value = match value_node {
+                    Some(value_node) => value_node.utf8_text(self.src.as_bytes()).ok()?,
+                    None => "",
                 };
+                let range = value_node.map(|value_node| {
+                    Range::new(
+                        Position::new(
+                            value_node.start_position().row as u32,
+                            value_node.start_position().column as u32,
+                        ),
+                        Position::new(
+                            value_node.end_position().row as u32,
+                            value_node.end_position().column as u32,
+                        ),
+                    )
+                });
+
+                tracing::trace!("Searching locales for {value}");
 
-                let locales = match node.child_by_field_name("value") {
-                    Some(value_node) => {
-                        let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-
-                        tracing::trace!("Searching locales for {value}");
-
-                        let mut locales = lock
-                            .par_iter()
-                            .map(|l| (strsim::jaro_winkler(value, &l.key), l))
-                            .filter(|(diff, _)| *diff >= 0.8)
-                            .map(|(d, l)| {
-                                (
-                                    d,
-                                    map(l.key.clone(), {
-                                        let start = Position::new(
-                                            value_node.start_position().row as u32,
-                                            value_node.start_position().column as u32,
-                                        );
-                                        let end = Position::new(
-                                            value_node.end_position().row as u32,
-                                            value_node.end_position().column as u32,
-                                        );
-                                        Some(Range::new(start, end))
-                                    }),
-                                )
-                            })
-                            .collect::<Vec<_>>();
-
-                        locales.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                        locales.reverse();
-                        locales.truncate(100);
-
-                        locales.into_iter().map(|(_, l)| l).collect::<Vec<_>>()
-                    }
-                    None => {
-                        let mut locales = lock
-                            .par_iter()
-                            .map(|l| map(l.key.clone(), None))
-                            .collect::<Vec<_>>();
-
-                        locales.truncate(100);
-                        locales
-                    }
-                };
+                let lock = block_in_place(|| self.context.locales.blocking_read());
+                let mut keys = lock.names(FluentKeyKind::Message).collect::<Vec<_>>();
+                keys.sort();
 
-                locales
+                Matcher::from_context(&self.context)
+                    .rank(value, keys, |key| *key)
+                    .into_iter()
+                    .map(|key| CompletionItem {
+                        label: key.to_owned(),
+                        kind: Some(CompletionItemKind::VALUE),
+                        detail: Some("locale".to_owned()),
+                        text_edit: range.map(|range| {
+                            CompletionTextEdit::Edit(TextEdit {
+                                new_text: key.to_owned(),
+                                range,
+                            })
+                        }),
+                        ..Default::default()
+                    })
+                    .collect::<Vec<_>>()
             }
             _ => vec![],
         };
@@ -804,6 +845,7 @@ impl YamlCompletion {
             node.kind() == "flow_sequence"
                 || node.kind() == "flow_node"
                 || node.kind() == "block_mapping_pair"
+                || node.kind() == "block_sequence_item"
         );
 
         #[rustfmt::skip]
@@ -811,6 +853,7 @@ impl YamlCompletion {
             "flow_sequence" => node.parent()?.prev_named_sibling()?.utf8_text(self.src.as_bytes()).ok()?,
             "flow_node" => node.parent()?.parent()?.prev_named_sibling()?.utf8_text(self.src.as_bytes()).ok()?,
             "block_mapping_pair" => node.child_by_field_name("key")?.utf8_text(self.src.as_bytes()).ok()?,
+            "block_sequence_item" => node.parent()?.parent()?.parent()?.child_by_field_name("key")?.utf8_text(self.src.as_bytes()).ok()?,
             _ => return None,
         };
 
@@ -818,10 +861,15 @@ impl YamlCompletion {
             return None;
         }
 
+        let matcher = Matcher::from_context(&self.context);
+
         let proto_name = match node.kind() {
             "flow_sequence" => self.get_object_name(&node.parent()?.parent()?.parent()?)?,
             "flow_node" => self.get_object_name(&node.parent()?.parent()?.parent()?.parent()?)?,
             "block_mapping_pair" => self.get_object_name(&node.parent()?)?,
+            "block_sequence_item" => {
+                self.get_object_name(&node.parent()?.parent()?.parent()?.parent()?)?
+            }
             _ => return None,
         };
 
@@ -830,6 +878,15 @@ impl YamlCompletion {
             "flow_sequence" => self.get_specified_parents(&node).unwrap_or_default(),
             "flow_node" => self.get_specified_parents(&node.parent()?).unwrap_or_default(),
             "block_mapping_pair" => vec![],
+            // The item itself is the one being completed.
+            "block_sequence_item" => {
+                let value = node.named_child(0).and_then(|v| v.utf8_text(self.src.as_bytes()).ok());
+                let mut parents = self.get_specified_parents(&node.parent()?).unwrap_or_default();
+                if let Some(i) = parents.iter().position(|p| Some(*p) == value) {
+                    parents.remove(i);
+                }
+                parents
+            }
             _ => return None,
         };
 
@@ -839,151 +896,430 @@ impl YamlCompletion {
             .filter(|p| p.prototype == proto_name)
             .filter(|p| !specified_parents.contains(&p.id.as_str()));
 
-        let map = |id: String,
-                   prototype: String,
+        let map = |p: &YamlPrototype,
+                   score: f64,
                    start_position: u32,
-                   end_position: Option<Point>| CompletionItem {
-            label: id.clone(),
-            kind: Some(CompletionItemKind::CLASS),
-            detail: Some(prototype),
-            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                range: {
-                    let position = Position::new(self.position.line, start_position);
-                    lsp_types::Range {
-                        start: position,
-                        end: if let Some(end_position) = end_position {
-                            Position::new(end_position.row as u32, end_position.column as u32)
-                        } else {
-                            position
-                        },
-                    }
-                },
-                new_text: id,
-            })),
-            ..Default::default()
+                   end_position: Option<Point>| {
+            let item = CompletionItem {
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: {
+                        let position = Position::new(self.position.line, start_position);
+                        lsp_types::Range {
+                            start: position,
+                            end: if let Some(end_position) = end_position {
+                                Position::new(end_position.row as u32, end_position.column as u32)
+                            } else {
+                                position
+                            },
+                        }
+                    },
+                    new_text: p.id.clone(),
+                })),
+                ..self.prototype_item(p, &p.prototype)
+            };
+
+            (is_base_prototype(p), score, item)
+        };
+        let score = |value: &str, p: &YamlPrototype| {
+            matcher.score_near(value, &p.id, &self.path, self.definition_path(p))
         };
 
         let parents = match node.kind() {
             "flow_sequence" => {
                 let child_count = node.child_count();
                 let last_child = node.child(child_count - 2)?;
-                let position = Position::new(
-                    self.position.line,
-                    match last_child.kind() {
-                        "," => last_child.end_position().column as u32 + 1,
-                        "flow_node" => return None,
-                        _ => node.start_position().column as u32 + 1,
-                    },
-                );
-
-                let mut parents = filtered_prototypes
-                    .map(|p| CompletionItem {
-                        label: p.id.clone(),
-                        kind: Some(CompletionItemKind::CLASS),
-                        detail: Some(p.prototype.clone()),
-                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                            range: {
-                                lsp_types::Range {
-                                    start: position,
-                                    end: position,
-                                }
-                            },
-                            new_text: p.id.clone(),
-                        })),
-                        ..Default::default()
-                    })
-                    .collect::<Vec<_>>();
-
-                parents.sort_by(|a, b| a.label.cmp(&b.label));
-                parents.truncate(100);
+                let column = match last_child.kind() {
+                    "," => last_child.end_position().column as u32 + 1,
+                    "flow_node" => return None,
+                    _ => node.start_position().column as u32 + 1,
+                };
 
-                parents
+                filtered_prototypes
+                    .filter_map(|p| Some((score("", p)?, p)))
+                    .map(|(score, p)| map(p, score, column, None))
+                    .collect::<Vec<_>>()
             }
             "flow_node" => {
                 let value = node.utf8_text(self.src.as_bytes()).ok()?;
-                let mut parents = filtered_prototypes
-                    .map(|p| (strsim::jaro_winkler(value, &p.id), p))
-                    .filter(|(diff, _)| diff > &0.8)
-                    .map(|(diff, p)| {
-                        (
-                            diff,
-                            map(
-                                p.id.clone(),
-                                p.prototype.clone(),
-                                node.start_position().column as u32,
-                                Some(node.end_position()),
-                            ),
+                filtered_prototypes
+                    .filter_map(|p| Some((score(value, p)?, p)))
+                    .map(|(score, p)| {
+                        map(
+                            p,
+                            score,
+                            node.start_position().column as u32,
+                            Some(node.end_position()),
                         )
                     })
-                    .collect::<Vec<_>>();
-
-                parents.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                parents.reverse();
-                parents.truncate(100);
-
-                parents.into_iter().map(|(_, p)| p).collect()
+                    .collect::<Vec<_>>()
             }
-            "block_mapping_pair" => match node.child_by_field_name("value") {
+            "block_mapping_pair" => {
+                let key_node = node.child_by_field_name("key")?;
+                let (value, range) =
+                    self.value_range(key_node, node.child_by_field_name("value"))?;
+                let end_position =
+                    Point::new(range.end.line as usize, range.end.character as usize);
+
+                filtered_prototypes
+                    .filter_map(|p| Some((score(value, p)?, p)))
+                    .map(|(score, p)| map(p, score, range.start.character, Some(end_position)))
+                    .collect::<Vec<_>>()
+            }
+            "block_sequence_item" => match node.named_child(0) {
                 Some(value_node) => {
                     let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                    let key_node = node.child_by_field_name("key")?;
-                    let mut parents = filtered_prototypes
-                        .map(|p| (strsim::jaro_winkler(value, &p.id), p))
-                        .filter(|(diff, _)| diff > &0.8)
-                        .map(|(diff, p)| {
-                            (
-                                diff,
-                                map(
-                                    p.id.clone(),
-                                    p.prototype.clone(),
-                                    key_node.end_position().column as u32 + 2,
-                                    Some(value_node.end_position()),
-                                ),
-                            )
-                        })
-                        .collect::<Vec<_>>();
+                    let column = value_node.start_position().column as u32;
+                    filtered_prototypes
+                        .filter_map(|p| Some((score(value, p)?, p)))
+                        .map(|(score, p)| map(p, score, column, Some(value_node.end_position())))
+                        .collect::<Vec<_>>()
+                }
+                None => {
+                    // Nothing is typed after the `- ` yet.
+                    let column = node.start_position().column as u32 + 2;
+                    let end_position = Point::new(
+                        self.position.line as usize,
+                        self.position.character as usize,
+                    );
+
+                    filtered_prototypes
+                        .filter_map(|p| Some((score("", p)?, p)))
+                        .map(|(score, p)| map(p, score, column, Some(end_position)))
+                        .collect::<Vec<_>>()
+                }
+            },
+            _ => vec![],
+        };
 
-                    parents.sort_by_key(|(diff, _)| (*diff * 100.0) as u32);
-                    parents.reverse();
-                    parents.truncate(100);
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items: rank_parents(parents, matcher.limit()),
+        }))
+    }
+
+    /// Returns the name of the prototype and the path of the metadata field the node belongs to,
+    /// e.g. `entity` and `placement.mode`. Free-form string fields of the prototype, e.g. `name`,
+    /// are completed the same way since nothing else knows their values.
+    fn get_metadata_field<'a>(&'a self, node: Node) -> Option<(&'a str, String)> {
+        let mut pair = node;
+        while pair.kind() != "block_mapping_pair" {
+            pair = pair.parent()?;
+        }
 
-                    parents.into_iter().map(|(_, p)| p).collect()
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let mut path = vec![];
+        // Metadata fields are nested at most one mapping deep.
+        for _ in 0..2 {
+            path.push(
+                pair.child_by_field_name("key")?
+                    .utf8_text(self.src.as_bytes())
+                    .ok()?,
+            );
+
+            let mapping = pair.parent()?;
+            if let Some(SchemaType::Prototype(proto)) = resolver.resolve(mapping) {
+                path.reverse();
+                let path = path.join(".");
+                if !METADATA_FIELDS.contains(&path.as_str()) && !self.is_string_field(&proto, &path)
+                {
+                    return None;
                 }
-                None => {
-                    let key_node = node.child_by_field_name("key")?;
-                    let mut parents = filtered_prototypes
-                        .map(|p| {
-                            map(
-                                p.id.clone(),
-                                p.prototype.clone(),
-                                key_node.end_position().column as u32 + 2,
-                                None,
-                            )
-                        })
-                        .collect::<Vec<_>>();
+                return Some((self.get_object_name(&mapping)?, path));
+            }
 
-                    parents.truncate(100);
+            pair = mapping.parent()?.parent()?;
+            if pair.kind() != "block_mapping_pair" {
+                return None;
+            }
+        }
 
-                    parents
+        None
+    }
+
+    fn is_string_field(&self, proto: &CsharpClass, name: &str) -> bool {
+        if name == "id" {
+            return false;
+        }
+
+        let reflection = ReflectionManager::new(&self.context);
+        block(|| reflection.get_fields(proto)).into_iter().any(|f| {
+            f.get_data_field_name() == name && f.type_name.trim_end_matches('?') == "string"
+        })
+    }
+
+    /// Counts the prototypes of the kind that use each value of the field, the counts are cached
+    /// until the prototypes change.
+    fn used_values(&self, proto_name: &str, path: &str) -> Arc<HashMap<String, usize>> {
+        let key = (proto_name.to_owned(), path.to_owned());
+        // The prototypes stay locked until the counts are cached, so they can't be outdated.
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let cached = block_in_place(|| self.context.used_values.blocking_read())
+            .get(&key)
+            .cloned();
+        if let Some(used) = cached {
+            return used;
+        }
+
+        let mut used = HashMap::<String, usize>::new();
+        for proto in lock.iter().filter(|p| p.prototype == proto_name) {
+            let values = match proto.lists.get(path) {
+                Some(values) => values.iter().collect::<Vec<_>>(),
+                None => proto.fields.get(path).into_iter().collect(),
+            };
+            for value in values.into_iter().filter(|v| !v.is_empty()) {
+                *used.entry(value.clone()).or_default() += 1;
+            }
+        }
+
+        let used = Arc::new(used);
+        block_in_place(|| self.context.used_values.blocking_write()).insert(key, used.clone());
+        used
+    }
+
+    /// Offers the values of a metadata field, e.g. `suffix` or `categories`,
+    /// that are already used by the prototypes of the same kind, the most used ones first.
+    fn metadata_completion(&self, node: Node) -> CompletionResult {
+        let (proto_name, path) = self.get_metadata_field(node)?;
+        let matcher = Matcher::from_context(&self.context);
+
+        // The range of the typed value and the values already specified in the list.
+        let (range, specified) = match node.kind() {
+            "block_mapping_pair" => match node.child_by_field_name("value") {
+                Some(value_node) if value_node.kind() == "flow_node" => {
+                    (node_range(value_node), vec![])
+                }
+                Some(_) => return None,
+                None => {
+                    let column = node.child_by_field_name("key")?.end_position().column as u32 + 2;
+                    let start =
+                        Position::new(self.position.line, column.min(self.position.character));
+                    (Range::new(start, self.position), vec![])
                 }
             },
-            _ => vec![],
+            "flow_node" => (
+                node_range(node),
+                self.get_specified_parents(&node.parent()?)?,
+            ),
+            "flow_sequence" => (
+                Range::new(self.position, self.position),
+                self.get_specified_parents(&node)?,
+            ),
+            "block_sequence_item" => {
+                let range = match node.named_child(0) {
+                    Some(value_node) => node_range(value_node),
+                    None => Range::new(self.position, self.position),
+                };
+                let sequence = node.parent()?;
+                let specified = (0..sequence.named_child_count())
+                    .filter_map(|i| sequence.named_child(i))
+                    .filter(|item| item.id() != node.id())
+                    .filter_map(|item| item.named_child(0)?.utf8_text(self.src.as_bytes()).ok())
+                    .collect();
+                (range, specified)
+            }
+            _ => return None,
         };
 
+        let typed = self.get_typed_value(range.start.character);
+
+        let used = self.used_values(proto_name, &path);
+        let mut values = used
+            .iter()
+            .map(|(value, count)| (value.as_str(), *count))
+            .filter(|(value, _)| !specified.contains(value))
+            .filter_map(|(value, count)| Some((matcher.score(typed, value)?, count, value)))
+            .collect::<Vec<_>>();
+        values.sort_by(|(a_score, a_count, a), (b_score, b_count, b)| {
+            b_score
+                .total_cmp(a_score)
+                .then(b_count.cmp(a_count))
+                .then_with(|| a.cmp(b))
+        });
+
+        values.truncate(matcher.limit());
+
+        let items = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, count, value))| CompletionItem {
+                label: value.to_owned(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(match count {
+                    1 => format!("Used in 1 {proto_name} prototype"),
+                    _ => format!("Used in {count} {proto_name} prototypes"),
+                }),
+                sort_text: Some(format!("{i:04}")),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: value.to_owned(),
+                })),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
         Some(CompletionResponse::List(CompletionList {
             is_incomplete: true,
-            items: parents,
+            items,
         }))
     }
 
-    fn component_fields_completion(&self, node: Node) -> CompletionResult {
+    /// The item of a prototype id, a prototype defined in several files, e.g. by a fork
+    /// and by upstream, is offered once and tells how many files define it.
+    fn prototype_item(&self, proto: &YamlPrototype, kind: &str) -> CompletionItem {
+        let files = proto
+            .definitions()
+            .map(|d| &d.index().0)
+            .collect::<HashSet<_>>()
+            .len();
+
+        CompletionItem {
+            label: proto.id.clone(),
+            kind: Some(CompletionItemKind::CLASS),
+            detail: Some(match files {
+                1 => kind.to_owned(),
+                files => format!("{kind}, defined in {files} files"),
+            }),
+            label_details: (files > 1).then(|| {
+                let path = self.definition_path(proto);
+                CompletionItemLabelDetails {
+                    description: Some(
+                        path.strip_prefix(&self.root_path)
+                            .unwrap_or(path)
+                            .display()
+                            .to_string(),
+                    ),
+                    ..Default::default()
+                }
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// The file of the definition in the `Resources` of the workspace, the one the user edits,
+    /// or of the highest-priority definition when the workspace doesn't define the prototype.
+    fn definition_path<'a>(&self, proto: &'a YamlPrototype) -> &'a Path {
+        let resources = self.root_path.join("Resources");
+        proto
+            .definitions()
+            .map(|d| d.index().0.as_path())
+            .find(|path| path.starts_with(&resources))
+            .unwrap_or(&proto.index().0)
+    }
+
+    fn snippets_enabled(&self) -> bool {
+        let enabled = block_in_place(|| self.context.config.blocking_read())
+            .completion
+            .snippets;
+        let supported = block_in_place(|| self.context.client_capabilities.blocking_read())
+            .text_document
+            .as_ref()
+            .and_then(|t| t.completion.as_ref())
+            .and_then(|c| c.completion_item.as_ref())
+            .and_then(|i| i.snippet_support)
+            .unwrap_or(false);
+
+        enabled && supported
+    }
+
+    /// Returns the value of the pair and the range the completed value replaces. A value that
+    /// isn't parsed yet is what is typed after the key, up to the cursor.
+    fn value_range<'a>(
+        &'a self,
+        key_node: Node,
+        value_node: Option<Node>,
+    ) -> Option<(&'a str, Range)> {
+        if let Some(value_node) = value_node {
+            let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
+            return Some((value, node_range(value_node)));
+        }
+
+        // The value starts past the colon and the spaces after it.
+        let line = self
+            .src
+            .lines()
+            .nth(self.position.line as usize)
+            .unwrap_or_default();
+        let colon = (key_node.end_position().column + 1).min(line.len());
+        let cursor = (self.position.character as usize).clamp(colon, line.len());
+        let typed = line.get(colon..cursor).unwrap_or_default();
+        let start = cursor - typed.trim_start().len();
+
+        let range = Range::new(
+            Position::new(self.position.line, start as u32),
+            Position::new(self.position.line, cursor as u32),
+        );
+        Some((typed.trim(), range))
+    }
+
+    /// Returns the text typed on the current line between the column and the cursor.
+    fn get_typed_value(&self, column: u32) -> &str {
+        let line = self
+            .src
+            .lines()
+            .nth(self.position.line as usize)
+            .unwrap_or_default();
+        let start = (column as usize).min(line.len());
+        let end = (self.position.character as usize).clamp(start, line.len());
+
+        line.get(start..end).unwrap_or_default().trim()
+    }
+
+    /// Offers the implementations of the field type for a `!type:` tag.
+    fn tag_completion(&self, tag: Node) -> CompletionResult {
+        debug_assert_eq!(tag.kind(), "tag");
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let declared_type = resolver.resolve_declared_type(tag)?;
+
+        let range = lsp_types::Range::new(
+            Position::new(
+                tag.start_position().row as u32,
+                tag.start_position().column as u32,
+            ),
+            Position::new(
+                tag.end_position().row as u32,
+                tag.end_position().column as u32,
+            ),
+        );
+
+        let reflection = ReflectionManager::new(&self.context);
+        let mut subclasses = block(|| reflection.get_subclasses(&declared_type));
+        subclasses.retain(|c| {
+            !c.modifiers.contains("abstract") && block(|| reflection.is_data_definition(c))
+        });
+        subclasses.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let items = subclasses
+            .into_iter()
+            .map(|c| {
+                let tag = format!("!type:{}", c.name);
+                CompletionItem {
+                    label: c.name,
+                    kind: Some(CompletionItemKind::CLASS),
+                    detail: Some(declared_type.clone()),
+                    filter_text: Some(tag.clone()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range,
+                        new_text: tag,
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            None
+        } else {
+            Some(CompletionResponse::Array(items))
+        }
+    }
+
+    fn data_fields_completion(&self, node: Node, class: &CsharpClass) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping");
 
-        let comp_name = self.get_object_name(&node)?;
         let specified_fields = self.get_specified_fields(&node);
-        let reflection = ReflectionManager::new(self.context.classes.clone());
-        let comp = block(|| reflection.get_component_by_name(comp_name))?;
-        let fields = block(|| reflection.get_fields(&comp))
+        let reflection = ReflectionManager::new(&self.context);
+        let fields = block(|| reflection.get_fields(class))
             .into_par_iter()
             .filter(|f| {
                 f.attributes.contains("DataField") || f.attributes.contains("IncludeDataField")
@@ -994,7 +1330,7 @@ impl YamlCompletion {
                 CompletionItem {
                     label: name.clone(),
                     kind: Some(CompletionItemKind::FIELD),
-                    detail: Some(f.type_name),
+                    detail: Some(field_detail(&f)),
                     text_edit: Some(CompletionTextEdit::Edit(TextEdit {
                         range: {
                             let position = Position::new(
@@ -1020,25 +1356,74 @@ impl YamlCompletion {
         }
     }
 
-    fn prototype_fields_completion(&self, node: Node) -> CompletionResult {
+    /// Offers the prototype ids for the keys of a dictionary keyed by ids,
+    /// e.g. the damage types of `damage: { types: ... }`.
+    fn dictionary_keys_completion(&self, node: Node, prototype_name: String) -> CompletionResult {
+        debug_assert_eq!(node.kind(), "block_mapping");
+
+        let specified_keys = self.get_specified_fields(&node);
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let mut ids = lock
+            .par_iter()
+            .filter(|p| p.prototype == prototype_name)
+            .filter(|p| !specified_keys.contains(&p.id.as_str()))
+            .map(|p| p.id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        let limit = block_in_place(|| self.context.config.blocking_read())
+            .completion
+            .max_items;
+        ids.truncate(limit);
+
+        let position = Position::new(self.position.line, node.start_position().column as u32);
+        let keys = ids
+            .into_iter()
+            .map(|id| CompletionItem {
+                label: id.clone(),
+                kind: Some(CompletionItemKind::CLASS),
+                detail: Some(prototype_name.clone()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: Range::new(position, position),
+                    new_text: format!("{id}: "),
+                })),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            None
+        } else {
+            Some(CompletionResponse::Array(keys))
+        }
+    }
+
+    fn prototype_fields_completion(&self, node: Node, proto: &Prototype) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping");
 
-        let proto_name = self.get_object_name(&node)?;
         let specified_fields = self.get_specified_fields(&node);
-        let reflection = ReflectionManager::new(self.context.classes.clone());
-        let proto = block(|| reflection.get_prototype_by_name(proto_name))?;
-        let fields = block(|| reflection.get_fields(&proto))
+        let reflection = ReflectionManager::new(&self.context);
+        // The components are only completed inside the list, so it's inserted along with the key.
+        let components_indent = self
+            .snippets_enabled()
+            .then(|| " ".repeat(node.start_position().column));
+        let fields = block(|| reflection.get_fields(proto))
             .into_par_iter()
             .filter(|f| f.attributes.contains("DataField"))
             .chain([CsharpClassField::new_empty("id", "string")])
             .filter(|f| !specified_fields.contains(&f.get_data_field_name().as_str()))
             .map(|f| {
                 let name = f.get_data_field_name();
+                let snippet = match (name.as_str(), &components_indent) {
+                    ("components", Some(indent)) => Some(components_snippet(indent)),
+                    _ => None,
+                };
 
                 CompletionItem {
                     label: name.clone(),
                     kind: Some(CompletionItemKind::FIELD),
-                    detail: Some(f.type_name),
+                    detail: Some(field_detail(&f)),
+                    insert_text_format: snippet.as_ref().map(|_| InsertTextFormat::SNIPPET),
                     text_edit: Some(CompletionTextEdit::Edit(TextEdit {
                         range: {
                             let position = Position::new(
@@ -1050,9 +1435,9 @@ impl YamlCompletion {
                                 end: position,
                             }
                         },
-                        new_text: format!("{name}: "),
+                        new_text: snippet.unwrap_or_else(|| format!("{name}: ")),
                     })),
-                    sort_text: if f.name == "id" || f.name == "components" {
+                    sort_text: if name == "id" || name == "components" {
                         Some("0".to_owned())
                     } else {
                         Some("1".to_owned())
@@ -1072,145 +1457,139 @@ impl YamlCompletion {
     fn prototype_completion(&self, node: Node, key_node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let value_node = node
-            .child_by_field_name("value")
-            .map(|v| v.utf8_text(self.src.as_bytes()).unwrap());
+        let (typed, range) = self.value_range(key_node, node.child_by_field_name("value"))?;
+
+        // A snippet is only offered for a prototype that has nothing but the `type` yet.
+        let snippet_indent = (self.snippets_enabled() && node.parent()?.named_child_count() == 1)
+            .then(|| " ".repeat(key_node.start_position().column));
+
+        Some(CompletionResponse::Array(self.prototype_kind_items(
+            typed,
+            range,
+            false,
+            snippet_indent,
+        )))
+    }
+
+    /// Offers the prototype kinds in a file that has nothing but the current line yet,
+    /// the `- type: ` of the first prototype is inserted along with the kind.
+    fn new_file_completion(&self) -> CompletionResult {
+        let line = self.position.line as usize;
+        let is_new = self
+            .src
+            .lines()
+            .enumerate()
+            .all(|(i, l)| i == line || l.trim().is_empty() || l.trim_start().starts_with('#'));
+        let current = self.src.lines().nth(line).unwrap_or_default();
+        // Lines that start a sequence item are completed by the tree.
+        if !is_new || current.starts_with(char::is_whitespace) || current.starts_with('-') {
+            return None;
+        }
+
+        let typed = self.get_typed_value(0);
+        let kind = match typed.strip_prefix("type:") {
+            Some(kind) => kind.trim(),
+            None if "type:".starts_with(typed) => "",
+            None => return None,
+        };
+
+        let snippet_indent = self.snippets_enabled().then(|| "  ".to_owned());
+        let range = Range::new(Position::new(self.position.line, 0), self.position);
+        Some(CompletionResponse::Array(self.prototype_kind_items(
+            kind,
+            range,
+            true,
+            snippet_indent,
+        )))
+    }
+
+    /// The prototype kinds that match the typed text. The edit either inserts the kind
+    /// at the range or replaces the range with the whole `- type: <kind>` line.
+    fn prototype_kind_items(
+        &self,
+        typed: &str,
+        range: Range,
+        whole_line: bool,
+        snippet_indent: Option<String>,
+    ) -> Vec<CompletionItem> {
+        let matcher = Matcher::from_context(&self.context);
 
         let lock = tokio::task::block_in_place(|| self.context.classes.blocking_read());
-        let completions = lock
-            .par_iter()
+        lock.par_iter()
             .filter_map(|c| Prototype::try_from(c).ok())
             .filter(|p| {
-                if let Some(value) = value_node {
-                    let name = p.get_prototype_name().to_lowercase();
-                    let diff = strsim::damerau_levenshtein(value.to_lowercase().as_str(), &name);
-
-                    diff < name.len()
-                } else {
-                    true
-                }
+                let name = camel_case(&p.get_prototype_name());
+                matcher.score(typed, &name).is_some()
             })
             .map(|p| {
                 let name = p.get_prototype_name();
+                let kind = camel_case(name.as_str());
+                let priority = p.get_load_priority();
+                let snippet = match (kind.as_str(), &snippet_indent) {
+                    ("entity", Some(indent)) => Some(entity_snippet(indent)),
+                    _ => None,
+                };
+                let value = snippet.clone().unwrap_or_else(|| kind.clone());
 
                 CompletionItem {
                     label: name.to_owned(),
                     kind: Some(CompletionItemKind::CLASS),
                     label_details: Some(CompletionItemLabelDetails {
-                        detail: Some("Prototype".to_owned()),
+                        detail: Some(format!("Prototype, load priority {priority}")),
                         ..Default::default()
                     }),
+                    insert_text_format: snippet.as_ref().map(|_| InsertTextFormat::SNIPPET),
+                    // The client filters the items by the text of the range, `type:` included.
+                    filter_text: whole_line.then(|| format!("type: {kind}")),
                     text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                        range: {
-                            let position = Position::new(
-                                self.position.line,
-                                key_node.end_position().column as u32 + 2,
-                            );
-                            lsp_types::Range {
-                                start: position,
-                                end: position,
-                            }
+                        range,
+                        new_text: match whole_line {
+                            true => format!("- type: {value}"),
+                            false => value,
                         },
-                        new_text: camel_case(name.as_str()),
                     })),
+                    // Entities go first, then the kinds that are loaded earlier.
                     sort_text: if name.to_lowercase() == "entity" {
                         Some("0".to_owned())
                     } else {
-                        Some("1".to_owned())
+                        Some(format!("1{}{name}", load_order_key(priority)))
                     },
                     ..Default::default()
                 }
             })
-            .collect::<Vec<_>>();
-
-        Some(CompletionResponse::Array(completions))
+            .collect()
     }
 
     fn components_completion(&self, node: Node, key_node: Node) -> CompletionResult {
         debug_assert_eq!(node.kind(), "block_mapping_pair");
 
-        let is_components_node = {
-            let mut node = node;
-            for _ in 0..6 {
-                node = node.parent()?;
-            }
-            if node.kind() != "block_mapping_pair" {
-                false
-            } else {
-                let key_node = node.child_by_field_name("key")?;
-                let key_value = key_node.utf8_text(self.src.as_bytes()).ok()?;
-                key_value == "components"
-            }
-        };
-
-        if !is_components_node {
-            return None;
-        }
-
-        let value = node.child_by_field_name("value");
+        let (typed, range) = self.value_range(key_node, node.child_by_field_name("value"))?;
 
         let lock = tokio::task::block_in_place(|| self.context.classes.blocking_read());
-        let completions = lock.par_iter().filter_map(|c| Component::try_from(c).ok());
-
-        let map = |c: &Component| {
-            let name = c.get_component_name();
+        let names = lock
+            .par_iter()
+            .filter_map(|c| Component::try_from(c).ok())
+            .map(|c| c.get_component_name())
+            .collect::<Vec<_>>();
+        drop(lock);
 
-            CompletionItem {
+        let items = Matcher::from_context(&self.context)
+            .rank(typed, names, |name| name.as_str())
+            .into_iter()
+            .map(|name| CompletionItem {
                 label: name.clone(),
                 kind: Some(CompletionItemKind::CLASS),
                 label_details: Some(CompletionItemLabelDetails {
                     detail: Some("Component".to_owned()),
                     ..Default::default()
                 }),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: name.clone(),
+                })),
                 ..Default::default()
-            }
-        };
-
-        let items = match value {
-            Some(value_node) => {
-                let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-                let mut items = completions
-                    .map(|c| (strsim::jaro_winkler(value, &c.get_component_name()), c))
-                    .filter(|(diff, _)| *diff > 0.8)
-                    .map(|(diff, c)| {
-                        let item = map(&c);
-                        let name = c.get_component_name();
-                        (
-                            diff,
-                            CompletionItem {
-                                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
-                                    range: {
-                                        let start = Position::new(
-                                            self.position.line,
-                                            key_node.end_position().column as u32 + 2,
-                                        );
-                                        let end = Position::new(
-                                            self.position.line,
-                                            value_node.end_position().column as u32,
-                                        );
-                                        lsp_types::Range { start, end }
-                                    },
-                                    new_text: name,
-                                })),
-                                ..item
-                            },
-                        )
-                    })
-                    .collect::<Vec<_>>();
-
-                items.sort_by_key(|(diff, _)| (*diff * 100.) as u32);
-                items.reverse();
-                items.truncate(100);
-
-                items.into_iter().map(|(_, c)| c).collect()
-            }
-            None => completions
-                .map(|c| CompletionItem {
-                    insert_text: Some(c.get_component_name().to_owned()),
-                    ..map(&c)
-                })
-                .collect(),
-        };
+            })
+            .collect();
 
         Some(CompletionResponse::List(CompletionList {
             is_incomplete: true,
@@ -1218,3 +1597,78 @@ impl YamlCompletion {
         }))
     }
 }
+
+fn field_detail(field: &CsharpClassField) -> String {
+    let detail = match &field.default_value {
+        Some(default) => format!("{}, default: {default}", field.type_name),
+        None => field.type_name.to_string(),
+    };
+    match field.is_server_only() {
+        true => format!("{detail}, server only"),
+        false => detail,
+    }
+}
+
+fn node_range(node: Node) -> Range {
+    Range::new(
+        Position::new(
+            node.start_position().row as u32,
+            node.start_position().column as u32,
+        ),
+        Position::new(
+            node.end_position().row as u32,
+            node.end_position().column as u32,
+        ),
+    )
+}
+
+/// The skeleton of a new entity, the lines are indented to the `type` key.
+fn entity_snippet(indent: &str) -> String {
+    [
+        "entity",
+        "id: $1",
+        "name: $2",
+        "description: $3",
+        "components:",
+        "- type: $0",
+    ]
+    .join(&format!("\n{indent}"))
+}
+
+/// The `components` key with the first item of the list, indented to the key.
+fn components_snippet(indent: &str) -> String {
+    format!("components:\n{indent}- type: $0")
+}
+
+/// Sorts the higher load priorities first when compared as text, negative ones included.
+fn load_order_key(priority: i64) -> String {
+    format!("{:020}", u64::MAX - ((priority as u64) ^ (1 << 63)))
+}
+
+/// Parents are usually abstract base prototypes, e.g. `BaseItem`.
+fn is_base_prototype(proto: &YamlPrototype) -> bool {
+    proto.is_abstract() || proto.id.starts_with("Base")
+}
+
+/// Puts base prototypes first and the closest matches first within them.
+fn rank_parents(
+    mut parents: Vec<(bool, f64, CompletionItem)>,
+    limit: usize,
+) -> Vec<CompletionItem> {
+    parents.sort_by(|(a_base, a_score, a), (b_base, b_score, b)| {
+        b_base
+            .cmp(a_base)
+            .then(b_score.total_cmp(a_score))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    parents.truncate(limit);
+
+    parents
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, _, item))| CompletionItem {
+            sort_text: Some(format!("{i:04}")),
+            ..item
+        })
+        .collect()
+}
@@ -0,0 +1,320 @@
+use crate::{
+    completion::matcher::MatchAlgorithm,
+    diagnostic::rules::RuleSeverity,
+    logging::{LogFormat, LogLevel},
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Server settings, received through `initializationOptions`
+/// and `workspace/didChangeConfiguration`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    pub log: LogConfig,
+    pub diagnostics: DiagnosticsConfig,
+    pub completion: CompletionConfig,
+    pub locale: LocaleConfig,
+    pub prototypes: PrototypesConfig,
+    pub timeouts: TimeoutsConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogConfig {
+    pub level: Option<LogLevel>,
+    pub format: Option<LogFormat>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiagnosticsConfig {
+    /// Reports YAML values that repeat the default value of the C# field.
+    pub redundant_defaults: bool,
+    /// Reports `[DataField(serverOnly: true)]` fields set in the resources of a client project,
+    /// e.g. `Content.Client`.
+    pub server_only_fields: bool,
+    pub entity_locale: EntityLocaleConfig,
+    /// Renamed components and fields reported in addition to the built-in ones.
+    pub renames: RenamesConfig,
+    /// Components mapped to the components an entity must have along with them,
+    /// e.g. `"Anchorable": ["Transform"]`.
+    pub component_dependencies: HashMap<String, Vec<String>>,
+    /// Prototype kinds mapped to the case their ids are written in,
+    /// e.g. `"entity": "PascalCase"`.
+    pub id_cases: HashMap<String, IdCase>,
+    /// Rule ids mapped to the severity they are reported with, or `off` to disable them,
+    /// e.g. `"missing-locale": "hint"`.
+    pub rules: HashMap<String, RuleSeverity>,
+}
+
+/// A naming convention of prototype ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum IdCase {
+    #[serde(rename = "PascalCase")]
+    Pascal,
+    #[serde(rename = "camelCase")]
+    Camel,
+    #[serde(rename = "snake_case")]
+    Snake,
+    #[serde(rename = "kebab-case")]
+    Kebab,
+}
+
+impl IdCase {
+    /// Whether the id follows the convention, acronyms and digits are allowed
+    /// in the Pascal and camel cases, e.g. `ClothingHeadHatUSSP` or `Sheet10`.
+    pub fn matches(self, id: &str) -> bool {
+        let Some(first) = id.chars().next() else {
+            return true;
+        };
+
+        match self {
+            Self::Pascal => {
+                first.is_ascii_uppercase() && id.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            Self::Camel => {
+                first.is_ascii_lowercase() && id.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            Self::Snake => {
+                first.is_ascii_lowercase()
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            }
+            Self::Kebab => {
+                first.is_ascii_lowercase()
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            }
+        }
+    }
+
+    /// The id written in the convention.
+    pub fn convert(self, id: &str) -> String {
+        match self {
+            Self::Pascal => stringcase::pascal_case(id),
+            Self::Camel => stringcase::camel_case(id),
+            Self::Snake => stringcase::snake_case(id),
+            Self::Kebab => stringcase::kebab_case(id),
+        }
+    }
+}
+
+impl fmt::Display for IdCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pascal => "PascalCase",
+            Self::Camel => "camelCase",
+            Self::Snake => "snake_case",
+            Self::Kebab => "kebab-case",
+        })
+    }
+}
+
+/// Old names mapped to the new ones.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenamesConfig {
+    /// Component names, e.g. `"HandVirtualItem": "VirtualItem"`.
+    pub components: HashMap<String, String>,
+    /// Field names prefixed with the component name, the prototype kind or the class name,
+    /// e.g. `"Clothing.Slots": "slots"`.
+    pub fields: HashMap<String, String>,
+}
+
+/// The convention of the locale keys that name and describe entities.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EntityLocaleConfig {
+    /// Reports entities that have neither a `name`/`description` nor the locale keys.
+    pub enabled: bool,
+    /// The prefix of the message named after the entity id, e.g. `ent-` for `ent-Crowbar`.
+    pub prefix: String,
+    /// The attribute of the message that holds the description.
+    pub description_attribute: String,
+}
+
+impl Default for EntityLocaleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            prefix: "ent-".to_owned(),
+            description_attribute: "desc".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CompletionConfig {
+    /// The maximum number of items in lists that can be huge, e.g. prototype ids.
+    pub max_items: usize,
+    /// Expands completed prototypes into snippets with their usual fields.
+    pub snippets: bool,
+    /// How the typed text is matched against the items.
+    pub matcher: MatchAlgorithm,
+}
+
+impl Default for CompletionConfig {
+    fn default() -> Self {
+        Self {
+            max_items: 100,
+            snippets: true,
+            matcher: MatchAlgorithm::default(),
+        }
+    }
+}
+
+/// How long the providers may run before the server gives up and returns an empty result,
+/// in milliseconds, `0` waits for them however long they take.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TimeoutsConfig {
+    /// Completion is requested while typing, so it gives up sooner.
+    pub completion: u64,
+    /// The other providers, e.g. hover, diagnostics or semantic tokens.
+    pub providers: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            completion: 2000,
+            providers: 5000,
+        }
+    }
+}
+
+impl TimeoutsConfig {
+    pub fn completion(&self) -> Option<Duration> {
+        to_duration(self.completion)
+    }
+
+    pub fn providers(&self) -> Option<Duration> {
+        to_duration(self.providers)
+    }
+}
+
+fn to_duration(millis: u64) -> Option<Duration> {
+    (millis > 0).then(|| Duration::from_millis(millis))
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PrototypesConfig {
+    /// Treats classes with either `[Prototype]` or `IPrototype` as prototypes, not only with both.
+    pub relaxed: bool,
+    /// Folders whose prototypes override the ones with the same id, e.g. the fork's own
+    /// prototypes over the vendored upstream ones, the highest priority first.
+    /// Relative folders are resolved against the workspace root.
+    pub override_roots: Vec<PathBuf>,
+    /// Globs relative to the workspace root of the YAML files that are prototypes,
+    /// all the files of the prototype folders when empty.
+    pub include: Vec<String>,
+    /// Globs of the YAML files that aren't prototypes despite their folder,
+    /// e.g. CI configs or map metadata.
+    pub exclude: Vec<String>,
+    /// Prototype kinds mapped to the fields that list components like the `components`
+    /// of entities, e.g. `"gameRule": ["components"]`. Nested fields are joined with dots
+    /// and the sequences on the way are skipped, e.g. `"steps.components"`.
+    pub component_lists: HashMap<String, Vec<String>>,
+    #[serde(skip)]
+    files: PrototypeFiles,
+}
+
+/// The `include` and `exclude` globs compiled once the workspace root is known.
+#[derive(Debug, Clone, Default)]
+struct PrototypeFiles {
+    root: PathBuf,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PrototypesConfig {
+    /// The priority of the file among the override roots, the lower the higher,
+    /// files outside of all roots have the lowest one.
+    pub fn priority(&self, path: &Path) -> usize {
+        self.override_roots
+            .iter()
+            .position(|root| path.starts_with(root))
+            .unwrap_or(self.override_roots.len())
+    }
+
+    /// Compiles the `include` and `exclude` globs against the workspace root.
+    pub fn compile_files(&mut self, root: &Path) {
+        self.files = PrototypeFiles {
+            root: root.to_path_buf(),
+            include: (!self.include.is_empty()).then(|| glob_set(&self.include)),
+            exclude: glob_set(&self.exclude),
+        };
+    }
+
+    /// Whether the YAML file is indexed as prototypes and diagnosed, see `include` and `exclude`.
+    pub fn is_prototype_file(&self, path: &Path) -> bool {
+        let path = path.strip_prefix(&self.files.root).unwrap_or(path);
+        self.files
+            .include
+            .as_ref()
+            .is_none_or(|set| set.is_match(path))
+            && !self.files.exclude.is_match(path)
+    }
+}
+
+fn glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(err) => tracing::warn!("Skipping the invalid glob `{pattern}`: {err}"),
+        }
+    }
+    builder.build().unwrap_or_else(|err| {
+        tracing::warn!("Failed to compile the globs: {err}");
+        GlobSet::empty()
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LocaleConfig {
+    /// The locale preferred when a key is defined in several locales.
+    pub primary: String,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            primary: "en-US".to_owned(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the settings either from the `robust-lsp` section or from the object itself.
+    pub fn from_value(value: serde_json::Value) -> Option<Self> {
+        let value = match value {
+            serde_json::Value::Object(mut map) if map.contains_key("robust-lsp") => {
+                map.remove("robust-lsp")?
+            }
+            value => value,
+        };
+
+        match serde_json::from_value(value) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                tracing::warn!("Failed to read the settings: {err}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::{rules::default_severity, DiagnosticResult, Diagnostics};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::csharp::is_relaxed_prototypes,
+    },
+};
+use std::{path::PathBuf, sync::Arc};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+pub const NOT_A_PROTOTYPE: &str = "not-a-prototype";
+
+/// Reports the classes of the file that look like prototypes but aren't indexed as them.
+pub struct CsharpDiagnostics {
+    context: Arc<Context>,
+    path: PathBuf,
+}
+
+impl Diagnostics for CsharpDiagnostics {
+    fn diagnostics(&self) -> DiagnosticResult {
+        let relaxed = is_relaxed_prototypes();
+        let lock = block_in_place(|| self.context.classes.blocking_read());
+
+        let mut diagnostics = lock
+            .iter()
+            .filter(|c| c.index().0 == self.path)
+            .filter_map(|class| {
+                let has_attribute = class.attributes.contains("Prototype");
+                let implements = class.implements_prototype();
+
+                let problem = match (has_attribute, implements) {
+                    (true, false) => "has the `[Prototype]` attribute but doesn't implement `IPrototype`",
+                    (false, true) if !class.modifiers.contains("abstract") => {
+                        "implements `IPrototype` but has no `[Prototype]` attribute"
+                    }
+                    _ => return None,
+                };
+
+                let (severity, consequence) = if relaxed {
+                    (
+                        Some(DiagnosticSeverity::INFORMATION),
+                        "but it is still indexed as a prototype because `prototypes.relaxed` is enabled",
+                    )
+                } else {
+                    (
+                        default_severity(NOT_A_PROTOTYPE),
+                        "so it isn't indexed as a prototype, enable `prototypes.relaxed` to index it anyway",
+                    )
+                };
+
+                Some(Diagnostic {
+                    range: to_lsp_range(class.index())?,
+                    severity,
+                    code: Some(NumberOrString::String(NOT_A_PROTOTYPE.to_owned())),
+                    source: Some("robust-lsp".to_owned()),
+                    message: format!("`{}` {problem}, {consequence}.", class.name),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        diagnostics.sort_by_key(|d| d.range.start);
+        diagnostics
+    }
+}
+
+impl CsharpDiagnostics {
+    pub fn new(context: Arc<Context>, path: PathBuf) -> Self {
+        Self { context, path }
+    }
+}
+
+fn to_lsp_range(index: &DefinitionIndex) -> Option<Range> {
+    let range = index.1?;
+    Some(Range::new(
+        Position::new(
+            range.start_point.row as u32,
+            range.start_point.column as u32,
+        ),
+        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+    ))
+}
@@ -0,0 +1,276 @@
+use super::{rules::default_severity, DiagnosticResult, Diagnostics};
+use crate::{
+    backend::Context,
+    parse::{
+        common::Index,
+        fluent::{render_pattern, span_to_range},
+        structs::fluent::{locale_of, FluentKeyKind},
+    },
+};
+use fluent_syntax::ast::{
+    Entry, Expression, Identifier, InlineExpression, Pattern, PatternElement, Resource,
+};
+use ropey::Rope;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString, Position, Range};
+
+pub const UNKNOWN_TERM: &str = "unknown-term";
+pub const CONFLICTING_DUPLICATE: &str = "conflicting-duplicate";
+pub const UNKNOWN_ENTITY_LOCALE: &str = "unknown-entity-locale";
+
+pub struct FluentDiagnostics {
+    context: Arc<Context>,
+    path: PathBuf,
+    src: String,
+}
+
+impl Diagnostics for FluentDiagnostics {
+    fn diagnostics(&self) -> DiagnosticResult {
+        let resource = match fluent_syntax::parser::parse(self.src.as_str()) {
+            Ok(resource) => resource,
+            Err((resource, _)) => resource,
+        };
+
+        let mut defined = HashSet::new();
+        let mut references = vec![];
+        let mut diagnostics = self.conflicting_duplicates(&resource);
+        diagnostics.extend(self.unknown_entity_keys(&resource));
+
+        for entry in resource.body.iter() {
+            let patterns = match entry {
+                Entry::Message(msg) => msg
+                    .value
+                    .iter()
+                    .chain(msg.attributes.iter().map(|a| &a.value))
+                    .collect::<Vec<_>>(),
+                Entry::Term(term) => {
+                    defined.insert(term.id.name);
+                    std::iter::once(&term.value)
+                        .chain(term.attributes.iter().map(|a| &a.value))
+                        .collect()
+                }
+                _ => continue,
+            };
+
+            for pattern in patterns {
+                collect_from_pattern(pattern, &mut references);
+            }
+        }
+
+        let locales = block_in_place(|| self.context.locales.blocking_read());
+        if locales.is_empty() {
+            return diagnostics;
+        }
+
+        let unknown_terms = references
+            .into_iter()
+            .filter(|id| !defined.contains(id.name))
+            .filter(|id| !locales.contains(id.name, FluentKeyKind::Term))
+            .map(|id| {
+                let range = span_to_range(&self.src, &id.span);
+                Diagnostic {
+                    range: Range::new(
+                        Position::new(
+                            range.start_point.row as u32,
+                            range.start_point.column as u32,
+                        ),
+                        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+                    ),
+                    severity: default_severity(UNKNOWN_TERM),
+                    code: Some(NumberOrString::String(UNKNOWN_TERM.to_owned())),
+                    source: Some("robust-lsp".to_owned()),
+                    message: format!("Unknown term `-{}`", id.name),
+                    ..Default::default()
+                }
+            });
+        diagnostics.extend(unknown_terms);
+
+        diagnostics
+    }
+}
+
+impl FluentDiagnostics {
+    pub fn new(context: Arc<Context>, path: PathBuf, rope: &Rope) -> Self {
+        Self {
+            context,
+            path,
+            src: rope.to_string(),
+        }
+    }
+
+    /// Flags the messages named like the locale of an entity, e.g. `ent-Crowbar`,
+    /// whose id isn't the id of any entity, often left behind by a rename.
+    fn unknown_entity_keys(&self, resource: &Resource<&str>) -> Vec<Diagnostic> {
+        let prefix = block_in_place(|| self.context.config.blocking_read())
+            .diagnostics
+            .entity_locale
+            .prefix
+            .clone();
+        if prefix.is_empty() {
+            return vec![];
+        }
+
+        let prototypes = block_in_place(|| self.context.prototypes.blocking_read());
+        let entities = prototypes
+            .iter()
+            .filter(|p| p.prototype == "entity")
+            .map(|p| p.id.as_str())
+            .collect::<HashSet<_>>();
+        // Nothing is known about the entities until the prototypes are indexed.
+        if entities.is_empty() {
+            return vec![];
+        }
+
+        resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Message(msg) => Some(&msg.id),
+                _ => None,
+            })
+            .filter_map(|id| Some((id, id.name.strip_prefix(prefix.as_str())?)))
+            .filter(|(_, entity)| !entities.contains(entity))
+            .map(|(id, entity)| {
+                let range = span_to_range(&self.src, &id.span);
+                Diagnostic {
+                    range: Range::new(
+                        Position::new(
+                            range.start_point.row as u32,
+                            range.start_point.column as u32,
+                        ),
+                        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+                    ),
+                    severity: default_severity(UNKNOWN_ENTITY_LOCALE),
+                    code: Some(NumberOrString::String(UNKNOWN_ENTITY_LOCALE.to_owned())),
+                    source: Some("robust-lsp".to_owned()),
+                    message: format!("No entity has the id `{entity}` of `{}`.", id.name),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Flags the keys defined again in the same locale with a different text, which is
+    /// likely a merge mistake. Definitions with the same text are left alone.
+    fn conflicting_duplicates(&self, resource: &Resource<&str>) -> Vec<Diagnostic> {
+        let locale = locale_of(&self.path).unwrap_or_default();
+        let locales = block_in_place(|| self.context.locales.blocking_read());
+
+        let mut defined = HashMap::new();
+        let mut diagnostics = vec![];
+        for entry in resource.body.iter() {
+            let (id, value, kind, prefix) = match entry {
+                Entry::Message(msg) => match &msg.value {
+                    Some(value) => (&msg.id, value, FluentKeyKind::Message, ""),
+                    None => continue,
+                },
+                Entry::Term(term) => (&term.id, &term.value, FluentKeyKind::Term, "-"),
+                _ => continue,
+            };
+            let text = render_pattern(value);
+            let range = span_to_range(&self.src, &id.span);
+
+            // The current file is checked as it is written, not as it was indexed.
+            let conflict = match defined.get(&(id.name, kind)) {
+                Some((first_text, line)) if *first_text != text => {
+                    Some(format!("on line {}", line + 1))
+                }
+                Some(_) => None,
+                None => locales
+                    .get(id.name, kind)
+                    .iter()
+                    .filter(|k| k.locale == locale)
+                    .filter(|k| k.index().0 != self.path && k.value != text)
+                    .map(|k| format!("in {}", k.index().0.display()))
+                    .min(),
+            };
+            defined
+                .entry((id.name, kind))
+                .or_insert((text, range.start_point.row));
+
+            if let Some(conflict) = conflict {
+                let name = format!("{prefix}{}", id.name);
+                diagnostics.push(Diagnostic {
+                    range: Range::new(
+                        Position::new(
+                            range.start_point.row as u32,
+                            range.start_point.column as u32,
+                        ),
+                        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+                    ),
+                    severity: default_severity(CONFLICTING_DUPLICATE),
+                    code: Some(NumberOrString::String(CONFLICTING_DUPLICATE.to_owned())),
+                    source: Some("robust-lsp".to_owned()),
+                    message: format!("`{name}` is also defined {conflict} with a different text."),
+                    ..Default::default()
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+fn collect_from_pattern<'a, 's>(
+    pattern: &'a Pattern<&'s str>,
+    references: &mut Vec<&'a Identifier<&'s str>>,
+) {
+    for element in pattern.elements.iter() {
+        if let PatternElement::Placeable { expression } = element {
+            collect_from_expression(expression, references);
+        }
+    }
+}
+
+fn collect_from_expression<'a, 's>(
+    expression: &'a Expression<&'s str>,
+    references: &mut Vec<&'a Identifier<&'s str>>,
+) {
+    match expression {
+        Expression::Inline(inline, ..) => collect_from_inline(inline, references),
+        Expression::Select { selector, variants } => {
+            collect_from_inline(selector, references);
+            for variant in variants {
+                collect_from_pattern(&variant.value, references);
+            }
+        }
+    }
+}
+
+fn collect_from_inline<'a, 's>(
+    inline: &'a InlineExpression<&'s str>,
+    references: &mut Vec<&'a Identifier<&'s str>>,
+) {
+    match inline {
+        InlineExpression::TermReference { id, arguments, .. } => {
+            references.push(id);
+            if let Some(arguments) = arguments {
+                for arg in arguments
+                    .positional
+                    .iter()
+                    .chain(arguments.named.iter().map(|n| &n.value))
+                {
+                    collect_from_inline(arg, references);
+                }
+            }
+        }
+        InlineExpression::FunctionReference { arguments, .. } => {
+            for arg in arguments
+                .positional
+                .iter()
+                .chain(arguments.named.iter().map(|n| &n.value))
+            {
+                collect_from_inline(arg, references);
+            }
+        }
+        InlineExpression::Placeable { expression } => {
+            collect_from_expression(expression, references)
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,12 @@
+#[cfg(feature = "csharp")]
+pub mod csharp;
+#[cfg(feature = "fluent")]
+pub mod fluent;
+pub mod rules;
+pub mod yaml;
+
+pub type DiagnosticResult = Vec<tower_lsp::lsp_types::Diagnostic>;
+
+pub trait Diagnostics {
+    fn diagnostics(&self) -> DiagnosticResult;
+}
@@ -0,0 +1,174 @@
+#[cfg(feature = "csharp")]
+use super::csharp::NOT_A_PROTOTYPE;
+#[cfg(feature = "fluent")]
+use super::fluent::{CONFLICTING_DUPLICATE, UNKNOWN_ENTITY_LOCALE, UNKNOWN_TERM};
+use super::yaml::{
+    DEPRECATED, DUPLICATE_FIELD, DUPLICATE_ID, ID_CASE, MISSING_DEPENDENCY, MISSING_ENTITY_LOCALE,
+    MISSING_LOCALE, MISSING_REQUIRED, REDUNDANT_DEFAULT, RENAMED, SERVER_ONLY_FIELD,
+    TAB_INDENTATION, UNKNOWN_COMPONENT, UNKNOWN_GRAPH_NODE, UNKNOWN_PROTOTYPE,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{CodeDescription, Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+
+/// The page that describes the rules, linked from the code of every diagnostic.
+const RULES_URL: &str = "https://github.com/Ertanic/robust-lsp#diagnostic-rules";
+
+/// A kind of diagnostic, its id is the code of the diagnostics it reports.
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: DiagnosticSeverity,
+    pub description: &'static str,
+}
+
+pub static RULES: &[Rule] = &[
+    Rule {
+        id: DUPLICATE_ID,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A prototype id is defined more than once.",
+    },
+    Rule {
+        id: UNKNOWN_COMPONENT,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A component isn't declared in the C# code.",
+    },
+    Rule {
+        id: UNKNOWN_PROTOTYPE,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A dictionary key or a construction graph isn't a known prototype.",
+    },
+    Rule {
+        id: UNKNOWN_GRAPH_NODE,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A construction `node` isn't one of the nodes of its `graph`.",
+    },
+    Rule {
+        id: MISSING_REQUIRED,
+        severity: DiagnosticSeverity::ERROR,
+        description: "The `id` or a required field is missing.",
+    },
+    Rule {
+        id: TAB_INDENTATION,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A line is indented with tabs, the rest of the file isn't checked then.",
+    },
+    Rule {
+        id: DUPLICATE_FIELD,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A key is specified more than once in the same mapping.",
+    },
+    Rule {
+        id: MISSING_LOCALE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A locale key isn't defined in any Fluent file.",
+    },
+    Rule {
+        id: MISSING_ENTITY_LOCALE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "An entity has neither a name or description nor the locale keys.",
+    },
+    Rule {
+        id: MISSING_DEPENDENCY,
+        severity: DiagnosticSeverity::WARNING,
+        description: "An entity lacks the components another of its components depends on.",
+    },
+    Rule {
+        id: SERVER_ONLY_FIELD,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A server only field is set in the resources of a client project.",
+    },
+    Rule {
+        id: DEPRECATED,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A component or field is marked `[Obsolete]`.",
+    },
+    Rule {
+        id: RENAMED,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A component or field has been renamed.",
+    },
+    Rule {
+        id: ID_CASE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A prototype id isn't written in the case set for its kind.",
+    },
+    Rule {
+        id: REDUNDANT_DEFAULT,
+        severity: DiagnosticSeverity::HINT,
+        description: "A value equals the default of the C# field.",
+    },
+    #[cfg(feature = "fluent")]
+    Rule {
+        id: UNKNOWN_TERM,
+        severity: DiagnosticSeverity::ERROR,
+        description: "A Fluent term reference has no definition.",
+    },
+    #[cfg(feature = "fluent")]
+    Rule {
+        id: CONFLICTING_DUPLICATE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A locale key is defined again in the same locale with a different text.",
+    },
+    #[cfg(feature = "fluent")]
+    Rule {
+        id: UNKNOWN_ENTITY_LOCALE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A locale key named after an entity, e.g. `ent-Crowbar`, names no entity.",
+    },
+    #[cfg(feature = "csharp")]
+    Rule {
+        id: NOT_A_PROTOTYPE,
+        severity: DiagnosticSeverity::WARNING,
+        description: "A C# class has only one of `[Prototype]` and `IPrototype`.",
+    },
+];
+
+/// The severity a rule is reported with, set in `diagnostics.rules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Off,
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+pub fn get_rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.id == id)
+}
+
+/// The severity the providers report the rule with, `diagnostics.rules` may override it.
+pub fn default_severity(id: &str) -> Option<DiagnosticSeverity> {
+    get_rule(id).map(|rule| rule.severity)
+}
+
+/// Drops the diagnostics of the disabled rules, changes the severities set in the settings
+/// and links every diagnostic of a known rule to its description.
+pub fn apply_rules(diagnostics: &mut Vec<Diagnostic>, settings: &HashMap<String, RuleSeverity>) {
+    diagnostics.retain(|d| code(d).and_then(|c| settings.get(c)) != Some(&RuleSeverity::Off));
+
+    for diagnostic in diagnostics.iter_mut() {
+        let Some(rule) = code(diagnostic).and_then(get_rule) else {
+            continue;
+        };
+
+        diagnostic.severity = match settings.get(rule.id) {
+            Some(RuleSeverity::Error) => Some(DiagnosticSeverity::ERROR),
+            Some(RuleSeverity::Warning) => Some(DiagnosticSeverity::WARNING),
+            Some(RuleSeverity::Information) => Some(DiagnosticSeverity::INFORMATION),
+            Some(RuleSeverity::Hint) => Some(DiagnosticSeverity::HINT),
+            _ => diagnostic.severity.or(Some(rule.severity)),
+        };
+        diagnostic.code_description = Url::parse(RULES_URL)
+            .ok()
+            .map(|href| CodeDescription { href });
+    }
+}
+
+fn code(diagnostic: &Diagnostic) -> Option<&str> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code),
+        _ => None,
+    }
+}
@@ -0,0 +1,1115 @@
+use super::{rules::default_severity, DiagnosticResult, Diagnostics};
+use crate::{
+    backend::Context,
+    config::{EntityLocaleConfig, IdCase},
+    parse::{
+        common::Index,
+        structs::{
+            csharp::{CsharpClassField, CsharpLiteral, ReflectionManager},
+            fluent::FluentKeyKind,
+            yaml::YamlPrototype,
+        },
+        yaml::get_block_sequence_nodes,
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::block,
+};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use ropey::Rope;
+use serde_json::json;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use stringcase::camel_case;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticTag, NumberOrString, Position, Range};
+use tree_sitter::{Node, Parser, Tree};
+
+pub const DUPLICATE_ID: &str = "duplicate-id";
+pub const UNKNOWN_COMPONENT: &str = "unknown-component";
+pub const MISSING_LOCALE: &str = "missing-locale";
+pub const DEPRECATED: &str = "deprecated";
+pub const REDUNDANT_DEFAULT: &str = "redundant-default";
+pub const UNKNOWN_PROTOTYPE: &str = "unknown-prototype";
+pub const MISSING_REQUIRED: &str = "missing-required";
+pub const MISSING_ENTITY_LOCALE: &str = "missing-entity-locale";
+pub const RENAMED: &str = "renamed";
+pub const MISSING_DEPENDENCY: &str = "missing-dependency";
+pub const SERVER_ONLY_FIELD: &str = "server-only-field";
+pub const UNKNOWN_GRAPH_NODE: &str = "unknown-graph-node";
+pub const DUPLICATE_FIELD: &str = "duplicate-field";
+pub const ID_CASE: &str = "id-case";
+pub const TAB_INDENTATION: &str = "tab-indentation";
+
+/// Components renamed across the engine versions, the old names first.
+static RENAMED_COMPONENTS: &[(&str, &str)] = &[
+    ("HandVirtualItem", "VirtualItem"),
+    ("ItemCooldown", "UseDelay"),
+];
+/// Fields renamed across the engine versions, prefixed with their owner like in [`crate::config::RenamesConfig::fields`].
+static RENAMED_FIELDS: &[(&str, &str)] = &[("Clothing.Slots", "slots")];
+
+/// A `# robust-lsp: ignore[rule-id, ...]` comment, it suppresses the listed rules,
+/// or all of them without the brackets, on its line or in the whole prototype
+/// when it is on the first line of the prototype.
+struct IgnoreDirective {
+    start_line: u32,
+    end_line: u32,
+    rules: Vec<String>,
+}
+
+impl IgnoreDirective {
+    fn parse(comment: &str) -> Option<Vec<String>> {
+        let rest = comment
+            .trim_start_matches('#')
+            .trim()
+            .strip_prefix("robust-lsp:")?
+            .trim()
+            .strip_prefix("ignore")?;
+
+        let Some(rules) = rest.strip_prefix('[') else {
+            return rest.trim().is_empty().then(Vec::new);
+        };
+        let rules = &rules[..rules.find(']')?];
+        Some(
+            rules
+                .split(',')
+                .map(str::trim)
+                .filter(|r| !r.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+
+    fn suppresses(&self, diagnostic: &Diagnostic) -> bool {
+        let line = diagnostic.range.start.line;
+        if line < self.start_line || line > self.end_line {
+            return false;
+        }
+
+        match &diagnostic.code {
+            _ if self.rules.is_empty() => true,
+            Some(NumberOrString::String(code)) => self.rules.contains(code),
+            _ => false,
+        }
+    }
+}
+
+pub struct YamlDiagnostics {
+    context: Arc<Context>,
+    path: PathBuf,
+    src: String,
+    tree: Tree,
+}
+
+impl Diagnostics for YamlDiagnostics {
+    fn diagnostics(&self) -> DiagnosticResult {
+        // The grammar can't nest the lines indented with tabs, the other checks would misfire.
+        let tabs = self.check_tab_indentation();
+        if !tabs.is_empty() {
+            return tabs;
+        }
+
+        let root_node = self.tree.root_node();
+        let block_sequences = get_block_sequence_nodes(root_node);
+        if block_sequences.is_empty() {
+            return vec![];
+        }
+
+        let mut diagnostics = vec![];
+        let mut defined = HashMap::new();
+        let (entity_locale, dependencies, id_cases) = {
+            let config = block_in_place(|| self.context.config.blocking_read());
+            (
+                config.diagnostics.entity_locale.clone(),
+                config.diagnostics.component_dependencies.clone(),
+                config.diagnostics.id_cases.clone(),
+            )
+        };
+
+        let block_sequence_items = block_sequences
+            .into_iter()
+            .flat_map(|n| (0..n.named_child_count()).filter_map(move |i| n.named_child(i)));
+        for block_sequence_item in block_sequence_items {
+            let Some(block_mapping) = find_child_node(block_sequence_item, "block_node")
+                .and_then(|n| find_child_node(n, "block_mapping"))
+            else {
+                continue;
+            };
+
+            let Some(proto_name) = self.get_value(&block_mapping, "type") else {
+                continue;
+            };
+
+            if let Some(id_node) = self
+                .get_field(&block_mapping, "id")
+                .and_then(|n| n.child_by_field_name("value"))
+            {
+                self.check_duplicate_id(proto_name, id_node, &mut defined, &mut diagnostics);
+                if let Some(case) = id_cases.get(proto_name) {
+                    self.check_id_case(proto_name, id_node, *case, &mut diagnostics);
+                }
+
+                if proto_name == "entity" && entity_locale.enabled {
+                    self.check_entity_locale(
+                        block_mapping,
+                        id_node,
+                        &entity_locale,
+                        &mut diagnostics,
+                    );
+                }
+                if proto_name == "entity" && !dependencies.is_empty() {
+                    self.check_component_dependencies(
+                        block_mapping,
+                        id_node,
+                        &dependencies,
+                        &mut diagnostics,
+                    );
+                }
+            }
+        }
+
+        let mut block_mappings = vec![];
+        collect_block_mappings(root_node, &mut block_mappings);
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        for block_mapping in block_mappings {
+            self.check_duplicate_fields(block_mapping, &mut diagnostics);
+            self.check_mapping(&resolver, block_mapping, &mut diagnostics);
+        }
+
+        let directives = self.ignore_directives();
+        diagnostics.retain(|d| !directives.iter().any(|directive| directive.suppresses(d)));
+        diagnostics
+    }
+}
+
+impl YamlDiagnostics {
+    pub fn new(context: Arc<Context>, path: PathBuf, rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            path,
+            src,
+            tree,
+        }
+    }
+
+    fn check_tab_indentation(&self) -> DiagnosticResult {
+        tab_indented_lines(&self.src)
+            .map(|(line, indent)| Diagnostic {
+                range: Range::new(
+                    Position::new(line, 0),
+                    Position::new(line, indent.len() as u32),
+                ),
+                severity: default_severity(TAB_INDENTATION),
+                code: Some(NumberOrString::String(TAB_INDENTATION.to_owned())),
+                source: Some("robust-lsp".to_owned()),
+                message: "Tabs are not valid YAML indentation.".to_owned(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn check_duplicate_id<'a>(
+        &'a self,
+        proto_name: &'a str,
+        id_node: Node<'a>,
+        defined: &mut HashMap<(&'a str, &'a str), Node<'a>>,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let Ok(id) = id_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        if let Some(first) = defined.insert((proto_name, id), id_node) {
+            diagnostics.push(diagnostic(
+                id_node,
+                DUPLICATE_ID,
+                format!(
+                    "Prototype `{id}` of kind `{proto_name}` is already defined on line {}.",
+                    first.start_position().row + 1
+                ),
+            ));
+            return;
+        }
+
+        // Definitions from different override roots are intentional overrides.
+        let config = block_in_place(|| self.context.config.blocking_read());
+        let priority = config.prototypes.priority(&self.path);
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let dummy = YamlPrototype::dummy(proto_name, id);
+        let Some(proto) = lock.get(&dummy) else {
+            return;
+        };
+
+        let other = proto.definitions().find(|d| {
+            d.index().0 != self.path && config.prototypes.priority(&d.index().0) == priority
+        });
+        if let Some(other) = other {
+            // The index keeps the winning definition first, the rest are shadowed by it.
+            let winner = match &proto.index().0 {
+                path if *path == self.path => "this definition".to_owned(),
+                path => format!("the one in {}", path.display()),
+            };
+            diagnostics.push(diagnostic(
+                id_node,
+                DUPLICATE_ID,
+                format!(
+                    "Prototype `{id}` of kind `{proto_name}` is also defined in {}, {winner} takes precedence.",
+                    other.index().0.display()
+                ),
+            ));
+        }
+    }
+
+    /// Reports the ids that don't follow the convention of their prototype kind
+    /// and suggests the id written in it.
+    fn check_id_case(
+        &self,
+        proto_name: &str,
+        id_node: Node,
+        case: IdCase,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let Ok(id) = id_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+        if case.matches(id) {
+            return;
+        }
+
+        let new_name = case.convert(id);
+        diagnostics.push(Diagnostic {
+            data: (!new_name.is_empty() && new_name != id).then(|| json!({ "newName": new_name })),
+            ..diagnostic(
+                id_node,
+                ID_CASE,
+                format!("Id `{id}` of kind `{proto_name}` isn't written in {case}."),
+            )
+        });
+    }
+
+    /// Reports the keys specified again in the same mapping, only the last value is kept.
+    fn check_duplicate_fields(&self, block_mapping: Node, diagnostics: &mut DiagnosticResult) {
+        let mut specified = HashMap::new();
+
+        for i in 0..block_mapping.named_child_count() {
+            let Some(key_node) = block_mapping
+                .named_child(i)
+                .and_then(|n| n.child_by_field_name("key"))
+            else {
+                continue;
+            };
+            let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+
+            if let Some(first) = specified.insert(key, key_node) {
+                diagnostics.push(diagnostic(
+                    key_node,
+                    DUPLICATE_FIELD,
+                    format!(
+                        "Field `{key}` is already specified on line {}, this value replaces it.",
+                        first.start_position().row + 1
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Checks that the entity gets a name and a description either from
+    /// its fields or from the locale keys, of its own or of its parents.
+    fn check_entity_locale(
+        &self,
+        block_mapping: Node,
+        id_node: Node,
+        convention: &EntityLocaleConfig,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        if self.get_value(&block_mapping, "abstract") == Some("true") {
+            return;
+        }
+        let Ok(id) = id_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        let locales = block_in_place(|| self.context.locales.blocking_read());
+        if locales.is_empty() {
+            return;
+        }
+
+        let has_locale = |id: &str, attribute: Option<&str>| {
+            let key = format!("{}{id}", convention.prefix);
+            locales
+                .get(&key, FluentKeyKind::Message)
+                .iter()
+                .any(|k| attribute.is_none_or(|a| k.attributes.contains(a)))
+        };
+        let mut has_name = self.get_field(&block_mapping, "name").is_some() || has_locale(id, None);
+        let mut has_description = self.get_field(&block_mapping, "description").is_some()
+            || has_locale(id, Some(&convention.description_attribute));
+
+        if !(has_name && has_description) && self.get_field(&block_mapping, "parent").is_some() {
+            let prototypes = block_in_place(|| self.context.prototypes.blocking_read());
+            // The parents of an entity that isn't indexed yet are unknown.
+            let Some(proto) = prototypes.get(&YamlPrototype::dummy("entity", id)) else {
+                return;
+            };
+
+            let merged = proto.merge(&prototypes);
+            has_name |= merged.fields.contains_key("name")
+                || merged.ancestors.iter().any(|a| has_locale(a, None));
+            has_description |= merged.fields.contains_key("description")
+                || merged
+                    .ancestors
+                    .iter()
+                    .any(|a| has_locale(a, Some(&convention.description_attribute)));
+        }
+
+        let key = format!("{}{id}", convention.prefix);
+        let message = match (has_name, has_description) {
+            (true, true) => return,
+            (false, true) => {
+                format!("Entity `{id}` has no name, add the `name` field or the `{key}` locale key.")
+            }
+            (true, false) => format!(
+                "Entity `{id}` has no description, add the `description` field or the `.{}` attribute to `{key}`.",
+                convention.description_attribute
+            ),
+            (false, false) => format!(
+                "Entity `{id}` has no name and description, add the `name` and `description` fields or the `{key}` locale key with the `.{}` attribute.",
+                convention.description_attribute
+            ),
+        };
+
+        diagnostics.push(diagnostic(id_node, MISSING_ENTITY_LOCALE, message));
+    }
+
+    /// Checks that the components listed by the entity have the components they depend on,
+    /// either listed along with them or inherited from the parents.
+    fn check_component_dependencies(
+        &self,
+        block_mapping: Node,
+        id_node: Node,
+        dependencies: &HashMap<String, Vec<String>>,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        // Abstract entities are completed by their children.
+        if self.get_value(&block_mapping, "abstract") == Some("true") {
+            return;
+        }
+        let Ok(id) = id_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        let type_nodes = self
+            .get_field(&block_mapping, "components")
+            .and_then(|n| n.child_by_field_name("value"))
+            .and_then(|n| find_child_node(n, "block_sequence"))
+            .map(|sequence| {
+                (0..sequence.named_child_count())
+                    .filter_map(|i| sequence.named_child(i))
+                    .filter_map(|item| {
+                        let mapping = find_child_node(item, "block_node")
+                            .and_then(|n| find_child_node(n, "block_mapping"))?;
+                        self.get_field(&mapping, "type")?
+                            .child_by_field_name("value")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let mut components = type_nodes
+            .iter()
+            .filter_map(|n| n.utf8_text(self.src.as_bytes()).ok())
+            .map(|c| c.to_owned())
+            .collect::<Vec<_>>();
+
+        if self.get_field(&block_mapping, "parent").is_some() {
+            let prototypes = block_in_place(|| self.context.prototypes.blocking_read());
+            // The parents of an entity that isn't indexed yet are unknown.
+            let Some(proto) = prototypes.get(&YamlPrototype::dummy("entity", id)) else {
+                return;
+            };
+            components.extend(proto.merge(&prototypes).components);
+        }
+
+        for type_node in type_nodes {
+            let Ok(comp_name) = type_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+            let Some(required) = dependencies.get(comp_name) else {
+                continue;
+            };
+
+            for dependency in required.iter().filter(|d| !components.contains(d)) {
+                diagnostics.push(Diagnostic {
+                    data: Some(json!({ "component": dependency })),
+                    ..diagnostic(
+                        type_node,
+                        MISSING_DEPENDENCY,
+                        format!(
+                            "Component `{comp_name}` requires `{dependency}`, which entity `{id}` doesn't have."
+                        ),
+                    )
+                });
+            }
+        }
+    }
+
+    fn check_mapping(
+        &self,
+        resolver: &YamlSchemaResolver,
+        block_mapping: Node,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        // Whether the fields that aren't specified here may be inherited from the parents.
+        let mut inherited = false;
+        let owner_name;
+        let class = match resolver.resolve(block_mapping) {
+            Some(SchemaType::Component(comp)) => {
+                owner_name = comp.get_component_name();
+                inherited = self.is_component_inherited(block_mapping);
+                if owner_name == "Construction" {
+                    self.check_construction(block_mapping, diagnostics);
+                }
+                if let (Some(message), Some(comp_name)) = (
+                    comp.attributes.get_obsolete(),
+                    self.get_field(&block_mapping, "type")
+                        .and_then(|n| n.child_by_field_name("value")),
+                ) {
+                    diagnostics.push(deprecation(
+                        comp_name,
+                        format!(
+                            "Component `{}` is obsolete",
+                            comp_name.utf8_text(self.src.as_bytes()).unwrap_or_default()
+                        ),
+                        message,
+                    ));
+                }
+                comp.clone()
+            }
+            Some(SchemaType::Prototype(proto)) => {
+                owner_name = camel_case(&proto.get_prototype_name());
+                inherited = self.get_field(&block_mapping, "parent").is_some()
+                    || self.get_value(&block_mapping, "abstract") == Some("true");
+                proto.clone()
+            }
+            Some(SchemaType::DataDefinition(class)) => {
+                owner_name = class.name.clone();
+                class
+            }
+            Some(SchemaType::Dictionary(key_type, _)) => {
+                if let Some(prototype_name) =
+                    resolver.resolve_key_prototype(block_mapping, &key_type)
+                {
+                    self.check_dictionary_keys(block_mapping, &prototype_name, diagnostics);
+                }
+                return;
+            }
+            Some(_) => return,
+            None => {
+                if let Some(SchemaType::ComponentRegistry) =
+                    resolver.resolve_container(block_mapping)
+                {
+                    self.check_unknown_component(block_mapping, diagnostics);
+                }
+                return;
+            }
+        };
+
+        let reflection = ReflectionManager::new(&self.context);
+        let fields = block(|| reflection.get_fields(&class));
+        self.check_deprecated_fields(block_mapping, &fields, diagnostics);
+        self.check_renamed_fields(block_mapping, &owner_name, &fields, diagnostics);
+        self.check_locale_fields(block_mapping, &fields, diagnostics);
+        self.check_required_fields(block_mapping, &fields, inherited, diagnostics);
+
+        let (redundant_defaults, server_only_fields) = {
+            let config = block_in_place(|| self.context.config.blocking_read());
+            (
+                config.diagnostics.redundant_defaults,
+                config.diagnostics.server_only_fields,
+            )
+        };
+        if redundant_defaults {
+            self.check_redundant_defaults(block_mapping, &fields, diagnostics);
+        }
+        if server_only_fields && self.is_client_resource() {
+            self.check_server_only_fields(block_mapping, &fields, diagnostics);
+        }
+    }
+
+    /// Whether the file belongs to a client project, e.g. `Content.Client/Resources`.
+    fn is_client_resource(&self) -> bool {
+        self.path
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .any(|c| c.ends_with(".Client"))
+    }
+
+    /// Reports the fields only the server reads, the client never uses them.
+    fn check_server_only_fields(
+        &self,
+        block_mapping: Node,
+        fields: &[CsharpClassField],
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        for i in 0..block_mapping.named_child_count() {
+            let Some(key_node) = block_mapping
+                .named_child(i)
+                .and_then(|n| n.child_by_field_name("key"))
+            else {
+                continue;
+            };
+            let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+
+            if fields
+                .iter()
+                .any(|f| f.is_server_only() && f.get_data_field_name() == key)
+            {
+                diagnostics.push(diagnostic(
+                    key_node,
+                    SERVER_ONLY_FIELD,
+                    format!("`{key}` is read only by the server, the client ignores it."),
+                ));
+            }
+        }
+    }
+
+    fn check_unknown_component(&self, block_mapping: Node, diagnostics: &mut DiagnosticResult) {
+        let lock = block_in_place(|| self.context.classes.blocking_read());
+        if lock.is_empty() {
+            return;
+        }
+
+        let Some(type_node) = self
+            .get_field(&block_mapping, "type")
+            .and_then(|n| n.child_by_field_name("value"))
+        else {
+            return;
+        };
+        let Ok(comp_name) = type_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        // Components are often declared through shared base classes,
+        // so only the class name itself is a reliable sign of absence.
+        let class_name = format!("{comp_name}Component");
+        if lock.iter().any(|c| c.name == class_name) {
+            return;
+        }
+
+        let config = block_in_place(|| self.context.config.blocking_read());
+        let renames = &config.diagnostics.renames.components;
+        if let Some(new_name) = find_rename(renames, RENAMED_COMPONENTS, comp_name) {
+            diagnostics.push(renamed(
+                type_node,
+                format!("Component `{comp_name}` has been renamed to `{new_name}`."),
+                new_name,
+            ));
+        } else {
+            diagnostics.push(diagnostic(
+                type_node,
+                UNKNOWN_COMPONENT,
+                format!("Component `{comp_name}` is not found."),
+            ));
+        }
+    }
+
+    /// Checks that the `graph` of a construction component exists and has the `node`.
+    fn check_construction(&self, block_mapping: Node, diagnostics: &mut DiagnosticResult) {
+        let Some(graph_node) = self
+            .get_field(&block_mapping, "graph")
+            .and_then(|n| n.child_by_field_name("value"))
+        else {
+            return;
+        };
+        let Ok(graph) = graph_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        // Without any graphs the index is likely incomplete.
+        if !lock.par_iter().any(|p| p.prototype == "constructionGraph") {
+            return;
+        }
+
+        let Some(prototype) = lock.get(&YamlPrototype::dummy("constructionGraph", graph)) else {
+            diagnostics.push(diagnostic(
+                graph_node,
+                UNKNOWN_PROTOTYPE,
+                format!("Prototype `{graph}` of type `constructionGraph` is not found."),
+            ));
+            return;
+        };
+
+        let Some(node) = self
+            .get_field(&block_mapping, "node")
+            .and_then(|n| n.child_by_field_name("value"))
+        else {
+            return;
+        };
+        let Ok(name) = node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+
+        if !prototype.graph_nodes().iter().any(|n| n == name) {
+            diagnostics.push(diagnostic(
+                node,
+                UNKNOWN_GRAPH_NODE,
+                format!("Node `{name}` is not found in the construction graph `{graph}`."),
+            ));
+        }
+    }
+
+    /// Checks that the keys of a dictionary keyed by prototype ids are known ids.
+    fn check_dictionary_keys(
+        &self,
+        block_mapping: Node,
+        prototype_name: &str,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        // Without any prototypes of the kind the index is likely incomplete.
+        if !lock.par_iter().any(|p| p.prototype == prototype_name) {
+            return;
+        }
+
+        for i in 0..block_mapping.named_child_count() {
+            let block_mapping_pair = block_mapping.named_child(i).unwrap();
+            let Some(key_node) = block_mapping_pair.child_by_field_name("key") else {
+                continue;
+            };
+            let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+
+            if !lock.contains(&YamlPrototype::dummy(prototype_name, key)) {
+                diagnostics.push(diagnostic(
+                    key_node,
+                    UNKNOWN_PROTOTYPE,
+                    format!("Prototype `{key}` of type `{prototype_name}` is not found."),
+                ));
+            }
+        }
+    }
+
+    fn check_required_fields(
+        &self,
+        block_mapping: Node,
+        fields: &[CsharpClassField],
+        inherited: bool,
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let missing = fields
+            .iter()
+            .filter(|f| f.is_required())
+            // The id is the only required field that is never inherited.
+            .filter(|f| !inherited || f.attributes.contains("IdDataField"))
+            .map(|f| f.get_data_field_name())
+            .filter(|name| self.get_field(&block_mapping, name).is_none())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+
+        let Some(node) = self
+            .get_field(&block_mapping, "type")
+            .and_then(|n| n.child_by_field_name("value"))
+            .or_else(|| {
+                block_mapping
+                    .named_child(0)
+                    .and_then(|n| n.child_by_field_name("key"))
+            })
+        else {
+            return;
+        };
+
+        let message = match missing.as_slice() {
+            [name] => format!("Required field `{name}` is missing."),
+            names => format!(
+                "Required fields {} are missing.",
+                names
+                    .iter()
+                    .map(|n| format!("`{n}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        diagnostics.push(Diagnostic {
+            data: Some(json!({ "fields": missing })),
+            ..diagnostic(node, MISSING_REQUIRED, message)
+        });
+    }
+
+    /// Whether an ancestor of the entity declares the component too, so its fields are merged.
+    fn is_component_inherited(&self, block_mapping: Node) -> bool {
+        let Some(comp_name) = self.get_value(&block_mapping, "type") else {
+            return false;
+        };
+
+        let mut proto_mapping = block_mapping;
+        let mut node = block_mapping;
+        while let Some(parent) = node.parent() {
+            if parent.kind() == "block_mapping" {
+                proto_mapping = parent;
+            }
+            node = parent;
+        }
+
+        if self.get_field(&proto_mapping, "parent").is_none() {
+            return false;
+        }
+        let (Some(proto_name), Some(id)) = (
+            self.get_value(&proto_mapping, "type"),
+            self.get_value(&proto_mapping, "id"),
+        ) else {
+            return true;
+        };
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        match lock.get(&YamlPrototype::dummy(proto_name, id)) {
+            Some(proto) => {
+                let merged = proto.merge(&lock);
+                merged.ancestors.iter().any(|id| {
+                    lock.get(&YamlPrototype::dummy(proto_name, id))
+                        .is_some_and(|p| p.components.iter().any(|c| c == comp_name))
+                })
+            }
+            // The parents of a prototype that isn't indexed yet are unknown.
+            None => true,
+        }
+    }
+
+    fn check_deprecated_fields(
+        &self,
+        block_mapping: Node,
+        fields: &[CsharpClassField],
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        for i in 0..block_mapping.named_child_count() {
+            let block_mapping_pair = block_mapping.named_child(i).unwrap();
+            let Some(key_node) = block_mapping_pair.child_by_field_name("key") else {
+                continue;
+            };
+            let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+
+            let Some(message) = fields
+                .iter()
+                .find(|f| f.get_data_field_name() == key)
+                .and_then(|f| f.attributes.get_obsolete())
+            else {
+                continue;
+            };
+
+            diagnostics.push(deprecation(
+                key_node,
+                format!("Field `{key}` is obsolete"),
+                message,
+            ));
+        }
+    }
+
+    /// Reports the keys that aren't fields of the class but have been renamed to one of them.
+    fn check_renamed_fields(
+        &self,
+        block_mapping: Node,
+        owner_name: &str,
+        fields: &[CsharpClassField],
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let config = block_in_place(|| self.context.config.blocking_read());
+        let renames = &config.diagnostics.renames.fields;
+
+        for i in 0..block_mapping.named_child_count() {
+            let block_mapping_pair = block_mapping.named_child(i).unwrap();
+            let Some(key_node) = block_mapping_pair.child_by_field_name("key") else {
+                continue;
+            };
+            let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+            if fields.iter().any(|f| f.get_data_field_name() == key) {
+                continue;
+            }
+
+            let Some(new_name) =
+                find_rename(renames, RENAMED_FIELDS, &format!("{owner_name}.{key}"))
+            else {
+                continue;
+            };
+            if !fields.iter().any(|f| f.get_data_field_name() == new_name) {
+                continue;
+            }
+
+            diagnostics.push(renamed(
+                key_node,
+                format!("Field `{key}` has been renamed to `{new_name}`."),
+                new_name,
+            ));
+        }
+    }
+
+    fn check_locale_fields(
+        &self,
+        block_mapping: Node,
+        fields: &[CsharpClassField],
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        let locales = block_in_place(|| self.context.locales.blocking_read());
+        if locales.is_empty() {
+            return;
+        }
+
+        for i in 0..block_mapping.named_child_count() {
+            let block_mapping_pair = block_mapping.named_child(i).unwrap();
+            let (Some(key_node), Some(value_node)) = (
+                block_mapping_pair.child_by_field_name("key"),
+                block_mapping_pair.child_by_field_name("value"),
+            ) else {
+                continue;
+            };
+            let (Ok(key), Ok(value)) = (
+                key_node.utf8_text(self.src.as_bytes()),
+                value_node.utf8_text(self.src.as_bytes()),
+            ) else {
+                continue;
+            };
+
+            let is_locale = fields.iter().any(|f| {
+                f.get_data_field_name() == key && f.type_name.trim_end_matches('?') == "LocId"
+            });
+
+            if is_locale && !locales.contains(value, FluentKeyKind::Message) {
+                diagnostics.push(diagnostic(
+                    value_node,
+                    MISSING_LOCALE,
+                    format!("Locale key `{value}` is not found."),
+                ));
+            }
+        }
+    }
+
+    fn check_redundant_defaults(
+        &self,
+        block_mapping: Node,
+        fields: &[CsharpClassField],
+        diagnostics: &mut DiagnosticResult,
+    ) {
+        for i in 0..block_mapping.named_child_count() {
+            let block_mapping_pair = block_mapping.named_child(i).unwrap();
+            let (Some(key_node), Some(value_node)) = (
+                block_mapping_pair.child_by_field_name("key"),
+                block_mapping_pair.child_by_field_name("value"),
+            ) else {
+                continue;
+            };
+            let (Ok(key), Ok(value)) = (
+                key_node.utf8_text(self.src.as_bytes()),
+                value_node.utf8_text(self.src.as_bytes()),
+            ) else {
+                continue;
+            };
+
+            let Some(field) = fields.iter().find(|f| f.get_data_field_name() == key) else {
+                continue;
+            };
+            let (Some(default), Some(literal)) = (&field.default_value, field.default_literal())
+            else {
+                continue;
+            };
+
+            if is_default(value, literal) {
+                diagnostics.push(Diagnostic {
+                    tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                    ..diagnostic(
+                        block_mapping_pair,
+                        REDUNDANT_DEFAULT,
+                        format!("`{key}` is already `{default}` by default."),
+                    )
+                });
+            }
+        }
+    }
+
+    /// Collects the ignore directives of the file with the lines they apply to.
+    fn ignore_directives(&self) -> Vec<IgnoreDirective> {
+        let mut comments = vec![];
+        collect_comments(self.tree.root_node(), &mut comments);
+
+        // The lines of the prototypes, from the first one to the last one with the content.
+        let prototypes = get_block_sequence_nodes(self.tree.root_node())
+            .into_iter()
+            .flat_map(|n| (0..n.named_child_count()).filter_map(move |i| n.named_child(i)))
+            .map(|item| {
+                let end = item.end_position();
+                let end_line = match end.column {
+                    0 => end.row.saturating_sub(1),
+                    _ => end.row,
+                };
+                (
+                    item.start_position().row,
+                    end_line.max(item.start_position().row),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        comments
+            .into_iter()
+            .filter_map(|comment| {
+                let rules = IgnoreDirective::parse(comment.utf8_text(self.src.as_bytes()).ok()?)?;
+                let line = comment.start_position().row;
+                let end_line = prototypes.get(&line).copied().unwrap_or(line);
+                Some(IgnoreDirective {
+                    start_line: line as u32,
+                    end_line: end_line as u32,
+                    rules,
+                })
+            })
+            .collect()
+    }
+
+    fn get_value<'a>(&'a self, node: &Node<'a>, name: &str) -> Option<&'a str> {
+        self.get_field(node, name)?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()
+    }
+
+    fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
+        debug_assert_eq!(node.kind(), "block_mapping");
+
+        for i in 0..node.named_child_count() {
+            let field_node = node.named_child(i)?;
+            let key = field_node
+                .child_by_field_name("key")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?
+                .to_owned();
+
+            if key == name {
+                return Some(field_node);
+            }
+        }
+        None
+    }
+}
+
+fn diagnostic(node: Node, code: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range::new(
+            Position::new(
+                node.start_position().row as u32,
+                node.start_position().column as u32,
+            ),
+            Position::new(
+                node.end_position().row as u32,
+                node.end_position().column as u32,
+            ),
+        ),
+        severity: default_severity(code),
+        code: Some(NumberOrString::String(code.to_owned())),
+        source: Some("robust-lsp".to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// The lines whose indentation has tabs, with the indentation, blank lines are left alone.
+pub fn tab_indented_lines(src: &str) -> impl Iterator<Item = (u32, &str)> {
+    src.lines().enumerate().filter_map(|(line, text)| {
+        let content = text.trim_start_matches([' ', '\t']);
+        let indent = &text[..text.len() - content.len()];
+        (indent.contains('\t') && !content.is_empty()).then_some((line as u32, indent))
+    })
+}
+
+fn deprecation(node: Node, subject: String, message: String) -> Diagnostic {
+    let message = if message.is_empty() {
+        format!("{subject}.")
+    } else {
+        format!("{subject}: {message}")
+    };
+
+    Diagnostic {
+        tags: Some(vec![DiagnosticTag::DEPRECATED]),
+        ..diagnostic(node, DEPRECATED, message)
+    }
+}
+
+fn renamed(node: Node, message: String, new_name: String) -> Diagnostic {
+    Diagnostic {
+        tags: Some(vec![DiagnosticTag::DEPRECATED]),
+        data: Some(json!({ "newName": new_name })),
+        ..diagnostic(node, RENAMED, message)
+    }
+}
+
+/// Finds the new name in the settings first and then in the built-in renames.
+fn find_rename(
+    configured: &HashMap<String, String>,
+    builtin: &[(&str, &str)],
+    old: &str,
+) -> Option<String> {
+    configured.get(old).cloned().or_else(|| {
+        builtin
+            .iter()
+            .find(|(old_name, _)| *old_name == old)
+            .map(|(_, new_name)| new_name.to_string())
+    })
+}
+
+/// Compares a YAML scalar with a C# literal, e.g. `5` with `5f` or `Low` with `Priority.Low`.
+fn is_default(value: &str, default: CsharpLiteral) -> bool {
+    let value = value.trim_matches(|c| c == '"' || c == '\'');
+
+    match default {
+        CsharpLiteral::String(default) => value == default,
+        CsharpLiteral::Bool(default) => value == default.to_string(),
+        CsharpLiteral::Int(default) => value.parse::<f64>() == Ok(default as f64),
+        CsharpLiteral::Float(default) => value.parse::<f64>() == Ok(default),
+        // Enum members are written without the type name in YAML.
+        CsharpLiteral::EnumMember(member) => value == member,
+    }
+}
+
+fn collect_block_mappings<'a>(node: Node<'a>, block_mappings: &mut Vec<Node<'a>>) {
+    if node.kind() == "block_mapping" {
+        block_mappings.push(node);
+    }
+
+    for i in 0..node.named_child_count() {
+        collect_block_mappings(node.named_child(i).unwrap(), block_mappings);
+    }
+}
+
+fn collect_comments<'a>(node: Node<'a>, comments: &mut Vec<Node<'a>>) {
+    if node.kind() == "comment" {
+        comments.push(node);
+    }
+
+    for i in 0..node.named_child_count() {
+        collect_comments(node.named_child(i).unwrap(), comments);
+    }
+}
+
+fn find_child_node<'a>(node: Node<'a>, name: &'a str) -> Option<Node<'a>> {
+    let mut n = None;
+    for i in 0..node.named_child_count() {
+        let found_node = node.named_child(i).unwrap();
+        if found_node.kind() == name {
+            n = Some(found_node);
+            break;
+        }
+    }
+    n
+}
@@ -0,0 +1,384 @@
+//! The providers count the columns of the positions in bytes, the way tree-sitter does,
+//! while the client counts them in the negotiated encoding, UTF-16 by default.
+
+use crate::vfs::FileSystem;
+use ropey::Rope;
+use std::{borrow::Cow, collections::HashMap};
+use tower_lsp::lsp_types::{
+    CodeActionOrCommand, CodeLens, CompletionItem, CompletionResponse, CompletionTextEdit,
+    Diagnostic, DocumentChangeOperation, DocumentChanges, GotoDefinitionResponse, Hover, InlayHint,
+    Location, LocationLink, OneOf, Position, PositionEncodingKind, PrepareRenameResponse, Range,
+    SemanticToken, SemanticTokens, TextEdit, Url, WorkspaceEdit,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Picks UTF-8 when the client offers it, since it needs no conversion,
+    /// then UTF-32 and falls back to UTF-16 that every client supports.
+    pub fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        let offered = offered.unwrap_or_default();
+        [Self::Utf8, Self::Utf32]
+            .into_iter()
+            .find(|e| offered.contains(&e.kind()))
+            .unwrap_or(Self::Utf16)
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    /// The byte offset of the column counted in this encoding within the line.
+    /// Columns past the end of the line are kept past it.
+    pub fn to_byte_column(self, line: &str, character: u32) -> usize {
+        let character = character as usize;
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= character {
+                return i;
+            }
+            units += match self {
+                Self::Utf8 => c.len_utf8(),
+                Self::Utf16 => c.len_utf16(),
+                Self::Utf32 => 1,
+            };
+        }
+        line.len() + character.saturating_sub(units)
+    }
+
+    /// The column of the byte offset within the line counted in this encoding.
+    /// Offsets past the end of the line are kept past it.
+    pub fn from_byte_column(self, line: &str, byte: usize) -> u32 {
+        let mut end = byte.min(line.len());
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let head = &line[..end];
+        let units = match self {
+            Self::Utf8 => end,
+            Self::Utf16 => head.encode_utf16().count(),
+            Self::Utf32 => head.chars().count(),
+        };
+        (units + byte.saturating_sub(line.len())) as u32
+    }
+
+    /// The char index of the position counted in this encoding, to edit the rope.
+    pub fn char_index(self, rope: &Rope, position: Position) -> usize {
+        let line = (position.line as usize).min(rope.len_lines());
+        let Some(text) = rope.get_line(line).map(Cow::from) else {
+            return rope.len_chars();
+        };
+
+        let byte = self
+            .to_byte_column(&text, position.character)
+            .min(text.len());
+        rope.line_to_char(line) + text[..byte].chars().count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the client encoding to the byte columns.
+    FromClient,
+    /// From the byte columns to the client encoding.
+    ToClient,
+}
+
+/// Converts the positions of the documents, the files that aren't opened are read once.
+pub struct PositionConverter<'a> {
+    encoding: PositionEncoding,
+    direction: Direction,
+    opened: &'a HashMap<Url, Rope>,
+    fs: &'a dyn FileSystem,
+    read: HashMap<Url, Option<Rope>>,
+}
+
+impl<'a> PositionConverter<'a> {
+    pub fn new(
+        encoding: PositionEncoding,
+        direction: Direction,
+        opened: &'a HashMap<Url, Rope>,
+        fs: &'a dyn FileSystem,
+    ) -> Self {
+        Self {
+            encoding,
+            direction,
+            opened,
+            fs,
+            read: HashMap::new(),
+        }
+    }
+
+    pub fn position(&mut self, uri: &Url, position: Position) -> Position {
+        if self.encoding == PositionEncoding::Utf8 {
+            return position;
+        }
+
+        let (encoding, direction) = (self.encoding, self.direction);
+        let Some(line) = self
+            .rope(uri)
+            .and_then(|r| r.get_line(position.line as usize))
+            .map(Cow::from)
+        else {
+            return position;
+        };
+
+        let character = match direction {
+            Direction::FromClient => encoding.to_byte_column(&line, position.character) as u32,
+            Direction::ToClient => encoding.from_byte_column(&line, position.character as usize),
+        };
+        Position::new(position.line, character)
+    }
+
+    pub fn range(&mut self, uri: &Url, range: Range) -> Range {
+        Range::new(
+            self.position(uri, range.start),
+            self.position(uri, range.end),
+        )
+    }
+
+    fn rope(&mut self, uri: &Url) -> Option<&Rope> {
+        let opened = self.opened;
+        if let Some(rope) = opened.get(uri) {
+            return Some(rope);
+        }
+
+        let fs = self.fs;
+        self.read
+            .entry(uri.clone())
+            .or_insert_with(|| {
+                let path = uri.to_file_path().ok()?;
+                fs.read_to_string(&path).ok().map(|s| Rope::from_str(&s))
+            })
+            .as_ref()
+    }
+}
+
+/// Values exchanged with the client that hold positions.
+pub trait ConvertPositions {
+    /// The positions belong to the document of the uri, unless the value names its own.
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter);
+}
+
+impl ConvertPositions for Position {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        *self = converter.position(uri, *self);
+    }
+}
+
+impl ConvertPositions for Range {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        *self = converter.range(uri, *self);
+    }
+}
+
+impl<T: ConvertPositions> ConvertPositions for Option<T> {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        if let Some(value) = self {
+            value.convert(uri, converter);
+        }
+    }
+}
+
+impl<T: ConvertPositions> ConvertPositions for Vec<T> {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        for value in self {
+            value.convert(uri, converter);
+        }
+    }
+}
+
+impl ConvertPositions for TextEdit {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.range.convert(uri, converter);
+    }
+}
+
+impl ConvertPositions for Location {
+    fn convert(&mut self, _: &Url, converter: &mut PositionConverter) {
+        self.range = converter.range(&self.uri, self.range);
+    }
+}
+
+impl ConvertPositions for LocationLink {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.origin_selection_range.convert(uri, converter);
+        self.target_range = converter.range(&self.target_uri, self.target_range);
+        self.target_selection_range =
+            converter.range(&self.target_uri, self.target_selection_range);
+    }
+}
+
+impl ConvertPositions for Diagnostic {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.range.convert(uri, converter);
+        for info in self.related_information.iter_mut().flatten() {
+            info.location.convert(uri, converter);
+        }
+    }
+}
+
+impl ConvertPositions for GotoDefinitionResponse {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        match self {
+            Self::Scalar(location) => location.convert(uri, converter),
+            Self::Array(locations) => locations.convert(uri, converter),
+            Self::Link(links) => links.convert(uri, converter),
+        }
+    }
+}
+
+impl ConvertPositions for CompletionItem {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        match &mut self.text_edit {
+            Some(CompletionTextEdit::Edit(edit)) => edit.convert(uri, converter),
+            Some(CompletionTextEdit::InsertAndReplace(edit)) => {
+                edit.insert.convert(uri, converter);
+                edit.replace.convert(uri, converter);
+            }
+            None => {}
+        }
+        self.additional_text_edits.convert(uri, converter);
+    }
+}
+
+impl ConvertPositions for CompletionResponse {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        match self {
+            Self::Array(items) => items.convert(uri, converter),
+            Self::List(list) => list.items.convert(uri, converter),
+        }
+    }
+}
+
+impl ConvertPositions for Hover {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.range.convert(uri, converter);
+    }
+}
+
+impl ConvertPositions for InlayHint {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.position.convert(uri, converter);
+        self.text_edits.convert(uri, converter);
+    }
+}
+
+impl ConvertPositions for WorkspaceEdit {
+    fn convert(&mut self, _: &Url, converter: &mut PositionConverter) {
+        for (uri, edits) in self.changes.iter_mut().flatten() {
+            edits.convert(uri, converter);
+        }
+
+        let document_edits = match &mut self.document_changes {
+            Some(DocumentChanges::Edits(edits)) => edits.iter_mut().collect(),
+            Some(DocumentChanges::Operations(operations)) => operations
+                .iter_mut()
+                .filter_map(|o| match o {
+                    DocumentChangeOperation::Edit(edit) => Some(edit),
+                    DocumentChangeOperation::Op(_) => None,
+                })
+                .collect(),
+            None => vec![],
+        };
+        for document_edit in document_edits {
+            let uri = &document_edit.text_document.uri;
+            for edit in document_edit.edits.iter_mut() {
+                match edit {
+                    OneOf::Left(edit) => edit.convert(uri, converter),
+                    OneOf::Right(edit) => edit.text_edit.convert(uri, converter),
+                }
+            }
+        }
+    }
+}
+
+impl ConvertPositions for CodeActionOrCommand {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        if let Self::CodeAction(action) = self {
+            action.diagnostics.convert(uri, converter);
+            action.edit.convert(uri, converter);
+        }
+    }
+}
+
+impl ConvertPositions for CodeLens {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.range.convert(uri, converter);
+
+        // The position and the locations listed by `editor.action.showReferences`.
+        let Some(arguments) = self.command.as_mut().and_then(|c| c.arguments.as_mut()) else {
+            return;
+        };
+        if let [_, position, locations] = arguments.as_mut_slice() {
+            convert_value::<Position>(position, uri, converter);
+            convert_value::<Vec<Location>>(locations, uri, converter);
+        }
+    }
+}
+
+/// Converts the positions of a value that is passed as a command argument.
+fn convert_value<T>(value: &mut serde_json::Value, uri: &Url, converter: &mut PositionConverter)
+where
+    T: ConvertPositions + serde::Serialize + serde::de::DeserializeOwned,
+{
+    if let Ok(mut typed) = serde_json::from_value::<T>(value.clone()) {
+        typed.convert(uri, converter);
+        *value = serde_json::to_value(typed).unwrap_or_default();
+    }
+}
+
+impl ConvertPositions for PrepareRenameResponse {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        match self {
+            Self::Range(range) => range.convert(uri, converter),
+            Self::RangeWithPlaceholder { range, .. } => range.convert(uri, converter),
+            Self::DefaultBehavior { .. } => {}
+        }
+    }
+}
+
+impl ConvertPositions for SemanticTokens {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        let (mut line, mut start) = (0, 0);
+        let (mut new_line, mut new_start) = (0, 0);
+        for token in self.data.iter_mut() {
+            line += token.delta_line;
+            start = match token.delta_line {
+                0 => start + token.delta_start,
+                _ => token.delta_start,
+            };
+
+            let range = converter.range(
+                uri,
+                Range::new(
+                    Position::new(line, start),
+                    Position::new(line, start + token.length),
+                ),
+            );
+            *token = SemanticToken {
+                delta_line: line - new_line,
+                delta_start: match line == new_line {
+                    true => range.start.character - new_start,
+                    false => range.start.character,
+                },
+                length: range.end.character - range.start.character,
+                ..*token
+            };
+
+            new_line = line;
+            new_start = range.start.character;
+        }
+    }
+}
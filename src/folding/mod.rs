@@ -0,0 +1,7 @@
+pub mod yaml;
+
+pub type FoldingRangeResult = Option<Vec<tower_lsp::lsp_types::FoldingRange>>;
+
+pub trait FoldingRange {
+    fn folding_range(&self) -> FoldingRangeResult;
+}
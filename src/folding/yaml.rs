@@ -0,0 +1,69 @@
+use super::{FoldingRange, FoldingRangeResult};
+use ropey::Rope;
+use tower_lsp::lsp_types::{self, FoldingRangeKind};
+use tree_sitter::{Node, Parser, Tree};
+
+pub struct YamlFoldingRange {
+    src: String,
+    tree: Tree,
+}
+
+impl FoldingRange for YamlFoldingRange {
+    fn folding_range(&self) -> FoldingRangeResult {
+        let mut ranges = vec![];
+        self.collect_ranges(self.tree.root_node(), &mut ranges);
+        // An entry and its first field can start on the same line, e.g. `- components:`.
+        ranges.dedup_by_key(|r| r.start_line);
+
+        if ranges.is_empty() {
+            None
+        } else {
+            Some(ranges)
+        }
+    }
+}
+
+impl YamlFoldingRange {
+    pub fn new(rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self { src, tree }
+    }
+
+    /// Folds every prototype, component entry and nested field that spans several lines.
+    fn collect_ranges(&self, node: Node, ranges: &mut Vec<lsp_types::FoldingRange>) {
+        if matches!(node.kind(), "block_sequence_item" | "block_mapping_pair") {
+            let start_line = node.start_position().row;
+            let end_line = self.end_line(node);
+
+            if end_line > start_line {
+                ranges.push(lsp_types::FoldingRange {
+                    start_line: start_line as u32,
+                    end_line: end_line as u32,
+                    kind: Some(FoldingRangeKind::Region),
+                    ..Default::default()
+                });
+            }
+        }
+
+        for i in 0..node.named_child_count() {
+            self.collect_ranges(node.named_child(i).unwrap(), ranges);
+        }
+    }
+
+    /// The last line with the content of the node, nodes may end at the start of the next line.
+    fn end_line(&self, node: Node) -> usize {
+        let end = node.end_position();
+        let text = &self.src[node.byte_range()];
+
+        if end.column == 0 || text.ends_with('\n') {
+            end.row.saturating_sub(1)
+        } else {
+            end.row
+        }
+    }
+}
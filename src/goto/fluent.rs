@@ -0,0 +1,187 @@
+use super::{GotoDefinition, GotoDefinitionResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        fluent::span_to_range,
+        structs::fluent::{locale_of, preferred_definitions, FluentKeyKind},
+    },
+    utils::position_offset,
+};
+use fluent_syntax::ast::{
+    Entry, Expression, Identifier, InlineExpression, Pattern, PatternElement, Resource,
+};
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, GotoDefinitionResponse, LocationLink, Position, Url};
+
+pub struct FluentGotoDefinition {
+    context: Arc<Context>,
+    uri: Url,
+    position: Position,
+    src: String,
+}
+
+impl GotoDefinition for FluentGotoDefinition {
+    fn goto_definition(&self) -> GotoDefinitionResult {
+        let offset = position_offset(&self.src, self.position)?;
+        let resource = match fluent_syntax::parser::parse(self.src.as_str()) {
+            Ok(resource) => resource,
+            // Broken entries are skipped, the rest of the file is still usable.
+            Err((resource, _)) => resource,
+        };
+
+        let (id, kind) = resource.body.iter().find_map(|entry| match entry {
+            Entry::Message(msg) => msg
+                .value
+                .iter()
+                .chain(msg.attributes.iter().map(|a| &a.value))
+                .find_map(|p| find_in_pattern(p, offset)),
+            Entry::Term(term) => std::iter::once(&term.value)
+                .chain(term.attributes.iter().map(|a| &a.value))
+                .find_map(|p| find_in_pattern(p, offset)),
+            _ => None,
+        })?;
+
+        tracing::trace!("Found {kind:?} reference: {}", id.name);
+
+        let origin = span_to_range(&self.src, &id.span);
+        if let Some(target) = self.find_local_definition(&resource, id.name, kind) {
+            let link = get_location_link(self.uri.clone(), target, origin);
+            return Some(GotoDefinitionResponse::Link(vec![link]));
+        }
+
+        // The definitions from the locale of the file itself are preferred.
+        let path = self.uri.to_file_path().ok()?;
+        let locale = locale_of(&path).unwrap_or_default();
+        let primary = block_in_place(|| self.context.config.blocking_read())
+            .locale
+            .primary
+            .clone();
+
+        let lock = block_in_place(|| self.context.locales.blocking_read());
+        let definitions = lock.get(id.name, kind).iter().collect::<Vec<_>>();
+
+        let mut links = preferred_definitions(definitions, &[locale, &primary])
+            .into_iter()
+            .filter_map(|k| {
+                let DefinitionIndex(path, target) = k.index();
+                let uri = Url::from_file_path(path).ok()?;
+                Some(get_location_link(uri, (*target)?, origin))
+            })
+            .collect::<Vec<_>>();
+        if links.is_empty() {
+            return None;
+        }
+
+        links.sort_by(|a, b| a.target_uri.as_str().cmp(b.target_uri.as_str()));
+        Some(GotoDefinitionResponse::Link(links))
+    }
+}
+
+impl FluentGotoDefinition {
+    pub fn new(context: Arc<Context>, uri: Url, position: Position, rope: &Rope) -> Self {
+        Self {
+            context,
+            uri,
+            position,
+            src: rope.to_string(),
+        }
+    }
+
+    // The current file is searched first, it may not be saved and indexed yet.
+    fn find_local_definition(
+        &self,
+        resource: &Resource<&str>,
+        name: &str,
+        kind: FluentKeyKind,
+    ) -> Option<tree_sitter::Range> {
+        resource.body.iter().find_map(|entry| match (entry, kind) {
+            (Entry::Message(msg), FluentKeyKind::Message) if msg.id.name == name => {
+                Some(span_to_range(&self.src, &msg.id.span))
+            }
+            (Entry::Term(term), FluentKeyKind::Term) if term.id.name == name => {
+                Some(span_to_range(&self.src, &term.id.span))
+            }
+            _ => None,
+        })
+    }
+}
+
+fn find_in_pattern<'a, 's>(
+    pattern: &'a Pattern<&'s str>,
+    offset: usize,
+) -> Option<(&'a Identifier<&'s str>, FluentKeyKind)> {
+    pattern.elements.iter().find_map(|element| match element {
+        PatternElement::Placeable { expression } => find_in_expression(expression, offset),
+        _ => None,
+    })
+}
+
+fn find_in_expression<'a, 's>(
+    expression: &'a Expression<&'s str>,
+    offset: usize,
+) -> Option<(&'a Identifier<&'s str>, FluentKeyKind)> {
+    match expression {
+        Expression::Inline(inline, ..) => find_in_inline(inline, offset),
+        Expression::Select { selector, variants } => {
+            find_in_inline(selector, offset).or_else(|| {
+                variants
+                    .iter()
+                    .find_map(|v| find_in_pattern(&v.value, offset))
+            })
+        }
+    }
+}
+
+fn find_in_inline<'a, 's>(
+    inline: &'a InlineExpression<&'s str>,
+    offset: usize,
+) -> Option<(&'a Identifier<&'s str>, FluentKeyKind)> {
+    match inline {
+        InlineExpression::MessageReference { id, .. }
+            if (id.span.start..=id.span.end).contains(&offset) =>
+        {
+            Some((id, FluentKeyKind::Message))
+        }
+        // The span of the identifier doesn't include the leading `-`.
+        InlineExpression::TermReference { id, .. }
+            if (id.span.start.saturating_sub(1)..=id.span.end).contains(&offset) =>
+        {
+            Some((id, FluentKeyKind::Term))
+        }
+        InlineExpression::TermReference {
+            arguments: Some(arguments),
+            ..
+        }
+        | InlineExpression::FunctionReference { arguments, .. } => arguments
+            .positional
+            .iter()
+            .chain(arguments.named.iter().map(|n| &n.value))
+            .find_map(|arg| find_in_inline(arg, offset)),
+        InlineExpression::Placeable { expression } => find_in_expression(expression, offset),
+        _ => None,
+    }
+}
+
+fn get_location_link(
+    uri: Url,
+    target: tree_sitter::Range,
+    origin: tree_sitter::Range,
+) -> LocationLink {
+    let to_range = |range: tree_sitter::Range| lsp_types::Range {
+        start: Position::new(
+            range.start_point.row as u32,
+            range.start_point.column as u32,
+        ),
+        end: Position::new(range.end_point.row as u32, range.end_point.column as u32),
+    };
+
+    LocationLink {
+        origin_selection_range: Some(to_range(origin)),
+        target_uri: uri,
+        target_range: to_range(target),
+        target_selection_range: to_range(target),
+    }
+}
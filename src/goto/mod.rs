@@ -1,3 +1,5 @@
+#[cfg(feature = "fluent")]
+pub mod fluent;
 pub mod yml;
 
 pub type GotoDefinitionResult = Option<tower_lsp::lsp_types::GotoDefinitionResponse>;
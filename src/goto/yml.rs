@@ -4,16 +4,14 @@ use crate::{
     parse::{
         common::{DefinitionIndex, Index},
         structs::{
-            csharp::{Component, Prototype, ReflectionManager},
-            fluent::FluentKey,
+            fluent::{preferred_definitions, FluentKeyKind},
+            yaml::YamlPrototype,
         },
     },
-    utils::block,
+    schema::{yaml::YamlSchemaResolver, SchemaType},
 };
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use ropey::Rope;
 use std::sync::Arc;
-use stringcase::camel_case;
 use tokio::task::block_in_place;
 use tower_lsp::lsp_types::{self, GotoDefinitionResponse, Location, LocationLink, Position, Url};
 use tree_sitter::{Node, Parser, Point, Tree};
@@ -35,16 +33,14 @@ impl GotoDefinition for YamlGotoDefinition {
         let root_node = self.tree.root_node();
         let found_node = root_node.named_descendant_for_point_range(point, point)?;
 
-        let nest = self.get_nesting(&found_node);
-        match nest {
-            2 => self
-                .try_goto_locid_definition(found_node, nest)
-                .or_else(|| self.try_goto_prototype_definition(found_node)),
-            4 => self
-                .try_goto_locid_definition(found_node, nest)
-                .or_else(|| self.try_goto_component_definition(found_node)),
-            _ => None,
-        }
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+
+        self.try_goto_tag_definition(&resolver, found_node)
+            .or_else(|| self.try_goto_locid_definition(&resolver, found_node))
+            .or_else(|| self.try_goto_class_definition(&resolver, found_node))
+            .or_else(|| self.try_goto_field_definition(&resolver, found_node))
+            .or_else(|| self.try_goto_parent_definition(&resolver, found_node))
+            .or_else(|| self.try_goto_id_definition(&resolver, found_node))
     }
 }
 
@@ -64,240 +60,193 @@ impl YamlGotoDefinition {
         }
     }
 
-    #[tracing::instrument(skip(self), ret)]
-    fn try_goto_locid_definition(&self, found_node: Node<'_>, nest: usize) -> GotoDefinitionResult {
-        let block_mapping_pair = {
-            let mut node = found_node;
-            while let Some(n) = node.parent() {
-                node = n;
-                if n.kind() == "block_mapping_pair" {
-                    break;
-                }
-            }
-            node
-        };
-
-        if block_mapping_pair.kind() != "block_mapping_pair" {
-            tracing::trace!("not a block mapping pair");
-            return None;
-        }
-
-        let key_node = block_mapping_pair.child_by_field_name("key")?;
-        let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-        if key_name == found_node.utf8_text(self.src.as_bytes()).ok()? {
+    /// Goes to the class selected by a `!type:` tag.
+    fn try_goto_tag_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
+        if found_node.kind() != "tag" {
             return None;
         }
 
-        let value_node = block_mapping_pair.child_by_field_name("value")?;
-        let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
-
-        match nest {
-            2 => {
-                let proto_name = self
-                    .get_field(&block_mapping_pair.parent()?, "type")?
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                let reflection = ReflectionManager::new(self.context.classes.clone());
-                let proto = block(|| reflection.get_prototype_by_name(proto_name))?;
-
-                let field = block(|| reflection.get_fields(&proto))
-                    .into_iter()
-                    .find(|f| f.get_data_field_name() == key_name)?;
-
-                if field.type_name != "LocId" {
-                    tracing::trace!("not a locid field");
-                    return None;
-                }
-
-                let lock = block_in_place(|| self.context.locales.blocking_read());
-                let locale = lock.get(&FluentKey::dummy(value))?;
+        let class = resolver.resolve_tag(found_node)?;
+        self.index_to_definition(class.index())
+    }
 
-                let location = get_location_link(locale.index(), value_node)?;
+    #[tracing::instrument(skip_all, ret)]
+    fn try_goto_locid_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
+        let value_node = find_value_node(found_node)?;
 
-                Some(GotoDefinitionResponse::Link(vec![location]))
+        match resolver.resolve(value_node)? {
+            SchemaType::Value(type_name) if type_name == "LocId" => {}
+            _ => {
+                tracing::trace!("not a locid field");
+                return None;
             }
-            4 => {
-                let comp_name = self
-                    .get_field(&block_mapping_pair.parent()?, "type")?
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                let reflection = ReflectionManager::new(self.context.classes.clone());
-                let comp = block(|| reflection.get_component_by_name(comp_name))?;
-
-                let field = block(|| reflection.get_fields(&comp))
-                    .into_iter()
-                    .find(|f| f.get_data_field_name() == key_name)?;
+        }
 
-                if field.type_name != "LocId" {
-                    tracing::trace!("not a locid field");
-                    return None;
-                }
+        let value = value_node.utf8_text(self.src.as_bytes()).ok()?;
+        let primary = block_in_place(|| self.context.config.blocking_read())
+            .locale
+            .primary
+            .clone();
+
+        let lock = block_in_place(|| self.context.locales.blocking_read());
+        let definitions = lock
+            .get(value, FluentKeyKind::Message)
+            .iter()
+            .collect::<Vec<_>>();
+
+        let mut locations = preferred_definitions(definitions, &[&primary])
+            .into_iter()
+            .filter_map(|k| get_location_link(k.index(), value_node))
+            .collect::<Vec<_>>();
+        if locations.is_empty() {
+            return None;
+        }
 
-                let lock = block_in_place(|| self.context.locales.blocking_read());
-                let locale = lock.get(&FluentKey::dummy(value))?;
+        locations.sort_by(|a, b| a.target_uri.as_str().cmp(b.target_uri.as_str()));
+        Some(GotoDefinitionResponse::Link(locations))
+    }
 
-                let location = get_location_link(locale.index(), value_node)?;
+    /// Goes to the prototype or component class from the value of its `type` field.
+    fn try_goto_class_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
+        let mapping_pair_node = find_mapping_pair_node(found_node)?;
+        if !self.is_value_of(found_node, mapping_pair_node, "type") {
+            return None;
+        }
 
-                Some(GotoDefinitionResponse::Link(vec![location]))
-            }
+        match resolver.resolve(mapping_pair_node.parent()?)? {
+            SchemaType::Prototype(proto) => self.index_to_definition(proto.index()),
+            SchemaType::Component(comp) => self.index_to_definition(comp.index()),
             _ => None,
         }
     }
 
-    fn try_goto_component_definition(&self, found_node: Node<'_>) -> GotoDefinitionResult {
+    fn try_goto_field_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
+        let mapping_pair_node = find_mapping_pair_node(found_node)?;
+        let key_node = mapping_pair_node.child_by_field_name("key")?;
+        if !key_node.byte_range().contains(&found_node.start_byte()) {
+            return None;
+        }
+
+        let field = resolver.resolve_field_definition(mapping_pair_node)?;
+        self.index_to_definition(field.index())
+    }
+
+    fn try_goto_parent_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
         let seeking = found_node.utf8_text(self.src.as_bytes()).ok()?;
+        let mapping_pair_node = find_mapping_pair_node(found_node)?;
+        if !self.is_value_of(found_node, mapping_pair_node, "parent") {
+            return None;
+        }
 
-        let mapping_pair_node = {
-            let mut node = found_node;
-            while let Some(n) = node.parent() {
-                node = n;
-                if n.kind() == "block_mapping_pair" {
-                    break;
-                }
-            }
-            node
+        let block_mapping = mapping_pair_node.parent()?;
+        let SchemaType::Prototype(_) = resolver.resolve(block_mapping)? else {
+            return None;
         };
 
-        let key_node = mapping_pair_node.child_by_field_name("key")?;
-        let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-        match key_name {
-            "type" => {
-                let value = mapping_pair_node
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                if seeking != value {
-                    return None;
-                }
-
-                let comp = block_in_place(|| self.context.classes.blocking_read())
-                    .par_iter()
-                    .filter_map(|c| Component::try_from(c).ok())
-                    .find_any(|p| camel_case(&p.get_component_name()) == camel_case(seeking))?;
-
-                let index = comp.index();
-                self.index_to_definition(index)
-            }
-            _ => {
-                if seeking != key_name {
-                    return None;
-                }
-
-                let comp_name = self
-                    .get_field(&mapping_pair_node.parent()?, "type")?
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                let comp = block_in_place(|| self.context.classes.blocking_read())
-                    .par_iter()
-                    .filter_map(|c| Component::try_from(c).ok())
-                    .find_any(|p| camel_case(&p.get_component_name()) == camel_case(comp_name))?;
-
-                let reflection = ReflectionManager::new(self.context.classes.clone());
-                let field = block(|| reflection.get_fields(&comp))
-                    .into_iter()
-                    .find(|f| f.get_data_field_name() == key_name)?;
-
-                let index = field.index();
-                self.index_to_definition(index)
-            }
+        let type_field_value = self
+            .get_field(&block_mapping, "type")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+
+        // The overriding definition goes first, the overridden ones are still listed.
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let prototype = lock.get(&YamlPrototype::dummy(type_field_value, seeking))?;
+        let locations = prototype
+            .definitions()
+            .filter_map(|p| get_location_link(p.index(), found_node))
+            .collect::<Vec<_>>();
+        if locations.is_empty() {
+            return None;
         }
+
+        Some(GotoDefinitionResponse::Link(locations))
     }
 
-    fn try_goto_prototype_definition(&self, node: Node) -> GotoDefinitionResult {
-        let seeking = node.utf8_text(self.src.as_bytes()).ok()?;
+    /// Goes from the id of a prototype to its class and, for entities, to the locale keys named after it.
+    fn try_goto_id_definition(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node<'_>,
+    ) -> GotoDefinitionResult {
+        let id = found_node.utf8_text(self.src.as_bytes()).ok()?;
+        let mapping_pair_node = find_mapping_pair_node(found_node)?;
+        if !self.is_value_of(found_node, mapping_pair_node, "id") {
+            return None;
+        }
 
-        let mapping_pair_node = {
-            let mut node = node;
-            while let Some(n) = node.parent() {
-                node = n;
-                if n.kind() == "block_mapping_pair" {
-                    break;
-                }
-            }
-            node
+        let block_mapping = mapping_pair_node.parent()?;
+        let SchemaType::Prototype(proto) = resolver.resolve(block_mapping)? else {
+            return None;
         };
 
-        let key_node = mapping_pair_node.child_by_field_name("key")?;
-        let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-        match key_name {
-            "type" => {
-                let value = mapping_pair_node
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                if seeking != value {
-                    return None;
-                }
-
-                let prototype = block_in_place(|| self.context.classes.blocking_read())
-                    .par_iter()
-                    .filter_map(|c| Prototype::try_from(c).ok())
-                    .find_any(|p| camel_case(&p.get_prototype_name()) == seeking)?;
-
-                let index = prototype.index();
-                self.index_to_definition(index)
-            }
-            "parent" => {
-                let value = mapping_pair_node
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                if seeking != value {
-                    return None;
-                }
-
-                let type_field_node = self.get_field(&mapping_pair_node.parent()?, "type")?;
-                let type_field_value = type_field_node
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                let lock = block_in_place(|| self.context.prototypes.blocking_read());
-                let prototype = lock
-                    .par_iter()
-                    .filter(|p| p.prototype == type_field_value)
-                    .find_any(|p| p.id == seeking)?;
-
-                let index = prototype.index();
-                self.index_to_definition(index)
-            }
-            _ => {
-                if seeking != key_name {
-                    return None;
-                }
-
-                let proto_name = self
-                    .get_field(&mapping_pair_node.parent()?, "type")?
-                    .child_by_field_name("value")?
-                    .utf8_text(self.src.as_bytes())
-                    .ok()?;
-
-                let prototype = block_in_place(|| self.context.classes.blocking_read())
-                    .par_iter()
-                    .filter_map(|c| Prototype::try_from(c).ok())
-                    .find_any(|p| camel_case(&p.get_prototype_name()) == proto_name)?;
-
-                let reflection = ReflectionManager::new(self.context.classes.clone());
-                let field = block(|| reflection.get_fields(&prototype))
-                    .into_iter()
-                    .find(|f| f.get_data_field_name() == key_name)?;
-
-                let index = field.index();
-                self.index_to_definition(index)
-            }
+        let mut locations = Vec::from_iter(get_location_link(proto.index(), found_node));
+
+        let type_field_value = self
+            .get_field(&block_mapping, "type")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        if type_field_value == "entity" {
+            let (prefix, primary) = {
+                let config = block_in_place(|| self.context.config.blocking_read());
+                (
+                    config.diagnostics.entity_locale.prefix.clone(),
+                    config.locale.primary.clone(),
+                )
+            };
+            let key = format!("{prefix}{id}");
+
+            let lock = block_in_place(|| self.context.locales.blocking_read());
+            let definitions = lock
+                .get(&key, FluentKeyKind::Message)
+                .iter()
+                .collect::<Vec<_>>();
+
+            let mut keys = preferred_definitions(definitions, &[&primary])
+                .into_iter()
+                .filter_map(|k| get_location_link(k.index(), found_node))
+                .collect::<Vec<_>>();
+            keys.sort_by(|a, b| a.target_uri.as_str().cmp(b.target_uri.as_str()));
+            locations.extend(keys);
+        }
+
+        if locations.is_empty() {
+            return None;
         }
+
+        Some(GotoDefinitionResponse::Link(locations))
+    }
+
+    fn is_value_of(&self, found_node: Node, mapping_pair_node: Node, key: &str) -> bool {
+        let key_name = mapping_pair_node
+            .child_by_field_name("key")
+            .and_then(|n| n.utf8_text(self.src.as_bytes()).ok());
+        let value = mapping_pair_node
+            .child_by_field_name("value")
+            .and_then(|n| n.utf8_text(self.src.as_bytes()).ok());
+
+        key_name == Some(key) && value == found_node.utf8_text(self.src.as_bytes()).ok()
     }
 
     fn index_to_definition(&self, index: &DefinitionIndex) -> GotoDefinitionResult {
@@ -321,20 +270,6 @@ impl YamlGotoDefinition {
         Some(definition)
     }
 
-    fn get_nesting(&self, node: &Node) -> usize {
-        let mut nest = 0;
-
-        let mut parent = node.parent();
-        while let Some(node) = parent {
-            if node.kind() == "block_node" {
-                nest += 1;
-            }
-            parent = node.parent();
-        }
-
-        nest
-    }
-
     fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
         debug_assert_eq!(node.kind(), "block_mapping");
 
@@ -354,6 +289,22 @@ impl YamlGotoDefinition {
     }
 }
 
+fn find_mapping_pair_node(node: Node) -> Option<Node> {
+    let mut node = node;
+    while node.kind() != "block_mapping_pair" {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+fn find_value_node(node: Node) -> Option<Node> {
+    let mut node = node;
+    while !matches!(node.kind(), "block_node" | "flow_node") {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
 fn get_location_link(index: &DefinitionIndex, node: Node) -> Option<LocationLink> {
     let DefinitionIndex(path, Some(locale_range)) = index else {
         return None;
@@ -0,0 +1,154 @@
+use crate::{
+    encoding::{ConvertPositions, PositionConverter},
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::yaml::YamlPrototype,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tower_lsp::lsp_types::{Location, Position, Range, Url};
+
+pub const PROTOTYPE_GRAPH_METHOD: &str = "robust-lsp/prototypeGraph";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrototypeGraphParams {
+    /// The kind of the prototype, e.g. `entity`.
+    #[serde(default = "default_kind")]
+    pub prototype: String,
+    pub id: String,
+}
+
+fn default_kind() -> String {
+    "entity".to_owned()
+}
+
+/// The ancestors and descendants of a prototype, e.g. to draw its inheritance graph.
+#[derive(Debug, Default, Serialize)]
+pub struct PrototypeGraph {
+    pub nodes: Vec<GraphNode>,
+    /// Edges from the children to their parents.
+    pub edges: Vec<GraphEdge>,
+
+    #[serde(skip)]
+    added_nodes: HashSet<String>,
+    #[serde(skip)]
+    added_edges: HashSet<(String, String)>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    pub id: String,
+    #[serde(rename = "abstract")]
+    pub is_abstract: bool,
+    /// Whether it is the requested prototype.
+    pub root: bool,
+    /// The definition of the prototype, missing for parents that aren't indexed.
+    pub location: Option<Location>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphEdge {
+    pub child: String,
+    pub parent: String,
+}
+
+impl PrototypeGraph {
+    /// Collects the graph around the prototype, `None` if it isn't indexed.
+    pub fn new(prototypes: &HashSet<YamlPrototype>, kind: &str, id: &str) -> Option<Self> {
+        let get = |id: &str| prototypes.get(&YamlPrototype::dummy(kind, id));
+        get(id)?;
+
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for proto in prototypes.iter().filter(|p| p.prototype == kind) {
+            for parent in proto.parents.iter() {
+                children.entry(parent).or_default().push(&proto.id);
+            }
+        }
+        children.values_mut().for_each(|c| c.sort());
+
+        let mut graph = PrototypeGraph::default();
+
+        // The ancestors are found through the parents and the descendants through the children,
+        // so that the siblings of the prototype don't end up in the graph.
+        let mut visited = HashSet::new();
+        let mut queue = vec![id.to_owned()];
+        while let Some(current) = queue.pop() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            graph.push_node(get(&current), &current, current == id);
+
+            for parent in get(&current).iter().flat_map(|p| p.parents.iter()) {
+                graph.push_edge(&current, parent);
+                queue.push(parent.clone());
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut queue = vec![id.to_owned()];
+        while let Some(current) = queue.pop() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            graph.push_node(get(&current), &current, current == id);
+
+            for child in children.get(current.as_str()).into_iter().flatten() {
+                graph.push_edge(child, &current);
+                queue.push(child.to_string());
+            }
+        }
+
+        Some(graph)
+    }
+
+    fn push_node(&mut self, proto: Option<&YamlPrototype>, id: &str, root: bool) {
+        if !self.added_nodes.insert(id.to_owned()) {
+            return;
+        }
+
+        self.nodes.push(GraphNode {
+            id: id.to_owned(),
+            is_abstract: proto.is_some_and(YamlPrototype::is_abstract),
+            root,
+            location: proto.and_then(|p| index_to_location(p.index())),
+        });
+    }
+
+    fn push_edge(&mut self, child: &str, parent: &str) {
+        if self
+            .added_edges
+            .insert((child.to_owned(), parent.to_owned()))
+        {
+            self.edges.push(GraphEdge {
+                child: child.to_owned(),
+                parent: parent.to_owned(),
+            });
+        }
+    }
+}
+
+impl ConvertPositions for GraphNode {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.location.convert(uri, converter);
+    }
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location {
+        uri: Url::from_file_path(path).ok()?,
+        range: Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    })
+}
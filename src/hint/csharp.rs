@@ -0,0 +1,67 @@
+use super::InlayHint;
+use crate::{backend::Context, parse::common::Index};
+use std::{path::PathBuf, sync::Arc};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{InlayHintLabel, InlayHintTooltip, Position, Range};
+
+type CsharpInlayHintResult = Option<Vec<tower_lsp::lsp_types::InlayHint>>;
+
+/// Hints the YAML name after every `[DataField]` of the indexed classes of the file.
+pub struct CsharpInlayHint {
+    context: Arc<Context>,
+    path: PathBuf,
+    range: Range,
+}
+
+impl InlayHint for CsharpInlayHint {
+    fn inlay_hint(&self) -> CsharpInlayHintResult {
+        let lock = block_in_place(|| self.context.classes.blocking_read());
+
+        let mut hints = lock
+            .iter()
+            .flat_map(|class| class.declarations())
+            .filter(|c| c.index().0 == self.path)
+            .flat_map(|class| &class.fields)
+            .filter(|field| field.attributes.contains("DataField"))
+            .filter_map(|field| {
+                let range = field.index().1?;
+                let line = range.end_point.row as u32;
+                if line < self.range.start.line || line > self.range.end.line {
+                    return None;
+                }
+
+                Some(tower_lsp::lsp_types::InlayHint {
+                    kind: None,
+                    position: Position::new(line, range.end_point.column as u32),
+                    label: InlayHintLabel::String(field.get_data_field_name()),
+                    tooltip: Some(InlayHintTooltip::String(
+                        "The name of the field in the YAML prototypes.".to_owned(),
+                    )),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    text_edits: None,
+                    data: None,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        tracing::trace!("Found {} inlay hints.", hints.len());
+
+        if hints.is_empty() {
+            None
+        } else {
+            hints.sort_by_key(|h| h.position);
+            Some(hints)
+        }
+    }
+}
+
+impl CsharpInlayHint {
+    pub fn new(context: Arc<Context>, path: PathBuf, range: Range) -> Self {
+        Self {
+            context,
+            path,
+            range,
+        }
+    }
+}
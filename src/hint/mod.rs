@@ -1,3 +1,5 @@
+#[cfg(feature = "csharp")]
+pub mod csharp;
 pub mod yaml;
 
 pub trait InlayHint {
@@ -1,20 +1,25 @@
 use super::InlayHint;
-use crate::{backend::CsharpClasses, parse::structs::csharp::ReflectionManager, utils::block};
+use crate::{
+    backend::Context,
+    parse::structs::{
+        csharp::{CsharpClassField, ReflectionManager},
+        yaml::YamlPrototype,
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::block,
+};
 use ropey::Rope;
-use stringcase::camel_case;
-use tower_lsp::lsp_types::{InlayHintKind, InlayHintLabel, Position, Range};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{InlayHintKind, InlayHintLabel, InlayHintTooltip, Position, Range};
 use tree_sitter::{Node, Parser, Tree};
 
 type YamlInlayHintResult = Option<Vec<tower_lsp::lsp_types::InlayHint>>;
-
-enum FieldType<'a> {
-    Prototype(String, Node<'a>),
-    Component(String, Node<'a>),
-    // Tag(String, Node<'a>),
-}
+/// The fields provided by the parents of a prototype: the key, the value
+/// and the closest parent that sets it.
+type InheritedFields = Vec<(String, String, String)>;
 
 pub struct YamlInlayHint {
-    classes: CsharpClasses,
+    context: Arc<Context>,
     range: Range,
     src: String,
     tree: Tree,
@@ -23,28 +28,16 @@ pub struct YamlInlayHint {
 impl InlayHint for YamlInlayHint {
     fn inlay_hint(&self) -> YamlInlayHintResult {
         let root_node = self.tree.root_node();
-        let document = find_child_node(root_node, "document")?;
-        let block_node = find_child_node(document, "block_node")?;
-        let block_sequence = find_child_node(block_node, "block_sequence")?;
-
-        let mut hints = Vec::new();
-
-        for i in 0..block_sequence.named_child_count() {
-            let block_sequence_item_node = block_sequence.named_child(i).unwrap();
-            if block_sequence_item_node.kind() != "block_sequence_item" {
-                continue;
-            }
 
-            let block_node = find_child_node(block_sequence_item_node, "block_node")?;
-            let block_mapping = find_child_node(block_node, "block_mapping")?;
+        let mut block_mappings = Vec::new();
+        self.collect_block_mappings(root_node, &mut block_mappings);
 
-            let fields = match self.collect_hints_from_prototype(block_mapping) {
-                Some(fields) => fields,
-                None => continue,
-            };
-
-            hints.extend(self.fields_map_to_hints(fields));
-        }
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let hints = block_mappings
+            .into_iter()
+            .filter_map(|block_mapping| self.collect_hints(&resolver, block_mapping))
+            .flatten()
+            .collect::<Vec<_>>();
 
         tracing::trace!("Found {} inlay hints.", hints.len());
 
@@ -57,7 +50,7 @@ impl InlayHint for YamlInlayHint {
 }
 
 impl YamlInlayHint {
-    pub fn new(classes: CsharpClasses, range: Range, rope: &Rope) -> Self {
+    pub fn new(context: Arc<Context>, range: Range, rope: &Rope) -> Self {
         let src = rope.to_string();
 
         let mut parser = Parser::new();
@@ -65,206 +58,137 @@ impl YamlInlayHint {
         let tree = parser.parse(&src, None).unwrap();
 
         Self {
-            classes,
+            context,
             range,
             src,
             tree,
         }
     }
 
-    fn fields_map_to_hints(&self, fields: Vec<FieldType>) -> Vec<tower_lsp::lsp_types::InlayHint> {
-        let reflection = ReflectionManager::new(self.classes.clone());
-        fields
-            .into_iter()
-            .filter_map(|f| match f {
-                FieldType::Prototype(name, node) => {
-                    let proto = block(|| reflection.get_prototype_by_name(&name))?;
-                    if camel_case(&proto.get_prototype_name()) != name {
-                        return None;
-                    }
-
-                    let key_node = node.child_by_field_name("key")?;
-                    let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-                    let field = block(|| reflection.get_fields(&proto))
-                        .into_iter()
-                        .find(|f| f.get_data_field_name() == key_name)?;
-
-                    let hint = tower_lsp::lsp_types::InlayHint {
-                        kind: Some(InlayHintKind::TYPE),
-                        position: Position {
-                            line: key_node.end_position().row as u32,
-                            character: key_node.end_position().column as u32,
-                        },
-                        label: InlayHintLabel::String(field.type_name.clone()),
-                        tooltip: None,
-                        padding_left: Some(true),
-                        padding_right: None,
-                        text_edits: None,
-                        data: None,
-                    };
-
-                    Some(hint)
-                }
-                FieldType::Component(name, node) => {
-                    let reflection = ReflectionManager::new(self.classes.clone());
-                    let comp = block(|| reflection.get_component_by_name(&name))?;
-                    if comp.get_component_name() != name {
-                        return None;
-                    }
-
-                    let key_node = node.child_by_field_name("key")?;
-                    let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-                    let field = block(|| reflection.get_fields(&comp))
-                        .into_iter()
-                        .find(|f| f.get_data_field_name() == key_name)?;
-
-                    let hint = tower_lsp::lsp_types::InlayHint {
-                        kind: Some(InlayHintKind::TYPE),
-                        position: Position {
-                            line: key_node.end_position().row as u32,
-                            character: key_node.end_position().column as u32,
-                        },
-                        label: InlayHintLabel::String(field.type_name.clone()),
-                        tooltip: None,
-                        padding_left: Some(true),
-                        padding_right: None,
-                        text_edits: None,
-                        data: None,
-                    };
-
-                    Some(hint)
-                }
-            })
-            .collect()
-    }
-
-    fn collect_hints_from_prototype<'a>(
+    /// Hints the C# type of every field of the mapping that is in range.
+    fn collect_hints(
         &self,
-        block_mapping: Node<'a>,
-    ) -> Option<Vec<FieldType<'a>>> {
-        debug_assert_eq!(block_mapping.kind(), "block_mapping");
+        resolver: &YamlSchemaResolver,
+        block_mapping: Node,
+    ) -> Option<Vec<tower_lsp::lsp_types::InlayHint>> {
+        let owner = resolver.resolve(block_mapping)?;
+        let class = owner.class()?;
+        let inherited = self.inherited_fields(&owner, block_mapping);
 
-        let type_node = self
-            .get_field(&block_mapping, "type")?
-            .child_by_field_name("value")?;
-        let proto_name = type_node.utf8_text(self.src.as_bytes()).ok()?;
-
-        let is_entity = proto_name == "entity";
-
-        let mut fields = Vec::new();
+        let reflection = ReflectionManager::new(&self.context);
+        let fields = block(|| reflection.get_fields(class));
 
+        let mut hints = Vec::new();
         for i in 0..block_mapping.named_child_count() {
             let block_mapping_pair = block_mapping.named_child(i).unwrap();
-            if block_mapping_pair.kind() != "block_mapping_pair" {
+            if block_mapping_pair.kind() != "block_mapping_pair"
+                || !self.in_range(&block_mapping_pair)
+            {
                 continue;
             }
 
-            if !self.in_range(&block_mapping_pair) {
+            let Some(key_node) = block_mapping_pair.child_by_field_name("key") else {
+                continue;
+            };
+            let Ok(key_name) = key_node.utf8_text(self.src.as_bytes()) else {
+                continue;
+            };
+            if key_name == "type" {
                 continue;
             }
 
-            let key_node = match block_mapping_pair.child_by_field_name("key") {
-                Some(key_node) => key_node,
-                None => continue,
+            let Some(field) = fields.iter().find(|f| f.get_data_field_name() == key_name) else {
+                continue;
             };
-            let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
-
-            if is_entity && key_name == "components" {
-                let block_node = match block_mapping_pair.child_by_field_name("value") {
-                    Some(component_node) if component_node.kind() == "block_node" => component_node,
-                    _ => continue,
-                };
-
-                let comp_fields = match self.collect_hints_from_components(block_node) {
-                    Some(f) => f,
-                    None => continue,
-                };
 
-                fields.extend(comp_fields);
-            } else if !is_entity {
-                fields.push(FieldType::Prototype(
-                    proto_name.to_owned(),
-                    block_mapping_pair,
-                ));
-            }
+            hints.push(tower_lsp::lsp_types::InlayHint {
+                kind: Some(InlayHintKind::TYPE),
+                position: Position {
+                    line: key_node.end_position().row as u32,
+                    character: key_node.end_position().column as u32,
+                },
+                label: InlayHintLabel::String(field.type_name.to_string()),
+                tooltip: tooltip(field, inherited.as_ref(), key_name),
+                padding_left: Some(true),
+                padding_right: None,
+                text_edits: None,
+                data: None,
+            });
         }
 
-        if fields.is_empty() {
-            None
-        } else {
-            Some(fields)
-        }
+        Some(hints)
     }
 
-    fn collect_hints_from_components<'a>(
-        &self,
-        block_node: Node<'a>,
-    ) -> Option<Vec<FieldType<'a>>> {
-        debug_assert_eq!(block_node.kind(), "block_node");
-
-        let block_sequence = find_child_node(block_node, "block_sequence")?;
-
-        let mut fields = Vec::new();
-
-        // Components
-        for i in 0..block_sequence.named_child_count() {
-            let block_sequence_item_node = block_sequence.named_child(i).unwrap();
-            if block_sequence_item_node.kind() != "block_sequence_item" {
-                continue;
+    /// The prototype that owns the mapping, either directly or through its component,
+    /// with the fields its parents provide, keyed like the fields of the mapping.
+    fn inherited_fields(&self, owner: &SchemaType, block_mapping: Node) -> Option<InheritedFields> {
+        let (prototype_mapping, component) = match owner {
+            SchemaType::Prototype(_) => (block_mapping, None),
+            SchemaType::Component(_) => {
+                // The component mapping -> its list item -> the list -> the `components` value
+                // -> the `components` pair -> the prototype mapping.
+                let mut node = block_mapping;
+                for _ in 0..6 {
+                    node = node.parent()?;
+                }
+                let component = self.field_value(block_mapping, "type")?;
+                (node, Some(component.to_owned()))
             }
+            _ => return None,
+        };
 
-            let block_node = match find_child_node(block_sequence_item_node, "block_node") {
-                Some(n) => n,
-                None => continue,
-            };
+        let kind = self.field_value(prototype_mapping, "type")?;
+        let id = self.field_value(prototype_mapping, "id")?;
+        let prototypes = block(|| self.context.prototypes.read());
+        let prototype = prototypes.get(&YamlPrototype::dummy(kind, id))?;
 
-            let block_mapping = match find_child_node(block_node, "block_mapping") {
-                Some(n) => n,
-                None => continue,
+        let mut fields = Vec::<(String, String, String)>::new();
+        for ancestor in prototype.merge(&prototypes).ancestors {
+            let Some(parent) = prototypes.get(&YamlPrototype::dummy(kind, &ancestor)) else {
+                continue;
             };
-
-            let type_pair_node = match self.get_field(&block_mapping, "type") {
-                Some(n) => n,
-                None => continue,
+            let parent_fields = match &component {
+                Some(component) => parent
+                    .component_fields
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.strip_prefix(component)?.strip_prefix('.')?, v)))
+                    .collect::<Vec<_>>(),
+                None => parent.fields.iter().map(|(k, v)| (k.as_str(), v)).collect(),
             };
-
-            let type_node = match type_pair_node.child_by_field_name("value") {
-                Some(n) => n,
-                None => continue,
-            };
-
-            let comp_name = type_node.utf8_text(self.src.as_bytes()).ok()?;
-
-            // Component fields
-            for i in 0..block_mapping.named_child_count() {
-                let block_mapping_pair = block_mapping.named_child(i).unwrap();
-                if block_mapping_pair.kind() != "block_mapping_pair" {
-                    continue;
+            for (key, value) in parent_fields {
+                if !fields.iter().any(|(k, _, _)| k == key) {
+                    fields.push((key.to_owned(), value.clone(), ancestor.clone()));
                 }
+            }
+        }
 
-                let key_node = match block_mapping_pair.child_by_field_name("key") {
-                    Some(key_node) => key_node,
-                    None => continue,
-                };
-                let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
+        Some(fields)
+    }
 
-                if key_name == "type" {
-                    continue;
-                } else if self.in_range(&block_mapping_pair) {
-                    fields.push(FieldType::Component(
-                        comp_name.to_owned(),
-                        block_mapping_pair,
-                    ));
-                }
+    fn field_value<'a>(&'a self, block_mapping: Node, name: &str) -> Option<&'a str> {
+        for i in 0..block_mapping.named_child_count() {
+            let pair = block_mapping.named_child(i).unwrap();
+            let Some(key) = pair.child_by_field_name("key") else {
+                continue;
+            };
+            if key.utf8_text(self.src.as_bytes()).ok()? == name {
+                let value = pair.child_by_field_name("value")?;
+                return value.utf8_text(self.src.as_bytes()).ok();
             }
         }
+        None
+    }
 
-        if fields.is_empty() {
-            None
-        } else {
-            Some(fields)
+    fn collect_block_mappings<'a>(&self, node: Node<'a>, block_mappings: &mut Vec<Node<'a>>) {
+        if node.kind() == "block_mapping" {
+            block_mappings.push(node);
+        }
+
+        for i in 0..node.named_child_count() {
+            let child = node.named_child(i).unwrap();
+            if self.overlaps_range(&child) {
+                self.collect_block_mappings(child, block_mappings);
+            }
         }
     }
 
@@ -276,33 +200,31 @@ impl YamlInlayHint {
         (start_position.row >= start.line as usize) && (end_position.row <= end.line as usize)
     }
 
-    fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
-        debug_assert_eq!(node.kind(), "block_mapping");
-
-        for i in 0..node.named_child_count() {
-            let field_node = node.named_child(i)?;
-            let key = field_node
-                .child_by_field_name("key")?
-                .utf8_text(self.src.as_bytes())
-                .ok()?
-                .to_owned();
+    fn overlaps_range(&self, node: &Node) -> bool {
+        let Range { start, end } = &self.range;
 
-            if key == name {
-                return Some(field_node);
-            }
-        }
-        None
+        node.end_position().row >= start.line as usize
+            && node.start_position().row <= end.line as usize
     }
 }
 
-fn find_child_node<'a>(node: Node<'a>, name: &'a str) -> Option<Node<'a>> {
-    let mut n = None;
-    for i in 0..node.named_child_count() {
-        let found_node = node.named_child(i).unwrap();
-        if found_node.kind() == name {
-            n = Some(found_node);
-            break;
-        }
+/// Tells the value the field overrides, if a parent sets it, and the default of the C# field.
+fn tooltip(
+    field: &CsharpClassField,
+    inherited: Option<&InheritedFields>,
+    key_name: &str,
+) -> Option<InlayHintTooltip> {
+    let mut lines = vec![];
+    if let Some((_, value, parent)) = inherited
+        .into_iter()
+        .flatten()
+        .find(|(key, _, _)| key == key_name)
+    {
+        lines.push(format!("Overrides `{value}` inherited from `{parent}`"));
     }
-    n
+    if let Some(default) = &field.default_value {
+        lines.push(format!("Default: `{default}`"));
+    }
+
+    (!lines.is_empty()).then(|| InlayHintTooltip::String(lines.join("\n")))
 }
@@ -0,0 +1,7 @@
+pub mod yaml;
+
+pub type HoverResult = Option<tower_lsp::lsp_types::Hover>;
+
+pub trait Hover {
+    fn hover(&self) -> HoverResult;
+}
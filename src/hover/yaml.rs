@@ -0,0 +1,435 @@
+use super::{Hover, HoverResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::Index,
+        structs::{
+            audio::AudioMeta,
+            csharp::{CsharpAttributeCollection, CsharpClass},
+            yaml::{MergedPrototype, YamlPrototype},
+        },
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::{resource_path, RESOURCE_PATH_SEPARATORS},
+};
+use ropey::Rope;
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, HoverContents, MarkupContent, MarkupKind, Position, Range, Url};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+/// Fields shown in the summary of a prototype.
+static PREVIEW_FIELDS: &[&str] = &["name", "description", "suffix"];
+/// The entity category that keeps entities out of the spawn menu.
+static HIDE_SPAWN_MENU: &str = "HideSpawnMenu";
+const SPRITES_RES_PATH: &str = "Resources/Textures/";
+const RESOURCES_PATH: &str = "Resources/";
+const AUDIO_RES_PREFIX: &str = "/Audio/";
+/// The number of states listed on hover over an RSI.
+const MAX_LISTED_STATES: usize = 30;
+
+pub struct YamlHover {
+    context: Arc<Context>,
+    position: Position,
+    src: String,
+    tree: Tree,
+    root_path: PathBuf,
+}
+
+impl Hover for YamlHover {
+    fn hover(&self) -> HoverResult {
+        let point = Point::new(
+            self.position.line as usize,
+            self.position.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let found_node = root_node.named_descendant_for_point_range(point, point)?;
+        let seeking = found_node.utf8_text(self.src.as_bytes()).ok()?;
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        if found_node.kind() == "tag" {
+            let class = resolver.resolve_tag(found_node)?;
+            return Some(hover(class_markdown(&class), found_node));
+        }
+
+        // Audio paths are hovered anywhere, e.g. in the `files` of a sound collection.
+        let resource = seeking.trim_matches(['"', '\'']);
+        if resource.starts_with(AUDIO_RES_PREFIX) {
+            return Some(hover(self.audio_summary(resource)?, found_node));
+        }
+
+        let mapping_pair_node = {
+            let mut node = found_node;
+            while let Some(n) = node.parent() {
+                node = n;
+                if n.kind() == "block_mapping_pair" {
+                    break;
+                }
+            }
+            node
+        };
+
+        if mapping_pair_node.kind() != "block_mapping_pair" {
+            return None;
+        }
+
+        let key_node = mapping_pair_node.child_by_field_name("key")?;
+        let key_name = key_node.utf8_text(self.src.as_bytes()).ok()?;
+
+        let value = if key_node.byte_range().contains(&found_node.start_byte()) {
+            let field = resolver.resolve_field_definition(mapping_pair_node)?;
+
+            let signature = match &field.default_value {
+                Some(default) => format!("{} {} = {default}", field.type_name, field.name),
+                None => format!("{} {}", field.type_name, field.name),
+            };
+            markdown(&signature, &field.attributes)
+        } else if key_name == "type" {
+            let type_name = mapping_pair_node
+                .child_by_field_name("value")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?;
+            if seeking != type_name {
+                return None;
+            }
+
+            match resolver.resolve(mapping_pair_node.parent()?)? {
+                SchemaType::Prototype(proto) => {
+                    let mut value = class_markdown(&proto);
+                    if let Some(namespace) = &proto.namespace {
+                        value.push_str(&format!("\n\nNamespace: `{namespace}`"));
+                    }
+                    if let Some(file) = self.class_file(&proto) {
+                        value.push_str(&format!("\n\nDefined in {file}"));
+                    }
+                    let priority = proto.get_load_priority();
+                    value.push_str(&format!("\n\nLoad priority: {priority}"));
+                    value
+                }
+                SchemaType::Component(comp) => class_markdown(&comp),
+                _ => return None,
+            }
+        } else if key_name == "id" {
+            let block_mapping = mapping_pair_node.parent()?;
+            let SchemaType::Prototype(_) = resolver.resolve(block_mapping)? else {
+                return None;
+            };
+
+            self.prototype_summary(block_mapping, seeking)?
+        } else if matches!(key_name, "sprite" | "state") {
+            let value_node = mapping_pair_node.child_by_field_name("value")?;
+            if value_node.utf8_text(self.src.as_bytes()).ok()? != seeking {
+                return None;
+            }
+
+            match key_name {
+                "sprite" => self.rsi_summary(seeking, None)?,
+                _ => {
+                    let sprite = self.find_sprite(mapping_pair_node.parent()?)?;
+                    self.rsi_summary(sprite, Some(seeking))?
+                }
+            }
+        } else {
+            return None;
+        };
+
+        Some(hover(value, found_node))
+    }
+}
+
+impl YamlHover {
+    pub fn new(context: Arc<Context>, position: Position, rope: &Rope, root_path: PathBuf) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            position,
+            src,
+            tree,
+            root_path,
+        }
+    }
+
+    /// Describes the RSI from its `meta.json` and, for a state, shows its image
+    /// if the client renders markdown.
+    fn rsi_summary(&self, sprite: &str, state: Option<&str>) -> Option<String> {
+        let sprite = sprite.trim_end_matches(RESOURCE_PATH_SEPARATORS);
+        if !sprite.ends_with(".rsi") {
+            return None;
+        }
+
+        let sprites_folder = resource_path(&self.root_path, SPRITES_RES_PATH);
+        let rsi_path = resource_path(&sprites_folder, sprite);
+        let meta = self
+            .context
+            .rsi_meta
+            .get(&*self.context.fs, &rsi_path.join("meta.json"))?;
+
+        let rsi_name = rsi_path.file_name()?.to_string_lossy();
+        let mut value = format!("**{rsi_name}** {}×{}", meta.size.x, meta.size.y);
+        value.push_str(&format!("\n\nLicense: {}", meta.license));
+        value.push_str(&format!("\n\nCopyright: {}", meta.copyright));
+
+        match state {
+            Some(state) => match meta.states.iter().find(|s| s.name == state) {
+                Some(rsi_state) => {
+                    let directions = rsi_state.directions.unwrap_or(1);
+                    value.push_str(&format!("\n\nState `{state}`, directions: {directions}"));
+
+                    let image = rsi_path.join(format!("{state}.png"));
+                    if self.context.fs.exists(&image) && self.supports_markdown() {
+                        if let Ok(url) = Url::from_file_path(&image) {
+                            value.push_str(&format!("\n\n![{state}]({url})"));
+                        }
+                    }
+                }
+                None => value.push_str(&format!("\n\nState `{state}` is not found in the RSI.")),
+            },
+            None => {
+                let mut states = meta
+                    .states
+                    .iter()
+                    .take(MAX_LISTED_STATES)
+                    .map(|s| format!("`{}`", s.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if meta.states.len() > MAX_LISTED_STATES {
+                    states.push_str(&format!(
+                        " and {} more",
+                        meta.states.len() - MAX_LISTED_STATES
+                    ));
+                }
+                value.push_str(&format!("\n\nStates: {states}"));
+            }
+        }
+
+        Some(value)
+    }
+
+    /// Describes the audio file from its size and headers, or warns that it is missing.
+    fn audio_summary(&self, resource: &str) -> Option<String> {
+        let path = resource_path(&resource_path(&self.root_path, RESOURCES_PATH), resource);
+        let name = path.file_name()?.to_string_lossy();
+
+        if !self.context.fs.is_file(&path) {
+            return Some(format!(
+                "**{name}**\n\n⚠ The file is not found in `Resources{}`.",
+                resource
+                    .rsplit_once(RESOURCE_PATH_SEPARATORS)
+                    .map_or("", |(folder, _)| folder)
+            ));
+        }
+
+        let meta = AudioMeta::read(&*self.context.fs, &path)?;
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let mut value = format!(
+            "**{name}**\n\nFormat: {extension}, size: {}",
+            file_size(meta.size)
+        );
+        if let Some(duration) = meta.duration {
+            value.push_str(&format!("\n\nDuration: {:.2} s", duration.as_secs_f64()));
+        }
+
+        Some(value)
+    }
+
+    /// Finds the sprite the state belongs to, either in the same mapping, e.g. of a layer,
+    /// or in the mappings above it, e.g. of the `Sprite` component.
+    fn find_sprite(&self, block_mapping: Node) -> Option<&str> {
+        let mut node = Some(block_mapping);
+        while let Some(current) = node {
+            let sprite = self
+                .get_field(&current, "sprite")
+                .and_then(|f| f.child_by_field_name("value"))
+                .and_then(|v| v.utf8_text(self.src.as_bytes()).ok());
+            if sprite.is_some() {
+                return sprite;
+            }
+            node = current.parent();
+        }
+        None
+    }
+
+    /// A link to the file of the class, named by its path relative to the root.
+    fn class_file(&self, class: &CsharpClass) -> Option<String> {
+        let path = &class.index().0;
+        let name = path.strip_prefix(&self.root_path).unwrap_or(path);
+        let uri = Url::from_file_path(path).ok()?;
+        Some(format!("[`{}`]({uri})", name.display()))
+    }
+
+    fn supports_markdown(&self) -> bool {
+        block_in_place(|| self.context.client_capabilities.blocking_read())
+            .text_document
+            .as_ref()
+            .and_then(|t| t.hover.as_ref())
+            .and_then(|h| h.content_format.as_ref())
+            .is_some_and(|formats| formats.contains(&MarkupKind::Markdown))
+    }
+
+    /// Describes the prototype with its parents applied.
+    fn prototype_summary(&self, block_mapping: Node, id: &str) -> Option<String> {
+        let proto_name = self
+            .get_field(&block_mapping, "type")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+
+        let lock = block_in_place(|| self.context.prototypes.blocking_read());
+        let proto = lock.get(&YamlPrototype::dummy(proto_name, id))?;
+        let merged = proto.merge(&lock);
+
+        let mut value = format!("**{proto_name}** `{id}`");
+        if let Some(doc) = &proto.doc {
+            value.push_str(&format!("\n\n{doc}"));
+        }
+        if !merged.ancestors.is_empty() {
+            let ancestors = merged
+                .ancestors
+                .iter()
+                .map(|a| format!("`{a}`"))
+                .collect::<Vec<_>>()
+                .join(" → ");
+            value.push_str(&format!("\n\nParents: {ancestors}"));
+        }
+        if !merged.components.is_empty() {
+            value.push_str(&format!("\n\nComponents: {}", merged.components.len()));
+        }
+        for field in PREVIEW_FIELDS {
+            if let Some(field_value) = merged.fields.get(*field) {
+                value.push_str(&format!("\n\n{field}: {field_value}"));
+            }
+        }
+        if proto_name == "entity" {
+            value.push_str(&spawn_menu_summary(proto, &merged, &lock));
+        }
+
+        Some(value)
+    }
+
+    fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
+        if node.kind() != "block_mapping" {
+            return None;
+        }
+
+        for i in 0..node.named_child_count() {
+            let field_node = node.named_child(i)?;
+            let key = field_node
+                .child_by_field_name("key")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?;
+
+            if key == name {
+                return Some(field_node);
+            }
+        }
+        None
+    }
+}
+
+/// Explains whether the entity is listed in the spawn menu and under which categories.
+fn spawn_menu_summary(
+    proto: &YamlPrototype,
+    merged: &MergedPrototype,
+    prototypes: &HashSet<YamlPrototype>,
+) -> String {
+    let own = proto.lists.get("categories").cloned().unwrap_or_default();
+    // Categories marked `inheritable: false`, e.g. `HideSpawnMenu`, only apply to the prototype itself.
+    let categories = merged
+        .lists
+        .get("categories")
+        .into_iter()
+        .flatten()
+        .filter(|c| {
+            own.contains(c)
+                || prototypes
+                    .get(&YamlPrototype::dummy("entityCategory", c))
+                    .and_then(|category| category.fields.get("inheritable"))
+                    .is_none_or(|inheritable| inheritable != "false")
+        })
+        .collect::<Vec<_>>();
+
+    let reason = if proto.fields.get("abstract").is_some_and(|v| v == "true") {
+        Some("the prototype is abstract".to_owned())
+    } else if merged.fields.get("noSpawn").is_some_and(|v| v == "true") {
+        Some("`noSpawn` is set".to_owned())
+    } else if categories.iter().any(|c| *c == HIDE_SPAWN_MENU) {
+        Some(format!("it is in the `{HIDE_SPAWN_MENU}` category"))
+    } else {
+        None
+    };
+
+    let mut value = match reason {
+        Some(reason) => format!("\n\nHidden from the spawn menu: {reason}"),
+        None => "\n\nShown in the spawn menu".to_owned(),
+    };
+    if !categories.is_empty() {
+        let categories = categories
+            .iter()
+            .map(|c| format!("`{c}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        value.push_str(&format!("\n\nCategories: {categories}"));
+    }
+
+    value
+}
+
+fn file_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn hover(value: String, node: Node) -> lsp_types::Hover {
+    lsp_types::Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(Range::new(
+            Position::new(
+                node.start_position().row as u32,
+                node.start_position().column as u32,
+            ),
+            Position::new(
+                node.end_position().row as u32,
+                node.end_position().column as u32,
+            ),
+        )),
+    }
+}
+
+fn class_markdown(class: &CsharpClass) -> String {
+    let signature = match class.base.is_empty() {
+        true => format!("class {}", class.name),
+        false => format!("class {} : {}", class.name, class.base.join(", ")),
+    };
+    let mut value = markdown(&signature, &class.attributes);
+    if let Some(summary) = &class.summary {
+        value.push_str("\n\n");
+        value.push_str(summary);
+    }
+    value
+}
+
+fn markdown(signature: &str, attributes: &CsharpAttributeCollection) -> String {
+    let mut value = format!("```csharp\n{signature}\n```");
+
+    if let Some(message) = attributes.get_obsolete() {
+        value.push_str("\n\n**Obsolete**");
+        if !message.is_empty() {
+            value.push_str(": ");
+            value.push_str(&message);
+        }
+    }
+
+    value
+}
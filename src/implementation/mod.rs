@@ -0,0 +1,7 @@
+pub mod yaml;
+
+pub type ImplementationResult = Option<tower_lsp::lsp_types::request::GotoImplementationResponse>;
+
+pub trait Implementation {
+    fn implementation(&self) -> ImplementationResult;
+}
@@ -0,0 +1,154 @@
+use super::{Implementation, ImplementationResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::csharp::ReflectionManager,
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::block,
+};
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, GotoDefinitionResponse, Location, Position, Url};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+pub struct YamlImplementation {
+    context: Arc<Context>,
+    position: Position,
+    src: String,
+    tree: Tree,
+}
+
+impl Implementation for YamlImplementation {
+    fn implementation(&self) -> ImplementationResult {
+        let point = Point::new(
+            self.position.line as usize,
+            self.position.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let found_node = root_node.named_descendant_for_point_range(point, point)?;
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+
+        let mut locations = match found_node.kind() {
+            "tag" => self.tag_implementations(&resolver, found_node)?,
+            _ => self.prototype_references(&resolver, found_node)?,
+        };
+
+        if locations.is_empty() {
+            return None;
+        }
+
+        locations.sort_by(|a, b| {
+            (a.uri.as_str(), a.range.start.line).cmp(&(b.uri.as_str(), b.range.start.line))
+        });
+        Some(GotoDefinitionResponse::Array(locations))
+    }
+}
+
+impl YamlImplementation {
+    pub fn new(context: Arc<Context>, position: Position, rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            position,
+            src,
+            tree,
+        }
+    }
+
+    /// Finds the C# fields that reference the prototype from the value of its `type` field.
+    fn prototype_references(
+        &self,
+        resolver: &YamlSchemaResolver,
+        found_node: Node,
+    ) -> Option<Vec<Location>> {
+        let seeking = found_node.utf8_text(self.src.as_bytes()).ok()?;
+
+        let mapping_pair_node = {
+            let mut node = found_node;
+            while node.kind() != "block_mapping_pair" {
+                node = node.parent()?;
+            }
+            node
+        };
+
+        let key_name = mapping_pair_node
+            .child_by_field_name("key")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        let value = mapping_pair_node
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        if key_name != "type" || value != seeking {
+            return None;
+        }
+
+        let SchemaType::Prototype(proto) = resolver.resolve(mapping_pair_node.parent()?)? else {
+            return None;
+        };
+
+        let lock = block_in_place(|| self.context.classes.blocking_read());
+        let locations = lock
+            .iter()
+            .flat_map(|class| class.fields.iter())
+            .filter(|field| references(&field.type_name, &proto.name))
+            .filter_map(|field| index_to_location(field.index()))
+            .collect();
+
+        Some(locations)
+    }
+
+    /// Finds the classes that can be selected by a `!type:` tag in place of the field type.
+    fn tag_implementations(
+        &self,
+        resolver: &YamlSchemaResolver,
+        tag: Node,
+    ) -> Option<Vec<Location>> {
+        let declared_type = resolver.resolve_declared_type(tag)?;
+
+        let reflection = ReflectionManager::new(&self.context);
+        let locations = block(|| reflection.get_subclasses(&declared_type))
+            .iter()
+            .filter(|class| !class.modifiers.contains("abstract"))
+            .filter_map(|class| index_to_location(class.index()))
+            .collect();
+
+        Some(locations)
+    }
+}
+
+/// Checks whether a field type mentions the prototype, e.g. `ProtoId<TagPrototype>`
+/// or `List<EntProtoId>` for entities.
+fn references(type_name: &str, proto_class: &str) -> bool {
+    type_name
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|ident| {
+            ident == proto_class || (proto_class == "EntityPrototype" && ident == "EntProtoId")
+        })
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location {
+        uri: Url::from_file_path(path).ok()?,
+        range: lsp_types::Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    })
+}
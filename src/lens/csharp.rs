@@ -0,0 +1,110 @@
+use super::{CodeLens, SHOW_REFERENCES_COMMAND};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::csharp::{Component, CsharpClass, Prototype},
+    },
+};
+use serde_json::json;
+use std::{path::PathBuf, sync::Arc};
+use stringcase::camel_case;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, Command, Location, Position, Url};
+
+type CsharpCodeLensResult = Option<Vec<lsp_types::CodeLens>>;
+
+/// Counts the YAML prototypes of the prototype classes of the file
+/// and the entities that use its components.
+pub struct CsharpCodeLens {
+    context: Arc<Context>,
+    path: PathBuf,
+}
+
+impl CodeLens for CsharpCodeLens {
+    fn code_lens(&self) -> CsharpCodeLensResult {
+        let classes = block_in_place(|| self.context.classes.blocking_read());
+
+        let mut lenses = classes
+            .iter()
+            .filter(|c| c.index().0 == self.path)
+            .filter_map(|class| self.class_lens(class))
+            .collect::<Vec<_>>();
+
+        if lenses.is_empty() {
+            None
+        } else {
+            lenses.sort_by_key(|l| l.range.start);
+            Some(lenses)
+        }
+    }
+}
+
+impl CsharpCodeLens {
+    pub fn new(context: Arc<Context>, path: PathBuf) -> Self {
+        Self { context, path }
+    }
+
+    fn class_lens(&self, class: &CsharpClass) -> Option<lsp_types::CodeLens> {
+        let range = to_lsp_range(class.index())?;
+        let prototypes = block_in_place(|| self.context.prototypes.blocking_read());
+
+        let (mut locations, noun) = if let Ok(prototype) = Prototype::try_from(class) {
+            let kind = camel_case(&prototype.get_prototype_name());
+            let locations = prototypes
+                .iter()
+                .filter(|p| p.prototype == kind)
+                .filter_map(|p| index_to_location(p.index()))
+                .collect::<Vec<_>>();
+            (locations, "prototype")
+        } else if let Ok(component) = Component::try_from(class) {
+            let name = component.get_component_name();
+            let locations = prototypes
+                .iter()
+                .filter(|p| p.prototype == "entity" && p.components.contains(&name))
+                .filter_map(|p| index_to_location(p.index()))
+                .collect::<Vec<_>>();
+            (locations, "entity usage")
+        } else {
+            return None;
+        };
+
+        locations.sort_by(|a, b| {
+            (a.uri.as_str(), a.range.start.line).cmp(&(b.uri.as_str(), b.range.start.line))
+        });
+
+        let title = match locations.len() {
+            1 => format!("1 {noun}"),
+            count => format!("{count} {noun}s"),
+        };
+        let uri = Url::from_file_path(&self.path).ok()?;
+
+        Some(lsp_types::CodeLens {
+            range,
+            command: Some(Command {
+                title,
+                command: SHOW_REFERENCES_COMMAND.to_owned(),
+                arguments: Some(vec![json!(uri), json!(range.start), json!(locations)]),
+            }),
+            data: None,
+        })
+    }
+}
+
+fn to_lsp_range(index: &DefinitionIndex) -> Option<lsp_types::Range> {
+    let range = index.1?;
+    Some(lsp_types::Range::new(
+        Position::new(
+            range.start_point.row as u32,
+            range.start_point.column as u32,
+        ),
+        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+    ))
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    Some(Location::new(
+        Url::from_file_path(&index.0).ok()?,
+        to_lsp_range(index)?,
+    ))
+}
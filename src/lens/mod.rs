@@ -0,0 +1,11 @@
+#[cfg(feature = "csharp")]
+pub mod csharp;
+pub mod yaml;
+
+/// The client command that lists the locations, it takes the uri, the position
+/// the list is shown at and the locations.
+pub const SHOW_REFERENCES_COMMAND: &str = "editor.action.showReferences";
+
+pub trait CodeLens {
+    fn code_lens(&self) -> Option<Vec<tower_lsp::lsp_types::CodeLens>>;
+}
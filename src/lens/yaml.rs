@@ -0,0 +1,134 @@
+use super::{CodeLens, SHOW_REFERENCES_COMMAND};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        yaml::get_block_sequence_nodes,
+    },
+};
+use ropey::Rope;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, Command, Location, Position, Range, Url};
+use tree_sitter::{Node, Parser, Tree};
+
+type YamlCodeLensResult = Option<Vec<lsp_types::CodeLens>>;
+
+/// Counts the children of the prototypes of the file, the ones that list them in `parent`.
+pub struct YamlCodeLens {
+    context: Arc<Context>,
+    uri: Url,
+    src: String,
+    tree: Tree,
+}
+
+impl CodeLens for YamlCodeLens {
+    fn code_lens(&self) -> YamlCodeLensResult {
+        let root_node = self.tree.root_node();
+        let lenses = get_block_sequence_nodes(root_node)
+            .into_iter()
+            .flat_map(|n| (0..n.named_child_count()).filter_map(move |i| n.named_child(i)))
+            .filter_map(|item| self.prototype_lens(item))
+            .collect::<Vec<_>>();
+
+        if lenses.is_empty() {
+            None
+        } else {
+            Some(lenses)
+        }
+    }
+}
+
+impl YamlCodeLens {
+    pub fn new(context: Arc<Context>, uri: Url, rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            uri,
+            src,
+            tree,
+        }
+    }
+
+    /// The lens above the prototype, shown only when some prototypes inherit from it.
+    fn prototype_lens(&self, block_sequence_item: Node) -> Option<lsp_types::CodeLens> {
+        let block_mapping = block_sequence_item.named_child(0)?.named_child(0)?;
+        if block_mapping.kind() != "block_mapping" {
+            return None;
+        }
+
+        let kind = self.get_value(&block_mapping, "type")?;
+        let id = self.get_value(&block_mapping, "id")?;
+
+        let prototypes = block_in_place(|| self.context.prototypes.blocking_read());
+        let mut locations = prototypes
+            .iter()
+            .filter(|p| p.prototype == kind && p.parents.iter().any(|parent| parent == id))
+            .filter_map(|p| index_to_location(p.index()))
+            .collect::<Vec<_>>();
+        if locations.is_empty() {
+            return None;
+        }
+        locations.sort_by(|a, b| {
+            (a.uri.as_str(), a.range.start.line).cmp(&(b.uri.as_str(), b.range.start.line))
+        });
+
+        let title = match locations.len() {
+            1 => "1 child".to_owned(),
+            count => format!("{count} children"),
+        };
+        let start = block_sequence_item.start_position();
+        let position = Position::new(start.row as u32, start.column as u32);
+
+        Some(lsp_types::CodeLens {
+            range: Range::new(position, position),
+            command: Some(Command {
+                title,
+                command: SHOW_REFERENCES_COMMAND.to_owned(),
+                arguments: Some(vec![json!(self.uri), json!(position), json!(locations)]),
+            }),
+            data: None,
+        })
+    }
+
+    fn get_value<'a>(&'a self, node: &Node<'a>, name: &str) -> Option<&'a str> {
+        for i in 0..node.named_child_count() {
+            let field_node = node.named_child(i)?;
+            let key = field_node
+                .child_by_field_name("key")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?;
+
+            if key == name {
+                return field_node
+                    .child_by_field_name("value")?
+                    .utf8_text(self.src.as_bytes())
+                    .ok();
+            }
+        }
+        None
+    }
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    ))
+}
@@ -0,0 +1,28 @@
+pub mod action;
+pub mod backend;
+pub mod cli;
+pub mod completion;
+pub mod config;
+pub mod diagnostic;
+pub mod encoding;
+pub mod folding;
+pub mod goto;
+pub mod graph;
+pub mod hint;
+pub mod hover;
+pub mod implementation;
+pub mod lens;
+#[cfg(feature = "fluent")]
+pub mod locale;
+pub mod logging;
+pub mod parse;
+pub mod picker;
+pub mod references;
+pub mod rename;
+pub mod schema;
+pub mod semantic;
+pub mod status;
+pub mod timings;
+pub mod type_definition;
+pub mod utils;
+pub mod vfs;
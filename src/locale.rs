@@ -0,0 +1,189 @@
+use crate::{
+    backend::Context,
+    encoding::{ConvertPositions, PositionConverter},
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::fluent::FluentKeyKind,
+    },
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use tower_lsp::lsp_types::{Location, Position, Range, Url};
+use tree_sitter::{Node, Parser};
+
+/// YAML nodes whose text may be a locale key.
+static SCALAR_KINDS: &[&str] = &["plain_scalar", "double_quote_scalar", "single_quote_scalar"];
+
+/// A locale key that isn't referenced anywhere in the project.
+#[derive(Debug, Serialize)]
+pub struct UnusedLocaleKey {
+    pub key: String,
+    /// The locale folder the key is defined in, e.g. `en-US`.
+    pub locale: String,
+    pub location: Location,
+}
+
+impl ConvertPositions for UnusedLocaleKey {
+    fn convert(&mut self, uri: &Url, converter: &mut PositionConverter) {
+        self.location.convert(uri, converter);
+    }
+}
+
+/// Finds the locale messages that are referenced neither from the YAML prototypes,
+/// e.g. `LocId` fields, nor from the C# code, i.e. `Loc.GetString` calls and `LocId` defaults,
+/// nor from other messages, nor by the entity locale convention, e.g. `ent-Crowbar`.
+pub struct UnusedLocaleKeys {
+    context: Arc<Context>,
+}
+
+impl UnusedLocaleKeys {
+    pub fn new(context: Arc<Context>) -> Self {
+        Self { context }
+    }
+
+    pub async fn find(&self) -> Vec<UnusedLocaleKey> {
+        let used = self.used_keys().await;
+        let locales = self.context.locales.read().await;
+
+        let mut unused = locales
+            .iter()
+            .filter(|k| k.kind == FluentKeyKind::Message && !used.contains(&k.key))
+            .filter_map(|k| {
+                Some(UnusedLocaleKey {
+                    key: k.key.clone(),
+                    locale: k.locale.clone(),
+                    location: index_to_location(k.index())?,
+                })
+            })
+            .collect::<Vec<_>>();
+        unused.sort_by(|a, b| {
+            let a = (a.location.uri.as_str(), a.location.range.start.line);
+            a.cmp(&(b.location.uri.as_str(), b.location.range.start.line))
+        });
+
+        unused
+    }
+
+    async fn used_keys(&self) -> HashSet<String> {
+        let mut used = HashSet::new();
+
+        // Messages may reference each other, e.g. `{ tool-verb-use }`.
+        for key in self.context.locales.read().await.iter() {
+            used.extend(message_references(&key.value).map(str::to_owned));
+        }
+
+        let prefix = self
+            .context
+            .config
+            .read()
+            .await
+            .diagnostics
+            .entity_locale
+            .prefix
+            .clone();
+        let files = {
+            let prototypes = self.context.prototypes.read().await;
+            used.extend(
+                prototypes
+                    .iter()
+                    .filter(|p| p.prototype == "entity")
+                    .map(|p| format!("{prefix}{}", p.id)),
+            );
+            prototypes
+                .iter()
+                .flat_map(|p| p.definitions())
+                .map(|p| p.index().0.clone())
+                .collect::<BTreeSet<_>>()
+        };
+        for file in files {
+            used.extend(self.yaml_scalars(file));
+        }
+
+        #[cfg(feature = "csharp")]
+        {
+            let classes = self.context.classes.read().await;
+            used.extend(
+                classes
+                    .iter()
+                    .flat_map(|c| c.fields.iter())
+                    .filter(|f| f.type_name.trim_end_matches('?') == "LocId")
+                    .filter_map(|f| f.default_value.as_deref())
+                    .filter(|v| v.starts_with('"'))
+                    .map(|v| v.trim_matches('"').to_owned()),
+            );
+
+            let locale_usages = self.context.locale_usages.read().await;
+            used.extend(locale_usages.names().map(str::to_owned));
+        }
+
+        used
+    }
+
+    /// The texts of all the scalars of the YAML file.
+    fn yaml_scalars(&self, path: PathBuf) -> Vec<String> {
+        let Ok(src) = self.context.fs.read_to_string(&path) else {
+            tracing::warn!("Failed to read the file {}", path.display());
+            return vec![];
+        };
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let Some(tree) = parser.parse(&src, None) else {
+            return vec![];
+        };
+
+        let mut scalars = vec![];
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            if SCALAR_KINDS.contains(&node.kind()) {
+                scalars.extend(scalar_text(node, &src));
+                continue;
+            }
+            for i in 0..node.named_child_count() {
+                stack.push(node.named_child(i).unwrap());
+            }
+        }
+
+        scalars
+    }
+}
+
+fn scalar_text(node: Node, src: &str) -> Option<String> {
+    let text = node.utf8_text(src.as_bytes()).ok()?;
+    Some(text.trim_matches(|c| c == '"' || c == '\'').to_owned())
+}
+
+/// The messages referenced in the placeables of the text, e.g. `other` in `Hello { other }`.
+fn message_references(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{').skip(1).filter_map(|placeable| {
+        let reference = placeable.split('}').next()?.trim();
+        // A reference to an attribute, e.g. `{ other.desc }`, uses the message too.
+        let reference = reference.split('.').next()?;
+        let is_message = reference.starts_with(|c: char| c.is_ascii_alphabetic())
+            && reference
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        is_message.then_some(reference)
+    })
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    ))
+}
@@ -0,0 +1,168 @@
+use serde::Deserialize;
+use std::{io, str::FromStr, sync::OnceLock};
+use tracing_subscriber::{
+    filter::{self, LevelFilter, Targets},
+    layer::SubscriberExt,
+    registry::Registry,
+    reload,
+    util::SubscriberInitExt,
+    Layer,
+};
+
+type FilteredRegistry =
+    tracing_subscriber::layer::Layered<reload::Layer<Targets, Registry>, Registry>;
+type FormatLayer = Box<dyn Layer<FilteredRegistry> + Send + Sync>;
+
+struct Handles {
+    filter: reload::Handle<Targets, Registry>,
+    format: reload::Handle<FormatLayer, FilteredRegistry>,
+}
+
+static HANDLES: OnceLock<Handles> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(format!("unknown log level: {s}")),
+        }
+    }
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Compact,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format: {s}")),
+        }
+    }
+}
+
+/// Installs the global subscriber writing to stderr.
+pub fn init(level: LogLevel, format: LogFormat) {
+    let (filter, filter_handle) = reload::Layer::new(targets(level));
+    let (format, format_handle) = reload::Layer::new(format_layer(format));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(format)
+        .init();
+
+    let _ = HANDLES.set(Handles {
+        filter: filter_handle,
+        format: format_handle,
+    });
+}
+
+/// Routes panics through the logger so that the payload and location end up
+/// in the same stream as the rest of the server output.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non-string payload>");
+
+        match info.location() {
+            Some(location) => tracing::error!(
+                "Panicked at {}:{}: {}",
+                location.file(),
+                location.line(),
+                payload
+            ),
+            None => tracing::error!("Panicked: {}", payload),
+        }
+    }));
+}
+
+/// Changes the level and/or format of the already installed subscriber.
+pub fn reconfigure(level: Option<LogLevel>, format: Option<LogFormat>) {
+    let Some(handles) = HANDLES.get() else {
+        return;
+    };
+
+    if let Some(level) = level {
+        if let Err(err) = handles.filter.reload(targets(level)) {
+            tracing::warn!("Failed to change the log level: {err}");
+        }
+    }
+
+    if let Some(format) = format {
+        if let Err(err) = handles.format.reload(format_layer(format)) {
+            tracing::warn!("Failed to change the log format: {err}");
+        }
+    }
+}
+
+fn targets(level: LogLevel) -> Targets {
+    let targets = filter::Targets::new().with_target("robust_lsp", level);
+    #[cfg(debug_assertions)]
+    let targets = targets.with_target("tower_lsp", level);
+    targets
+}
+
+fn format_layer(format: LogFormat) -> FormatLayer {
+    match format {
+        LogFormat::Compact => tracing_subscriber::fmt::layer()
+            .compact()
+            .with_ansi(false)
+            .without_time()
+            .with_line_number(true)
+            .with_file(true)
+            .with_writer(io::stderr)
+            .with_thread_ids(true)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_line_number(true)
+            .with_file(true)
+            .with_writer(io::stderr)
+            .with_thread_ids(true)
+            .boxed(),
+    }
+}
@@ -1,21 +1,63 @@
-use backend::Backend;
-use clap::{arg, command, crate_version};
-use std::io;
-use tower_lsp::{LspService, Server};
-use tracing_subscriber::{filter, layer::SubscriberExt, util::SubscriberInitExt};
-
-mod backend;
-mod completion;
-mod goto;
-mod hint;
-mod parse;
-mod utils;
+use clap::{arg, command, crate_version, Command};
+use robust_lsp::{
+    backend::Backend,
+    cli,
+    logging::{self, LogFormat, LogLevel},
+};
+use std::path::PathBuf;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+};
+use tower_lsp::Server;
 
 #[tokio::main]
 async fn main() {
     let matches = command!()
         .disable_version_flag(true)
         .arg(arg!(-v --version "Print version information"))
+        .arg(
+            arg!(--"bench-parse" <PATH> "Index the project at the path and print timings")
+                .value_parser(clap::value_parser!(PathBuf))
+                .hide(true),
+        )
+        .arg(
+            arg!(--"log-level" <LEVEL> "Minimum level of the logs written to stderr")
+                .value_parser(["off", "error", "warn", "info", "debug", "trace"])
+                .default_value("trace"),
+        )
+        .arg(
+            arg!(--"log-format" <FORMAT> "Format of the logs written to stderr")
+                .value_parser(["compact", "json"])
+                .default_value("compact"),
+        )
+        .arg(
+            arg!(--listen <ADDR> "Listen for the client on a TCP address instead of stdio")
+                .conflicts_with("pipe"),
+        )
+        .arg(arg!(--pipe <NAME> "Connect to the client through a named pipe instead of stdio"))
+        .subcommand(
+            Command::new("check")
+                .about("Run diagnostics over the project and print the findings as JSON")
+                .arg(arg!(<ROOT> "Project root").value_parser(clap::value_parser!(PathBuf))),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare the prototypes of two snapshots and print the changed ids as JSON")
+                .arg(arg!(<OLD> "Old snapshot root").value_parser(clap::value_parser!(PathBuf)))
+                .arg(arg!(<NEW> "New snapshot root").value_parser(clap::value_parser!(PathBuf))),
+        )
+        .subcommand(
+            Command::new("index")
+                .about(
+                    "Index the project and write the classes, prototypes and locale keys as JSON",
+                )
+                .arg(arg!(<ROOT> "Project root").value_parser(clap::value_parser!(PathBuf)))
+                .arg(
+                    arg!(-o --output <FILE> "File to write the index to instead of stdout")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
         .get_matches();
 
     if matches.get_one::<bool>("version") == Some(&true) {
@@ -23,27 +65,84 @@ async fn main() {
         return;
     }
 
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .compact()
-        .with_ansi(false)
-        .without_time()
-        .with_line_number(true)
-        .with_file(true)
-        .with_writer(io::stderr)
-        .with_thread_ids(true);
-
-    let targets = filter::Targets::new().with_target("robust_lsp", filter::LevelFilter::TRACE);
-    #[cfg(debug_assertions)]
-    let targets = targets.with_target("tower_lsp", filter::LevelFilter::TRACE);
-
-    tracing_subscriber::registry()
-        .with(targets)
-        .with(fmt_layer)
-        .init();
-
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
-
-    let (service, socket) = LspService::new(|client| Backend::new(client));
-    Server::new(stdin, stdout, socket).serve(service).await;
+    if let Some(path) = matches.get_one::<PathBuf>("bench-parse") {
+        cli::bench_parse(path).await;
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("check") {
+        let root = matches.get_one::<PathBuf>("ROOT").unwrap();
+        std::process::exit(cli::check(root).await);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("diff") {
+        let old = matches.get_one::<PathBuf>("OLD").unwrap();
+        let new = matches.get_one::<PathBuf>("NEW").unwrap();
+        std::process::exit(cli::print_prototype_diff(old, new).await);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("index") {
+        let root = matches.get_one::<PathBuf>("ROOT").unwrap();
+        let output = matches.get_one::<PathBuf>("output");
+        std::process::exit(cli::export_index(root, output.map(PathBuf::as_path)).await);
+    }
+
+    let level = matches.get_one::<String>("log-level").unwrap();
+    let format = matches.get_one::<String>("log-format").unwrap();
+    logging::init(
+        level.parse::<LogLevel>().unwrap(),
+        format.parse::<LogFormat>().unwrap(),
+    );
+    logging::install_panic_hook();
+
+    if let Some(addr) = matches.get_one::<String>("listen") {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                tracing::error!("Failed to listen on {addr}: {err}");
+                std::process::exit(1);
+            }
+        };
+
+        tracing::info!("Waiting for the client on {addr}...");
+        match listener.accept().await {
+            Ok((stream, client_addr)) => {
+                tracing::info!("Client connected from {client_addr}.");
+                let (read, write) = tokio::io::split(stream);
+                serve(read, write).await;
+            }
+            Err(err) => tracing::error!("Failed to accept the client: {err}"),
+        }
+    } else if let Some(name) = matches.get_one::<String>("pipe") {
+        match connect_pipe(name).await {
+            Ok((read, write)) => serve(read, write).await,
+            Err(err) => {
+                tracing::error!("Failed to connect to the pipe {name}: {err}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        serve(tokio::io::stdin(), tokio::io::stdout()).await;
+    }
+}
+
+async fn serve(read: impl AsyncRead + Unpin, write: impl AsyncWrite) {
+    let (service, socket) = Backend::service();
+    Server::new(read, write, socket).serve(service).await;
+}
+
+#[cfg(unix)]
+async fn connect_pipe(
+    name: &str,
+) -> std::io::Result<(impl AsyncRead + Unpin, impl AsyncWrite + Unpin)> {
+    let stream = tokio::net::UnixStream::connect(name).await?;
+    Ok(tokio::io::split(stream))
+}
+
+#[cfg(windows)]
+async fn connect_pipe(
+    name: &str,
+) -> std::io::Result<(impl AsyncRead + Unpin, impl AsyncWrite + Unpin)> {
+    let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(name)?;
+    Ok(tokio::io::split(pipe))
 }
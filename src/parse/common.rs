@@ -1,10 +1,4 @@
-use super::Result;
-use ropey::Rope;
-use std::{
-    path::{Path, PathBuf},
-    sync::Arc,
-};
-use tree_sitter::Node;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DefinitionIndex(pub PathBuf, pub Option<tree_sitter::Range>);
@@ -13,8 +7,13 @@ pub trait Index {
     fn index(&self) -> &DefinitionIndex;
 }
 
+#[cfg(feature = "csharp")]
 pub(super) trait ParseFromNode {
-    fn get(node: Node, src: Arc<Rope>, path: &Path) -> Result<Self>
+    fn get(
+        node: tree_sitter::Node,
+        src: std::sync::Arc<ropey::Rope>,
+        path: &std::path::Path,
+    ) -> super::Result<Self>
     where
         Self: Sized;
 }
@@ -1,8 +1,11 @@
 use super::{
     common::{DefinitionIndex, ParseFromNode},
-    structs::csharp::{
-        CsharpAttribute, CsharpAttributeArgument, CsharpAttributeArgumentType,
-        CsharpAttributeCollection, CsharpClass, CsharpClassField,
+    structs::{
+        csharp::{
+            CsharpAttribute, CsharpAttributeArgument, CsharpAttributeArgumentType,
+            CsharpAttributeCollection, CsharpClass, CsharpClassField, CsharpUsage,
+        },
+        intern::Interned,
     },
     ParseResult,
 };
@@ -17,8 +20,15 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tree_sitter::Node;
-
+use tree_sitter::{Node, Tree};
+
+/// Declarations indexed as classes, data definitions are often records or structs,
+/// e.g. `DamageModifierSet`.
+static TYPE_DECLARATIONS: &[&str] = &[
+    "class_declaration",
+    "record_declaration",
+    "struct_declaration",
+];
 static PROTOTYPE_ATTR_ARGS: &[&str] = &["type", "loadPriority"];
 static DATA_FIELD_ATTR_ARGS: &[&str] = &[
     "tag",
@@ -29,6 +39,32 @@ static DATA_FIELD_ATTR_ARGS: &[&str] = &[
     "customTypeSerializer",
 ];
 static ID_DATA_FIELD_ATTR_ARGS: &[&str] = &["priority", "customTypeSerializer"];
+static OBSOLETE_ATTR_ARGS: &[&str] = &["message", "error"];
+/// Generic entity system APIs whose type arguments are components, e.g. `TryComp<T>`.
+pub(crate) static COMPONENT_APIS: &[&str] = &[
+    "Comp",
+    "CompOrNull",
+    "TryComp",
+    "EnsureComp",
+    "HasComp",
+    "AddComp",
+    "RemComp",
+    "RemCompDeferred",
+    "GetComponent",
+    "TryGetComponent",
+    "EnsureComponent",
+    "HasComponent",
+    "AddComponent",
+    "RemoveComponent",
+    "GetEntityQuery",
+    "EntityQuery",
+    "EntityQueryEnumerator",
+    "AllEntityQuery",
+    "SubscribeLocalEvent",
+    "Entity",
+];
+/// Methods of `Loc` whose first argument is a locale key, e.g. `Loc.GetString("key")`.
+static LOCALE_APIS: &[&str] = &["GetString", "TryGetString"];
 
 type Result<T, E = ()> = std::result::Result<T, E>;
 
@@ -36,13 +72,15 @@ pub(crate) fn dispatch(
     result: ParseResult,
     context: Arc<crate::backend::Context>,
 ) -> BoxFuture<'static, ()> {
-    let ParseResult::Csharp(classes) = result else {
+    let ParseResult::Csharp(classes, usages, locale_usages) = result else {
         tracing::warn!("Failed to parse C# prototypes.");
         return ready(()).boxed();
     };
 
     Box::pin(async move {
-        context.classes.write().await.extend(classes);
+        context.extend_classes(classes).await;
+        context.usages.write().await.extend(usages);
+        context.locale_usages.write().await.extend(locale_usages);
     })
 }
 
@@ -54,17 +92,24 @@ pub(crate) fn parse(
 }
 
 async fn p(path: PathBuf, parsed_files: ParsedFiles) -> Result<ParseResult> {
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&tree_sitter_c_sharp::LANGUAGE.into())
-        .expect("Failed to load C# grammer");
+    let Ok(rope) = std::fs::File::open(&path).and_then(Rope::from_reader) else {
+        tracing::warn!("Failed to read the file {}", path.display());
+        return Err(());
+    };
 
-    let rope = Rope::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+    parse_rope(path, rope, parsed_files).await
+}
 
+/// Parses the text of the file, e.g. the one sent by the client on save.
+pub(crate) async fn parse_rope(
+    path: PathBuf,
+    rope: Rope,
+    parsed_files: ParsedFiles,
+) -> Result<ParseResult> {
     let mut lock = parsed_files.write().await;
     let old_tree = lock.get_mut(&path);
 
-    let tree = parser.parse(rope.to_string(), old_tree.as_deref());
+    let tree = parse_tree(&rope.to_string(), old_tree.as_deref());
     if let Some(tree) = tree {
         if let Some(old_tree) = old_tree {
             *old_tree = tree.clone();
@@ -74,6 +119,8 @@ async fn p(path: PathBuf, parsed_files: ParsedFiles) -> Result<ParseResult> {
         let root_node = tree.root_node();
         let src = Arc::new(rope);
         let mut stack = vec![root_node];
+        let mut usages = vec![];
+        let mut locale_usages = vec![];
 
         // TODO: Replace scope to TokioScope
         let classes = std::thread::scope(|s| {
@@ -82,13 +129,19 @@ async fn p(path: PathBuf, parsed_files: ParsedFiles) -> Result<ParseResult> {
             while !stack.is_empty() {
                 let node = stack.pop().unwrap();
 
-                if node.kind() == "class_declaration" {
+                if TYPE_DECLARATIONS.contains(&node.kind()) {
                     let src = src.clone();
                     handles.push(s.spawn({
                         let path = path.clone();
                         move || CsharpClass::get(node, src, &path)
                     }));
                 }
+                if node.kind() == "generic_name" {
+                    usages.extend(get_type_usages(node, &src, &path));
+                }
+                if node.kind() == "invocation_expression" {
+                    locale_usages.extend(get_locale_usage(node, &src, &path));
+                }
 
                 for i in 0..node.named_child_count() {
                     stack.push(node.named_child(i).unwrap());
@@ -97,17 +150,81 @@ async fn p(path: PathBuf, parsed_files: ParsedFiles) -> Result<ParseResult> {
 
             handles
                 .into_iter()
-                .map(|h| h.join().unwrap())
+                .filter_map(|h| h.join().ok())
                 .filter_map(Result::ok)
                 .collect::<Vec<_>>()
         });
 
-        return Ok(ParseResult::Csharp(classes));
+        return Ok(ParseResult::Csharp(classes, usages, locale_usages));
     }
 
     Err(())
 }
 
+/// Parses the text, incrementally when the old tree is edited to match it.
+pub(crate) fn parse_tree(src: &str, old_tree: Option<&Tree>) -> Option<Tree> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&tree_sitter_c_sharp::LANGUAGE.into())
+        .expect("Failed to load C# grammer");
+
+    parser.parse(src, old_tree)
+}
+
+/// Returns the type arguments of a generic entity system API call, e.g. `TryComp<T>`.
+fn get_type_usages(generic_name_node: Node, src: &Rope, path: &Path) -> Vec<CsharpUsage> {
+    let (Some(name_node), Some(arguments_node)) = (
+        generic_name_node.named_child(0),
+        generic_name_node.named_child(1),
+    ) else {
+        return vec![];
+    };
+    let name = src.byte_slice(name_node.byte_range()).to_string();
+    if !COMPONENT_APIS.contains(&name.as_str()) {
+        return vec![];
+    }
+
+    let mut cursor = arguments_node.walk();
+    arguments_node
+        .named_children(&mut cursor)
+        .filter(|n| matches!(n.kind(), "identifier" | "qualified_name"))
+        .map(|n| {
+            let type_name = src.byte_slice(n.byte_range()).to_string();
+            let type_name = type_name.rsplit('.').next().unwrap_or_default().to_owned();
+            CsharpUsage::new(
+                type_name,
+                DefinitionIndex(path.to_path_buf(), Some(n.range())),
+            )
+        })
+        .collect()
+}
+
+/// Returns the locale key passed as a string literal to `Loc.GetString` or `Loc.TryGetString`.
+fn get_locale_usage(invocation_node: Node, src: &Rope, path: &Path) -> Option<CsharpUsage> {
+    let function_node = invocation_node.child_by_field_name("function")?;
+    let name_node = match function_node.kind() {
+        "member_access_expression" => function_node.child_by_field_name("name")?,
+        "identifier" => function_node,
+        _ => return None,
+    };
+    let name = src.byte_slice(name_node.byte_range()).to_string();
+    if !LOCALE_APIS.contains(&name.as_str()) {
+        return None;
+    }
+
+    let arguments_node = invocation_node.child_by_field_name("arguments")?;
+    let literal_node = arguments_node.named_child(0)?.named_child(0)?;
+    if literal_node.kind() != "string_literal" {
+        return None;
+    }
+
+    let literal = src.byte_slice(literal_node.byte_range()).to_string();
+    Some(CsharpUsage::new(
+        literal.trim_matches('"').to_owned(),
+        DefinitionIndex(path.to_path_buf(), Some(literal_node.range())),
+    ))
+}
+
 impl ParseFromNode for CsharpClass {
     fn get(node: Node, src: Arc<Rope>, path: &Path) -> Result<Self> {
         let mut cursor = node.walk();
@@ -123,8 +240,8 @@ impl ParseFromNode for CsharpClass {
             let source = src.clone().to_string();
             match node.kind() {
                 "modifier" => {
-                    let modifier = node.utf8_text(source.as_bytes()).unwrap().to_owned();
-                    modifiers.insert(modifier);
+                    let modifier = node.utf8_text(source.as_bytes()).unwrap();
+                    modifiers.insert(Interned::new(modifier));
                 }
                 "identifier" => {
                     let indent = node.utf8_text(source.as_bytes()).unwrap().to_owned();
@@ -148,9 +265,16 @@ impl ParseFromNode for CsharpClass {
                     for node in node.named_children(&mut cursor) {
                         match node.kind() {
                             "field_declaration" | "property_declaration" => {
-                                if let Ok(field) = CsharpClassField::get(node, src.clone(), path) {
-                                    fields.push(field);
+                                let Ok(field) = CsharpClassField::get(node, src.clone(), path)
+                                else {
+                                    continue;
+                                };
+                                // Computed properties, e.g. `bool Lit => Fuel > 0`, can't be set
+                                // from YAML unless they are data fields anyway.
+                                if is_get_only(node, &source) && !field.is_serialized() {
+                                    continue;
                                 }
+                                fields.push(field);
                             }
                             _ => {}
                         }
@@ -160,18 +284,118 @@ impl ParseFromNode for CsharpClass {
             }
         }
 
-        match name {
-            Some(name) => Ok(CsharpClass::new(
-                name,
-                base,
-                attributes,
-                fields,
-                modifiers,
-                DefinitionIndex(path.to_path_buf(), name_range),
-            )),
-            _ => Err(()),
+        resolve_constants(&mut attributes, &mut fields);
+
+        let Some(name) = name else {
+            return Err(());
+        };
+
+        let mut class = CsharpClass::new(
+            name,
+            base,
+            attributes,
+            fields,
+            modifiers,
+            DefinitionIndex(path.to_path_buf(), name_range),
+        );
+        class.namespace = get_namespace(node, &src);
+        class.summary = get_summary(node, &src);
+        Ok(class)
+    }
+}
+
+/// Whether the property has neither a `set` nor an `init` accessor,
+/// e.g. `Count => items.Count` or `Empty { get { ... } }`.
+fn is_get_only(node: Node, src: &str) -> bool {
+    if node.kind() != "property_declaration" {
+        return false;
+    }
+    let Some(accessors) = node.child_by_field_name("accessors") else {
+        return true;
+    };
+
+    let mut cursor = accessors.walk();
+    let has_setter = accessors
+        .named_children(&mut cursor)
+        .filter_map(|accessor| accessor.child_by_field_name("name"))
+        .any(|name| matches!(name.utf8_text(src.as_bytes()), Ok("set" | "init")));
+    !has_setter
+}
+
+/// Returns the namespace of the declaration, either the enclosing blocks
+/// or the file-scoped one, e.g. `namespace Content.Shared.Tools;`.
+fn get_namespace(node: Node, src: &Rope) -> Option<String> {
+    let mut names = vec![];
+    let mut parent = node.parent();
+    while let Some(n) = parent {
+        let namespace = match n.kind() {
+            "namespace_declaration" => Some(n),
+            "compilation_unit" => {
+                let mut cursor = n.walk();
+                let namespace = n
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "file_scoped_namespace_declaration");
+                namespace
+            }
+            _ => None,
+        };
+        if let Some(name_node) = namespace.and_then(|n| n.child_by_field_name("name")) {
+            names.push(src.byte_slice(name_node.byte_range()).to_string());
         }
+        parent = n.parent();
     }
+
+    if names.is_empty() {
+        return None;
+    }
+    names.reverse();
+    Some(names.join("."))
+}
+
+/// Returns the `<summary>` of the `///` comments right above the declaration,
+/// the references such as `<see cref="EntityUid"/>` are kept as their names.
+fn get_summary(node: Node, src: &Rope) -> Option<String> {
+    let mut lines = vec![];
+    let mut sibling = node.prev_named_sibling();
+    while let Some(comment) = sibling.filter(|n| n.kind() == "comment") {
+        let text = src.byte_slice(comment.byte_range()).to_string();
+        let Some(line) = text.strip_prefix("///") else {
+            break;
+        };
+        lines.push(line.trim().to_owned());
+        sibling = comment.prev_named_sibling();
+    }
+    lines.reverse();
+
+    let doc = lines.join(" ");
+    let summary = match (doc.find("<summary>"), doc.find("</summary>")) {
+        (Some(start), Some(end)) if start < end => &doc[start + "<summary>".len()..end],
+        _ => doc.as_str(),
+    };
+
+    let mut text = String::new();
+    let mut rest = summary;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        if let Some(reference) = ["cref=\"", "langword=\"", "name=\""]
+            .iter()
+            .find_map(|attr| tag.split_once(attr))
+            .and_then(|(_, value)| value.split('"').next())
+        {
+            let reference = reference.rsplit([':', '.']).next().unwrap_or(reference);
+            text.push_str(&format!("`{reference}`"));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    let summary = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!summary.is_empty()).then_some(summary)
 }
 
 impl ParseFromNode for CsharpClassField {
@@ -184,6 +408,7 @@ impl ParseFromNode for CsharpClassField {
         let mut type_name = None;
         let mut field_name = None;
         let mut name_range = None;
+        let mut default_value = None;
 
         if node.kind() == "field_declaration" {
             for node in node.named_children(&mut cursor) {
@@ -191,8 +416,8 @@ impl ParseFromNode for CsharpClassField {
                     "attribute_list" => attributes
                         .extend(Vec::<CsharpAttribute>::get(node, src.clone(), path)?.into_iter()),
                     "modifier" => {
-                        let modifier = node.utf8_text(source.as_bytes()).unwrap().to_owned();
-                        modifiers.insert(modifier);
+                        let modifier = node.utf8_text(source.as_bytes()).unwrap();
+                        modifiers.insert(Interned::new(modifier));
                     }
                     "variable_declaration" => {
                         let type_node = node.child_by_field_name("type");
@@ -208,6 +433,7 @@ impl ParseFromNode for CsharpClassField {
                                     );
                                     name_range = Some(name_node.range());
                                 }
+                                default_value = get_initializer(declarator_node, &source);
                             }
                         }
                     }
@@ -227,14 +453,20 @@ impl ParseFromNode for CsharpClassField {
                 _ => return Err(()),
             }
 
+            default_value = node
+                .child_by_field_name("value")
+                .filter(|n| n.kind() != "arrow_expression_clause")
+                .and_then(|n| n.utf8_text(source.as_bytes()).ok())
+                .map(str::to_owned);
+
             for prop_node in node.named_children(&mut cursor) {
                 match prop_node.kind() {
                     "attribute_list" => attributes.extend(
                         Vec::<CsharpAttribute>::get(prop_node, src.clone(), path)?.into_iter(),
                     ),
                     "modifier" => {
-                        let modifier = prop_node.utf8_text(source.as_bytes()).unwrap().to_owned();
-                        modifiers.insert(modifier);
+                        let modifier = prop_node.utf8_text(source.as_bytes()).unwrap();
+                        modifiers.insert(Interned::new(modifier));
                     }
                     _ => {}
                 }
@@ -244,9 +476,10 @@ impl ParseFromNode for CsharpClassField {
         match (field_name, type_name) {
             (Some(field_name), Some(type_name)) => Ok(CsharpClassField::new(
                 field_name,
-                type_name,
+                type_name.into(),
                 attributes,
                 modifiers,
+                default_value,
                 DefinitionIndex(path.to_path_buf(), name_range),
             )),
             _ => Err(()),
@@ -254,6 +487,94 @@ impl ParseFromNode for CsharpClassField {
     }
 }
 
+/// Returns the name given to `nameof`, quoted the way string literals are kept,
+/// e.g. `"Damage"` for `nameof(Damage)` and `nameof(WelderComponent.Damage)`.
+fn get_nameof(invocation_node: Node, src: &str) -> Option<String> {
+    let function = invocation_node.child_by_field_name("function")?;
+    if function.utf8_text(src.as_bytes()).ok()? != "nameof" {
+        return None;
+    }
+
+    let arguments = invocation_node.child_by_field_name("arguments")?;
+    let argument = arguments.named_child(0)?.utf8_text(src.as_bytes()).ok()?;
+    nameof(argument)
+}
+
+/// The name `nameof` evaluates the expression to, quoted.
+fn nameof(expression: &str) -> Option<String> {
+    let name = expression.rsplit('.').next()?.split('<').next()?.trim();
+    Some(format!("\"{name}\""))
+}
+
+/// Replaces the references to the constants of the class in the attributes of the class
+/// and its fields, e.g. `[DataField(FuelTag)]` with `public const string FuelTag = "fuel";`.
+fn resolve_constants(attributes: &mut CsharpAttributeCollection, fields: &mut [CsharpClassField]) {
+    let constants = fields
+        .iter()
+        .filter(|f| f.modifiers.contains("const"))
+        .filter_map(|f| {
+            Some((
+                f.name.clone(),
+                get_constant_value(f.default_value.as_ref()?)?,
+            ))
+        })
+        .collect::<HashMap<_, _>>();
+    if constants.is_empty() {
+        return;
+    }
+
+    let attributes = attributes.attributes.iter_mut().chain(
+        fields
+            .iter_mut()
+            .flat_map(|f| f.attributes.attributes.iter_mut()),
+    );
+    for attribute in attributes {
+        for argument in attribute.arguments.values_mut() {
+            if let CsharpAttributeArgumentType::Constant(ref name) = argument.value {
+                if let Some(value) = constants.get(name) {
+                    argument.value = value.clone();
+                }
+            }
+        }
+    }
+}
+
+/// The value of the initializer of a constant, only literals and `nameof` are evaluated.
+fn get_constant_value(initializer: &str) -> Option<CsharpAttributeArgumentType> {
+    let initializer = initializer.trim();
+    if initializer.starts_with('"') {
+        return Some(CsharpAttributeArgumentType::String(initializer.to_owned()));
+    }
+    if let Some(expression) = initializer
+        .strip_prefix("nameof(")
+        .and_then(|n| n.strip_suffix(')'))
+    {
+        return nameof(expression).map(CsharpAttributeArgumentType::String);
+    }
+
+    if let Ok(value) = initializer.parse() {
+        return Some(CsharpAttributeArgumentType::Bool(value));
+    }
+    if let Ok(value) = initializer.parse() {
+        return Some(CsharpAttributeArgumentType::Int(value));
+    }
+    initializer
+        .trim_end_matches(['f', 'F', 'd', 'D', 'm', 'M'])
+        .parse()
+        .ok()
+        .map(CsharpAttributeArgumentType::Real)
+}
+
+/// Returns the initializer of a variable declarator, e.g. `5f` in `Speed = 5f`.
+fn get_initializer(declarator_node: Node, src: &str) -> Option<String> {
+    let mut cursor = declarator_node.walk();
+    let mut children = declarator_node.children(&mut cursor);
+    children.find(|n| n.kind() == "=")?;
+
+    let value_node = children.find(|n| n.is_named())?;
+    value_node.utf8_text(src.as_bytes()).ok().map(str::to_owned)
+}
+
 impl ParseFromNode for Vec<CsharpAttribute> {
     fn get(node: Node, src: Arc<Rope>, _path: &Path) -> Result<Self> {
         let mut cursor = node.walk();
@@ -307,15 +628,22 @@ impl ParseFromNode for Vec<CsharpAttribute> {
                                             .unwrap()
                                             .to_owned();
 
-                                        arg_name = Some(
-                                            if arg_within_node.next_sibling().is_none()
-                                                && name == "ProtoName"
-                                            {
-                                                "audioMetadata".to_owned()
-                                            } else {
-                                                name
-                                            },
-                                        );
+                                        // `name: value` and `Name = value`, otherwise the value itself
+                                        match arg_within_node
+                                            .next_sibling()
+                                            .is_some_and(|n| matches!(n.kind(), ":" | "="))
+                                        {
+                                            true => arg_name = Some(name),
+                                            false => {
+                                                arg_value = Some(
+                                                    CsharpAttributeArgumentType::Constant(name),
+                                                )
+                                            }
+                                        }
+                                    }
+                                    "invocation_expression" => {
+                                        arg_value = get_nameof(arg_within_node, &src)
+                                            .map(CsharpAttributeArgumentType::String);
                                     }
                                     "string_literal" => {
                                         if cursor.goto_first_child() {
@@ -335,53 +663,57 @@ impl ParseFromNode for Vec<CsharpAttribute> {
                                             .utf8_text(src.as_bytes())
                                             .unwrap()
                                             .to_owned();
-                                        arg_value = Some(CsharpAttributeArgumentType::Bool(
-                                            value.parse().unwrap(),
-                                        ));
+                                        arg_value = value
+                                            .parse()
+                                            .ok()
+                                            .map(CsharpAttributeArgumentType::Bool);
                                     }
                                     "real_literal" => {
                                         let value = arg_within_node
                                             .utf8_text(src.as_bytes())
                                             .unwrap()
                                             .to_owned();
-                                        arg_value = Some(CsharpAttributeArgumentType::Real(
-                                            value.parse().unwrap(),
-                                        ));
+                                        arg_value = value
+                                            .parse()
+                                            .ok()
+                                            .map(CsharpAttributeArgumentType::Real);
                                     }
                                     "integer_literal" => {
                                         let value = arg_within_node
                                             .utf8_text(src.as_bytes())
                                             .unwrap()
                                             .to_owned();
-                                        arg_value = Some(CsharpAttributeArgumentType::Int(
-                                            value.parse().unwrap(),
-                                        ));
+                                        arg_value = value
+                                            .parse()
+                                            .ok()
+                                            .map(CsharpAttributeArgumentType::Int);
                                     }
                                     "prefix_unary_expression" => {
                                         let unary_val_node = cursor.node();
 
                                         if cursor.goto_first_child() {
-                                            // prefix_unary_operator
+                                            // skip the prefix_unary_operator to the operand
+                                            cursor.goto_next_sibling();
                                             match cursor.node().kind() {
                                                 "integer_literal" => {
                                                     let value = unary_val_node
                                                         .utf8_text(src.as_bytes())
                                                         .unwrap()
                                                         .to_owned();
-                                                    arg_value =
-                                                        Some(CsharpAttributeArgumentType::Int(
-                                                            value.parse().unwrap(),
-                                                        ));
+                                                    arg_value = value
+                                                        .parse()
+                                                        .ok()
+                                                        .map(CsharpAttributeArgumentType::Int);
                                                 }
                                                 "real_literal" => {
                                                     let value = unary_val_node
                                                         .utf8_text(src.as_bytes())
                                                         .unwrap()
                                                         .to_owned();
-                                                    arg_value =
-                                                        Some(CsharpAttributeArgumentType::Real(
-                                                            value.parse().unwrap(),
-                                                        ));
+                                                    arg_value = value
+                                                        .parse()
+                                                        .ok()
+                                                        .map(CsharpAttributeArgumentType::Real);
                                                 }
                                                 _ => {}
                                             }
@@ -464,6 +796,16 @@ impl ParseFromNode for Vec<CsharpAttribute> {
                                                 continue;
                                             }
                                         }
+                                        "Obsolete" => {
+                                            if OBSOLETE_ATTR_ARGS.len() > arg_index
+                                                && !args.contains_key(OBSOLETE_ATTR_ARGS[arg_index])
+                                            {
+                                                OBSOLETE_ATTR_ARGS[arg_index].to_owned()
+                                            } else {
+                                                cursor.goto_parent();
+                                                continue;
+                                            }
+                                        }
                                         _ => arg_index.to_string(),
                                     }
                                 } else {
@@ -493,7 +835,7 @@ impl ParseFromNode for Vec<CsharpAttribute> {
 
             if let Some(attr_name) = attr_name {
                 attributes.push(CsharpAttribute {
-                    name: attr_name,
+                    name: attr_name.into(),
                     arguments: args,
                 });
             }
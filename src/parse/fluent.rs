@@ -1,12 +1,18 @@
 use super::{common::DefinitionIndex, structs::fluent::FluentKey, ParsedFiles, Result};
 use crate::parse::ParseResult;
-use fluent_syntax::ast::{Entry, Expression, InlineExpression, PatternElement};
+use fluent_syntax::ast::{
+    CallArguments, Entry, Expression, InlineExpression, Pattern, PatternElement, VariantKey,
+};
 use futures::{
     future::{ready, BoxFuture},
     FutureExt,
 };
 use rayon::join;
-use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 pub fn dispatch(
     result: ParseResult,
@@ -30,50 +36,183 @@ pub(crate) fn parse(
 }
 
 async fn p(path: PathBuf, _parsed_files: ParsedFiles) -> Result<ParseResult> {
-    let content = std::fs::read_to_string(&path).unwrap_or_default();
-    let resource = fluent_syntax::parser::parse(content.as_ref()).or(Err(()))?;
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        tracing::warn!("Failed to read the file {}", path.display());
+        return Err(());
+    };
+    Ok(ParseResult::Fluent(get_keys(&path, &content)))
+}
+
+/// Collects the keys of a Fluent file, entries with syntax errors are skipped
+/// so a file that is being edited still keeps the rest of its keys.
+pub(crate) fn get_keys(path: &Path, content: &str) -> Vec<FluentKey> {
+    let resource = fluent_syntax::parser::parse(content).unwrap_or_else(|(resource, _)| resource);
 
-    let keys = resource
+    resource
         .body
         .into_iter()
         .filter_map(|entry| match entry {
-            Entry::Message(msg) if msg.value.is_some() => Some(msg),
+            Entry::Message(msg) => {
+                let value = msg.value?;
+                let range = span_to_range(content, &msg.id.span);
+                let index = DefinitionIndex(path.to_path_buf(), Some(range));
+
+                let attributes = msg
+                    .attributes
+                    .iter()
+                    .map(|a| a.id.name.to_string())
+                    .collect();
+
+                let text = render_pattern(&value);
+                Some(
+                    FluentKey::new(msg.id.name.to_string(), get_args(value), index)
+                        .with_attributes(attributes)
+                        .with_value(text),
+                )
+            }
+            Entry::Term(term) => {
+                let range = span_to_range(content, &term.id.span);
+                let index = DefinitionIndex(path.to_path_buf(), Some(range));
+
+                let text = render_pattern(&term.value);
+                Some(
+                    FluentKey::term(term.id.name.to_string(), get_args(term.value), index)
+                        .with_value(text),
+                )
+            }
             _ => None,
         })
-        .map(|msg| {
-            let args = msg
-                .value
-                .unwrap()
-                .elements
-                .into_iter()
-                .filter_map(|v| match v {
-                    PatternElement::Placeable { expression, .. } => Some(expression),
-                    _ => None,
-                })
-                .filter_map(|expr| match expr {
-                    // TODO: Get variables from functions calls
-                    Expression::Inline(InlineExpression::VariableReference { id, .. }, ..) => {
-                        Some(id.name.to_owned())
-                    }
-                    Expression::Select {
-                        selector: InlineExpression::VariableReference { id, .. },
-                        ..
-                    } => Some(id.name.to_owned()),
-                    _ => None,
-                })
-                .collect::<HashSet<_>>();
-
-            let range = span_to_range(&content, &msg.id.span);
-            let index = DefinitionIndex(path.clone(), Some(range));
-
-            FluentKey::new(msg.id.name.to_string(), args, index)
-        })
-        .collect();
+        .collect()
+}
+
+/// Writes the pattern back as it reads in the file, the placeables are written
+/// the same way however they are spaced, e.g. `Weld with { -brand }`.
+pub(crate) fn render_pattern(pattern: &Pattern<&str>) -> String {
+    let mut text = String::new();
+    write_pattern(pattern, &mut text);
+    text
+}
+
+fn write_pattern(pattern: &Pattern<&str>, out: &mut String) {
+    for element in pattern.elements.iter() {
+        match element {
+            PatternElement::TextElement { value } => out.push_str(value),
+            PatternElement::Placeable { expression } => {
+                out.push_str("{ ");
+                write_expression(expression, out);
+                out.push_str(" }");
+            }
+        }
+    }
+}
+
+fn write_expression(expression: &Expression<&str>, out: &mut String) {
+    match expression {
+        Expression::Inline(inline, ..) => write_inline(inline, out),
+        Expression::Select { selector, variants } => {
+            write_inline(selector, out);
+            out.push_str(" ->");
+            for variant in variants {
+                out.push_str(if variant.default { " *[" } else { " [" });
+                match &variant.key {
+                    VariantKey::Identifier { name } => out.push_str(name),
+                    VariantKey::NumberLiteral { value } => out.push_str(value),
+                }
+                out.push_str("] ");
+                write_pattern(&variant.value, out);
+            }
+        }
+    }
+}
 
-    Ok(ParseResult::Fluent(keys))
+fn write_inline(inline: &InlineExpression<&str>, out: &mut String) {
+    match inline {
+        InlineExpression::StringLiteral { value } => {
+            out.push('"');
+            out.push_str(value);
+            out.push('"');
+        }
+        InlineExpression::NumberLiteral { value } => out.push_str(value),
+        InlineExpression::FunctionReference { id, arguments } => {
+            out.push_str(id.name);
+            write_arguments(arguments, out);
+        }
+        InlineExpression::MessageReference { id, attribute } => {
+            out.push_str(id.name);
+            if let Some(attribute) = attribute {
+                out.push('.');
+                out.push_str(attribute.name);
+            }
+        }
+        InlineExpression::TermReference {
+            id,
+            attribute,
+            arguments,
+        } => {
+            out.push('-');
+            out.push_str(id.name);
+            if let Some(attribute) = attribute {
+                out.push('.');
+                out.push_str(attribute.name);
+            }
+            if let Some(arguments) = arguments {
+                write_arguments(arguments, out);
+            }
+        }
+        InlineExpression::VariableReference { id } => {
+            out.push('$');
+            out.push_str(id.name);
+        }
+        InlineExpression::Placeable { expression } => {
+            out.push_str("{ ");
+            write_expression(expression, out);
+            out.push_str(" }");
+        }
+    }
+}
+
+fn write_arguments(arguments: &CallArguments<&str>, out: &mut String) {
+    out.push('(');
+    for (i, argument) in arguments.positional.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_inline(argument, out);
+    }
+    for (i, argument) in arguments.named.iter().enumerate() {
+        if i > 0 || !arguments.positional.is_empty() {
+            out.push_str(", ");
+        }
+        out.push_str(argument.name.name);
+        out.push_str(": ");
+        write_inline(&argument.value, out);
+    }
+    out.push(')');
+}
+
+fn get_args(pattern: Pattern<&str>) -> HashSet<String> {
+    pattern
+        .elements
+        .into_iter()
+        .filter_map(|v| match v {
+            PatternElement::Placeable { expression, .. } => Some(expression),
+            _ => None,
+        })
+        .filter_map(|expr| match expr {
+            // TODO: Get variables from functions calls
+            Expression::Inline(InlineExpression::VariableReference { id, .. }, ..) => {
+                Some(id.name.to_owned())
+            }
+            Expression::Select {
+                selector: InlineExpression::VariableReference { id, .. },
+                ..
+            } => Some(id.name.to_owned()),
+            _ => None,
+        })
+        .collect()
 }
 
-fn span_to_range(src: &str, span: &fluent_syntax::ast::Span) -> tree_sitter::Range {
+pub(crate) fn span_to_range(src: &str, span: &fluent_syntax::ast::Span) -> tree_sitter::Range {
     let lines = std::iter::once(0)
         .chain(
             src.char_indices()
@@ -8,20 +8,34 @@ use globset::{Glob, GlobMatcher};
 use rayon::prelude::*;
 use std::{
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use structs::{
+    csharp::{CsharpClass, CsharpUsage},
+    fluent::FluentKey,
+    yaml::YamlPrototype,
 };
-use structs::{csharp::CsharpClass, fluent::FluentKey, yaml::YamlPrototype};
 use tokio::sync::Mutex;
-use tower_lsp::{lsp_types::Url, Client};
+use tower_lsp::{
+    lsp_types::{MessageType, Url},
+    Client,
+};
 use tracing::instrument;
 
 pub mod common;
+#[cfg(feature = "csharp")]
 pub mod csharp;
+#[cfg(feature = "fluent")]
 pub mod fluent;
 pub mod structs;
 pub mod yaml;
 
 pub(crate) type Result<T, E = ()> = std::result::Result<T, E>;
+/// The tokens of the parsing progresses start with it, followed by the name of the file group.
+pub(crate) const PROGRESS_TOKEN_PREFIX: &str = "parse-";
 #[rustfmt::skip]
 pub(crate) type Parser = Arc<dyn (Fn(PathBuf, ParsedFiles) -> BoxFuture<'static, Result<ParseResult>>) + Send + Sync>;
 #[rustfmt::skip]
@@ -38,39 +52,186 @@ fn get_folders(uri: &Url) -> Vec<PathBuf> {
         "Content.Shared",
         "Resources/Prototypes",
         "Resources/Locale",
+        // The layout of the RobustToolbox repository opened on its own.
+        "Robust.Client",
+        "Robust.Server",
+        "Robust.Shared",
+        "Resources/EnginePrototypes",
+        "Resources/EngineLocale",
     ]
     .into_iter()
-    .map(|f| uri.to_file_path().unwrap().join(f))
+    .filter_map(|f| uri.to_file_path().ok().map(|p| p.join(f)))
     .filter(|f| f.exists())
     .collect()
 }
 
 pub enum ParseResult {
-    Csharp(Vec<CsharpClass>),
+    /// The classes, the types used by the entity system APIs and the locale keys
+    /// passed to `Loc.GetString`.
+    Csharp(Vec<CsharpClass>, Vec<CsharpUsage>, Vec<CsharpUsage>),
     YamlPrototypes(Vec<YamlPrototype>),
     Fluent(Vec<FluentKey>),
 }
 
+impl ParseResult {
+    /// The number of the indexed items and what they are, e.g. `prototypes`.
+    fn count(&self) -> (usize, &'static str) {
+        match self {
+            Self::Csharp(classes, ..) => (classes.len(), "classes"),
+            Self::YamlPrototypes(prototypes) => (prototypes.len(), "prototypes"),
+            Self::Fluent(keys) => (keys.len(), "locale keys"),
+        }
+    }
+}
+
+/// Returns the file groups indexed in a Robust Toolbox project,
+/// the C# and Fluent files only when their features are enabled.
+// Without the features only the YAML files are pushed.
+#[allow(clippy::vec_init_then_push)]
+pub fn file_groups() -> Vec<FileGroup> {
+    let mut groups = vec![];
+
+    #[cfg(feature = "csharp")]
+    {
+        let csharp_parser = Arc::new(csharp::parse);
+        let csharp_dispatcher = Arc::new(csharp::dispatch);
+        groups.extend([
+            FileGroup::new(
+                "C# prototypes",
+                "*Prototype.cs",
+                csharp_parser.clone(),
+                csharp_dispatcher.clone(),
+            ),
+            FileGroup::new(
+                "C# components",
+                "*Component.cs",
+                csharp_parser.clone(),
+                csharp_dispatcher.clone(),
+            ),
+            FileGroup::new(
+                "others C# files",
+                "*.cs",
+                csharp_parser.clone(),
+                csharp_dispatcher.clone(),
+            ),
+        ]);
+    }
+
+    #[cfg(feature = "fluent")]
+    groups.push(FileGroup::new(
+        "fluent files",
+        "*.ftl",
+        Arc::new(fluent::parse),
+        Arc::new(fluent::dispatch),
+    ));
+
+    groups.push(prototype_file_group());
+    groups
+}
+
+/// The id of the group of the YAML prototype files.
+const PROTOTYPE_FILES: &str = "yaml files";
+
+/// The YAML prototype files, the only group needed to compare the prototypes of two snapshots.
+pub fn prototype_file_group() -> FileGroup {
+    FileGroup::new(
+        PROTOTYPE_FILES,
+        "**/{Prototypes,EnginePrototypes}/**/*.{yml,yaml}",
+        Arc::new(yaml::parse),
+        Arc::new(yaml::dispatch),
+    )
+}
+
+/// Stops the parsing of the project early, the files parsed by then stay in the index.
+/// Every run of the parsing gets its own, the progress of the run is named after its number.
+#[derive(Debug, Clone, Default)]
+pub struct ParseCancellation {
+    run: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ParseCancellation {
+    /// The cancellation of the run that follows this one.
+    pub fn next(&self) -> Self {
+        Self {
+            run: self.run + 1,
+            cancelled: Default::default(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The token of the progress of the file group, e.g. `parse-1-yaml files`.
+    fn progress_token(&self, group: &str) -> String {
+        format!("{PROGRESS_TOKEN_PREFIX}{}-{group}", self.run)
+    }
+
+    /// Whether the progress is the one of a file group of this run.
+    pub fn owns_progress(&self, token: &str) -> bool {
+        token
+            .strip_prefix(PROGRESS_TOKEN_PREFIX)
+            .and_then(|token| token.split_once('-'))
+            .is_some_and(|(run, _)| run == self.run.to_string())
+    }
+}
+
 pub struct ProjectParser {
     uri: Url,
     context: Arc<Context>,
-    client: Arc<Client>,
+    // Progress is only reported when the parser runs on behalf of an LSP client.
+    client: Option<Arc<Client>>,
+    cancellation: ParseCancellation,
 }
 
 impl ProjectParser {
-    pub fn new(uri: Url, context: Arc<Context>, client: Arc<Client>) -> Self {
+    pub fn new(uri: Url, context: Arc<Context>, client: Option<Arc<Client>>) -> Self {
         Self {
             uri,
             context,
             client,
+            cancellation: ParseCancellation::default(),
         }
     }
 
+    /// Lets the parsing be stopped through the token, e.g. from the progress in the client.
+    pub fn with_cancellation(mut self, cancellation: ParseCancellation) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
     pub async fn parse<'a>(&self, matchers: Vec<FileGroup>) {
         let matchers = Arc::new(matchers);
 
-        let folders = get_folders(&self.uri);
-        let collected_files = collect_files(folders, matchers.clone());
+        let mut folders = get_folders(&self.uri);
+        // Override roots may point to the prototypes of a vendored upstream,
+        // which lie outside of the usual folders.
+        let override_roots = self
+            .context
+            .config
+            .read()
+            .await
+            .prototypes
+            .override_roots
+            .clone();
+        for root in override_roots {
+            if root.exists() && !folders.iter().any(|f| root.starts_with(f)) {
+                folders.push(root);
+            }
+        }
+        let mut collected_files = collect_files(folders, matchers.clone());
+        let prototypes = self.context.config.read().await.prototypes.clone();
+        for (_, files) in collected_files
+            .iter_mut()
+            .filter(|(id, _)| id == PROTOTYPE_FILES)
+        {
+            files.retain(|file| prototypes.is_prototype_file(file));
+        }
 
         let mut files_handlers = futures::future::join_all(
             collected_files
@@ -81,26 +242,38 @@ impl ProjectParser {
                         id: id.clone(),
                         actual_count: 0,
                         total_count: files.len() as u32,
-                        status: get_status(self.client.clone(), &id.clone()).await,
+                        failed_count: 0,
+                        indexed: None,
+                        status: match self.client.clone() {
+                            Some(client) => {
+                                Some(get_status(client, &self.cancellation, &id.clone()).await)
+                            }
+                            None => None,
+                        },
                         finished: false,
                     }
                 }),
         )
         .await;
 
-        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<(String, Result<ParseResult>)>(100);
 
-        tokio::spawn({
+        let dispatching = tokio::spawn({
             let matchers = matchers.clone();
             let context = self.context.clone();
+            let cancellation = self.cancellation.clone();
             async move {
                 while let Some((id, result)) = rx.recv().await {
-                    files_handlers
-                        .iter_mut()
-                        .find(|h| h.id == id)
-                        .unwrap()
-                        .increment()
-                        .await;
+                    let handler = files_handlers.iter_mut().find(|h| h.id == id).unwrap();
+                    match &result {
+                        Ok(result) => {
+                            let (count, kind) = result.count();
+                            let indexed = handler.indexed.get_or_insert((0, kind));
+                            indexed.0 += count;
+                        }
+                        Err(_) => handler.failed_count += 1,
+                    }
+                    handler.increment().await;
 
                     let Ok(result) = result else {
                         continue;
@@ -110,14 +283,16 @@ impl ProjectParser {
                     let dispatcher = &matcher.dispatcher;
                     dispatcher(result, context.clone()).await;
                 }
+                let cancelled = cancellation.is_cancelled();
                 futures::future::join_all(
                     files_handlers
                         .iter_mut()
-                        .map(|h| async move { h.finish().await }),
+                        .map(|h| async move { h.finish(cancelled).await }),
                 )
                 .await;
 
                 tracing::trace!("Parsing finished.");
+                files_handlers.iter().map(|h| h.failed_count).sum::<u32>()
             }
         });
 
@@ -134,8 +309,14 @@ impl ProjectParser {
                         let context = self.context.clone();
                         let matchers = matchers.clone();
                         let id = id.clone();
+                        let cancellation = self.cancellation.clone();
 
                         tokio::spawn(async move {
+                            // The files that haven't been parsed yet are skipped.
+                            if cancellation.is_cancelled() {
+                                return;
+                            }
+
                             let matcher = matchers.iter().find(|m| m.id == id).unwrap();
                             let parser = matcher.parser.clone();
                             let result = parser(f, context.parsed_files.clone()).await;
@@ -150,6 +331,40 @@ impl ProjectParser {
         );
 
         handlers.await;
+
+        // All senders must be gone before the dispatching task can finish.
+        drop(tx);
+        let failed = match dispatching.await {
+            Ok(failed) => failed,
+            Err(err) => {
+                tracing::error!("Failed to dispatch parse results: {}", err);
+                0
+            }
+        };
+
+        if self.cancellation.is_cancelled() {
+            tracing::warn!("Parsing has been cancelled, the index is partial.");
+        }
+
+        let summary = self.summary(failed).await;
+        tracing::info!("{summary}");
+        if let Some(client) = &self.client {
+            client.log_message(MessageType::INFO, summary).await;
+        }
+    }
+
+    /// Sums up the whole index, e.g. `Indexed 120 classes, 3400 prototypes and 5000 locale keys.`
+    async fn summary(&self, failed: u32) -> String {
+        let mut summary = format!(
+            "Indexed {} classes, {} prototypes and {} locale keys",
+            self.context.classes.read().await.len(),
+            self.context.prototypes.read().await.len(),
+            self.context.locales.read().await.len(),
+        );
+        if failed > 0 {
+            summary += &format!(", {failed} files failed to parse");
+        }
+        summary + "."
     }
 }
 
@@ -237,7 +452,10 @@ struct ParserHandler {
     id: String,
     actual_count: u32,
     total_count: u32,
-    status: Arc<Mutex<ProgressStatus>>,
+    failed_count: u32,
+    /// The number of the indexed items and what they are, known once a file is parsed.
+    indexed: Option<(usize, &'static str)>,
+    status: Option<Arc<Mutex<ProgressStatus>>>,
     finished: bool,
 }
 
@@ -248,13 +466,18 @@ impl ParserHandler {
         if self.finished {
             return;
         } else {
+            let Some(status) = &self.status else {
+                self.finished = self.actual_count == self.total_count;
+                return;
+            };
+
             if self.actual_count == self.total_count {
                 self.finished = true;
-                self.status.lock().await.finish(None).await;
+                status.lock().await.finish(Some(&self.summary())).await;
             } else {
                 let percent = percentage(self.actual_count, self.total_count);
 
-                self.status
+                status
                     .lock()
                     .await
                     .next_state(
@@ -269,23 +492,46 @@ impl ParserHandler {
         }
     }
 
-    async fn finish(&mut self) {
+    async fn finish(&mut self, cancelled: bool) {
         if self.finished {
             return;
         } else {
             self.finished = true;
-            self.status.lock().await.finish(None).await;
+            if let Some(status) = &self.status {
+                let summary = match cancelled {
+                    true => format!("Cancelled, {}", self.summary()),
+                    false => self.summary(),
+                };
+                status.lock().await.finish(Some(&summary)).await;
+            }
         }
     }
+
+    /// E.g. `340 prototypes in 12 files, 1 failed to parse`.
+    fn summary(&self) -> String {
+        let parsed = self.actual_count - self.failed_count;
+        let mut summary = match self.indexed {
+            Some((count, kind)) => format!("{count} {kind} in {parsed} files"),
+            None => format!("{parsed} files"),
+        };
+        if self.failed_count > 0 {
+            summary += &format!(", {} failed to parse", self.failed_count);
+        }
+        summary
+    }
 }
 
-#[instrument(skip(client))]
+#[instrument(skip(client, cancellation))]
 #[inline(always)]
-async fn get_status(client: Arc<Client>, name: &str) -> Arc<Mutex<ProgressStatus>> {
+async fn get_status(
+    client: Arc<Client>,
+    cancellation: &ParseCancellation,
+    name: &str,
+) -> Arc<Mutex<ProgressStatus>> {
     let status = ProgressStatus::new_with(
         client.clone(),
         ProgressStatusInit {
-            id: format!("parse-{name}"),
+            id: cancellation.progress_token(name),
             title: format!("Parsing {name}"),
             cancellable: true,
             ..Default::default()
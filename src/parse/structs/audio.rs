@@ -0,0 +1,105 @@
+use crate::vfs::FileSystem;
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+/// How much of the end of an Ogg file is read to find its last page.
+const OGG_TAIL_SIZE: u64 = 64 * 1024;
+/// Opus always counts the granule positions at 48 kHz.
+const OPUS_SAMPLE_RATE: u64 = 48_000;
+
+/// What can be told about an audio file without decoding it.
+#[derive(Debug, Clone)]
+pub struct AudioMeta {
+    pub size: u64,
+    /// Read from the headers of WAV files and the pages of Ogg Vorbis and Opus files.
+    pub duration: Option<Duration>,
+}
+
+impl AudioMeta {
+    pub fn read(fs: &dyn FileSystem, path: &Path) -> Option<Self> {
+        let size = fs.metadata(path).ok()?.len;
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        let duration = match extension.as_str() {
+            "wav" => wav_duration(fs, path),
+            "ogg" | "opus" => ogg_duration(fs, path, size),
+            _ => None,
+        };
+
+        Some(Self { size, duration })
+    }
+}
+
+/// Divides the size of the `data` chunk by the byte rate from the `fmt ` chunk.
+fn wav_duration(fs: &dyn FileSystem, path: &Path) -> Option<Duration> {
+    let mut file = fs.open(path).ok()?;
+    let mut header = [0; 12];
+    file.read_exact(&mut header).ok()?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut byte_rate = None;
+    loop {
+        let mut chunk = [0; 8];
+        file.read_exact(&mut chunk).ok()?;
+        let chunk_size = u32::from_le_bytes(chunk[4..8].try_into().ok()?) as u64;
+
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut format = [0; 12];
+                file.read_exact(&mut format).ok()?;
+                byte_rate = Some(u32::from_le_bytes(format[8..12].try_into().ok()?) as u64);
+                let rest = (chunk_size + chunk_size % 2).checked_sub(12)?;
+                file.seek(SeekFrom::Current(rest as i64)).ok()?;
+            }
+            b"data" => {
+                let byte_rate = byte_rate.filter(|r| *r > 0)?;
+                return Some(Duration::from_secs_f64(
+                    chunk_size as f64 / byte_rate as f64,
+                ));
+            }
+            _ => {
+                // Chunks are padded to an even size.
+                file.seek(SeekFrom::Current((chunk_size + chunk_size % 2) as i64))
+                    .ok()?;
+            }
+        }
+    }
+}
+
+/// Divides the granule position of the last page by the sample rate from the first one.
+fn ogg_duration(fs: &dyn FileSystem, path: &Path, size: u64) -> Option<Duration> {
+    let mut file = fs.open(path).ok()?;
+
+    let mut head = vec![0; 512.min(size as usize)];
+    file.read_exact(&mut head).ok()?;
+    let sample_rate = if let Some(i) = find(&head, b"\x01vorbis") {
+        // The version takes 4 bytes and the channels 1, the sample rate follows them.
+        u32::from_le_bytes(head.get(i + 12..i + 16)?.try_into().ok()?) as u64
+    } else if find(&head, b"OpusHead").is_some() {
+        OPUS_SAMPLE_RATE
+    } else {
+        return None;
+    };
+    if sample_rate == 0 {
+        return None;
+    }
+
+    let tail_size = OGG_TAIL_SIZE.min(size);
+    file.seek(SeekFrom::Start(size - tail_size)).ok()?;
+    let mut tail = vec![0; tail_size as usize];
+    file.read_exact(&mut tail).ok()?;
+
+    let page = tail.windows(4).rposition(|w| w == b"OggS")?;
+    let granule = u64::from_le_bytes(tail.get(page + 6..page + 14)?.try_into().ok()?);
+
+    Some(Duration::from_secs_f64(granule as f64 / sample_rate as f64))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
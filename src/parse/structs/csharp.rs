@@ -1,32 +1,80 @@
 #![allow(unused)]
 
 use super::*;
-use crate::backend::CsharpClasses;
+use crate::backend::{Context, CsharpClasses, CsharpInheritors};
 use common::{DefinitionIndex, Index};
+use intern::Interned;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tree_sitter::Range;
 
+/// Fields of the data definitions read by custom type serializers,
+/// they replace the `[IncludeDataField]` the serializer is attached to.
+static SERIALIZED_FIELDS: &[(&str, &[(&str, &str)])] = &[(
+    "DamageSpecifier",
+    &[
+        (
+            "types",
+            "Dictionary<ProtoId<DamageTypePrototype>, FixedPoint2>",
+        ),
+        (
+            "groups",
+            "Dictionary<ProtoId<DamageGroupPrototype>, FixedPoint2>",
+        ),
+    ],
+)];
+
 pub struct ReflectionManager {
     classes: CsharpClasses,
+    inheritors: CsharpInheritors,
 }
 
 impl ReflectionManager {
-    pub fn new(classes: CsharpClasses) -> Self {
-        Self { classes }
+    pub fn new(context: &Context) -> Self {
+        Self {
+            classes: context.classes.clone(),
+            inheritors: context.inheritors.clone(),
+        }
     }
 
+    /// Returns the fields of the class together with the fields of all its base classes,
+    /// the fields of the most distant base come first.
     pub async fn get_fields(&self, class: &CsharpClass) -> Vec<CsharpClassField> {
         let lock = self.classes.read().await;
-        let bases = class
-            .base
-            .par_iter()
-            .map(|b| lock.par_iter().find_any(|c| c.name == *b))
-            .filter_map(|c| c)
-            .chain([class])
-            .collect::<Vec<_>>();
 
-        let mut fields = Vec::with_capacity(bases.len());
-        for base in bases {
-            fields.extend(base.fields.clone());
+        let mut classes = vec![class];
+        let mut index = 0;
+        while let Some(current) = classes.get(index) {
+            for base in &current.base {
+                let Some(base) = lock.get(&CsharpClass::from(normalize_type_name(base))) else {
+                    continue;
+                };
+                if !classes.contains(&base) {
+                    classes.push(base);
+                }
+            }
+            index += 1;
+        }
+
+        let mut fields = Vec::with_capacity(classes.len());
+        for class in classes.into_iter().rev() {
+            match SERIALIZED_FIELDS
+                .iter()
+                .find(|(name, _)| *name == class.name)
+            {
+                Some((_, serialized)) => {
+                    fields.extend(
+                        class
+                            .fields
+                            .iter()
+                            .filter(|f| !f.attributes.contains("IncludeDataField"))
+                            .cloned(),
+                    );
+                    fields.extend(serialized.iter().map(|(name, type_name)| {
+                        CsharpClassField::new_data_field(*name, *type_name)
+                    }));
+                }
+                None => fields.extend(class.fields.clone()),
+            }
         }
 
         fields
@@ -75,6 +123,166 @@ impl ReflectionManager {
 
         Component::try_from(class?).ok()
     }
+
+    /// Finds a class by a type name as it is written in a field declaration,
+    /// the namespace and the generic arguments are ignored.
+    pub async fn get_class_by_name(&self, type_name: impl AsRef<str>) -> Option<CsharpClass> {
+        let lock = self.classes.read().await;
+        lock.get(&CsharpClass::from(normalize_type_name(type_name.as_ref())))
+            .cloned()
+    }
+
+    /// Finds the classes that inherit the type directly or through other classes,
+    /// e.g. the implementations that can be selected with a `!type:` tag.
+    pub async fn get_subclasses(&self, type_name: impl AsRef<str>) -> Vec<CsharpClass> {
+        // The classes are locked first, the same as when the index is updated.
+        let classes = self.classes.read().await;
+        let inheritors = self.inheritors.read().await;
+
+        let mut names = vec![];
+        let mut pending = vec![normalize_type_name(type_name.as_ref())];
+        while let Some(base) = pending.pop() {
+            for name in inheritors.get_derived(base) {
+                if !names.contains(&name) {
+                    names.push(name);
+                    pending.push(name);
+                }
+            }
+        }
+
+        names
+            .into_iter()
+            .filter_map(|name| classes.get(&CsharpClass::from(name)).cloned())
+            .collect()
+    }
+
+    /// Checks whether the class can be read from YAML, either by its own attribute
+    /// or by `[ImplicitDataDefinitionForInheritors]` on one of its bases.
+    pub async fn is_data_definition(&self, class: &CsharpClass) -> bool {
+        if class.attributes.contains("DataDefinition") {
+            return true;
+        }
+
+        let lock = self.classes.read().await;
+
+        let mut visited = HashSet::new();
+        let mut pending = class
+            .base
+            .iter()
+            .map(|b| normalize_type_name(b))
+            .collect::<Vec<_>>();
+        while let Some(base) = pending.pop() {
+            if !visited.insert(base) {
+                continue;
+            }
+            let Some(base) = lock.get(&CsharpClass::from(base)) else {
+                continue;
+            };
+            if base
+                .attributes
+                .contains("ImplicitDataDefinitionForInheritors")
+            {
+                return true;
+            }
+            pending.extend(base.base.iter().map(|b| normalize_type_name(b)));
+        }
+
+        false
+    }
+}
+
+/// The names of the classes derived from each class or interface.
+#[derive(Default, Debug)]
+pub struct InheritanceGraph {
+    derived: HashMap<String, HashSet<String>>,
+}
+
+impl InheritanceGraph {
+    pub fn insert(&mut self, class: &CsharpClass) {
+        for base in &class.base {
+            self.derived
+                .entry(normalize_type_name(base).to_owned())
+                .or_default()
+                .insert(class.name.clone());
+        }
+    }
+
+    pub fn remove(&mut self, class: &CsharpClass) {
+        for base in &class.base {
+            if let Some(derived) = self.derived.get_mut(normalize_type_name(base)) {
+                derived.remove(&class.name);
+            }
+        }
+    }
+
+    /// Returns the classes that inherit the type directly.
+    pub fn get_derived(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.derived
+            .get(name)
+            .into_iter()
+            .flat_map(|derived| derived.iter().map(String::as_str))
+    }
+}
+
+/// A name used in the C# code, either a type passed to a generic entity system API,
+/// e.g. `FooComponent` in `TryComp<FooComponent>(uid)`, or a locale key passed to
+/// `Loc.GetString`, e.g. `"tool-verb-use"`.
+#[derive(Debug, Clone)]
+pub struct CsharpUsage {
+    pub name: String,
+
+    index: DefinitionIndex,
+}
+
+impl CsharpUsage {
+    pub fn new(name: String, index: DefinitionIndex) -> Self {
+        Self { name, index }
+    }
+}
+
+impl Index for CsharpUsage {
+    fn index(&self) -> &DefinitionIndex {
+        &self.index
+    }
+}
+
+/// The places in the C# code where the types or the locale keys are used, keyed by the name.
+#[derive(Default, Debug)]
+pub struct CsharpUsageIndex {
+    usages: HashMap<String, Vec<DefinitionIndex>>,
+}
+
+impl CsharpUsageIndex {
+    pub fn extend(&mut self, usages: Vec<CsharpUsage>) {
+        for usage in usages {
+            self.usages.entry(usage.name).or_default().push(usage.index);
+        }
+    }
+
+    /// Replaces the usages found in the file with the freshly parsed ones.
+    pub fn replace(&mut self, path: &std::path::Path, usages: Vec<CsharpUsage>) {
+        for indexes in self.usages.values_mut() {
+            indexes.retain(|index| index.0 != path);
+        }
+        self.usages.retain(|_, indexes| !indexes.is_empty());
+        self.extend(usages);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.usages.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> &[DefinitionIndex] {
+        self.usages.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Strips the namespace, the generic arguments and the nullability from a type name,
+/// e.g. `Robust.Shared.List<int>?` becomes `List`.
+fn normalize_type_name(name: &str) -> &str {
+    let name = name.trim_end_matches('?');
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit('.').next().unwrap_or(name)
 }
 
 #[derive(Default, Clone, Debug)]
@@ -120,6 +328,20 @@ impl CsharpAttributeCollection {
     pub fn len(&self) -> usize {
         self.attributes.len()
     }
+
+    /// Returns the message of the `[Obsolete]` attribute if there is one,
+    /// the message is empty if the attribute has no arguments.
+    pub fn get_obsolete(&self) -> Option<String> {
+        let attr = self.get("Obsolete")?;
+        let message = match attr.arguments.get("message").map(|a| &a.value) {
+            Some(CsharpAttributeArgumentType::String(message)) => {
+                message.trim_matches('"').to_owned()
+            }
+            _ => String::new(),
+        };
+
+        Some(message)
+    }
 }
 
 impl Iterator for CsharpAttributeCollection {
@@ -205,12 +427,53 @@ impl Prototype {
             name
         }
     }
+
+    /// The `loadPriority` argument of `[Prototype]`, the prototypes of kinds with
+    /// a higher priority are loaded first. RobustToolbox defaults it to 1.
+    pub fn get_load_priority(&self) -> i64 {
+        self.class
+            .attributes
+            .get("Prototype")
+            .and_then(|attr| attr.arguments.get("loadPriority"))
+            .and_then(|arg| match arg.value {
+                CsharpAttributeArgumentType::Int(priority) => Some(priority),
+                _ => None,
+            })
+            .unwrap_or(DEFAULT_LOAD_PRIORITY)
+    }
+}
+
+pub const DEFAULT_LOAD_PRIORITY: i64 = 1;
+
+/// Makes the classes that have only one of `[Prototype]` and `IPrototype` prototypes too.
+static RELAXED_PROTOTYPES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_relaxed_prototypes(relaxed: bool) {
+    RELAXED_PROTOTYPES.store(relaxed, Ordering::Relaxed);
+}
+
+pub fn is_relaxed_prototypes() -> bool {
+    RELAXED_PROTOTYPES.load(Ordering::Relaxed)
+}
+
+impl CsharpClass {
+    pub fn implements_prototype(&self) -> bool {
+        self.base
+            .iter()
+            .any(|base| normalize_type_name(base) == "IPrototype")
+    }
 }
 
 impl TryFrom<&CsharpClass> for Prototype {
     type Error = ();
     fn try_from(class: &CsharpClass) -> Result<Self, Self::Error> {
-        if class.base.contains(&"IPrototype".into()) && class.attributes.contains("Prototype") {
+        let has_attribute = class.attributes.contains("Prototype");
+        let implements = class.implements_prototype();
+        let relaxed = is_relaxed_prototypes()
+            && (has_attribute || implements)
+            && !class.modifiers.contains("abstract");
+
+        if has_attribute && implements || relaxed {
             Ok(Self {
                 class: class.clone(),
             })
@@ -234,7 +497,13 @@ pub struct CsharpClass {
     pub base: Vec<String>,
     pub attributes: CsharpAttributeCollection,
     pub fields: Vec<CsharpClassField>,
-    pub modifiers: HashSet<String>,
+    pub modifiers: HashSet<Interned>,
+    pub namespace: Option<String>,
+    /// The `<summary>` of the doc comment of the class as plain text.
+    pub summary: Option<String>,
+    /// The declarations of a partial class spread over several files, e.g. a component
+    /// registered in one file with its fields in another, the class merges their members.
+    pub parts: Vec<CsharpClass>,
 
     index: DefinitionIndex,
 }
@@ -245,7 +514,7 @@ impl CsharpClass {
         base: Vec<String>,
         attributes: CsharpAttributeCollection,
         fields: Vec<CsharpClassField>,
-        modifiers: HashSet<String>,
+        modifiers: HashSet<Interned>,
         index: DefinitionIndex,
     ) -> Self {
         Self {
@@ -254,10 +523,67 @@ impl CsharpClass {
             attributes,
             fields,
             modifiers,
+            namespace: None,
+            summary: None,
+            parts: vec![],
             index,
         }
     }
 
+    /// The declarations the class is merged from, or the class itself if it has only one.
+    pub fn declarations(&self) -> impl Iterator<Item = &CsharpClass> {
+        std::iter::once(self)
+            .filter(|c| c.parts.is_empty())
+            .chain(self.parts.iter())
+    }
+
+    pub fn into_declarations(self) -> Vec<CsharpClass> {
+        if self.parts.is_empty() {
+            vec![self]
+        } else {
+            self.parts
+        }
+    }
+
+    /// Whether both are declarations of the same partial class.
+    pub fn is_partial_of(&self, other: &CsharpClass) -> bool {
+        self.name == other.name
+            && self.namespace == other.namespace
+            && self.modifiers.contains("partial")
+            && other.modifiers.contains("partial")
+    }
+
+    /// Merges the declarations of a partial class, so that e.g. `[RegisterComponent]`
+    /// applies to the fields of all the files. The declaration with attributes
+    /// is the one the class is located at.
+    pub fn merge(mut declarations: Vec<CsharpClass>) -> Option<CsharpClass> {
+        if declarations.len() <= 1 {
+            return declarations.pop();
+        }
+        declarations.sort_by(|a, b| {
+            let key = |c: &CsharpClass| (c.attributes.attributes.is_empty(), c.index.0.clone());
+            key(a).cmp(&key(b))
+        });
+
+        let mut class = declarations[0].clone();
+        for part in &declarations[1..] {
+            for base in &part.base {
+                if !class.base.contains(base) {
+                    class.base.push(base.clone());
+                }
+            }
+            class
+                .attributes
+                .extend(part.attributes.attributes.iter().cloned());
+            class.fields.extend(part.fields.iter().cloned());
+            class.modifiers.extend(part.modifiers.iter().cloned());
+            class.summary = class.summary.or_else(|| part.summary.clone());
+        }
+        class.parts = declarations;
+
+        Some(class)
+    }
+
     pub fn set_file(&mut self, file: PathBuf) {
         self.index.0 = file;
     }
@@ -294,7 +620,7 @@ impl Index for CsharpClass {
 
 #[derive(Debug, Default, Clone)]
 pub struct CsharpAttribute {
-    pub name: String,
+    pub name: Interned,
     pub arguments: HashMap<String, CsharpAttributeArgument>,
 }
 
@@ -314,6 +640,9 @@ pub enum CsharpAttributeArgumentType {
     Real(f64),
     Int(i64),
 
+    /// A reference to a constant, replaced by its value once the class is parsed
+    /// and kept as is when the class doesn't declare it.
+    Constant(String),
     TypeOf(Box<CsharpAttributeArgumentType>),
     GenericType {
         indent: String,
@@ -321,12 +650,62 @@ pub enum CsharpAttributeArgumentType {
     },
 }
 
+/// A literal the field is initialized with, other expressions like `new()` aren't literals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsharpLiteral<'a> {
+    /// The text between the quotes.
+    String(&'a str),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// The member name of an enum value, e.g. `Low` of `Priority.Low`.
+    EnumMember(&'a str),
+}
+
+impl<'a> CsharpLiteral<'a> {
+    /// Reads a C# literal, e.g. `"tool"`, `true`, `1_000`, `5f` or `Priority.Low`.
+    pub fn parse(literal: &'a str) -> Option<Self> {
+        if let Some(string) = literal.strip_prefix('"').and_then(|l| l.strip_suffix('"')) {
+            return Some(Self::String(string));
+        }
+
+        match literal {
+            "true" => return Some(Self::Bool(true)),
+            "false" => return Some(Self::Bool(false)),
+            _ => {}
+        }
+
+        let number = literal
+            .trim_end_matches(|c: char| {
+                matches!(c, 'f' | 'F' | 'd' | 'D' | 'm' | 'M' | 'u' | 'U' | 'l' | 'L')
+            })
+            .replace('_', "");
+        if let Ok(int) = number.parse::<i64>() {
+            return Some(Self::Int(int));
+        }
+        if let Ok(float) = number.parse::<f64>() {
+            return Some(Self::Float(float));
+        }
+
+        let is_member = literal.contains('.')
+            && literal.split('.').all(|ident| {
+                !ident.is_empty() && ident.chars().all(|c| c.is_alphanumeric() || c == '_')
+            });
+        is_member
+            .then(|| literal.rsplit('.').next())
+            .flatten()
+            .map(Self::EnumMember)
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct CsharpClassField {
     pub name: String,
-    pub type_name: String,
+    pub type_name: Interned,
     pub attributes: CsharpAttributeCollection,
-    pub modifiers: HashSet<String>,
+    pub modifiers: HashSet<Interned>,
+    /// The initializer expression as it is written, e.g. `5f` or `new()`.
+    pub default_value: Option<String>,
 
     index: DefinitionIndex,
 }
@@ -334,9 +713,10 @@ pub struct CsharpClassField {
 impl CsharpClassField {
     pub fn new(
         name: String,
-        type_name: String,
+        type_name: Interned,
         attributes: CsharpAttributeCollection,
-        modifiers: HashSet<String>,
+        modifiers: HashSet<Interned>,
+        default_value: Option<String>,
         index: DefinitionIndex,
     ) -> Self {
         Self {
@@ -344,6 +724,7 @@ impl CsharpClassField {
             type_name,
             attributes,
             modifiers,
+            default_value,
             index,
         }
     }
@@ -351,11 +732,26 @@ impl CsharpClassField {
     pub fn new_empty<T: ToString>(name: T, type_name: T) -> Self {
         Self {
             name: name.to_string(),
-            type_name: type_name.to_string(),
+            type_name: Interned::new(&type_name.to_string()),
             ..Default::default()
         }
     }
 
+    /// Creates a `[DataField]` that is not declared in the code, e.g. one read by a serializer.
+    pub fn new_data_field<T: ToString>(name: T, type_name: T) -> Self {
+        let mut field = Self::new_empty(name, type_name);
+        field.attributes.push(CsharpAttribute {
+            name: Interned::new("DataField"),
+            arguments: HashMap::new(),
+        });
+        field
+    }
+
+    /// The default value of the field if it's initialized with a literal.
+    pub fn default_literal(&self) -> Option<CsharpLiteral<'_>> {
+        self.default_value.as_deref().and_then(CsharpLiteral::parse)
+    }
+
     pub fn get_data_field_name(&self) -> String {
         if let Some(attr) = self.attributes.get("DataField") {
             if let Some(name) = attr.arguments.get("tag") {
@@ -372,6 +768,52 @@ impl CsharpClassField {
 
         stringcase::camel_case(&self.name)
     }
+
+    /// Whether the field must be specified, i.e. it's the `[IdDataField]`
+    /// or a `[DataField(required: true)]`.
+    pub fn is_required(&self) -> bool {
+        if self.attributes.contains("IdDataField") {
+            return true;
+        }
+
+        let Some(attr) = self.attributes.get("DataField") else {
+            return false;
+        };
+        matches!(
+            attr.arguments.get("required").map(|a| &a.value),
+            Some(CsharpAttributeArgumentType::Bool(true))
+        )
+    }
+
+    /// Whether the serializer reads the field, i.e. it has `[DataField]`, `[IdDataField]`
+    /// or `[IncludeDataField]`.
+    pub fn is_serialized(&self) -> bool {
+        ["DataField", "IdDataField", "IncludeDataField"]
+            .iter()
+            .any(|attr| self.attributes.contains(attr))
+    }
+
+    /// Whether only the server reads the field, i.e. it's a `[DataField(serverOnly: true)]`.
+    pub fn is_server_only(&self) -> bool {
+        let Some(attr) = self.attributes.get("DataField") else {
+            return false;
+        };
+        matches!(
+            attr.arguments.get("serverOnly").map(|a| &a.value),
+            Some(CsharpAttributeArgumentType::Bool(true))
+        )
+    }
+
+    /// Whether the field is `[DataField(readOnly: true)]`, read from YAML but never written back.
+    pub fn is_read_only(&self) -> bool {
+        let Some(attr) = self.attributes.get("DataField") else {
+            return false;
+        };
+        matches!(
+            attr.arguments.get("readOnly").map(|a| &a.value),
+            Some(CsharpAttributeArgumentType::Bool(true))
+        )
+    }
 }
 
 impl Index for CsharpClassField {
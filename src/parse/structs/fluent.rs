@@ -1,18 +1,37 @@
 #![allow(dead_code)]
 
 use crate::parse::common::{DefinitionIndex, Index};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FluentKeyKind {
+    #[default]
+    Message,
+    Term,
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct FluentKey {
     pub key: String,
     pub args: std::collections::HashSet<String>,
+    /// Names of the attributes of the message, e.g. `desc` of `.desc = ...`.
+    pub attributes: std::collections::HashSet<String>,
+    pub kind: FluentKeyKind,
+    /// The locale folder the key is defined in, e.g. `en-US`.
+    pub locale: String,
+    /// The text of the value as it reads in the file, e.g. `Weld with { -brand }`.
+    pub value: String,
 
     index: DefinitionIndex,
 }
 
+/// A key may be defined in several files of a locale, each of the definitions is indexed.
 impl PartialEq for FluentKey {
     fn eq(&self, other: &Self) -> bool {
         self.key == other.key
+            && self.kind == other.kind
+            && self.locale == other.locale
+            && self.index.0 == other.index.0
     }
 }
 
@@ -30,32 +49,251 @@ impl FluentKey {
         args: std::collections::HashSet<String>,
         index: DefinitionIndex,
     ) -> Self {
-        Self { key, args, index }
+        Self {
+            key,
+            args,
+            attributes: Default::default(),
+            kind: FluentKeyKind::Message,
+            locale: locale_of(&index.0).unwrap_or_default().to_owned(),
+            value: Default::default(),
+            index,
+        }
+    }
+
+    /// Creates a term key, `key` is the term name without the leading `-`.
+    pub fn term(
+        key: String,
+        args: std::collections::HashSet<String>,
+        index: DefinitionIndex,
+    ) -> Self {
+        Self {
+            kind: FluentKeyKind::Term,
+            ..Self::new(key, args, index)
+        }
     }
 
     pub fn dummy(key: impl ToString) -> Self {
         Self {
             key: key.to_string(),
             args: Default::default(),
+            attributes: Default::default(),
+            kind: FluentKeyKind::Message,
+            locale: Default::default(),
+            value: Default::default(),
             index: Default::default(),
         }
     }
+
+    pub fn dummy_term(key: impl ToString) -> Self {
+        Self {
+            kind: FluentKeyKind::Term,
+            ..Self::dummy(key)
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: std::collections::HashSet<String>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_value(mut self, value: String) -> Self {
+        self.value = value;
+        self
+    }
+}
+
+/// Whether the name can identify a message or a term, e.g. `tool-verb-pry`.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the locale folder of a Fluent file, e.g. `en-US` for
+/// `Resources/Locale/en-US/entities.ftl` or `Resources/EngineLocale/en-US/input.ftl`.
+pub fn locale_of(path: &Path) -> Option<&str> {
+    let mut components = path.components().map(|c| c.as_os_str().to_str());
+    components.find(|c| matches!(c, Some("Locale" | "EngineLocale")))?;
+    components.next()?
+}
+
+/// Picks the definitions of a key to go to: the ones from the first preferred locale
+/// that has the key, or all of them if none of the preferred locales has it.
+pub fn preferred_definitions<'a>(
+    definitions: Vec<&'a FluentKey>,
+    preferred: &[&str],
+) -> Vec<&'a FluentKey> {
+    for locale in preferred {
+        let found = definitions
+            .iter()
+            .filter(|k| k.locale == *locale)
+            .copied()
+            .collect::<Vec<_>>();
+        if !found.is_empty() {
+            return found;
+        }
+    }
+
+    definitions
 }
 
 impl Ord for FluentKey {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.key.cmp(&other.key)
+        self.key
+            .cmp(&other.key)
+            .then_with(|| self.kind.cmp(&other.kind))
+            .then_with(|| self.locale.cmp(&other.locale))
+            .then_with(|| self.index.0.cmp(&other.index.0))
     }
 }
 
 impl PartialOrd for FluentKey {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.key.partial_cmp(&other.key)
+        Some(self.cmp(other))
     }
 }
 
 impl std::hash::Hash for FluentKey {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.key.hash(state);
+        self.kind.hash(state);
+        self.locale.hash(state);
+        self.index.0.hash(state);
+    }
+}
+
+/// The definitions of the locale keys in every locale, keyed by the kind and the name
+/// so that the definitions of a key are found without scanning the whole index.
+#[derive(Default, Debug)]
+pub struct FluentKeyIndex {
+    messages: HashMap<String, Vec<FluentKey>>,
+    terms: HashMap<String, Vec<FluentKey>>,
+}
+
+impl FluentKeyIndex {
+    /// Adds the definition, the same key defined in the same file is replaced.
+    pub fn insert(&mut self, key: FluentKey) {
+        let definitions = self.keys_mut(key.kind).entry(key.key.clone()).or_default();
+        match definitions.iter_mut().find(|k| **k == key) {
+            Some(definition) => *definition = key,
+            None => definitions.push(key),
+        }
+    }
+
+    pub fn extend(&mut self, keys: impl IntoIterator<Item = FluentKey>) {
+        for key in keys {
+            self.insert(key);
+        }
+    }
+
+    /// Removes the definitions of the file and returns them.
+    pub fn remove_file(&mut self, path: &Path) -> Vec<FluentKey> {
+        let mut removed = vec![];
+        for keys in [&mut self.messages, &mut self.terms] {
+            keys.retain(|_, definitions| {
+                let (gone, kept) = std::mem::take(definitions)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|k| k.index.0 == path);
+                removed.extend(gone);
+                *definitions = kept;
+                !definitions.is_empty()
+            });
+        }
+        removed
+    }
+
+    /// The definitions of the key in every locale.
+    pub fn get(&self, key: &str, kind: FluentKeyKind) -> &[FluentKey] {
+        self.keys(kind)
+            .get(key)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, key: &str, kind: FluentKeyKind) -> bool {
+        self.keys(kind).contains_key(key)
+    }
+
+    /// The names of the keys of the kind, each name once however many locales define it.
+    pub fn names(&self, kind: FluentKeyKind) -> impl Iterator<Item = &str> {
+        self.keys(kind).keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FluentKey> {
+        self.messages.values().chain(self.terms.values()).flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages
+            .values()
+            .chain(self.terms.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.terms.is_empty()
+    }
+
+    fn keys(&self, kind: FluentKeyKind) -> &HashMap<String, Vec<FluentKey>> {
+        match kind {
+            FluentKeyKind::Message => &self.messages,
+            FluentKeyKind::Term => &self.terms,
+        }
     }
+
+    fn keys_mut(&mut self, kind: FluentKeyKind) -> &mut HashMap<String, Vec<FluentKey>> {
+        match kind {
+            FluentKeyKind::Message => &mut self.messages,
+            FluentKeyKind::Term => &mut self.terms,
+        }
+    }
+}
+
+/// A function that can be called in placeables, e.g. `{ NUMBER($count, minimumFractionDigits: 2) }`.
+#[derive(Debug, Clone, Copy)]
+pub struct FluentFunction {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub documentation: &'static str,
+    pub named_arguments: &'static [&'static str],
+}
+
+/// The functions every Fluent implementation provides.
+pub static BUILTIN_FUNCTIONS: &[FluentFunction] = &[
+    FluentFunction {
+        name: "NUMBER",
+        signature: "NUMBER($number, ...options)",
+        documentation: "Formats a number according to the locale, e.g. with a grouping separator.",
+        named_arguments: &[
+            "currencyDisplay",
+            "useGrouping",
+            "minimumIntegerDigits",
+            "minimumFractionDigits",
+            "maximumFractionDigits",
+            "minimumSignificantDigits",
+            "maximumSignificantDigits",
+        ],
+    },
+    FluentFunction {
+        name: "DATETIME",
+        signature: "DATETIME($date, ...options)",
+        documentation: "Formats a date and a time according to the locale.",
+        named_arguments: &[
+            "hour12",
+            "weekday",
+            "era",
+            "year",
+            "month",
+            "day",
+            "hour",
+            "minute",
+            "second",
+            "timeZoneName",
+        ],
+    },
+];
+
+pub fn builtin_function(name: &str) -> Option<&'static FluentFunction> {
+    BUILTIN_FUNCTIONS.iter().find(|f| f.name == name)
 }
@@ -0,0 +1,153 @@
+use serde::{Serialize, Serializer};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::DefaultHasher, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+};
+
+/// The pool is split so that the classes parsed in parallel rarely wait for each other.
+const SHARDS: usize = 16;
+
+type Shard = Mutex<HashSet<Arc<str>>>;
+
+/// The strings repeated across the classes, e.g. the type names of the fields,
+/// the modifiers and the names of the attributes.
+static POOL: OnceLock<[Shard; SHARDS]> = OnceLock::new();
+
+/// A string of the pool, equal strings share a single allocation
+/// however many classes of a large fork use them.
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    pub fn new(value: &str) -> Self {
+        let mut pool = lock(shard(value));
+        if let Some(interned) = pool.get(value) {
+            return Self(interned.clone());
+        }
+
+        let interned = Arc::<str>::from(value);
+        pool.insert(interned.clone());
+        Self(interned)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Interned {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Interned {
+    fn from(value: String) -> Self {
+        Self::new(&value)
+    }
+}
+
+impl PartialEq<str> for Interned {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Interned {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Interned {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for Interned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+/// How much the pool saves, reported by the `robust-lsp.indexStats` command.
+#[derive(Debug, Default, Serialize)]
+pub struct InternerStats {
+    /// The distinct strings still used by the index.
+    pub strings: usize,
+    /// The bytes taken by the distinct strings.
+    pub bytes: usize,
+    /// How many times the strings are used, each use would be a copy without the pool.
+    pub references: usize,
+}
+
+/// Drops the strings no longer used by the index, e.g. the types of the removed classes.
+pub fn prune() {
+    for shard in shards() {
+        lock(shard).retain(|s| Arc::strong_count(s) > 1);
+    }
+}
+
+/// Counts the strings used by the index, the ones only the pool holds are left out.
+pub fn stats() -> InternerStats {
+    let mut stats = InternerStats::default();
+    for shard in shards() {
+        for s in lock(shard).iter().filter(|s| Arc::strong_count(s) > 1) {
+            stats.strings += 1;
+            stats.bytes += s.len();
+            stats.references += Arc::strong_count(s) - 1;
+        }
+    }
+    stats
+}
+
+fn shards() -> &'static [Shard; SHARDS] {
+    POOL.get_or_init(|| std::array::from_fn(|_| Shard::default()))
+}
+
+fn shard(value: &str) -> &'static Shard {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    &shards()[hasher.finish() as usize % SHARDS]
+}
+
+fn lock(shard: &Shard) -> MutexGuard<'_, HashSet<Arc<str>>> {
+    shard.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -1,11 +1,18 @@
 #![allow(dead_code)]
 
+use crate::vfs::FileSystem;
 use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Size2d {
-    x: u32,
-    y: u32,
+    pub x: u32,
+    pub y: u32,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -22,3 +29,35 @@ pub struct RsiMeta {
     pub size: Size2d,
     pub states: Vec<RsiState>,
 }
+
+/// The parsed `meta.json` files of RSIs keyed by their paths,
+/// a file is read again once it has been modified.
+#[derive(Default, Debug)]
+pub struct RsiMetaCache {
+    entries: RwLock<HashMap<PathBuf, (SystemTime, Arc<RsiMeta>)>>,
+}
+
+impl RsiMetaCache {
+    pub fn get(&self, fs: &dyn FileSystem, meta_path: &Path) -> Option<Arc<RsiMeta>> {
+        let modified = fs.metadata(meta_path).ok()?.modified?;
+        if let Some((time, meta)) = self.entries.read().ok()?.get(meta_path) {
+            if *time == modified {
+                return Some(meta.clone());
+            }
+        }
+
+        let meta: Arc<RsiMeta> = match serde_json::from_reader(fs.open(meta_path).ok()?) {
+            Ok(meta) => Arc::new(meta),
+            Err(err) => {
+                tracing::error!("Failed to read {meta_path:?}: {err}");
+                return None;
+            }
+        };
+
+        self.entries
+            .write()
+            .ok()?
+            .insert(meta_path.to_path_buf(), (modified, meta.clone()));
+        Some(meta)
+    }
+}
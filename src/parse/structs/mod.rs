@@ -7,7 +7,9 @@ use std::{
     path::PathBuf,
 };
 
+pub mod audio;
 pub mod csharp;
 pub mod yaml;
 pub mod json;
 pub mod fluent;
+pub mod intern;
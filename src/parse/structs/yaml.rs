@@ -1,17 +1,158 @@
+use super::intern::Interned;
 use crate::parse::common::{DefinitionIndex, Index};
-use std::hash::Hash;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// Fields that belong to the prototype itself and are never inherited.
+static NOT_INHERITED_FIELDS: &[&str] = &["abstract"];
 
 #[derive(Debug, Clone, Default)]
 pub struct YamlPrototype {
     pub prototype: String,
     pub id: String,
+    pub parents: Vec<String>,
+    /// Scalar fields declared in the prototype, e.g. `name` or `description`,
+    /// the fields of nested mappings are keyed by their path, e.g. `placement.mode`.
+    pub fields: HashMap<String, String>,
+    /// Lists of scalars declared in the prototype, e.g. `categories`, the scalar fields
+    /// of a list of mappings are keyed by their path, e.g. `graph.node`.
+    pub lists: HashMap<String, Vec<String>>,
+    /// Names of the components declared in the prototype.
+    pub components: Vec<String>,
+    /// Scalar fields of the components keyed by the component and the field,
+    /// e.g. `Tool.speedModifier`.
+    pub component_fields: HashMap<String, String>,
+    /// The `#` comment block right above the definition, shown on hover over the id.
+    pub doc: Option<String>,
+    /// The text of every scalar of the definition, keys included, so that the references
+    /// to an id are looked for only in the files that mention it.
+    pub scalars: HashSet<Interned>,
+    /// Other definitions of the same prototype from lower-priority override roots,
+    /// or from the same root, in which case they are duplicates.
+    pub shadowed: Vec<YamlPrototype>,
 
     index: DefinitionIndex,
 }
 
+/// A prototype with the fields and components of all of its parents applied.
+#[derive(Debug, Clone, Default)]
+pub struct MergedPrototype {
+    /// All ancestors in the order they are applied, the closest ones first.
+    pub ancestors: Vec<String>,
+    pub fields: HashMap<String, String>,
+    /// Lists of all the ancestors joined together, the own items first.
+    pub lists: HashMap<String, Vec<String>>,
+    pub components: Vec<String>,
+    pub component_fields: HashMap<String, String>,
+}
+
 impl YamlPrototype {
-    pub fn new(prototype: String, id: String, index: DefinitionIndex) -> Self {
-        Self { prototype, id, index }
+    pub fn new(
+        prototype: String,
+        id: String,
+        parents: Vec<String>,
+        fields: HashMap<String, String>,
+        lists: HashMap<String, Vec<String>>,
+        components: Vec<String>,
+        index: DefinitionIndex,
+    ) -> Self {
+        Self {
+            prototype,
+            id,
+            parents,
+            fields,
+            lists,
+            components,
+            component_fields: HashMap::new(),
+            doc: None,
+            scalars: HashSet::new(),
+            shadowed: vec![],
+            index,
+        }
+    }
+
+    pub fn dummy(prototype: impl ToString, id: impl ToString) -> Self {
+        Self {
+            prototype: prototype.to_string(),
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The names of the nodes of a construction graph.
+    pub fn graph_nodes(&self) -> &[String] {
+        self.lists
+            .get("graph.node")
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Abstract prototypes, e.g. `abstract: true` bases, are only used as parents.
+    pub fn is_abstract(&self) -> bool {
+        self.fields.get("abstract").is_some_and(|v| v == "true")
+    }
+
+    /// The definition itself and the ones it shadows, the highest-priority first.
+    pub fn definitions(&self) -> impl Iterator<Item = &YamlPrototype> {
+        std::iter::once(self).chain(self.shadowed.iter())
+    }
+
+    /// Applies the parents of the prototype, the prototype itself and
+    /// the closer parents take precedence over the further ones.
+    pub fn merge(&self, prototypes: &HashSet<YamlPrototype>) -> MergedPrototype {
+        let mut merged = MergedPrototype {
+            ancestors: vec![],
+            fields: self.fields.clone(),
+            lists: self.lists.clone(),
+            components: self.components.clone(),
+            component_fields: self.component_fields.clone(),
+        };
+
+        let mut visited = HashSet::from([self.id.clone()]);
+        let mut queue = self.parents.clone();
+        let mut i = 0;
+        while let Some(id) = queue.get(i).cloned() {
+            i += 1;
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+            merged.ancestors.push(id.clone());
+
+            let Some(parent) = prototypes.get(&YamlPrototype::dummy(&self.prototype, &id)) else {
+                continue;
+            };
+
+            for (key, value) in parent.fields.iter() {
+                if !NOT_INHERITED_FIELDS.contains(&key.as_str()) {
+                    merged.fields.entry(key.clone()).or_insert(value.clone());
+                }
+            }
+            for (key, values) in parent.lists.iter() {
+                let list = merged.lists.entry(key.clone()).or_default();
+                for value in values {
+                    if !list.contains(value) {
+                        list.push(value.clone());
+                    }
+                }
+            }
+            for component in parent.components.iter() {
+                if !merged.components.contains(component) {
+                    merged.components.push(component.clone());
+                }
+            }
+            for (key, value) in parent.component_fields.iter() {
+                merged
+                    .component_fields
+                    .entry(key.clone())
+                    .or_insert(value.clone());
+            }
+
+            queue.extend(parent.parents.iter().cloned());
+        }
+
+        merged
     }
 }
 
@@ -35,3 +176,12 @@ impl Hash for YamlPrototype {
         self.id.hash(state);
     }
 }
+
+/// Whether the name can be the id of a prototype: it's written unquoted in YAML and is a part
+/// of the `ent-<id>` locale key of an entity.
+pub fn is_prototype_id(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
@@ -1,12 +1,20 @@
-use super::{common::DefinitionIndex, structs::yaml::YamlPrototype, ParsedFiles, Result};
+use super::{
+    common::DefinitionIndex,
+    structs::{intern::Interned, yaml::YamlPrototype},
+    ParsedFiles, Result,
+};
 use crate::parse::ParseResult;
 use futures::{
     future::{ready, BoxFuture},
     FutureExt,
 };
 use ropey::Rope;
-use std::{path::PathBuf, sync::Arc};
-use tree_sitter::Node;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tree_sitter::{Node, Tree};
 
 pub fn dispatch(
     result: ParseResult,
@@ -18,7 +26,7 @@ pub fn dispatch(
     };
 
     Box::pin(async move {
-        context.prototypes.write().await.extend(protos);
+        context.extend_prototypes(protos).await;
     })
 }
 
@@ -30,55 +38,84 @@ pub(crate) fn parse(
 }
 
 async fn p(path: PathBuf, parsed_files: ParsedFiles) -> Result<ParseResult> {
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&tree_sitter_yaml::language())
-        .expect("Failed to load YAML grammer");
-
-    let rope = Rope::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+    let Ok(rope) = std::fs::File::open(&path).and_then(Rope::from_reader) else {
+        tracing::warn!("Failed to read the file {}", path.display());
+        return Err(());
+    };
 
-    let mut lock = parsed_files.write().await;
-    let old_tree = lock.get_mut(&path);
+    parse_rope(path, rope, parsed_files).await
+}
 
+/// Parses the text of the file, e.g. the one sent by the client on save.
+pub(crate) async fn parse_rope(
+    path: PathBuf,
+    rope: Rope,
+    parsed_files: ParsedFiles,
+) -> Result<ParseResult> {
     let src = rope.to_string();
+    let tree = reparse_kept(&mut *parsed_files.write().await, &path, &src).ok_or(())?;
+    get_prototypes(&tree, &src, &path)
+}
 
-    let tree = parser.parse(&src, old_tree.as_deref());
-    if let Some(tree) = tree {
-        if let Some(old_tree) = old_tree {
-            *old_tree = tree.clone();
-            drop(lock);
-        }
+/// Parses the text again, incrementally when the tree of the file is kept,
+/// in which case the new tree is kept in its place.
+pub(crate) fn reparse_kept(
+    trees: &mut HashMap<PathBuf, Tree>,
+    path: &Path,
+    src: &str,
+) -> Option<Tree> {
+    let old_tree = trees.get_mut(path);
+    let tree = parse_tree(src, old_tree.as_deref())?;
+    if let Some(old_tree) = old_tree {
+        *old_tree = tree.clone();
+    }
+    Some(tree)
+}
 
-        let root_node = tree.root_node();
-        if let Some(block_sequence_node) = get_block_sequence_node(&root_node) {
-            if block_sequence_node.kind() != "block_sequence" {
-                return Err(());
-            }
+/// Collects the prototypes of the parsed file. A file without a sequence of them at the top,
+/// e.g. an empty one, has none rather than fails to parse.
+pub(crate) fn get_prototypes(tree: &Tree, src: &str, path: &Path) -> Result<ParseResult> {
+    let block_sequence_nodes = get_block_sequence_nodes(tree.root_node());
 
-            let mut protos = vec![];
-            for i in 0..block_sequence_node.named_child_count() {
-                let block_sequence_item_node = block_sequence_node.named_child(i).unwrap();
-                if let Some(prototype) = get_yaml_prototype(block_sequence_item_node, &src, &path) {
-                    protos.push(prototype);
-                }
+    let lines = src.lines().collect::<Vec<_>>();
+    let mut protos = vec![];
+    for block_sequence_node in block_sequence_nodes {
+        for i in 0..block_sequence_node.named_child_count() {
+            let block_sequence_item_node = block_sequence_node.named_child(i).unwrap();
+            if let Some(mut prototype) = get_yaml_prototype(block_sequence_item_node, src, path) {
+                prototype.doc =
+                    leading_comment(&lines, block_sequence_item_node.start_position().row);
+                protos.push(prototype);
             }
-            return Ok(ParseResult::YamlPrototypes(protos));
         }
     }
+    Ok(ParseResult::YamlPrototypes(protos))
+}
 
-    Err(())
+/// Parses the text, incrementally when the old tree is edited to match it.
+pub(crate) fn parse_tree(src: &str, old_tree: Option<&Tree>) -> Option<Tree> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&tree_sitter_yaml::language())
+        .expect("Failed to load YAML grammer");
+
+    parser.parse(src, old_tree)
 }
 
 fn get_yaml_prototype(
     block_sequence_item_node: Node,
     src: &str,
-    path: &PathBuf,
+    path: &Path,
 ) -> Option<YamlPrototype> {
     if let Some(block_mapping_node) = get_block_mapping(block_sequence_item_node) {
         let mut prototype = None;
         let mut id = None;
         let mut id_range = None;
         let mut parents = vec![];
+        let mut fields = HashMap::new();
+        let mut lists = HashMap::new();
+        let mut components = vec![];
+        let mut component_fields = HashMap::new();
 
         for i in 0..block_mapping_node.named_child_count() {
             let mapping_pair_node = block_mapping_node.named_child(i).unwrap();
@@ -134,17 +171,39 @@ fn get_yaml_prototype(
                     }
                     _ => continue,
                 },
-                _ => continue,
+                "components" => (components, component_fields) = get_components(value_node, src),
+                _ => {
+                    if let Some(value) = get_scalar(value_node, src) {
+                        fields.insert(key_name.to_owned(), value);
+                    } else if let Some(values) = get_scalar_list(value_node, src) {
+                        lists.insert(key_name.to_owned(), values);
+                    } else if let Some(items) = get_mapping_list(value_node, src) {
+                        for (key, values) in items {
+                            lists.insert(format!("{key_name}.{key}"), values);
+                        }
+                    } else {
+                        for (key, value) in get_scalar_mapping(value_node, src) {
+                            fields.insert(format!("{key_name}.{key}"), value);
+                        }
+                    }
+                }
             }
         }
 
         match (prototype, id) {
             (Some(prototype), Some(id)) => {
-                return Some(YamlPrototype::new(
+                let mut prototype = YamlPrototype::new(
                     prototype,
                     id,
-                    DefinitionIndex(path.clone(), id_range),
-                ))
+                    parents,
+                    fields,
+                    lists,
+                    components,
+                    DefinitionIndex(path.to_path_buf(), id_range),
+                );
+                prototype.component_fields = component_fields;
+                prototype.scalars = get_scalars(block_mapping_node, src);
+                return Some(prototype);
             }
             _ => return None,
         }
@@ -153,10 +212,170 @@ fn get_yaml_prototype(
     None
 }
 
-fn get_block_sequence_node<'a>(root_node: &'a Node<'a>) -> Option<Node<'a>> {
-    let document = find_child_node(*root_node, "document")?;
-    let block_node = find_child_node(document, "block_node")?;
-    find_child_node(block_node, "block_sequence")
+/// The text of the scalars of the mapping and of the nodes nested in it, without the quotes.
+fn get_scalars(block_mapping_node: Node, src: &str) -> HashSet<Interned> {
+    let mut scalars = HashSet::new();
+    let mut stack = vec![block_mapping_node];
+    while let Some(node) = stack.pop() {
+        let is_scalar = node.kind() == "flow_node"
+            && node
+                .named_child(0)
+                .is_some_and(|n| n.kind().ends_with("_scalar"));
+        if is_scalar {
+            if let Ok(text) = node.utf8_text(src.as_bytes()) {
+                scalars.insert(Interned::new(unquote(text)));
+            }
+            continue;
+        }
+
+        for i in 0..node.named_child_count() {
+            stack.extend(node.named_child(i));
+        }
+    }
+    scalars
+}
+
+/// The text of a scalar without the quotes around it, if any.
+pub(crate) fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .or_else(|| text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+        .unwrap_or(text)
+}
+
+/// The block of `#` comments right above the line, without the `#` markers.
+fn leading_comment(lines: &[&str], row: usize) -> Option<String> {
+    let comment = lines[..row.min(lines.len())]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| {
+            let text = line.trim_start_matches('#');
+            text.strip_prefix(' ').unwrap_or(text)
+        })
+        .collect::<Vec<_>>();
+    if comment.iter().all(|line| line.trim().is_empty()) {
+        return None;
+    }
+
+    Some(comment.into_iter().rev().collect::<Vec<_>>().join("\n"))
+}
+
+fn get_scalar(value_node: Node, src: &str) -> Option<String> {
+    let scalar_node = value_node.named_child(0)?;
+    if !scalar_node.kind().ends_with("_scalar") {
+        return None;
+    }
+
+    let value = scalar_node.utf8_text(src.as_bytes()).ok()?;
+    Some(value.trim_matches(|c| c == '"' || c == '\'').to_owned())
+}
+
+/// Returns the scalar items of a flow or block sequence, e.g. `categories: [ Debug ]`.
+fn get_scalar_list(value_node: Node, src: &str) -> Option<Vec<String>> {
+    let sequence_node = value_node.named_child(0)?;
+    if !matches!(sequence_node.kind(), "flow_sequence" | "block_sequence") {
+        return None;
+    }
+
+    let mut values = vec![];
+    for i in 0..sequence_node.named_child_count() {
+        let item_node = sequence_node.named_child(i).unwrap();
+        let item_node = match item_node.kind() {
+            "block_sequence_item" => item_node.named_child(0)?,
+            _ => item_node,
+        };
+        values.push(get_scalar(item_node, src)?);
+    }
+    Some(values)
+}
+
+/// Returns the scalar fields of the mappings of a block sequence grouped by their keys,
+/// e.g. the `node` names of the `graph` of a construction graph.
+fn get_mapping_list(value_node: Node, src: &str) -> Option<HashMap<String, Vec<String>>> {
+    let block_sequence_node = find_child_node(value_node, "block_sequence")?;
+
+    let mut lists = HashMap::<String, Vec<String>>::new();
+    for i in 0..block_sequence_node.named_child_count() {
+        let Some(block_mapping_node) =
+            get_block_mapping(block_sequence_node.named_child(i).unwrap())
+        else {
+            continue;
+        };
+
+        for (key, value) in get_scalar_fields(block_mapping_node, src) {
+            lists.entry(key).or_default().push(value);
+        }
+    }
+    Some(lists)
+}
+
+/// Returns the scalar fields of a nested mapping, e.g. `mode` of `placement`.
+fn get_scalar_mapping(value_node: Node, src: &str) -> Vec<(String, String)> {
+    let Some(block_mapping_node) = find_child_node(value_node, "block_mapping") else {
+        return vec![];
+    };
+
+    get_scalar_fields(block_mapping_node, src)
+}
+
+fn get_scalar_fields(block_mapping_node: Node, src: &str) -> Vec<(String, String)> {
+    let mut fields = vec![];
+    for i in 0..block_mapping_node.named_child_count() {
+        let mapping_pair_node = block_mapping_node.named_child(i).unwrap();
+        let key = mapping_pair_node
+            .child_by_field_name("key")
+            .and_then(|n| n.utf8_text(src.as_bytes()).ok());
+        let value = mapping_pair_node
+            .child_by_field_name("value")
+            .and_then(|n| get_scalar(n, src));
+
+        if let (Some(key), Some(value)) = (key, value) {
+            fields.push((key.to_owned(), value));
+        }
+    }
+    fields
+}
+
+/// Returns the names of the components and their scalar fields keyed by the component
+/// and the field, e.g. `Tool.speedModifier`.
+fn get_components(value_node: Node, src: &str) -> (Vec<String>, HashMap<String, String>) {
+    let Some(block_sequence_node) = find_child_node(value_node, "block_sequence") else {
+        return (vec![], HashMap::new());
+    };
+
+    let mut components = vec![];
+    let mut component_fields = HashMap::new();
+    for i in 0..block_sequence_node.named_child_count() {
+        let Some(block_mapping_node) =
+            get_block_mapping(block_sequence_node.named_child(i).unwrap())
+        else {
+            continue;
+        };
+
+        let fields = get_scalar_fields(block_mapping_node, src);
+        let Some((_, component)) = fields.iter().find(|(key, _)| key == "type") else {
+            continue;
+        };
+        for (key, value) in fields.iter().filter(|(key, _)| key != "type") {
+            component_fields.insert(format!("{component}.{key}"), value.clone());
+        }
+        components.push(component.clone());
+    }
+
+    (components, component_fields)
+}
+
+/// Returns the prototype sequences of all documents of the stream, separated by `---`.
+pub(crate) fn get_block_sequence_nodes(root_node: Node) -> Vec<Node> {
+    let mut cursor = root_node.walk();
+    root_node
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() == "document")
+        .filter_map(|document| find_child_node(document, "block_node"))
+        .filter_map(|block_node| find_child_node(block_node, "block_sequence"))
+        .collect()
 }
 
 fn find_child_node<'a>(node: Node<'a>, name: &'a str) -> Option<Node<'a>> {
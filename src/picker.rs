@@ -0,0 +1,74 @@
+use crate::{
+    completion::matcher::Matcher,
+    config::Config,
+    parse::{common::Index, structs::yaml::YamlPrototype},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tower_lsp::lsp_types::Url;
+
+pub const ENTITY_PICKER_METHOD: &str = "robust-lsp/entities";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EntityPickerParams {
+    /// The text the ids are matched against, all entities are listed when it is empty.
+    pub query: String,
+    pub offset: usize,
+    /// The size of the page, `completion.maxItems` by default.
+    pub limit: Option<usize>,
+    pub include_abstract: bool,
+}
+
+/// A page of the entities that match the query, the best matches first.
+#[derive(Debug, Serialize)]
+pub struct EntityPage {
+    pub items: Vec<EntityItem>,
+    /// The number of the entities that match the query across all pages.
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntityItem {
+    pub id: String,
+    /// The own or the inherited name.
+    pub name: Option<String>,
+    pub parents: Vec<String>,
+    pub file: Option<Url>,
+}
+
+impl EntityPage {
+    pub fn new(
+        prototypes: &HashSet<YamlPrototype>,
+        config: &Config,
+        params: &EntityPickerParams,
+    ) -> Self {
+        let entities = prototypes
+            .iter()
+            .filter(|p| p.prototype == "entity")
+            .filter(|p| {
+                params.include_abstract || p.fields.get("abstract").is_none_or(|v| v != "true")
+            });
+
+        // The page is cut after ranking, so the matcher itself keeps every match.
+        let matcher = Matcher::new(config.completion.matcher, usize::MAX);
+        let matches = matcher.rank(&params.query, entities, |p| &p.id);
+
+        let items = matches
+            .iter()
+            .skip(params.offset)
+            .take(params.limit.unwrap_or(config.completion.max_items))
+            .map(|proto| EntityItem {
+                id: proto.id.clone(),
+                name: proto.merge(prototypes).fields.get("name").cloned(),
+                parents: proto.parents.clone(),
+                file: Url::from_file_path(&proto.index().0).ok(),
+            })
+            .collect();
+
+        Self {
+            items,
+            total: matches.len(),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use super::{References, ReferencesResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        fluent::span_to_range,
+        structs::fluent::{locale_of, FluentKeyKind},
+    },
+    utils::position_offset,
+};
+use fluent_syntax::ast::{Entry, Identifier};
+use ropey::Rope;
+use std::sync::Arc;
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, Location, Position, Url};
+
+/// Lists the translations of a Fluent key in the other locales.
+pub struct FluentReferences {
+    context: Arc<Context>,
+    uri: Url,
+    position: Position,
+    include_declaration: bool,
+    src: String,
+}
+
+impl References for FluentReferences {
+    fn references(&self) -> ReferencesResult {
+        let offset = position_offset(&self.src, self.position)?;
+        let resource = match fluent_syntax::parser::parse(self.src.as_str()) {
+            Ok(resource) => resource,
+            Err((resource, _)) => resource,
+        };
+
+        let (id, kind) = resource.body.iter().find_map(|entry| match entry {
+            Entry::Message(msg) if contains(&msg.id, offset) => {
+                Some((&msg.id, FluentKeyKind::Message))
+            }
+            Entry::Term(term) if contains(&term.id, offset) => {
+                Some((&term.id, FluentKeyKind::Term))
+            }
+            _ => None,
+        })?;
+
+        let path = self.uri.to_file_path().ok()?;
+        let locale = locale_of(&path).unwrap_or_default();
+
+        let lock = block_in_place(|| self.context.locales.blocking_read());
+        let mut locations = lock
+            .get(id.name, kind)
+            .iter()
+            .filter(|k| k.locale != locale)
+            .filter_map(|k| index_to_location(k.index()))
+            .collect::<Vec<_>>();
+        locations.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+
+        if self.include_declaration {
+            let range = span_to_range(&self.src, &id.span);
+            locations.insert(0, Location::new(self.uri.clone(), to_lsp_range(&range)));
+        }
+
+        if locations.is_empty() {
+            None
+        } else {
+            Some(locations)
+        }
+    }
+}
+
+impl FluentReferences {
+    pub fn new(
+        context: Arc<Context>,
+        uri: Url,
+        position: Position,
+        include_declaration: bool,
+        rope: &Rope,
+    ) -> Self {
+        Self {
+            context,
+            uri,
+            position,
+            include_declaration,
+            src: rope.to_string(),
+        }
+    }
+}
+
+// The span of a term identifier doesn't include the leading `-`.
+fn contains(id: &Identifier<&str>, offset: usize) -> bool {
+    (id.span.start.saturating_sub(1)..=id.span.end).contains(&offset)
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        to_lsp_range(range),
+    ))
+}
+
+fn to_lsp_range(range: &tree_sitter::Range) -> lsp_types::Range {
+    lsp_types::Range::new(
+        Position::new(
+            range.start_point.row as u32,
+            range.start_point.column as u32,
+        ),
+        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+    )
+}
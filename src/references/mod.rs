@@ -0,0 +1,9 @@
+#[cfg(feature = "fluent")]
+pub mod fluent;
+pub mod yaml;
+
+pub type ReferencesResult = Option<Vec<tower_lsp::lsp_types::Location>>;
+
+pub trait References {
+    fn references(&self) -> ReferencesResult;
+}
@@ -0,0 +1,359 @@
+use super::{References, ReferencesResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::{csharp::ReflectionManager, yaml::YamlPrototype},
+        yaml::unquote,
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::block,
+};
+use ropey::Rope;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, Location, Position, Url};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+/// Lists the places in the C# code where the component under the cursor is used,
+/// e.g. `TryComp<FooComponent>` for `- type: Foo`, or the places in the prototypes
+/// where the prototype whose id is under the cursor is used.
+pub struct YamlReferences {
+    context: Arc<Context>,
+    position: Position,
+    include_declaration: bool,
+    /// The snapshot of the opened documents, the prototype files are read from it as edited.
+    opened: HashMap<Url, Rope>,
+    src: String,
+    tree: Tree,
+}
+
+impl References for YamlReferences {
+    fn references(&self) -> ReferencesResult {
+        let point = Point::new(
+            self.position.line as usize,
+            self.position.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let found_node = root_node.named_descendant_for_point_range(point, point)?;
+        if let Some((kind, id)) = self.find_id(found_node) {
+            return self.id_references(kind, id);
+        }
+        let pair = self.find_type_pair(found_node)?;
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let Some(SchemaType::ComponentRegistry) = resolver.resolve_container(pair) else {
+            return None;
+        };
+
+        let name = pair
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        let reflection = ReflectionManager::new(&self.context);
+        let component = block(|| reflection.get_component_by_name(name))?;
+
+        let mut locations = block_in_place(|| self.context.usages.blocking_read())
+            .get(&component.name)
+            .iter()
+            .filter_map(index_to_location)
+            .collect::<Vec<_>>();
+        locations.sort_by(|a, b| {
+            (a.uri.as_str(), a.range.start.line).cmp(&(b.uri.as_str(), b.range.start.line))
+        });
+
+        if self.include_declaration {
+            if let Some(location) = index_to_location(component.index()) {
+                locations.insert(0, location);
+            }
+        }
+
+        if locations.is_empty() {
+            None
+        } else {
+            Some(locations)
+        }
+    }
+}
+
+impl YamlReferences {
+    pub fn new(
+        context: Arc<Context>,
+        position: Position,
+        include_declaration: bool,
+        rope: &Rope,
+        opened: HashMap<Url, Rope>,
+    ) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            position,
+            include_declaration,
+            opened,
+            src,
+            tree,
+        }
+    }
+
+    fn id_references(&self, kind: &str, id: &str) -> ReferencesResult {
+        let mut locations = prototype_references(&self.context, &self.opened, kind, id);
+
+        if self.include_declaration {
+            let lock = block_in_place(|| self.context.prototypes.blocking_read());
+            if let Some(location) = lock
+                .get(&YamlPrototype::dummy(kind, id))
+                .and_then(|proto| index_to_location(proto.index()))
+            {
+                locations.insert(0, location);
+            }
+        }
+
+        if locations.is_empty() {
+            None
+        } else {
+            Some(locations)
+        }
+    }
+
+    /// Finds the kind and the id of the prototype whose `id` value the node belongs to.
+    fn find_id(&self, node: Node) -> Option<(&str, &str)> {
+        let mut pair = node;
+        while pair.kind() != "block_mapping_pair" {
+            pair = pair.parent()?;
+        }
+
+        let value_node = pair.child_by_field_name("value")?;
+        if get_key(pair, &self.src)? != "id"
+            || !value_node.byte_range().contains(&node.start_byte())
+        {
+            return None;
+        }
+
+        let kind = get_value(pair.parent()?, "type", &self.src)?;
+        Some((kind, value_node.utf8_text(self.src.as_bytes()).ok()?))
+    }
+
+    /// Climbs from the value of a `type` field to its `block_mapping_pair`.
+    fn find_type_pair<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
+        let mut node = node;
+        while node.kind() != "block_mapping_pair" {
+            node = node.parent()?;
+        }
+
+        let key = node
+            .child_by_field_name("key")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        (key == "type").then_some(node)
+    }
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        lsp_types::Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    ))
+}
+
+/// Finds the places in the prototype files where the prototype is referenced by its id:
+/// the `parent` of the prototypes of its kind and the values and dictionary keys of
+/// the fields of id types, e.g. `EntProtoId` or `ProtoId<TagPrototype>`.
+/// The opened documents are read from the snapshot, as edited.
+pub fn prototype_references(
+    context: &Context,
+    opened: &HashMap<Url, Rope>,
+    kind: &str,
+    id: &str,
+) -> Vec<Location> {
+    find_scalars(context, opened, id, |resolver, scalar, src| {
+        references_id(resolver, scalar, src, kind)
+    })
+}
+
+/// Finds the places in the prototype files where a `LocId` field is set to the locale key.
+pub fn locale_references(
+    context: &Context,
+    opened: &HashMap<Url, Rope>,
+    key: &str,
+) -> Vec<Location> {
+    find_scalars(context, opened, key, |resolver, scalar, _| {
+        scalar
+            .parent()
+            .and_then(|parent| parent.child_by_field_name("key"))
+            != Some(scalar)
+            && find_pair(scalar).is_some_and(|pair| {
+                matches!(
+                    resolver.resolve_pair(pair),
+                    Some(SchemaType::Value(type_name) | SchemaType::Sequence(type_name))
+                        if type_name.trim_end_matches('?') == "LocId"
+                )
+            })
+    })
+}
+
+/// Finds the scalars of the prototype files that are the text and are accepted by the check.
+fn find_scalars(
+    context: &Context,
+    opened: &HashMap<Url, Rope>,
+    text: &str,
+    check: impl Fn(&YamlSchemaResolver, Node, &str) -> bool,
+) -> Vec<Location> {
+    // Only the files whose prototypes mention the text are parsed.
+    let mut files = {
+        let lock = block_in_place(|| context.prototypes.blocking_read());
+        let mut files = vec![];
+        let mut prototype_files = HashSet::new();
+        for definition in lock.iter().flat_map(|proto| proto.definitions()) {
+            let path = &definition.index().0;
+            if definition.scalars.contains(text) {
+                files.push(path.clone());
+            }
+            prototype_files.insert(path.as_path());
+        }
+
+        // The opened documents may mention the text before they are indexed again.
+        files.extend(
+            opened
+                .keys()
+                .filter_map(|uri| uri.to_file_path().ok())
+                .filter(|path| prototype_files.contains(path.as_path())),
+        );
+        files
+    };
+    files.sort();
+    files.dedup();
+
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_yaml::language()).unwrap();
+
+    let mut locations = vec![];
+    for path in files {
+        // The opened documents are read as edited, they may differ from the files.
+        let Some(src) = context.read_document(opened, &path) else {
+            continue;
+        };
+        if !src.contains(text) {
+            continue;
+        }
+        let (Some(tree), Ok(uri)) = (parser.parse(&src, None), Url::from_file_path(&path)) else {
+            continue;
+        };
+
+        let resolver = YamlSchemaResolver::new(context, &src);
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            let range = (node.kind() == "flow_node")
+                .then(|| scalar_range(node, &src, text))
+                .flatten();
+            match range {
+                Some(range) => {
+                    if check(&resolver, node, &src) {
+                        locations.push(Location::new(uri.clone(), range));
+                    }
+                }
+                None => {
+                    for i in (0..node.named_child_count()).rev() {
+                        stack.extend(node.named_child(i));
+                    }
+                }
+            }
+        }
+    }
+    locations
+}
+
+/// Whether the scalar is a reference to a prototype of the kind.
+fn references_id(resolver: &YamlSchemaResolver, scalar: Node, src: &str, kind: &str) -> bool {
+    let Some(parent) = scalar.parent() else {
+        return false;
+    };
+
+    // A key of a dictionary keyed by prototype ids.
+    if parent.kind() == "block_mapping_pair" && parent.child_by_field_name("key") == Some(scalar) {
+        return parent.parent().is_some_and(|dictionary| {
+            matches!(
+                resolver.resolve(dictionary),
+                Some(SchemaType::Dictionary(key_type, _))
+                    if resolver.resolve_key_prototype(dictionary, &key_type).as_deref() == Some(kind)
+            )
+        });
+    }
+
+    let Some(pair) = find_pair(parent) else {
+        return false;
+    };
+
+    match get_key(pair, src) {
+        Some("parent") => pair
+            .parent()
+            .and_then(|mapping| get_value(mapping, "type", src))
+            .is_some_and(|owner| owner == kind),
+        Some("id") => false,
+        _ => match resolver.resolve_pair(pair) {
+            Some(SchemaType::Value(type_name) | SchemaType::Sequence(type_name)) => {
+                resolver.resolve_id_prototype(&type_name).as_deref() == Some(kind)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// Climbs from a value to the `block_mapping_pair` it is the value of, or an item of.
+fn find_pair(node: Node) -> Option<Node> {
+    let mut node = node;
+    while node.kind() != "block_mapping_pair" {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+/// The range of the text of the scalar if it is the text, the quotes aren't included.
+fn scalar_range(node: Node, src: &str, text: &str) -> Option<lsp_types::Range> {
+    let value = node.utf8_text(src.as_bytes()).ok()?;
+    let unquoted = unquote(value);
+    if unquoted != text {
+        return None;
+    }
+
+    let quote = (unquoted.len() != value.len()) as u32;
+    let (start, end) = (node.start_position(), node.end_position());
+    Some(lsp_types::Range::new(
+        Position::new(start.row as u32, start.column as u32 + quote),
+        Position::new(end.row as u32, end.column as u32 - quote),
+    ))
+}
+
+fn get_key<'a>(pair: Node, src: &'a str) -> Option<&'a str> {
+    pair.child_by_field_name("key")?
+        .utf8_text(src.as_bytes())
+        .ok()
+}
+
+/// The value of the field of the mapping, e.g. the `type` of a prototype.
+fn get_value<'a>(mapping: Node, name: &str, src: &'a str) -> Option<&'a str> {
+    (0..mapping.named_child_count())
+        .filter_map(|i| mapping.named_child(i))
+        .find(|pair| get_key(*pair, src) == Some(name))?
+        .child_by_field_name("value")?
+        .utf8_text(src.as_bytes())
+        .ok()
+}
@@ -0,0 +1,269 @@
+use super::{PrepareRenameResult, Rename, RenameResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        fluent::span_to_range,
+        structs::fluent::FluentKeyKind,
+    },
+    references::yaml::locale_references,
+    utils::position_offset,
+};
+use fluent_syntax::ast::{
+    Entry, Expression, Identifier, InlineExpression, Pattern, PatternElement, Resource,
+};
+use ropey::Rope;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{self, Position, PrepareRenameResponse, TextEdit, Url, WorkspaceEdit};
+
+/// Renames a message or a term with its references in every Fluent file of the project,
+/// and a message with the `LocId` fields of the prototypes and the `Loc.GetString` calls.
+pub struct FluentRename {
+    context: Arc<Context>,
+    uri: Url,
+    position: Position,
+    /// The snapshot of the opened documents, the other files are read from it as edited.
+    opened: HashMap<Url, Rope>,
+    src: String,
+}
+
+impl Rename for FluentRename {
+    fn prepare_rename(&self) -> PrepareRenameResult {
+        let resource = parse(&self.src);
+        let (id, _) = self.find_identifier(&resource)?;
+
+        Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: to_lsp_range(&span_to_range(&self.src, &id.span)),
+            placeholder: id.name.to_owned(),
+        })
+    }
+
+    fn rename(&self, new_name: &str) -> RenameResult {
+        let resource = parse(&self.src);
+        let (id, kind) = self.find_identifier(&resource)?;
+        let name = id.name;
+
+        let mut changes = HashMap::from([(
+            self.uri.clone(),
+            identifier_edits(&self.src, &resource, name, kind, new_name),
+        )]);
+
+        // The keys may be referenced in any of the other files, the opened ones are read as edited.
+        let current = self.uri.to_file_path().ok();
+        let paths = block_in_place(|| self.context.locales.blocking_read())
+            .iter()
+            .map(|k| k.index().0.clone())
+            .filter(|path| Some(path) != current.as_ref())
+            .collect::<HashSet<PathBuf>>();
+
+        for path in paths {
+            let Some(src) = self.context.read_document(&self.opened, &path) else {
+                continue;
+            };
+            let edits = identifier_edits(&src, &parse(&src), name, kind, new_name);
+            if edits.is_empty() {
+                continue;
+            }
+            changes.insert(Url::from_file_path(&path).ok()?, edits);
+        }
+
+        // Only the messages are used by the prototypes and the code, the terms are private.
+        if kind == FluentKeyKind::Message {
+            for location in locale_references(&self.context, &self.opened, name) {
+                changes
+                    .entry(location.uri)
+                    .or_default()
+                    .push(TextEdit::new(location.range, new_name.to_owned()));
+            }
+
+            let usages = block_in_place(|| self.context.locale_usages.blocking_read());
+            for DefinitionIndex(path, range) in usages.get(name) {
+                let (Some(range), Ok(uri)) = (range, Url::from_file_path(path)) else {
+                    continue;
+                };
+                // The range of the usage is the whole string literal with its quotes.
+                let mut range = to_lsp_range(range);
+                range.start.character += 1;
+                range.end.character -= 1;
+                changes
+                    .entry(uri)
+                    .or_default()
+                    .push(TextEdit::new(range, new_name.to_owned()));
+            }
+        }
+
+        Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        })
+    }
+}
+
+impl FluentRename {
+    pub fn new(
+        context: Arc<Context>,
+        uri: Url,
+        position: Position,
+        rope: &Rope,
+        opened: HashMap<Url, Rope>,
+    ) -> Self {
+        Self {
+            context,
+            uri,
+            position,
+            opened,
+            src: rope.to_string(),
+        }
+    }
+
+    /// Finds the message or term under the cursor, either its definition or a reference.
+    fn find_identifier<'a, 's>(
+        &self,
+        resource: &'a Resource<&'s str>,
+    ) -> Option<(&'a Identifier<&'s str>, FluentKeyKind)> {
+        let offset = position_offset(&self.src, self.position)?;
+
+        identifiers(resource)
+            .into_iter()
+            .find(|(id, kind)| contains(id, *kind, offset))
+    }
+}
+
+fn parse(src: &str) -> Resource<&str> {
+    match fluent_syntax::parser::parse(src) {
+        Ok(resource) => resource,
+        // Broken entries are skipped, the rest of the file is still renamed.
+        Err((resource, _)) => resource,
+    }
+}
+
+fn identifier_edits(
+    src: &str,
+    resource: &Resource<&str>,
+    name: &str,
+    kind: FluentKeyKind,
+    new_name: &str,
+) -> Vec<TextEdit> {
+    identifiers(resource)
+        .into_iter()
+        .filter(|(id, k)| id.name == name && *k == kind)
+        .map(|(id, _)| {
+            let range = to_lsp_range(&span_to_range(src, &id.span));
+            TextEdit::new(range, new_name.to_owned())
+        })
+        .collect()
+}
+
+/// The identifiers of the definitions and the references of the messages and terms.
+fn identifiers<'a, 's>(
+    resource: &'a Resource<&'s str>,
+) -> Vec<(&'a Identifier<&'s str>, FluentKeyKind)> {
+    let mut identifiers = vec![];
+    for entry in &resource.body {
+        match entry {
+            Entry::Message(msg) => {
+                identifiers.push((&msg.id, FluentKeyKind::Message));
+                for pattern in msg
+                    .value
+                    .iter()
+                    .chain(msg.attributes.iter().map(|a| &a.value))
+                {
+                    collect_in_pattern(pattern, &mut identifiers);
+                }
+            }
+            Entry::Term(term) => {
+                identifiers.push((&term.id, FluentKeyKind::Term));
+                for pattern in
+                    std::iter::once(&term.value).chain(term.attributes.iter().map(|a| &a.value))
+                {
+                    collect_in_pattern(pattern, &mut identifiers);
+                }
+            }
+            _ => {}
+        }
+    }
+    identifiers
+}
+
+fn collect_in_pattern<'a, 's>(
+    pattern: &'a Pattern<&'s str>,
+    identifiers: &mut Vec<(&'a Identifier<&'s str>, FluentKeyKind)>,
+) {
+    for element in &pattern.elements {
+        if let PatternElement::Placeable { expression } = element {
+            collect_in_expression(expression, identifiers);
+        }
+    }
+}
+
+fn collect_in_expression<'a, 's>(
+    expression: &'a Expression<&'s str>,
+    identifiers: &mut Vec<(&'a Identifier<&'s str>, FluentKeyKind)>,
+) {
+    match expression {
+        Expression::Inline(inline, ..) => collect_in_inline(inline, identifiers),
+        Expression::Select { selector, variants } => {
+            collect_in_inline(selector, identifiers);
+            for variant in variants {
+                collect_in_pattern(&variant.value, identifiers);
+            }
+        }
+    }
+}
+
+fn collect_in_inline<'a, 's>(
+    inline: &'a InlineExpression<&'s str>,
+    identifiers: &mut Vec<(&'a Identifier<&'s str>, FluentKeyKind)>,
+) {
+    match inline {
+        InlineExpression::MessageReference { id, .. } => {
+            identifiers.push((id, FluentKeyKind::Message));
+        }
+        InlineExpression::TermReference { id, arguments, .. } => {
+            identifiers.push((id, FluentKeyKind::Term));
+            for arg in arguments
+                .iter()
+                .flat_map(|a| a.positional.iter().chain(a.named.iter().map(|n| &n.value)))
+            {
+                collect_in_inline(arg, identifiers);
+            }
+        }
+        InlineExpression::FunctionReference { arguments, .. } => {
+            for arg in arguments
+                .positional
+                .iter()
+                .chain(arguments.named.iter().map(|n| &n.value))
+            {
+                collect_in_inline(arg, identifiers);
+            }
+        }
+        InlineExpression::Placeable { expression } => {
+            collect_in_expression(expression, identifiers)
+        }
+        _ => {}
+    }
+}
+
+// The span of a term identifier doesn't include the leading `-`.
+fn contains(id: &Identifier<&str>, kind: FluentKeyKind, offset: usize) -> bool {
+    let start = match kind {
+        FluentKeyKind::Term => id.span.start.saturating_sub(1),
+        FluentKeyKind::Message => id.span.start,
+    };
+    (start..=id.span.end).contains(&offset)
+}
+
+fn to_lsp_range(range: &tree_sitter::Range) -> lsp_types::Range {
+    lsp_types::Range::new(
+        Position::new(
+            range.start_point.row as u32,
+            range.start_point.column as u32,
+        ),
+        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+    )
+}
@@ -0,0 +1,11 @@
+#[cfg(feature = "fluent")]
+pub mod fluent;
+pub mod yaml;
+
+pub type PrepareRenameResult = Option<tower_lsp::lsp_types::PrepareRenameResponse>;
+pub type RenameResult = Option<tower_lsp::lsp_types::WorkspaceEdit>;
+
+pub trait Rename {
+    fn prepare_rename(&self) -> PrepareRenameResult;
+    fn rename(&self, new_name: &str) -> RenameResult;
+}
@@ -0,0 +1,182 @@
+use super::{PrepareRenameResult, Rename, RenameResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::fluent::FluentKeyKind,
+    },
+    references::yaml::prototype_references,
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+};
+use ropey::Rope;
+use std::{collections::HashMap, sync::Arc};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{Position, PrepareRenameResponse, Range, TextEdit, Url, WorkspaceEdit};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+/// Locale keys of entities are named after their ids, e.g. `ent-Crowbar`.
+static ENTITY_LOCALE_PREFIX: &str = "ent-";
+
+pub struct YamlRename {
+    context: Arc<Context>,
+    uri: Url,
+    position: Position,
+    /// The snapshot of the opened documents, the prototype files are read from it as edited.
+    opened: HashMap<Url, Rope>,
+    src: String,
+    tree: Tree,
+}
+
+impl Rename for YamlRename {
+    fn prepare_rename(&self) -> PrepareRenameResult {
+        let (_, id_node) = self.find_id()?;
+        let id = id_node.utf8_text(self.src.as_bytes()).ok()?;
+
+        Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: node_range(id_node),
+            placeholder: id.to_owned(),
+        })
+    }
+
+    fn rename(&self, new_name: &str) -> RenameResult {
+        let (proto_name, id_node) = self.find_id()?;
+        let id = id_node.utf8_text(self.src.as_bytes()).ok()?;
+
+        let mut changes = HashMap::from([(
+            self.uri.clone(),
+            vec![TextEdit::new(node_range(id_node), new_name.to_owned())],
+        )]);
+
+        // The parents and the fields that name the prototype are renamed along.
+        for location in prototype_references(&self.context, &self.opened, proto_name, id) {
+            changes
+                .entry(location.uri)
+                .or_default()
+                .push(TextEdit::new(location.range, new_name.to_owned()));
+        }
+
+        if proto_name == "entity" {
+            let lock = block_in_place(|| self.context.locales.blocking_read());
+            let key = format!("{ENTITY_LOCALE_PREFIX}{id}");
+
+            // The key is renamed in every locale that defines it.
+            for locale in lock.get(&key, FluentKeyKind::Message) {
+                if let DefinitionIndex(path, Some(range)) = locale.index() {
+                    let uri = Url::from_file_path(path).ok()?;
+                    let range = Range::new(
+                        Position::new(
+                            range.start_point.row as u32,
+                            range.start_point.column as u32,
+                        ),
+                        Position::new(range.end_point.row as u32, range.end_point.column as u32),
+                    );
+
+                    changes.entry(uri).or_default().push(TextEdit::new(
+                        range,
+                        format!("{ENTITY_LOCALE_PREFIX}{new_name}"),
+                    ));
+                }
+            }
+        }
+
+        Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        })
+    }
+}
+
+impl YamlRename {
+    pub fn new(
+        context: Arc<Context>,
+        uri: Url,
+        position: Position,
+        rope: &Rope,
+        opened: HashMap<Url, Rope>,
+    ) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            uri,
+            position,
+            opened,
+            src,
+            tree,
+        }
+    }
+
+    /// Finds the value of the prototype `id` under the cursor and the prototype kind.
+    fn find_id(&self) -> Option<(&str, Node<'_>)> {
+        let point = Point::new(
+            self.position.line as usize,
+            self.position.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let found_node = root_node.named_descendant_for_point_range(point, point)?;
+
+        let mapping_pair_node = {
+            let mut node = found_node;
+            while node.kind() != "block_mapping_pair" {
+                node = node.parent()?;
+            }
+            node
+        };
+
+        let key = mapping_pair_node
+            .child_by_field_name("key")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+        let value_node = mapping_pair_node.child_by_field_name("value")?;
+        if key != "id" || !value_node.byte_range().contains(&found_node.start_byte()) {
+            return None;
+        }
+
+        let block_mapping = mapping_pair_node.parent()?;
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let SchemaType::Prototype(_) = resolver.resolve(block_mapping)? else {
+            return None;
+        };
+
+        let proto_name = self
+            .get_field(&block_mapping, "type")?
+            .child_by_field_name("value")?
+            .utf8_text(self.src.as_bytes())
+            .ok()?;
+
+        Some((proto_name, value_node))
+    }
+
+    fn get_field<'a>(&self, node: &Node<'a>, name: &str) -> Option<Node<'a>> {
+        for i in 0..node.named_child_count() {
+            let field_node = node.named_child(i)?;
+            let key = field_node
+                .child_by_field_name("key")?
+                .utf8_text(self.src.as_bytes())
+                .ok()?;
+
+            if key == name {
+                return Some(field_node);
+            }
+        }
+        None
+    }
+}
+
+fn node_range(node: Node) -> Range {
+    Range::new(
+        Position::new(
+            node.start_position().row as u32,
+            node.start_position().column as u32,
+        ),
+        Position::new(
+            node.end_position().row as u32,
+            node.end_position().column as u32,
+        ),
+    )
+}
@@ -0,0 +1,256 @@
+use super::{element_type, key_type, split_generic, value_type};
+use crate::{
+    backend::Context,
+    parse::structs::csharp::{
+        Component, CsharpClass, CsharpClassField, CsharpLiteral, Prototype, ReflectionManager,
+    },
+};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+use stringcase::camel_case;
+
+static SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+static INTEGER_TYPES: &[&str] = &[
+    "int", "uint", "long", "ulong", "short", "ushort", "byte", "sbyte",
+];
+static NUMBER_TYPES: &[&str] = &["float", "double", "decimal"];
+static STRING_TYPES: &[&str] = &["string", "LocId", "ResPath"];
+
+/// Generates a JSON Schema of the prototype files from the indexed C# classes and
+/// YAML prototypes, so that other tools, e.g. yaml-language-server, can validate them.
+pub struct JsonSchemaExporter {
+    context: Arc<Context>,
+    reflection: ReflectionManager,
+}
+
+impl JsonSchemaExporter {
+    pub fn new(context: Arc<Context>) -> Self {
+        Self {
+            reflection: ReflectionManager::new(&context),
+            context,
+        }
+    }
+
+    pub async fn export(&self) -> Value {
+        let classes = self
+            .context
+            .classes
+            .read()
+            .await
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut builder = SchemaBuilder {
+            ids: self.collect_ids().await,
+            prototype_names: HashMap::new(),
+            class_names: classes.iter().map(|c| c.name.clone()).collect(),
+            pending: Vec::new(),
+        };
+
+        let prototypes = classes
+            .iter()
+            .filter_map(|c| Prototype::try_from(c).ok())
+            .collect::<Vec<_>>();
+        let components = classes
+            .iter()
+            .filter_map(|c| Component::try_from(c).ok())
+            .collect::<Vec<_>>();
+
+        for proto in &prototypes {
+            let name = camel_case(&proto.get_prototype_name());
+            builder.prototype_names.insert(proto.name.clone(), name);
+        }
+
+        let mut definitions = BTreeMap::new();
+
+        let mut prototype_refs = Vec::with_capacity(prototypes.len());
+        for proto in &prototypes {
+            let name = builder.prototype_names[&proto.name].clone();
+            let mut schema = self.class_schema(proto, &mut builder).await;
+            schema["properties"]["type"] = json!({ "const": name });
+            schema["properties"]["id"] = json!({ "type": "string" });
+            schema["properties"]["parent"] = parent_schema(builder.ids_schema(&name));
+            schema["properties"]["abstract"] = json!({ "type": "boolean" });
+            schema["required"] = json!(["type", "id"]);
+
+            let key = format!("prototype.{name}");
+            prototype_refs.push(json!({ "$ref": format!("#/definitions/{key}") }));
+            definitions.insert(key, schema);
+        }
+
+        let mut component_refs = Vec::with_capacity(components.len());
+        for comp in &components {
+            let name = comp.get_component_name();
+            let mut schema = self.class_schema(comp, &mut builder).await;
+            schema["properties"]["type"] = json!({ "const": name });
+            schema["required"] = json!(["type"]);
+
+            let key = format!("component.{name}");
+            component_refs.push(json!({ "$ref": format!("#/definitions/{key}") }));
+            definitions.insert(key, schema);
+        }
+        definitions.insert(
+            "ComponentRegistry".to_owned(),
+            json!({ "type": "array", "items": { "anyOf": component_refs } }),
+        );
+
+        // Data definitions are added as they are met in the fields of other classes.
+        while let Some(name) = builder.pending.pop() {
+            if definitions.contains_key(&name) {
+                continue;
+            }
+
+            let schema = match self.reflection.get_class_by_name(&name).await {
+                Some(class) => self.class_schema(&class, &mut builder).await,
+                None => json!({}),
+            };
+            definitions.insert(name, schema);
+        }
+
+        json!({
+            "$schema": SCHEMA_DRAFT,
+            "title": "Robust prototypes",
+            "type": "array",
+            "items": { "anyOf": prototype_refs },
+            "definitions": definitions,
+        })
+    }
+
+    /// Groups the ids of the indexed prototypes by the prototype name, e.g. `entity`.
+    async fn collect_ids(&self) -> HashMap<String, Vec<String>> {
+        let lock = self.context.prototypes.read().await;
+
+        let mut ids = HashMap::<String, Vec<String>>::new();
+        for proto in lock.iter() {
+            ids.entry(proto.prototype.clone())
+                .or_default()
+                .push(proto.id.clone());
+        }
+        for list in ids.values_mut() {
+            list.sort();
+        }
+
+        ids
+    }
+
+    async fn class_schema(&self, class: &CsharpClass, builder: &mut SchemaBuilder) -> Value {
+        let fields = self.reflection.get_fields(class).await;
+
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for field in fields.iter().filter(|f| {
+            f.attributes.contains("DataField") || f.attributes.contains("IncludeDataField")
+        }) {
+            let name = field.get_data_field_name();
+            if field.is_required() {
+                required.push(name.clone());
+            }
+            properties.insert(name, builder.field_schema(field));
+        }
+
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            schema["required"] = json!(required);
+        }
+        schema
+    }
+}
+
+struct SchemaBuilder {
+    ids: HashMap<String, Vec<String>>,
+    /// Prototype class names mapped to the names used in YAML, e.g. `EntityPrototype` to `entity`.
+    prototype_names: HashMap<String, String>,
+    class_names: HashSet<String>,
+    /// Data definitions that are referenced but not generated yet.
+    pending: Vec<String>,
+}
+
+impl SchemaBuilder {
+    fn field_schema(&mut self, field: &CsharpClassField) -> Value {
+        let mut schema = self.type_schema(&field.type_name);
+        schema["description"] = json!(field.type_name);
+        if let Some(default) = field.default_literal().and_then(literal_to_json) {
+            schema["default"] = default;
+        }
+        schema
+    }
+
+    fn type_schema(&mut self, type_name: &str) -> Value {
+        let type_name = type_name.trim_end_matches('?');
+
+        if type_name == "bool" {
+            return json!({ "type": "boolean" });
+        }
+        if INTEGER_TYPES.contains(&type_name) {
+            return json!({ "type": "integer" });
+        }
+        if NUMBER_TYPES.contains(&type_name) {
+            return json!({ "type": "number" });
+        }
+        if STRING_TYPES.contains(&type_name) {
+            return json!({ "type": "string" });
+        }
+        if type_name == "EntProtoId" {
+            return self.ids_schema("entity");
+        }
+        if type_name == "ComponentRegistry" {
+            return json!({ "$ref": "#/definitions/ComponentRegistry" });
+        }
+        if let Some(element) = element_type(type_name) {
+            return json!({ "type": "array", "items": self.type_schema(element) });
+        }
+        if let Some(value) = value_type(type_name) {
+            let mut schema =
+                json!({ "type": "object", "additionalProperties": self.type_schema(value) });
+            // Only ids can be described as names, other keys are numbers or enums.
+            let keys = key_type(type_name).map(|key| self.type_schema(key));
+            if let Some(keys) = keys.filter(|k| k.get("enum").is_some()) {
+                schema["propertyNames"] = keys;
+            }
+            return schema;
+        }
+        if let Some((name, args)) = split_generic(type_name) {
+            if let ("ProtoId", [proto]) = (name, args.as_slice()) {
+                if let Some(name) = self.prototype_names.get(*proto).cloned() {
+                    return self.ids_schema(&name);
+                }
+            }
+            return json!({});
+        }
+
+        let name = type_name.rsplit('.').next().unwrap_or(type_name);
+        if self.class_names.contains(name) {
+            self.pending.push(name.to_owned());
+            return json!({ "$ref": format!("#/definitions/{name}") });
+        }
+
+        // Enums and types with custom serializers can't be described from the index.
+        json!({})
+    }
+
+    fn ids_schema(&self, prototype: &str) -> Value {
+        match self.ids.get(prototype) {
+            Some(ids) => json!({ "type": "string", "enum": ids }),
+            None => json!({ "type": "string" }),
+        }
+    }
+}
+
+fn parent_schema(id: Value) -> Value {
+    json!({ "anyOf": [id, { "type": "array", "items": id }] })
+}
+
+/// Converts a C# literal to JSON, the enum members have no JSON form.
+fn literal_to_json(literal: CsharpLiteral) -> Option<Value> {
+    match literal {
+        CsharpLiteral::String(string) => Some(json!(string)),
+        CsharpLiteral::Bool(bool) => Some(json!(bool)),
+        CsharpLiteral::Int(int) => Some(json!(int)),
+        CsharpLiteral::Float(float) => Some(json!(float)),
+        CsharpLiteral::EnumMember(_) => None,
+    }
+}
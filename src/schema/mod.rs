@@ -0,0 +1,114 @@
+pub mod json;
+pub mod yaml;
+
+use crate::parse::structs::csharp::{Component, CsharpClass, Prototype};
+
+static SEQUENCE_TYPES: &[&str] = &[
+    "List",
+    "IList",
+    "IReadOnlyList",
+    "HashSet",
+    "ISet",
+    "IReadOnlySet",
+    "SortedSet",
+    "ICollection",
+    "IReadOnlyCollection",
+    "IEnumerable",
+    "Queue",
+    "ValueList",
+];
+static DICTIONARY_TYPES: &[&str] = &[
+    "Dictionary",
+    "IDictionary",
+    "IReadOnlyDictionary",
+    "SortedDictionary",
+    "FrozenDictionary",
+];
+
+/// The type a YAML node is deserialized into.
+pub enum SchemaType {
+    /// The list of prototypes at the top of a file.
+    Prototypes,
+    /// A prototype, e.g. `- type: entity`.
+    Prototype(Prototype),
+    /// The list of components, e.g. the `components` of an entity.
+    ComponentRegistry,
+    /// An entry of a component list, e.g. `- type: Sprite`.
+    Component(Component),
+    /// Any other class with data fields.
+    DataDefinition(CsharpClass),
+    /// A sequence of values of the given type.
+    Sequence(String),
+    /// A mapping of keys of the first type to values of the second one.
+    Dictionary(String, String),
+    /// A scalar value, e.g. `bool`, `LocId` or `ProtoId<T>`.
+    Value(String),
+}
+
+impl SchemaType {
+    /// Returns the class behind the mapping, if the node is a mapping with fields.
+    pub fn class(&self) -> Option<&CsharpClass> {
+        match self {
+            SchemaType::Prototype(proto) => Some(proto),
+            SchemaType::Component(comp) => Some(comp),
+            SchemaType::DataDefinition(class) => Some(class),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a generic type into its name and arguments, e.g.
+/// `Dictionary<string, List<int>>` into `Dictionary` and `[string, List<int>]`.
+pub fn split_generic(type_name: &str) -> Option<(&str, Vec<&str>)> {
+    let start = type_name.find('<')?;
+    let end = type_name.rfind('>')?;
+    let name = &type_name[..start];
+    let inner = &type_name[start + 1..end];
+
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut arg_start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[arg_start..i].trim());
+                arg_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(inner[arg_start..].trim());
+
+    Some((name.rsplit('.').next().unwrap_or(name), args))
+}
+
+/// Returns the element type of a collection type, e.g. `T` for `List<T>` or `T[]`.
+pub fn element_type(type_name: &str) -> Option<&str> {
+    let type_name = type_name.trim_end_matches('?');
+    if let Some(element) = type_name.strip_suffix("[]") {
+        return Some(element);
+    }
+
+    match split_generic(type_name)? {
+        (name, args) if SEQUENCE_TYPES.contains(&name) && args.len() == 1 => Some(args[0]),
+        _ => None,
+    }
+}
+
+/// Returns the key type of a dictionary type, e.g. `K` for `Dictionary<K, V>`.
+pub fn key_type(type_name: &str) -> Option<&str> {
+    match split_generic(type_name.trim_end_matches('?'))? {
+        (name, args) if DICTIONARY_TYPES.contains(&name) && args.len() == 2 => Some(args[0]),
+        _ => None,
+    }
+}
+
+/// Returns the value type of a dictionary type, e.g. `V` for `Dictionary<K, V>`.
+pub fn value_type(type_name: &str) -> Option<&str> {
+    match split_generic(type_name.trim_end_matches('?'))? {
+        (name, args) if DICTIONARY_TYPES.contains(&name) && args.len() == 2 => Some(args[1]),
+        _ => None,
+    }
+}
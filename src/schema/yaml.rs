@@ -0,0 +1,409 @@
+use super::{element_type, key_type, split_generic, value_type, SchemaType};
+use crate::{
+    backend::Context,
+    parse::structs::csharp::{CsharpClass, CsharpClassField, ReflectionManager},
+    utils::block,
+};
+use std::{collections::HashMap, fmt, ops::Deref};
+use stringcase::camel_case;
+use tree_sitter::Node;
+
+/// Fields declared as `Dictionary<string, FixedPoint2>` and alike whose keys are reagent ids,
+/// by the name of the component or the prototype that owns them.
+static REAGENT_DICTIONARIES: &[(&str, &str)] =
+    &[("reaction", "reactants"), ("reaction", "products")];
+
+/// A step from a YAML value to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YamlPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// The location of a value in a YAML document, e.g. `[0].components[2].sprite`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YamlPath(Vec<YamlPathSegment>);
+
+impl YamlPath {
+    pub fn new(segments: Vec<YamlPathSegment>) -> Self {
+        Self(segments)
+    }
+
+    /// Builds the path from the document root to the value the node belongs to,
+    /// returns `None` for mapping keys since they aren't values.
+    pub fn from_node(node: Node, src: &str) -> Option<Self> {
+        let mut segments = vec![];
+        let mut value = find_value_node(node)?;
+
+        loop {
+            let parent = value.parent()?;
+            match parent.kind() {
+                "document" => break,
+                "block_mapping_pair" | "flow_pair" => {
+                    let key = parent.child_by_field_name("key")?;
+                    if key == value {
+                        return None;
+                    }
+
+                    let key = key.utf8_text(src.as_bytes()).ok()?;
+                    segments.push(YamlPathSegment::Key(key.to_owned()));
+                    value = find_value_node(parent.parent()?)?;
+                }
+                "block_sequence_item" => {
+                    let sequence = parent.parent()?;
+                    segments.push(YamlPathSegment::Index(item_index(sequence, parent)));
+                    value = find_value_node(sequence)?;
+                }
+                "flow_sequence" => {
+                    segments.push(YamlPathSegment::Index(item_index(parent, value)));
+                    value = find_value_node(parent)?;
+                }
+                _ => return None,
+            }
+        }
+
+        segments.reverse();
+        Some(Self(segments))
+    }
+}
+
+impl Deref for YamlPath {
+    type Target = [YamlPathSegment];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for YamlPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                YamlPathSegment::Key(key) if i == 0 => write!(f, "{key}")?,
+                YamlPathSegment::Key(key) => write!(f, ".{key}")?,
+                YamlPathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves YAML nodes to C# types by following the field types
+/// from the prototype at the top of the file down to the node.
+pub struct YamlSchemaResolver<'a> {
+    reflection: ReflectionManager,
+    /// See [`crate::config::PrototypesConfig::component_lists`].
+    component_lists: HashMap<String, Vec<String>>,
+    src: &'a str,
+}
+
+impl<'a> YamlSchemaResolver<'a> {
+    pub fn new(context: &Context, src: &'a str) -> Self {
+        Self {
+            reflection: ReflectionManager::new(context),
+            component_lists: block(|| context.config.read())
+                .prototypes
+                .component_lists
+                .clone(),
+            src,
+        }
+    }
+
+    /// Resolves the type of the value the node belongs to, e.g. a `block_mapping`
+    /// resolves to the class whose fields it contains.
+    pub fn resolve(&self, node: Node) -> Option<SchemaType> {
+        let path = YamlPath::from_node(node, self.src)?;
+        self.resolve_path(find_document(node)?, &path)
+    }
+
+    /// Resolves the type at the path by walking it down from the document root,
+    /// the `type` fields and tags met on the way pick the classes.
+    pub fn resolve_path(&self, document: Node, path: &YamlPath) -> Option<SchemaType> {
+        let mut node = find_value_child(document)?;
+        let mut schema = SchemaType::Prototypes;
+        // The kind of the prototype and the keys from it, for the configured component lists.
+        let mut kind: Option<String> = None;
+        let mut keys = vec![];
+
+        for segment in path.iter() {
+            let child = self.get_child(node, segment)?;
+            schema = match segment {
+                YamlPathSegment::Key(key) => {
+                    keys.push(key.as_str());
+                    match &kind {
+                        Some(kind) if self.is_component_list(kind, &keys.join(".")) => {
+                            SchemaType::ComponentRegistry
+                        }
+                        _ => self.resolve_field(schema, key, Some(child))?,
+                    }
+                }
+                YamlPathSegment::Index(_) => self.resolve_item(schema, child)?,
+            };
+            if let (None, SchemaType::Prototype(proto)) = (&kind, &schema) {
+                kind = Some(camel_case(&proto.get_prototype_name()));
+            }
+            node = child;
+        }
+
+        Some(schema)
+    }
+
+    /// Resolves the type of the sequence or mapping that holds the value
+    /// the node belongs to. Unlike [`Self::resolve`] it doesn't need the value
+    /// itself to be complete, e.g. an entry without a `type` yet.
+    pub fn resolve_container(&self, node: Node) -> Option<SchemaType> {
+        let value_node = find_value_node(node)?;
+        let parent = value_node.parent()?;
+
+        match parent.kind() {
+            "block_sequence_item" | "flow_sequence" | "block_mapping_pair" | "flow_pair" => {
+                self.resolve(parent.parent()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of the prototypes the keys of the dictionary are ids of,
+    /// e.g. `entity` for `Dictionary<EntProtoId, int>`.
+    pub fn resolve_key_prototype(&self, dictionary: Node, key_type: &str) -> Option<String> {
+        match key_type.trim_end_matches('?') {
+            "string" => {
+                let pair = find_value_node(dictionary)?.parent()?;
+                let key = self.get_key(pair)?;
+                let owner = match self.resolve(pair.parent()?)? {
+                    SchemaType::Component(comp) => comp.get_component_name(),
+                    SchemaType::Prototype(proto) => camel_case(&proto.get_prototype_name()),
+                    _ => return None,
+                };
+
+                REAGENT_DICTIONARIES
+                    .contains(&(owner.as_str(), key))
+                    .then(|| "reagent".to_owned())
+            }
+            key_type => self.resolve_id_prototype(key_type),
+        }
+    }
+
+    /// Returns the kind of the prototypes the values of the type are ids of,
+    /// e.g. `entity` for `EntProtoId` and `tag` for `ProtoId<TagPrototype>`.
+    pub fn resolve_id_prototype(&self, type_name: &str) -> Option<String> {
+        match type_name.trim_end_matches('?') {
+            "EntProtoId" => Some("entity".to_owned()),
+            "ReagentId" => Some("reagent".to_owned()),
+            type_name => {
+                let inner = type_name.strip_prefix("ProtoId<")?.strip_suffix('>')?;
+                let prototype = block(|| self.reflection.get_prototype_by_name(inner))?;
+                Some(camel_case(&prototype.get_prototype_name()))
+            }
+        }
+    }
+
+    /// Resolves the type of the value of a `block_mapping_pair`, the value may be missing.
+    pub fn resolve_pair(&self, pair: Node) -> Option<SchemaType> {
+        let key = self.get_key(pair)?;
+        let owner = self.resolve(pair.parent()?)?;
+
+        self.resolve_field(owner, key, pair.child_by_field_name("value"))
+    }
+
+    /// Finds the C# field declared for the key of a `block_mapping_pair`.
+    pub fn resolve_field_definition(&self, pair: Node) -> Option<CsharpClassField> {
+        let key = self.get_key(pair)?;
+        let owner = self.resolve(pair.parent()?)?;
+
+        self.find_field(owner.class()?, key)
+    }
+
+    /// Returns the C# type declared for the value the node belongs to, before a tag
+    /// picks the implementation, e.g. `IEffect` for `effect: !type:PlaySoundEffect`.
+    pub fn resolve_declared_type(&self, node: Node) -> Option<String> {
+        let path = YamlPath::from_node(node, self.src)?;
+        let (last, owner_path) = path.split_last()?;
+        let owner = self.resolve_path(find_document(node)?, &YamlPath::new(owner_path.to_vec()))?;
+
+        let type_name = match (owner, last) {
+            (SchemaType::Sequence(type_name), YamlPathSegment::Index(_)) => type_name,
+            (SchemaType::Dictionary(_, type_name), YamlPathSegment::Key(_)) => type_name,
+            (owner, YamlPathSegment::Key(key)) => {
+                self.find_field(owner.class()?, key)?.type_name.to_string()
+            }
+            _ => return None,
+        };
+        Some(type_name.trim_end_matches('?').to_owned())
+    }
+
+    /// Finds the class selected by a `!type:` tag.
+    pub fn resolve_tag(&self, tag: Node) -> Option<CsharpClass> {
+        let name = tag
+            .utf8_text(self.src.as_bytes())
+            .ok()?
+            .strip_prefix("!type:")?;
+        block(|| self.reflection.get_class_by_name(name))
+    }
+
+    fn is_component_list(&self, kind: &str, path: &str) -> bool {
+        self.component_lists
+            .get(kind)
+            .is_some_and(|paths| paths.iter().any(|p| p == path))
+    }
+
+    fn resolve_item(&self, owner: SchemaType, node: Node) -> Option<SchemaType> {
+        match owner {
+            SchemaType::Prototypes => {
+                let name = self.get_type(node)?;
+                block(|| self.reflection.get_prototype_by_name(name)).map(SchemaType::Prototype)
+            }
+            SchemaType::ComponentRegistry => {
+                let name = self.get_type(node)?;
+                block(|| self.reflection.get_component_by_name(name)).map(SchemaType::Component)
+            }
+            SchemaType::Sequence(type_name) => self.resolve_type(&type_name, Some(node)),
+            _ => None,
+        }
+    }
+
+    fn resolve_field(
+        &self,
+        owner: SchemaType,
+        key: &str,
+        value: Option<Node>,
+    ) -> Option<SchemaType> {
+        if let SchemaType::Dictionary(_, type_name) = owner {
+            return self.resolve_type(&type_name, value);
+        }
+
+        let field = self.find_field(owner.class()?, key)?;
+        self.resolve_type(&field.type_name, value)
+    }
+
+    fn resolve_type(&self, type_name: &str, node: Option<Node>) -> Option<SchemaType> {
+        // Abstract fields are specified with a tag, e.g. `!type:PlaySoundBehavior`.
+        if let Some(tag) = node.and_then(|n| self.get_tag(n)) {
+            if let Some(class) = block(|| self.reflection.get_class_by_name(tag)) {
+                return Some(SchemaType::DataDefinition(class));
+            }
+        }
+
+        let type_name = type_name.trim_end_matches('?');
+        if type_name == "ComponentRegistry" {
+            return Some(SchemaType::ComponentRegistry);
+        }
+        if let Some(element) = element_type(type_name) {
+            return Some(SchemaType::Sequence(element.to_owned()));
+        }
+        if let (Some(key), Some(value)) = (key_type(type_name), value_type(type_name)) {
+            return Some(SchemaType::Dictionary(key.to_owned(), value.to_owned()));
+        }
+        // The rest of generic types are ids and similar scalars, e.g. `ProtoId<T>`.
+        if split_generic(type_name).is_some() {
+            return Some(SchemaType::Value(type_name.to_owned()));
+        }
+
+        match block(|| self.reflection.get_class_by_name(type_name)) {
+            Some(class) => Some(SchemaType::DataDefinition(class)),
+            None => Some(SchemaType::Value(type_name.to_owned())),
+        }
+    }
+
+    fn find_field(&self, class: &CsharpClass, key: &str) -> Option<CsharpClassField> {
+        block(|| self.reflection.get_fields(class))
+            .into_iter()
+            .find(|f| f.get_data_field_name() == key)
+    }
+
+    fn get_child<'t>(&self, node: Node<'t>, segment: &YamlPathSegment) -> Option<Node<'t>> {
+        match segment {
+            YamlPathSegment::Key(key) => {
+                let mapping = find_child_node(node, "block_mapping")
+                    .or_else(|| find_child_node(node, "flow_mapping"))?;
+
+                let mut cursor = mapping.walk();
+                let pair = mapping
+                    .named_children(&mut cursor)
+                    .find(|pair| self.get_key(*pair) == Some(key.as_str()))?;
+                pair.child_by_field_name("value")
+            }
+            YamlPathSegment::Index(index) => {
+                let sequence = find_child_node(node, "block_sequence")
+                    .or_else(|| find_child_node(node, "flow_sequence"))?;
+
+                let mut cursor = sequence.walk();
+                let item = sequence
+                    .named_children(&mut cursor)
+                    .filter(|n| n.kind() != "comment")
+                    .nth(*index)?;
+                match item.kind() {
+                    "block_sequence_item" => find_value_child(item),
+                    _ => Some(item),
+                }
+            }
+        }
+    }
+
+    fn get_key(&self, pair: Node) -> Option<&'a str> {
+        pair.child_by_field_name("key")?
+            .utf8_text(self.src.as_bytes())
+            .ok()
+    }
+
+    fn get_type(&self, node: Node) -> Option<&'a str> {
+        let mapping = find_child_node(node, "block_mapping")?;
+
+        for i in 0..mapping.named_child_count() {
+            let pair = mapping.named_child(i)?;
+            if self.get_key(pair) == Some("type") {
+                return pair
+                    .child_by_field_name("value")?
+                    .utf8_text(self.src.as_bytes())
+                    .ok();
+            }
+        }
+        None
+    }
+
+    fn get_tag(&self, node: Node) -> Option<&'a str> {
+        let tag = find_child_node(node, "tag")?;
+        tag.utf8_text(self.src.as_bytes())
+            .ok()?
+            .strip_prefix("!type:")
+    }
+}
+
+fn find_document(node: Node) -> Option<Node> {
+    let mut node = node;
+    while node.kind() != "document" {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+fn find_value_node(node: Node) -> Option<Node> {
+    let mut node = node;
+    while !matches!(node.kind(), "block_node" | "flow_node") {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+fn find_value_child(node: Node) -> Option<Node> {
+    find_child_node(node, "block_node").or_else(|| find_child_node(node, "flow_node"))
+}
+
+fn item_index(sequence: Node, item: Node) -> usize {
+    let mut cursor = sequence.walk();
+    sequence
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() != "comment")
+        .take_while(|n| *n != item)
+        .count()
+}
+
+fn find_child_node<'a>(node: Node<'a>, name: &str) -> Option<Node<'a>> {
+    for i in 0..node.named_child_count() {
+        let child = node.named_child(i)?;
+        if child.kind() == name {
+            return Some(child);
+        }
+    }
+    None
+}
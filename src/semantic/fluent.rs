@@ -0,0 +1,172 @@
+use super::{encode, SemanticTokens, SemanticTokensResult, Token};
+use fluent_syntax::ast::{
+    Attribute, Entry, Expression, Identifier, InlineExpression, Pattern, PatternElement, Resource,
+    Span,
+};
+use ropey::Rope;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{Range, SemanticTokenModifier, SemanticTokenType};
+
+/// An opened Fluent file parsed once, the backend keeps it until the file changes.
+pub struct FluentDocument {
+    resource: Resource<String>,
+    /// The byte offsets the lines start at.
+    lines: Vec<usize>,
+}
+
+impl FluentDocument {
+    pub fn parse(rope: &Rope) -> Self {
+        let src = rope.to_string();
+        let lines = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        // Broken entries are skipped, the rest of the file is still highlighted.
+        let resource = fluent_syntax::parser::parse(src).unwrap_or_else(|(resource, _)| resource);
+
+        Self { resource, lines }
+    }
+
+    /// The token over the span, which never crosses a line in the ids and references.
+    fn token(&self, span: Span, token_type: &SemanticTokenType) -> Token {
+        let line = self.lines.partition_point(|start| *start <= span.start) - 1;
+        Token::new(
+            line as u32,
+            (span.start - self.lines[line]) as u32,
+            (span.end - span.start) as u32,
+            token_type,
+        )
+    }
+}
+
+/// Highlights the ids of the messages and terms, their attributes,
+/// and the references to the messages, terms, variables and functions.
+pub struct FluentSemanticTokens {
+    document: Arc<FluentDocument>,
+}
+
+impl SemanticTokens for FluentSemanticTokens {
+    fn semantic_tokens(&self, range: Option<Range>) -> SemanticTokensResult {
+        let mut tokens = vec![];
+        for entry in &self.document.resource.body {
+            let (id, value, attributes, prefixed) = match entry {
+                Entry::Message(msg) => (&msg.id, msg.value.as_ref(), &msg.attributes, false),
+                Entry::Term(term) => (&term.id, Some(&term.value), &term.attributes, true),
+                _ => continue,
+            };
+
+            tokens.push(
+                self.id_token(id, prefixed, &SemanticTokenType::VARIABLE)
+                    .with_modifier(&SemanticTokenModifier::DECLARATION),
+            );
+            if let Some(value) = value {
+                self.pattern_tokens(value, &mut tokens);
+            }
+            self.attribute_tokens(attributes, &mut tokens);
+        }
+
+        Some(tower_lsp::lsp_types::SemanticTokens {
+            result_id: None,
+            data: encode(tokens, range),
+        })
+    }
+}
+
+impl FluentSemanticTokens {
+    pub fn new(document: Arc<FluentDocument>) -> Self {
+        Self { document }
+    }
+
+    /// The spans of the identifiers don't include the leading `-` of the terms and `$` of the variables.
+    fn id_token(
+        &self,
+        id: &Identifier<String>,
+        prefixed: bool,
+        token_type: &SemanticTokenType,
+    ) -> Token {
+        let span = match prefixed {
+            true => Span {
+                start: id.span.start.saturating_sub(1),
+                end: id.span.end,
+            },
+            false => id.span,
+        };
+        self.document.token(span, token_type)
+    }
+
+    fn attribute_tokens(&self, attributes: &[Attribute<String>], tokens: &mut Vec<Token>) {
+        for attribute in attributes {
+            tokens.push(
+                self.id_token(&attribute.id, false, &SemanticTokenType::PROPERTY)
+                    .with_modifier(&SemanticTokenModifier::DECLARATION),
+            );
+            self.pattern_tokens(&attribute.value, tokens);
+        }
+    }
+
+    fn pattern_tokens(&self, pattern: &Pattern<String>, tokens: &mut Vec<Token>) {
+        for element in &pattern.elements {
+            if let PatternElement::Placeable { expression } = element {
+                self.expression_tokens(expression, tokens);
+            }
+        }
+    }
+
+    fn expression_tokens(&self, expression: &Expression<String>, tokens: &mut Vec<Token>) {
+        match expression {
+            Expression::Inline(inline, ..) => self.inline_tokens(inline, tokens),
+            Expression::Select { selector, variants } => {
+                self.inline_tokens(selector, tokens);
+                for variant in variants {
+                    self.pattern_tokens(&variant.value, tokens);
+                }
+            }
+        }
+    }
+
+    fn inline_tokens(&self, inline: &InlineExpression<String>, tokens: &mut Vec<Token>) {
+        match inline {
+            InlineExpression::MessageReference { id, attribute } => {
+                tokens.push(self.id_token(id, false, &SemanticTokenType::VARIABLE));
+                tokens.extend(
+                    attribute
+                        .iter()
+                        .map(|a| self.id_token(a, false, &SemanticTokenType::PROPERTY)),
+                );
+            }
+            InlineExpression::TermReference {
+                id,
+                attribute,
+                arguments,
+            } => {
+                tokens.push(self.id_token(id, true, &SemanticTokenType::VARIABLE));
+                tokens.extend(
+                    attribute
+                        .iter()
+                        .map(|a| self.id_token(a, false, &SemanticTokenType::PROPERTY)),
+                );
+                for argument in arguments.iter().flat_map(|a| &a.positional) {
+                    self.inline_tokens(argument, tokens);
+                }
+                for argument in arguments.iter().flat_map(|a| &a.named) {
+                    self.inline_tokens(&argument.value, tokens);
+                }
+            }
+            InlineExpression::FunctionReference { id, arguments } => {
+                tokens.push(self.id_token(id, false, &SemanticTokenType::FUNCTION));
+                for argument in &arguments.positional {
+                    self.inline_tokens(argument, tokens);
+                }
+                for argument in &arguments.named {
+                    self.inline_tokens(&argument.value, tokens);
+                }
+            }
+            InlineExpression::VariableReference { id } => {
+                tokens.push(self.id_token(id, true, &SemanticTokenType::PARAMETER));
+            }
+            InlineExpression::Placeable { expression } => {
+                self.expression_tokens(expression, tokens)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(feature = "fluent")]
+pub mod fluent;
+pub mod yaml;
+
+use tower_lsp::lsp_types::{
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend,
+};
+
+pub type SemanticTokensResult = Option<tower_lsp::lsp_types::SemanticTokens>;
+
+pub trait SemanticTokens {
+    /// Returns the tokens of the whole file or only of the lines of the range.
+    fn semantic_tokens(&self, range: Option<Range>) -> SemanticTokensResult;
+}
+
+/// The token types in the order of their indices in the legend.
+pub static TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::TYPE,
+    SemanticTokenType::CLASS,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::PARAMETER,
+];
+
+/// Marks the components that aren't declared in C#, so that themes can underline them
+/// along with the diagnostic.
+pub const UNRESOLVED: SemanticTokenModifier = SemanticTokenModifier::new("unresolved");
+
+/// The token modifiers in the order of their bits in the legend.
+pub static TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DEPRECATED,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+    SemanticTokenModifier::READONLY,
+    UNRESOLVED,
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// A token with an absolute position, before it is encoded relative to the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Token {
+    pub line: u32,
+    pub start: u32,
+    pub length: u32,
+    pub token_type: u32,
+    pub modifiers: u32,
+}
+
+impl Token {
+    pub fn new(line: u32, start: u32, length: u32, token_type: &SemanticTokenType) -> Self {
+        Self {
+            line,
+            start,
+            length,
+            token_type: TOKEN_TYPES
+                .iter()
+                .position(|t| t == token_type)
+                .unwrap_or_default() as u32,
+            modifiers: 0,
+        }
+    }
+
+    pub fn with_modifier(mut self, modifier: &SemanticTokenModifier) -> Self {
+        if let Some(bit) = TOKEN_MODIFIERS.iter().position(|m| m == modifier) {
+            self.modifiers |= 1 << bit;
+        }
+        self
+    }
+
+    pub fn with_modifier_if(self, condition: bool, modifier: &SemanticTokenModifier) -> Self {
+        match condition {
+            true => self.with_modifier(modifier),
+            false => self,
+        }
+    }
+}
+
+/// Sorts the tokens, drops the overlapping ones and the ones outside of the lines
+/// of the range, and encodes the rest relative to each other as the protocol requires.
+pub fn encode(mut tokens: Vec<Token>, range: Option<Range>) -> Vec<SemanticToken> {
+    if let Some(range) = range {
+        tokens.retain(|t| (range.start.line..=range.end.line).contains(&t.line));
+    }
+    tokens.sort();
+
+    let mut encoded = Vec::with_capacity(tokens.len());
+    let (mut line, mut start, mut end) = (0, 0, 0);
+    for token in tokens {
+        if token.line == line && token.start < end && !encoded.is_empty() {
+            continue;
+        }
+
+        let delta_line = token.line - line;
+        let delta_start = if delta_line == 0 {
+            token.start - start
+        } else {
+            token.start
+        };
+        encoded.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: token.length,
+            token_type: token.token_type,
+            token_modifiers_bitset: token.modifiers,
+        });
+
+        line = token.line;
+        start = token.start;
+        end = token.start + token.length;
+    }
+
+    encoded
+}
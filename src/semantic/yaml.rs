@@ -0,0 +1,239 @@
+use super::{encode, SemanticTokens, SemanticTokensResult, Token, UNRESOLVED};
+use crate::{
+    backend::Context,
+    parse::common::Index,
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+};
+use ropey::Rope;
+use std::{path::Component, sync::Arc};
+use tokio::task::block_in_place;
+use tower_lsp::lsp_types::{Range, SemanticTokenModifier, SemanticTokenType};
+use tree_sitter::{Node, Parser, Tree};
+
+/// Highlights what the YAML grammar alone can't tell apart: prototype kinds, components,
+/// `!type:` tags, the keys that are C# fields and the prototype ids. The modifiers tell
+/// the obsolete, read-only and engine declarations, and the components that aren't declared.
+pub struct YamlSemanticTokens {
+    context: Arc<Context>,
+    src: String,
+    tree: Tree,
+}
+
+impl SemanticTokens for YamlSemanticTokens {
+    fn semantic_tokens(&self, range: Option<Range>) -> SemanticTokensResult {
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+
+        let mut tokens = vec![];
+        self.collect_tokens(&resolver, self.tree.root_node(), range, &mut tokens);
+
+        Some(tower_lsp::lsp_types::SemanticTokens {
+            result_id: None,
+            data: encode(tokens, range),
+        })
+    }
+}
+
+impl YamlSemanticTokens {
+    pub fn new(context: Arc<Context>, rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self { context, src, tree }
+    }
+
+    /// Walks only the nodes on the lines of the range, so that large files
+    /// can be highlighted by the visible part.
+    fn collect_tokens(
+        &self,
+        resolver: &YamlSchemaResolver,
+        node: Node,
+        range: Option<Range>,
+        tokens: &mut Vec<Token>,
+    ) {
+        if let Some(range) = range {
+            if (node.end_position().row as u32) < range.start.line
+                || (node.start_position().row as u32) > range.end.line
+            {
+                return;
+            }
+        }
+
+        match node.kind() {
+            "tag" if resolver.resolve_tag(node).is_some() => {
+                tokens.extend(token(node, &SemanticTokenType::TYPE));
+            }
+            "block_mapping_pair" | "flow_pair" => self.pair_tokens(resolver, node, tokens),
+            _ => {}
+        }
+
+        for i in 0..node.named_child_count() {
+            if let Some(child) = node.named_child(i) {
+                self.collect_tokens(resolver, child, range, tokens);
+            }
+        }
+    }
+
+    fn pair_tokens(&self, resolver: &YamlSchemaResolver, pair: Node, tokens: &mut Vec<Token>) {
+        let Some(key_node) = pair.child_by_field_name("key") else {
+            return;
+        };
+        let Ok(key) = key_node.utf8_text(self.src.as_bytes()) else {
+            return;
+        };
+        let Some(owner) = pair.parent().and_then(|p| resolver.resolve(p)) else {
+            if key == "type" {
+                self.unresolved_component_tokens(resolver, pair, tokens);
+            }
+            return;
+        };
+        let value_node = pair.child_by_field_name("value");
+
+        if let SchemaType::Dictionary(key_type, _) = &owner {
+            if is_prototype_id(key_type) {
+                tokens.extend(token(key_node, &SemanticTokenType::VARIABLE));
+            }
+            return;
+        }
+
+        if let Some(field) = resolver.resolve_field_definition(pair) {
+            tokens.extend(token(key_node, &SemanticTokenType::PROPERTY).map(|t| {
+                t.with_modifier_if(
+                    field.attributes.get_obsolete().is_some(),
+                    &SemanticTokenModifier::DEPRECATED,
+                )
+                .with_modifier_if(field.is_read_only(), &SemanticTokenModifier::READONLY)
+                .with_modifier_if(is_engine(&field), &SemanticTokenModifier::DEFAULT_LIBRARY)
+            }));
+        }
+
+        let Some(value_node) = value_node else {
+            return;
+        };
+
+        match (&owner, key) {
+            (SchemaType::Prototype(proto), "type") => {
+                tokens.extend(
+                    scalar_tokens(value_node, &SemanticTokenType::TYPE).map(|t| {
+                        t.with_modifier_if(
+                            is_engine(&**proto),
+                            &SemanticTokenModifier::DEFAULT_LIBRARY,
+                        )
+                    }),
+                );
+            }
+            (SchemaType::Component(comp), "type") => {
+                tokens.extend(
+                    scalar_tokens(value_node, &SemanticTokenType::CLASS).map(|t| {
+                        t.with_modifier_if(
+                            comp.attributes.get_obsolete().is_some(),
+                            &SemanticTokenModifier::DEPRECATED,
+                        )
+                        .with_modifier_if(
+                            is_engine(&**comp),
+                            &SemanticTokenModifier::DEFAULT_LIBRARY,
+                        )
+                    }),
+                );
+            }
+            (SchemaType::Prototype(_), "id") => {
+                tokens.extend(
+                    scalar_tokens(value_node, &SemanticTokenType::VARIABLE)
+                        .map(|t| t.with_modifier(&SemanticTokenModifier::DECLARATION)),
+                );
+            }
+            (SchemaType::Prototype(_), "parent") => {
+                tokens.extend(scalar_tokens(value_node, &SemanticTokenType::VARIABLE));
+            }
+            _ => match resolver.resolve_pair(pair) {
+                Some(SchemaType::Value(type_name) | SchemaType::Sequence(type_name))
+                    if is_prototype_id(&type_name) =>
+                {
+                    tokens.extend(scalar_tokens(value_node, &SemanticTokenType::VARIABLE));
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Highlights the `type` of a component entry that names no component,
+    /// unless the C# code isn't indexed and no component is known.
+    fn unresolved_component_tokens(
+        &self,
+        resolver: &YamlSchemaResolver,
+        pair: Node,
+        tokens: &mut Vec<Token>,
+    ) {
+        let Some(value_node) = pair.child_by_field_name("value") else {
+            return;
+        };
+        if !matches!(
+            resolver.resolve_container(pair),
+            Some(SchemaType::ComponentRegistry)
+        ) || block_in_place(|| self.context.classes.blocking_read()).is_empty()
+        {
+            return;
+        }
+
+        tokens.extend(
+            scalar_tokens(value_node, &SemanticTokenType::CLASS)
+                .map(|t| t.with_modifier(&UNRESOLVED)),
+        );
+    }
+}
+
+/// Whether the declaration is in the engine rather than in the game, e.g. `Sprite`.
+fn is_engine(declaration: &impl Index) -> bool {
+    declaration
+        .index()
+        .0
+        .components()
+        .any(|c| c == Component::Normal("RobustToolbox".as_ref()))
+}
+
+/// Whether values of the type are prototype ids, e.g. `EntProtoId` or `ProtoId<TagPrototype>`.
+fn is_prototype_id(type_name: &str) -> bool {
+    let type_name = type_name.trim_end_matches('?');
+    type_name == "EntProtoId" || type_name.starts_with("ProtoId<")
+}
+
+/// Tokens over the scalars of a value, either the value itself or the items of a sequence.
+fn scalar_tokens(node: Node, token_type: &SemanticTokenType) -> impl Iterator<Item = Token> {
+    let mut found = vec![];
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "plain_scalar" | "double_quote_scalar" | "single_quote_scalar" => {
+                found.extend(token(node, token_type))
+            }
+            "flow_node"
+            | "block_node"
+            | "flow_sequence"
+            | "block_sequence"
+            | "block_sequence_item" => {
+                for i in (0..node.named_child_count()).rev() {
+                    stack.extend(node.named_child(i));
+                }
+            }
+            _ => {}
+        }
+    }
+    found.into_iter()
+}
+
+/// A token over the node, multiline nodes aren't highlighted.
+fn token(node: Node, token_type: &SemanticTokenType) -> Option<Token> {
+    let (start, end) = (node.start_position(), node.end_position());
+    if start.row != end.row {
+        return None;
+    }
+
+    Some(Token::new(
+        start.row as u32,
+        start.column as u32,
+        (end.column - start.column) as u32,
+        token_type,
+    ))
+}
@@ -0,0 +1,59 @@
+use crate::backend::Context;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tower_lsp::lsp_types::notification::Notification;
+
+pub const STATUS_METHOD: &str = "robust-lsp/status";
+
+/// The status sent periodically and once the project is indexed, e.g. for a status bar item.
+pub enum StatusNotification {}
+
+impl Notification for StatusNotification {
+    type Params = ServerStatus;
+    const METHOD: &'static str = "$/robustLsp/status";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub version: String,
+    /// Whether the project has been indexed, it is still parsed or its root isn't set otherwise.
+    pub indexed: bool,
+    pub classes: usize,
+    pub prototypes: usize,
+    pub locale_keys: usize,
+    /// The changed documents that wait to be parsed again.
+    pub pending_reparses: usize,
+    pub last_error: Option<String>,
+}
+
+impl ServerStatus {
+    pub async fn new(context: &Context, indexed: bool, pending_reparses: usize) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            indexed,
+            classes: context.classes.read().await.len(),
+            prototypes: context.prototypes.read().await.len(),
+            locale_keys: context.locales.read().await.len(),
+            pending_reparses,
+            last_error: context.last_error.get(),
+        }
+    }
+}
+
+/// The last failure of a provider, kept for the status until another one replaces it.
+#[derive(Debug, Clone, Default)]
+pub struct LastError(Arc<Mutex<Option<String>>>);
+
+impl LastError {
+    /// Records the failure to be reported in the next status.
+    pub fn record(&self, error: String) {
+        if let Ok(mut lock) = self.0.lock() {
+            *lock = Some(error);
+        }
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().ok()?.clone()
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// The timings of the requests since the start of the server, by the request,
+/// reported by the `robust-lsp.indexStats` command.
+#[derive(Debug, Clone, Default)]
+pub struct Timings(Arc<Mutex<BTreeMap<&'static str, RequestTimings>>>);
+
+impl Timings {
+    pub fn summary(&self) -> BTreeMap<&'static str, RequestTimings> {
+        self.0.lock().map(|t| t.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTimings {
+    pub count: u64,
+    pub total_ms: f64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+    /// The items returned by all the requests, e.g. completion items or locations.
+    pub results: u64,
+}
+
+/// Measures a request from its start to [`RequestTimer::finish`].
+pub struct RequestTimer {
+    timings: Timings,
+    request: &'static str,
+    start: Instant,
+}
+
+impl RequestTimer {
+    pub fn start(timings: &Timings, request: &'static str) -> Self {
+        Self {
+            timings: timings.clone(),
+            request,
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the elapsed time and the number of results in the span of the request,
+    /// its `elapsed_ms` and `results` fields, and adds them to the summary.
+    pub fn finish(self, results: usize) {
+        let elapsed_ms = self.start.elapsed().as_secs_f64() * 1000.0;
+
+        let span = tracing::Span::current();
+        span.record("elapsed_ms", elapsed_ms);
+        span.record("results", results);
+        tracing::debug!(
+            "{} took {elapsed_ms:.2}ms with {results} results.",
+            self.request
+        );
+
+        let Ok(mut timings) = self.timings.0.lock() else {
+            return;
+        };
+        let timings = timings.entry(self.request).or_default();
+        timings.count += 1;
+        timings.total_ms += elapsed_ms;
+        timings.average_ms = timings.total_ms / timings.count as f64;
+        timings.max_ms = timings.max_ms.max(elapsed_ms);
+        timings.results += results as u64;
+    }
+}
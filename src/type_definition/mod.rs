@@ -0,0 +1,7 @@
+pub mod yaml;
+
+pub type TypeDefinitionResult = Option<tower_lsp::lsp_types::request::GotoTypeDefinitionResponse>;
+
+pub trait TypeDefinition {
+    fn type_definition(&self) -> TypeDefinitionResult;
+}
@@ -0,0 +1,122 @@
+use super::{TypeDefinition, TypeDefinitionResult};
+use crate::{
+    backend::Context,
+    parse::{
+        common::{DefinitionIndex, Index},
+        structs::csharp::{CsharpClass, ReflectionManager},
+    },
+    schema::{yaml::YamlSchemaResolver, SchemaType},
+    utils::block,
+};
+use ropey::Rope;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{self, GotoDefinitionResponse, Location, Position, Url};
+use tree_sitter::{Node, Parser, Point, Tree};
+
+/// Goes from a field to the C# classes of its type, e.g. the data definition
+/// of the items of a list, while goto definition goes to the field itself.
+pub struct YamlTypeDefinition {
+    context: Arc<Context>,
+    position: Position,
+    src: String,
+    tree: Tree,
+}
+
+impl TypeDefinition for YamlTypeDefinition {
+    fn type_definition(&self) -> TypeDefinitionResult {
+        let point = Point::new(
+            self.position.line as usize,
+            self.position.character as usize,
+        );
+
+        let root_node = self.tree.root_node();
+        let found_node = root_node.named_descendant_for_point_range(point, point)?;
+        let mapping_pair_node = find_mapping_pair_node(found_node)?;
+
+        let resolver = YamlSchemaResolver::new(&self.context, &self.src);
+        let classes = match resolver.resolve_pair(mapping_pair_node)? {
+            SchemaType::Component(comp) => vec![CsharpClass::clone(&comp)],
+            SchemaType::Prototype(proto) => vec![CsharpClass::clone(&proto)],
+            SchemaType::DataDefinition(class) => vec![class],
+            SchemaType::Sequence(type_name) | SchemaType::Value(type_name) => {
+                self.get_classes(&type_name)
+            }
+            SchemaType::Dictionary(key_type, value_type) => {
+                let mut classes = self.get_classes(&key_type);
+                classes.extend(self.get_classes(&value_type));
+                classes
+            }
+            SchemaType::Prototypes | SchemaType::ComponentRegistry => return None,
+        };
+
+        let locations = classes
+            .iter()
+            .filter_map(|class| index_to_location(class.index()))
+            .collect::<Vec<_>>();
+
+        match locations.len() {
+            0 => None,
+            1 => Some(GotoDefinitionResponse::Scalar(locations[0].clone())),
+            _ => Some(GotoDefinitionResponse::Array(locations)),
+        }
+    }
+}
+
+impl YamlTypeDefinition {
+    pub fn new(context: Arc<Context>, position: Position, rope: &Rope) -> Self {
+        let src = rope.to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_yaml::language()).unwrap();
+        let tree = parser.parse(&src, None).unwrap();
+
+        Self {
+            context,
+            position,
+            src,
+            tree,
+        }
+    }
+
+    /// Finds the indexed classes mentioned in a type, e.g. `TagPrototype`
+    /// in `List<ProtoId<TagPrototype>>`, the ones that aren't indexed are skipped.
+    fn get_classes(&self, type_name: &str) -> Vec<CsharpClass> {
+        let reflection = ReflectionManager::new(&self.context);
+
+        let mut classes: Vec<CsharpClass> = vec![];
+        for ident in type_name.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            if ident.is_empty() || classes.iter().any(|c| c.name == ident) {
+                continue;
+            }
+            if let Some(class) = block(|| reflection.get_class_by_name(ident)) {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+}
+
+fn find_mapping_pair_node(node: Node) -> Option<Node> {
+    let mut node = node;
+    while node.kind() != "block_mapping_pair" {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+fn index_to_location(index: &DefinitionIndex) -> Option<Location> {
+    let DefinitionIndex(path, Some(range)) = index else {
+        return None;
+    };
+
+    Some(Location {
+        uri: Url::from_file_path(path).ok()?,
+        range: lsp_types::Range::new(
+            Position::new(
+                range.start_point.row as u32,
+                range.start_point.column as u32,
+            ),
+            Position::new(range.end_point.row as u32, range.end_point.column as u32),
+        ),
+    })
+}
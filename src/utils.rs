@@ -1,25 +1,48 @@
-use std::{future::Future, sync::Arc};
+use crate::status::LastError;
+use std::{
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tower_lsp::{
     lsp_types::{
         notification::Progress, request::WorkDoneProgressCreate, InitializeParams, NumberOrString,
-        Position, ProgressParams, ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin,
-        WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
+        Position, ProgressParams, ProgressParamsValue, Url, WorkDoneProgress,
+        WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
+        WorkDoneProgressReport,
     },
     Client,
 };
 use tracing::instrument;
 
+/// The files found in the root of a Space Station 14 project, or of a fork of it.
+/// The solutions found at the root of a game project, or of the engine repository itself.
+pub const PROJECT_MARKERS: &[&str] = &[
+    "SpaceStation14.sln",
+    "RobustToolbox/RobustToolbox.sln",
+    "RobustToolbox.sln",
+];
+
 pub fn check_project_compliance(params: &InitializeParams) -> bool {
     if let Some(root_uri) = params.root_uri.as_ref() {
-        let root_path = root_uri.to_file_path().unwrap();
+        let Ok(root_path) = root_uri.to_file_path() else {
+            return false;
+        };
 
-        return root_path.join("SpaceStation14.sln").exists()
-            || root_path.join("RobustToolbox/RobustToolbox.sln").exists();
+        return is_project_root(&root_path);
     }
 
     false
 }
 
+pub fn is_project_root(path: &Path) -> bool {
+    PROJECT_MARKERS
+        .iter()
+        .any(|marker| path.join(marker).exists())
+}
+
 #[derive(Default, Debug)]
 pub struct ProgressStatusInit {
     pub id: String,
@@ -44,12 +67,14 @@ impl ProgressStatus {
 
         tracing::trace!("Created new progress status");
 
-        client
+        if let Err(err) = client
             .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
                 token: NumberOrString::String(id.to_owned()),
             })
             .await
-            .unwrap();
+        {
+            tracing::warn!("Failed to create progress status: {}", err);
+        }
 
         Self {
             id: id.to_owned(),
@@ -138,6 +163,113 @@ where
     })
 }
 
+/// Runs a provider and swallows its panic, so that one bad file can't take
+/// down the whole session. The panic itself is logged by the panic hook.
+pub fn recover<T: Default>(provider: &str, last_error: &LastError, func: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(func)).unwrap_or_else(|_| {
+        tracing::error!("{} provider failed, returning an empty result.", provider);
+        last_error.record(format!("{provider} provider failed."));
+        T::default()
+    })
+}
+
+/// Runs a provider on the blocking pool like [`recover`], and gives up on it once the timeout
+/// is over, so that a pathological file can't make the editor hang. The provider still runs
+/// to the end in the background, but its result is dropped. The failures are recorded
+/// in the last error of the server.
+pub async fn recover_in_time<T: Default + Send + 'static>(
+    provider: &'static str,
+    last_error: &LastError,
+    timeout: Option<Duration>,
+    uri: &Url,
+    position: Option<Position>,
+    func: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    let errors = last_error.clone();
+    let task = tokio::task::spawn_blocking(move || recover(provider, &errors, func));
+    let Some(timeout) = timeout else {
+        return task.await.unwrap_or_default();
+    };
+
+    match tokio::time::timeout(timeout, task).await {
+        Ok(result) => result.unwrap_or_default(),
+        Err(_) => {
+            let at = match position {
+                Some(p) => format!("{uri}:{}:{}", p.line + 1, p.character + 1),
+                None => uri.to_string(),
+            };
+            tracing::warn!(
+                "{provider} provider didn't finish in {timeout:?} on {at}, returning an empty result."
+            );
+            last_error.record(format!(
+                "{provider} provider didn't finish in {timeout:?} on {at}."
+            ));
+            T::default()
+        }
+    }
+}
+
+/// Separators of the resource paths written in YAML, they are `/` by convention,
+/// but files edited on Windows may contain `\` as well.
+pub const RESOURCE_PATH_SEPARATORS: [char; 2] = ['/', '\\'];
+
+/// Joins a resource path, e.g. `Objects/Tools/crowbar.rsi`, to the folder it is relative to.
+/// The path is split on both separators and its segments are joined one by one,
+/// so that the result uses the separators of the platform and never escapes the folder:
+/// `..` goes up no further than the folder itself.
+pub fn resource_path(folder: &Path, path: &str) -> PathBuf {
+    let mut segments = vec![];
+    for segment in path
+        .split(RESOURCE_PATH_SEPARATORS)
+        .filter(|s| !s.is_empty() && *s != ".")
+    {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments
+        .into_iter()
+        .fold(folder.to_path_buf(), |path, segment| path.join(segment))
+}
+
+/// The path of the document, untitled and remote documents get the path of their uri,
+/// e.g. `untitled:/new.yml` is served as a YAML file.
+pub fn document_path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.path()))
+}
+
+/// The text of the line before the position. The providers count the columns in bytes,
+/// see `encoding`, a column inside a character is moved back to its start.
+pub fn line_before(src: &str, position: Position) -> Option<&str> {
+    let line = src.lines().nth(position.line as usize)?;
+    Some(&line[..char_boundary(line, position.character as usize)])
+}
+
+/// The byte offset of the position within the text, see [`line_before`].
+pub fn position_offset(src: &str, position: Position) -> Option<usize> {
+    let mut lines = src.split_inclusive('\n');
+    let line_offset = lines
+        .by_ref()
+        .take(position.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+    let line = lines.next()?;
+
+    Some(line_offset + char_boundary(line, position.character as usize))
+}
+
+fn char_boundary(line: &str, column: usize) -> usize {
+    let mut column = column.min(line.len());
+    while !line.is_char_boundary(column) {
+        column -= 1;
+    }
+    column
+}
+
 // Calculate the position for the correct node search.
 // P.S. Why on tree-sitter playground everything works correctly (in javascript)
 // even without dancing with tambourine - idk.
@@ -0,0 +1,190 @@
+//! The files the providers read besides the opened documents, e.g. RSIs and audio.
+//! They are read through [`FileSystem`] so that they can be served from memory.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Cursor, Read, Seek},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+pub trait FileSystem: Send + Sync {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>>;
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    /// The paths of the entries of the folder, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut contents = vec![];
+        self.open(path)?.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| !m.is_dir)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|m| m.is_dir)
+    }
+}
+
+/// The file system of a context, the real one by default.
+#[derive(Clone)]
+pub struct SharedFileSystem(Arc<dyn FileSystem>);
+
+impl SharedFileSystem {
+    pub fn new(fs: impl FileSystem + 'static) -> Self {
+        Self(Arc::new(fs))
+    }
+}
+
+impl Default for SharedFileSystem {
+    fn default() -> Self {
+        Self::new(RealFileSystem)
+    }
+}
+
+impl Deref for SharedFileSystem {
+    type Target = dyn FileSystem;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect()
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+/// Files kept in memory, the folders are the ones that contain any of the files.
+#[derive(Debug, Default)]
+pub struct InMemoryFileSystem {
+    files: RwLock<HashMap<PathBuf, InMemoryFile>>,
+}
+
+#[derive(Debug)]
+struct InMemoryFile {
+    modified: SystemTime,
+    contents: Arc<[u8]>,
+}
+
+impl InMemoryFileSystem {
+    pub fn insert(&self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        let file = InMemoryFile {
+            modified: SystemTime::now(),
+            contents: contents.into().into(),
+        };
+        self.files.write().unwrap().insert(path.into(), file);
+    }
+
+    pub fn remove(&self, path: &Path) {
+        self.files.write().unwrap().remove(path);
+    }
+}
+
+impl FileSystem for InMemoryFileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn ReadSeek>> {
+        match self.files.read().unwrap().get(path) {
+            Some(file) => Ok(Box::new(Cursor::new(file.contents.clone()))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let files = self.files.read().unwrap();
+        if let Some(file) = files.get(path) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: file.contents.len() as u64,
+                modified: Some(file.modified),
+            });
+        }
+
+        match files.keys().any(|file| file.starts_with(path)) {
+            true => Ok(Metadata {
+                is_dir: true,
+                len: 0,
+                modified: None,
+            }),
+            false => Err(not_found(path)),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let entries = self
+            .files
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|file| *file != path)
+            .filter_map(|file| file.strip_prefix(path).ok()?.components().next())
+            .map(|name| path.join(name))
+            .collect::<HashSet<_>>();
+
+        match entries.is_empty() {
+            true => Err(not_found(path)),
+            false => Ok(entries.into_iter().collect()),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.insert(path, contents);
+        Ok(())
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} is not found", path.display()),
+    )
+}
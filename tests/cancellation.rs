@@ -0,0 +1,64 @@
+mod common;
+
+use common::{fixture, TestClient};
+use robust_lsp::{
+    backend::Context,
+    parse::{file_groups, ParseCancellation, ProjectParser},
+};
+use serde_json::json;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{
+    notification::WorkDoneProgressCancel, request::ExecuteCommand, ExecuteCommandParams, Url,
+    WorkDoneProgressCancelParams,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancelled_parsing_skips_the_remaining_files() {
+    let uri = Url::from_directory_path(fixture("basic")).unwrap();
+    let context = Arc::new(Context::default());
+    let cancellation = ParseCancellation::default();
+    cancellation.cancel();
+
+    ProjectParser::new(uri, context.clone(), None)
+        .with_cancellation(cancellation)
+        .parse(file_groups())
+        .await;
+
+    assert!(context.classes.read().await.is_empty());
+    assert!(context.prototypes.read().await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn accepts_progress_cancellation() {
+    let (mut client, indexing) = TestClient::start_held("basic").await;
+
+    // The parsing waits for its progress, so it is cancelled before any file is parsed.
+    let token = client.progress_created().await;
+    client
+        .notify::<WorkDoneProgressCancel>(WorkDoneProgressCancelParams {
+            token: token.clone(),
+        })
+        .await;
+    client.release_progress();
+    indexing.await.unwrap();
+
+    let status = client.custom_request("robust-lsp/status", ()).await;
+    assert_eq!(status["indexed"], false);
+    assert_eq!(status["prototypes"], 0);
+
+    // Setting the root again indexes the project, the cancellation was of the previous run.
+    client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.setProjectRoot".to_owned(),
+            arguments: vec![json!(fixture("basic"))],
+            work_done_progress_params: Default::default(),
+        })
+        .await;
+    client
+        .notify::<WorkDoneProgressCancel>(WorkDoneProgressCancelParams { token })
+        .await;
+
+    let status = client.custom_request("robust-lsp/status", ()).await;
+    assert_eq!(status["indexed"], true);
+    assert_eq!(status["prototypes"], 4);
+}
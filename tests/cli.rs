@@ -0,0 +1,75 @@
+mod common;
+
+use common::fixture;
+use robust_lsp::cli::export_index;
+use serde_json::{json, Value};
+use std::fs;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exports_the_index_as_json() {
+    let output = std::env::temp_dir().join(format!("robust-lsp-index-{}.json", std::process::id()));
+
+    assert_eq!(export_index(&fixture("basic"), Some(&output)).await, 0);
+    let json = fs::read_to_string(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    let dump = serde_json::from_str::<Value>(&json).unwrap();
+
+    let tool = dump["classes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "ToolComponent")
+        .expect("No ToolComponent class");
+    assert_eq!(tool["file"], "Content.Shared/Tools/ToolComponent.cs");
+    assert_eq!(tool["base"], json!(["Component"]));
+    let verb = tool["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == "Verb")
+        .expect("No Verb field");
+    assert_eq!(verb["type"], "LocId");
+    assert_eq!(verb["yamlName"], "verb");
+
+    let crowbar = dump["prototypes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["type"] == "entity" && p["id"] == "Crowbar")
+        .expect("No Crowbar prototype");
+    assert_eq!(crowbar["file"], "Resources/Prototypes/Entities/tools.yml");
+    assert_eq!(crowbar["parents"], json!(["BaseTool"]));
+    assert_eq!(crowbar["components"], json!(["Tool"]));
+    assert_eq!(
+        crowbar["range"]["start"],
+        json!({ "line": 11, "character": 6 })
+    );
+
+    assert_eq!(
+        dump["locales"],
+        json!([
+            {
+                "key": "tool-verb-pry",
+                "term": false,
+                "locale": "en-US",
+                "text": "Pry",
+                "file": "Resources/Locale/en-US/tools.ftl",
+                "range": {
+                    "start": { "line": 1, "character": 0 },
+                    "end": { "line": 1, "character": 13 },
+                },
+            },
+            {
+                "key": "tool-verb-use",
+                "term": false,
+                "locale": "en-US",
+                "text": "Use",
+                "file": "Resources/Locale/en-US/tools.ftl",
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 13 },
+                },
+            },
+        ])
+    );
+}
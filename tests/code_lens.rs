@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestClient;
+use tower_lsp::lsp_types::{
+    request::CodeLensRequest, CodeLensParams, Location, TextDocumentIdentifier, Url,
+};
+
+/// The lenses with the line they are shown on, the title and the listed locations.
+async fn lenses(client: &mut TestClient, uri: &Url) -> Vec<(u32, String, Vec<Location>)> {
+    let lenses = client
+        .request::<CodeLensRequest>(CodeLensParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+
+    lenses
+        .into_iter()
+        .map(|lens| {
+            let command = lens.command.expect("A lens without a command");
+            assert_eq!(command.command, "editor.action.showReferences");
+            let arguments = command.arguments.unwrap();
+            let locations = serde_json::from_value(arguments[2].clone()).unwrap();
+            (lens.range.start.line, command.title, locations)
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_prototypes_of_a_kind() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client
+        .open("RobustToolbox/Robust.Shared/Prototypes/EntityPrototype.cs")
+        .await;
+
+    let lenses = lenses(&mut client, &uri).await;
+    assert_eq!(lenses.len(), 1);
+    let (line, title, locations) = &lenses[0];
+    assert_eq!((*line, title.as_str()), (3, "3 prototypes"));
+    assert_eq!(locations.len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_entities_using_a_component() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Content.Shared/Tools/ToolComponent.cs").await;
+
+    let tool = lenses(&mut client, &uri).await;
+    assert_eq!(tool.len(), 1);
+    let (line, title, locations) = &tool[0];
+    assert_eq!((*line, title.as_str()), (3, "2 entity usages"));
+    assert!(locations
+        .iter()
+        .all(|l| l.uri == client.uri("Resources/Prototypes/Entities/tools.yml")));
+
+    let uri = client.open("Content.Shared/Tools/WelderComponent.cs").await;
+    assert_eq!(lenses(&mut client, &uri).await[0].1, "0 entity usages");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_children_of_prototypes() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let lenses = lenses(&mut client, &uri).await;
+    assert_eq!(lenses.len(), 1);
+    let (line, title, locations) = &lenses[0];
+    assert_eq!((*line, title.as_str()), (0, "1 child"));
+    assert_eq!(locations[0].uri, uri);
+    assert_eq!(locations[0].range.start.line, 11);
+}
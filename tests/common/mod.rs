@@ -0,0 +1,394 @@
+//! An in-process client that drives the language server through its service,
+//! the way an editor would, over the fixture projects in `tests/fixtures`.
+
+#![allow(dead_code)]
+
+use futures::{SinkExt, StreamExt};
+use robust_lsp::{backend::Backend, vfs::SharedFileSystem};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::JoinHandle,
+};
+use tower::{Service, ServiceExt};
+use tower_lsp::{
+    jsonrpc::{self, Request, RequestBuilder, Response},
+    lsp_types::{
+        notification::{self, Notification},
+        request::{self, Request as _, WorkDoneProgressCreate},
+        ClientCapabilities, CompletionResponse, Diagnostic, DidChangeTextDocumentParams,
+        DidOpenTextDocumentParams, DidSaveTextDocumentParams, InitializeParams, InitializeResult,
+        InitializedParams, NumberOrString, Position, Range, TextDocumentContentChangeEvent,
+        TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
+        VersionedTextDocumentIdentifier, WorkDoneProgressCreateParams,
+    },
+    LspService,
+};
+
+/// How long a request or a notification may take, indexing the fixtures included.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The root of the fixture project with the name.
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+pub struct TestClient {
+    service: LspService<Backend>,
+    root: PathBuf,
+    next_id: i64,
+    next_version: i32,
+    /// The notifications sent by the server, e.g. the published diagnostics,
+    /// and the progress it creates.
+    notifications: mpsc::UnboundedReceiver<Request>,
+    /// The permits to answer the creation of the progress, see [`TestClient::start_held`].
+    progress: Arc<Semaphore>,
+    pub initialize_result: InitializeResult,
+}
+
+impl TestClient {
+    /// Starts the server on the fixture project and waits until it is indexed.
+    pub async fn start(fixture_name: &str) -> Self {
+        Self::start_with(fixture_name, Value::Null).await
+    }
+
+    /// Same as [`TestClient::start`], with the settings passed as the initialization options.
+    pub async fn start_with(fixture_name: &str, options: Value) -> Self {
+        Self::launch(
+            fixture_name,
+            options,
+            ClientCapabilities::default(),
+            SharedFileSystem::default(),
+        )
+        .await
+    }
+
+    /// Same as [`TestClient::start`], with the capabilities of the client, e.g. its position encodings.
+    pub async fn start_with_capabilities(
+        fixture_name: &str,
+        capabilities: ClientCapabilities,
+    ) -> Self {
+        Self::launch(
+            fixture_name,
+            Value::Null,
+            capabilities,
+            SharedFileSystem::default(),
+        )
+        .await
+    }
+
+    /// Same as [`TestClient::start`], the providers read the resources from the file system.
+    pub async fn start_with_file_system(fixture_name: &str, fs: SharedFileSystem) -> Self {
+        Self::launch(fixture_name, Value::Null, ClientCapabilities::default(), fs).await
+    }
+
+    /// Starts the server on the fixture project without waiting for the indexing, which stays
+    /// at the creation of its progress until [`TestClient::release_progress`]. The handle is
+    /// over once the project is indexed.
+    pub async fn start_held(fixture_name: &str) -> (Self, JoinHandle<()>) {
+        let mut client = Self::connect(
+            fixture_name,
+            Value::Null,
+            ClientCapabilities::default(),
+            SharedFileSystem::default(),
+            Semaphore::new(0),
+        )
+        .await;
+
+        let initialized = with_params(
+            Request::build(notification::Initialized::METHOD),
+            InitializedParams {},
+        );
+        let service = ServiceExt::<Request>::ready(&mut client.service)
+            .await
+            .unwrap();
+        let indexing = service.call(initialized);
+        let indexing = tokio::spawn(async move {
+            tokio::time::timeout(TIMEOUT, indexing)
+                .await
+                .expect("initialized timed out")
+                .unwrap();
+        });
+
+        (client, indexing)
+    }
+
+    async fn launch(
+        fixture_name: &str,
+        options: Value,
+        capabilities: ClientCapabilities,
+        fs: SharedFileSystem,
+    ) -> Self {
+        let permits = Semaphore::new(Semaphore::MAX_PERMITS);
+        let mut client = Self::connect(fixture_name, options, capabilities, fs, permits).await;
+        // The project is indexed by the time the notification is handled.
+        client
+            .notify::<notification::Initialized>(InitializedParams {})
+            .await;
+
+        client
+    }
+
+    /// Initializes the server, the creation of the progress is answered with the permits.
+    async fn connect(
+        fixture_name: &str,
+        options: Value,
+        capabilities: ClientCapabilities,
+        fs: SharedFileSystem,
+        permits: Semaphore,
+    ) -> Self {
+        let root = fixture(fixture_name);
+        let (service, socket) = Backend::service_with(fs);
+
+        let progress = Arc::new(permits);
+        let (tx, notifications) = mpsc::unbounded_channel();
+        tokio::spawn({
+            let progress = progress.clone();
+            async move {
+                let (mut requests, mut responses) = socket.split();
+                while let Some(request) = requests.next().await {
+                    match request.id().cloned() {
+                        // The server waits for the answers to its own requests,
+                        // e.g. to create the progress of the indexing.
+                        Some(id) => {
+                            if request.method() == WorkDoneProgressCreate::METHOD {
+                                let _ = tx.send(request);
+                                let _permit = progress.acquire().await;
+                            }
+                            let _ = responses.send(Response::from_ok(id, Value::Null)).await;
+                        }
+                        None => {
+                            let _ = tx.send(request);
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut client = Self {
+            service,
+            root,
+            next_id: 0,
+            next_version: 1,
+            notifications,
+            progress,
+            initialize_result: InitializeResult::default(),
+        };
+
+        client.initialize_result = client
+            .request::<request::Initialize>(InitializeParams {
+                root_uri: Some(Url::from_directory_path(&client.root).unwrap()),
+                capabilities,
+                initialization_options: Some(options),
+                ..Default::default()
+            })
+            .await;
+
+        client
+    }
+
+    /// Answers the creation of the progress held since [`TestClient::start_held`].
+    pub fn release_progress(&self) {
+        self.progress.add_permits(Semaphore::MAX_PERMITS);
+    }
+
+    /// Waits for the server to create a progress, e.g. `parse-1-yaml files`.
+    pub async fn progress_created(&mut self) -> NumberOrString {
+        loop {
+            let request = tokio::time::timeout(TIMEOUT, self.notifications.recv())
+                .await
+                .expect("No progress is created")
+                .expect("The server has stopped");
+            if request.method() == WorkDoneProgressCreate::METHOD {
+                let params: WorkDoneProgressCreateParams =
+                    serde_json::from_value(request.params().cloned().unwrap()).unwrap();
+                return params.token;
+            }
+        }
+    }
+
+    pub async fn request<R: request::Request>(&mut self, params: R::Params) -> R::Result {
+        self.try_request::<R>(params)
+            .await
+            .unwrap_or_else(|err| panic!("{} failed: {err}", R::METHOD))
+    }
+
+    /// Sends a request that is expected to fail, e.g. to check the error the user is shown.
+    pub async fn try_request<R: request::Request>(
+        &mut self,
+        params: R::Params,
+    ) -> Result<R::Result, jsonrpc::Error> {
+        let result = self.try_custom_request(R::METHOD, params).await?;
+        Ok(serde_json::from_value(result)
+            .unwrap_or_else(|err| panic!("Unexpected result of {}: {err}", R::METHOD)))
+    }
+
+    /// Sends a request by its method, e.g. one of the `robust-lsp/` requests.
+    pub async fn custom_request(&mut self, method: &'static str, params: impl Serialize) -> Value {
+        self.try_custom_request(method, params)
+            .await
+            .unwrap_or_else(|err| panic!("{method} failed: {err}"))
+    }
+
+    async fn try_custom_request(
+        &mut self,
+        method: &'static str,
+        params: impl Serialize,
+    ) -> Result<Value, jsonrpc::Error> {
+        self.next_id += 1;
+        let request = with_params(Request::build(method).id(self.next_id), params);
+
+        let response = self
+            .call(request)
+            .await
+            .unwrap_or_else(|| panic!("{method} is not answered"));
+        response.into_parts().1
+    }
+
+    pub async fn notify<N: Notification>(&mut self, params: N::Params) {
+        let notification = with_params(Request::build(N::METHOD), params);
+        self.call(notification).await;
+    }
+
+    async fn call(&mut self, request: Request) -> Option<Response> {
+        let method = request.method().to_owned();
+        let service = ServiceExt::<Request>::ready(&mut self.service)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(TIMEOUT, service.call(request))
+            .await
+            .unwrap_or_else(|_| panic!("{method} timed out"))
+            .unwrap()
+    }
+
+    /// The uri of the file of the fixture, e.g. `Resources/Prototypes/Entities/tools.yml`.
+    pub fn uri(&self, path: &str) -> Url {
+        Url::from_file_path(self.root.join(path)).unwrap()
+    }
+
+    /// Opens the file of the fixture the way an editor does.
+    pub async fn open(&mut self, path: &str) -> Url {
+        let uri = self.uri(path);
+        let text = fs::read_to_string(self.root.join(path)).unwrap();
+        self.open_text(&uri, &text).await;
+        uri
+    }
+
+    /// Opens a document with the text, the document doesn't have to exist on disk.
+    pub async fn open_text(&mut self, uri: &Url, text: &str) {
+        let language_id = match Path::new(uri.path()).extension().and_then(|e| e.to_str()) {
+            Some("yml" | "yaml") => "yaml",
+            Some("ftl") => "fluent",
+            Some("cs") => "csharp",
+            _ => "plaintext",
+        };
+        self.open_in_language(uri, language_id, text).await;
+    }
+
+    /// Opens a document in the language, e.g. an untitled one without an extension.
+    pub async fn open_in_language(&mut self, uri: &Url, language_id: &str, text: &str) {
+        self.notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                language_id.to_owned(),
+                1,
+                text.to_owned(),
+            ),
+        })
+        .await;
+    }
+
+    /// The path of the file of the fixture, the file doesn't have to exist.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Types the text at the position of the opened file, the file on disk is left as is.
+    pub async fn insert(&mut self, uri: &Url, position: Position, text: &str) {
+        self.next_version += 1;
+        self.notify::<notification::DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri.clone(), self.next_version),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(Range::new(position, position)),
+                range_length: None,
+                text: text.to_owned(),
+            }],
+        })
+        .await;
+    }
+
+    /// Saves the opened file with the text, the way an editor does when `includeText` is set.
+    /// The file on disk is left as is.
+    pub async fn save(&mut self, uri: &Url, text: &str) {
+        self.notify::<notification::DidSaveTextDocument>(DidSaveTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            text: Some(text.to_owned()),
+        })
+        .await;
+    }
+
+    /// Waits for the diagnostics published for the file.
+    pub async fn diagnostics(&mut self, uri: &Url) -> Vec<Diagnostic> {
+        self.wait_for::<notification::PublishDiagnostics>(|params| params.uri == *uri)
+            .await
+            .diagnostics
+    }
+
+    /// Waits for the notification sent by the server that matches, the other ones are skipped.
+    pub async fn wait_for<N: Notification>(
+        &mut self,
+        matches: impl Fn(&N::Params) -> bool,
+    ) -> N::Params {
+        loop {
+            let notification = tokio::time::timeout(TIMEOUT, self.notifications.recv())
+                .await
+                .unwrap_or_else(|_| panic!("No matching {} is sent", N::METHOD))
+                .expect("The server has stopped");
+            if notification.method() != N::METHOD {
+                continue;
+            }
+
+            let params: N::Params =
+                serde_json::from_value(notification.params().cloned().unwrap()).unwrap();
+            if matches(&params) {
+                return params;
+            }
+        }
+    }
+}
+
+/// Requests without parameters, e.g. `shutdown`, must not have `params: null`.
+fn with_params(builder: RequestBuilder, params: impl Serialize) -> Request {
+    match serde_json::to_value(params).unwrap() {
+        Value::Null => builder.finish(),
+        params => builder.params(params).finish(),
+    }
+}
+
+/// The position in the file, both numbers are zero-based.
+pub fn at(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+    TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier::new(uri.clone()),
+        position: Position::new(line, character),
+    }
+}
+
+/// The labels of the completion items, in the order the server sent them.
+pub fn labels(response: Option<CompletionResponse>) -> Vec<String> {
+    let items = match response {
+        Some(CompletionResponse::Array(items)) => items,
+        Some(CompletionResponse::List(list)) => list.items,
+        None => vec![],
+    };
+    items.into_iter().map(|item| item.label).collect()
+}
@@ -0,0 +1,467 @@
+mod common;
+
+use common::{at, labels, TestClient};
+use serde_json::json;
+use tower_lsp::lsp_types::{
+    request::Completion, ClientCapabilities, CompletionClientCapabilities,
+    CompletionItemCapability, CompletionParams, CompletionResponse, CompletionTextEdit,
+    InsertTextFormat, Position, Range, TextDocumentClientCapabilities, TextEdit,
+};
+
+fn params(position: tower_lsp::lsp_types::TextDocumentPositionParams) -> CompletionParams {
+    CompletionParams {
+        text_document_position: position,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+        context: None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_missing_fields() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    // The empty line after the `Tool` component of `BaseTool`.
+    let response = client.request::<Completion>(params(at(&uri, 8, 4))).await;
+
+    assert_eq!(labels(response), ["useSound", "verb"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_fields_named_by_constants_and_nameof() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    let text = "- type: entity\n  id: Torch\n  components:\n  - type: Welder\n    \n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 4, 4))).await;
+
+    let mut labels = labels(response);
+    labels.sort();
+    assert_eq!(labels, ["WeldingDamage", "fuelCapacity", "fuelConsumption"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn marks_server_only_fields() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    let text = "- type: entity\n  id: Torch\n  components:\n  - type: Welder\n    \n";
+    client.open_text(&uri, text).await;
+
+    let Some(CompletionResponse::Array(items)) =
+        client.request::<Completion>(params(at(&uri, 4, 4))).await
+    else {
+        panic!("No fields are completed");
+    };
+
+    let detail = |label: &str| {
+        let item = items.iter().find(|i| i.label == label).unwrap();
+        item.detail.clone().unwrap_or_default()
+    };
+    assert_eq!(
+        detail("fuelConsumption"),
+        "float, default: 0.1f, server only"
+    );
+    assert_eq!(detail("fuelCapacity"), "float, default: 100f");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_components() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    client
+        .insert(&uri, Position::new(18, 0), "  - type: Wel\n")
+        .await;
+
+    let response = client.request::<Completion>(params(at(&uri, 18, 13))).await;
+
+    assert_eq!(labels(response), ["Welder"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_prototype_kind_in_new_file() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/new.yml");
+    client.open_text(&uri, "type: ent").await;
+
+    let response = client.request::<Completion>(params(at(&uri, 0, 9))).await;
+
+    let Some(CompletionResponse::Array(items)) = response else {
+        panic!("Unexpected completion response: {response:?}");
+    };
+    let edits = items
+        .into_iter()
+        .filter_map(|item| match item.text_edit? {
+            CompletionTextEdit::Edit(edit) => Some(edit),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        edits,
+        [TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(0, 9)),
+            "- type: entity".to_owned()
+        )]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_parents_in_block_sequences() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    let entity = "\n- type: entity\n  id: Wrench\n  parent:\n  - Crowbar\n  - Bas\n";
+    client.insert(&uri, Position::new(18, 0), entity).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 23, 7))).await;
+    assert_eq!(labels(response), ["BaseTool"]);
+
+    // The parents that are already listed aren't offered again.
+    client.insert(&uri, Position::new(24, 0), "  - \n").await;
+    let response = client.request::<Completion>(params(at(&uri, 24, 4))).await;
+    let labels = labels(response);
+    assert!(labels.contains(&"BaseTool".to_owned()), "{labels:?}");
+    assert!(!labels.contains(&"Crowbar".to_owned()), "{labels:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_parents_in_flow_sequences_by_prefix() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/wrench.yml");
+    let text = "- type: entity\n  id: Wrench\n  parent: [Crowbar, baseto]\n";
+    client.open_text(&uri, text).await;
+
+    // The abstract parent from the other file, however long the rest of its id is.
+    let response = client.request::<Completion>(params(at(&uri, 2, 26))).await;
+    assert_eq!(labels(response), ["BaseTool"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_string_fields_from_used_values() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/wrench.yml");
+    let text = "- type: entity\n  id: Wrench\n  description: Pr\n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 2, 17))).await;
+    assert_eq!(labels(response), ["Pries things open."]);
+
+    // The cached values are counted again once the prototypes change, the saved ones included.
+    let entity = "- type: entity\n  id: Lever\n  description: Pries lids.\n";
+    client.insert(&uri, Position::new(3, 0), entity).await;
+    client.save(&uri, &format!("{text}{entity}")).await;
+    client.diagnostics(&uri).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 2, 17))).await;
+    assert_eq!(
+        labels(response),
+        ["Pr", "Pries lids.", "Pries things open."]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_construction_graphs_and_nodes() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/lever.yml");
+    let text = "\
+- type: entity
+  id: Lever
+  components:
+  - type: Construction
+    graph: Cr
+    node: 
+";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 4, 13))).await;
+    assert_eq!(labels(response), ["Crowbar"]);
+
+    // The nodes of the graph set next to the field.
+    client.insert(&uri, Position::new(4, 13), "owbar").await;
+    let response = client.request::<Completion>(params(at(&uri, 5, 10))).await;
+    let mut labels = labels(response);
+    labels.sort();
+    assert_eq!(labels, ["crowbar", "start"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_reagents() {
+    let mut client = TestClient::start("chemistry").await;
+    let uri = client.uri("Resources/Prototypes/Entities/sink.yml");
+    let text = "\
+- type: entity
+  id: Sink
+  components:
+  - type: Drain
+    output: Wa
+    filters:
+      Water: 1
+
+";
+    client.open_text(&uri, text).await;
+
+    // Keys of a `Dictionary<ReagentId, FixedPoint2>`, the specified ones aren't offered again.
+    let response = client.request::<Completion>(params(at(&uri, 7, 6))).await;
+    let mut keys = labels(response);
+    keys.sort();
+    assert_eq!(keys, ["Hydrogen", "Oxygen"]);
+
+    let response = client.request::<Completion>(params(at(&uri, 4, 14))).await;
+    assert_eq!(labels(response), ["Water"]);
+
+    // Keys of the known reagent dictionaries declared with `string` keys.
+    let uri = client.uri("Resources/Prototypes/Recipes/Reactions/steam.yml");
+    let text = "\
+- type: reaction
+  id: Steam
+  products:
+    Water: 1
+
+";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 4, 4))).await;
+    let mut keys = labels(response);
+    keys.sort();
+    assert_eq!(keys, ["Hydrogen", "Oxygen"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_fields_of_records_and_structs() {
+    let mut client = TestClient::start("chemistry").await;
+    let uri = client.uri("Resources/Prototypes/Entities/sink.yml");
+    let text = "\
+- type: entity
+  id: Sink
+  components:
+  - type: Drain
+    schedule:
+      limit:
+        volume: 5
+        
+";
+    client.open_text(&uri, text).await;
+
+    // `limit` is a field of a record struct and its fields are of a struct.
+    let response = client.request::<Completion>(params(at(&uri, 7, 8))).await;
+    assert_eq!(labels(response), ["overflow"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inserts_components_list_with_the_key() {
+    let capabilities = ClientCapabilities {
+        text_document: Some(TextDocumentClientCapabilities {
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    snippet_support: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let mut client = TestClient::start_with_capabilities("basic", capabilities).await;
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    client
+        .open_text(&uri, "- type: entity\n  id: Torch\n  \n")
+        .await;
+
+    let Some(CompletionResponse::Array(items)) =
+        client.request::<Completion>(params(at(&uri, 2, 2))).await
+    else {
+        panic!("No fields are completed");
+    };
+
+    let item = items.iter().find(|i| i.label == "components").unwrap();
+    assert_eq!(item.insert_text_format, Some(InsertTextFormat::SNIPPET));
+    assert_eq!(item.sort_text.as_deref(), Some("0"));
+    let Some(CompletionTextEdit::Edit(edit)) = &item.text_edit else {
+        panic!("No edit of the components key");
+    };
+    assert_eq!(edit.new_text, "components:\n  - type: $0");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replaces_partially_typed_values() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    let text = "- type: entity\n  id: Torch\n  parent: BaseT\n  components:\n  - type: Wel\n";
+    client.open_text(&uri, text).await;
+
+    let mut edit = async |line, character, new_text: &str| {
+        let items = match client
+            .request::<Completion>(params(at(&uri, line, character)))
+            .await
+        {
+            Some(CompletionResponse::Array(items)) => items,
+            Some(CompletionResponse::List(list)) => list.items,
+            None => vec![],
+        };
+        items
+            .into_iter()
+            .find_map(|item| match item.text_edit? {
+                CompletionTextEdit::Edit(edit) if edit.new_text == new_text => Some(edit.range),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("No edit to {new_text}"))
+    };
+    let range = |line, start, end| Range::new(Position::new(line, start), Position::new(line, end));
+
+    assert_eq!(edit(0, 11, "entity").await, range(0, 8, 14));
+    assert_eq!(edit(2, 15, "BaseTool").await, range(2, 10, 15));
+    assert_eq!(edit(4, 13, "Welder").await, range(4, 10, 13));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_configured_component_lists() {
+    let options = json!({ "prototypes": { "componentLists": { "constructionGraph": ["steps"] } } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let uri = client.uri("Resources/Prototypes/graph.yml");
+    let text = "- type: constructionGraph\n  id: Crowbar\n  steps:\n  - type: Wel\n  - type: Welder\n    \n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 3, 13))).await;
+    assert_eq!(labels(response), ["Welder"]);
+
+    // The fields of the listed components are completed too.
+    let response = client.request::<Completion>(params(at(&uri, 5, 4))).await;
+    assert_eq!(
+        labels(response),
+        ["fuelConsumption", "fuelCapacity", "WeldingDamage"]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_fluent_functions_and_their_arguments() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.uri("Resources/Locale/en-US/prices.ftl");
+    let text =
+        "count = { NU }\nprice = { NUMBER($n, minimumFractionDigits: 2, max }\nempty = { }\n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 0, 12))).await;
+    assert_eq!(labels(response), ["NUMBER"]);
+
+    let response = client.request::<Completion>(params(at(&uri, 1, 50))).await;
+    assert_eq!(
+        labels(response),
+        ["maximumFractionDigits", "maximumSignificantDigits"]
+    );
+
+    let response = client.request::<Completion>(params(at(&uri, 2, 10))).await;
+    assert_eq!(labels(response), ["NUMBER", "DATETIME"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ranks_nearby_prototypes_first() {
+    let mut client = TestClient::start("basic").await;
+    let far = client.uri("Resources/Prototypes/Other/far.yml");
+    client.save(&far, "- type: entity\n  id: ToolFar\n").await;
+    let near = client.uri("Resources/Prototypes/Entities/Objects/Tools/near.yml");
+    client
+        .save(&near, "- type: entity\n  id: ToolNearby\n")
+        .await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/Objects/Tools/torch.yml");
+    client
+        .open_text(&uri, "- type: entity\n  id: Torch\n  parent: Tool\n")
+        .await;
+
+    // `ToolFar` is the closer match alone, but `ToolNearby` is in the same folder.
+    let labels = labels(client.request::<Completion>(params(at(&uri, 2, 14))).await);
+    let position = |label: &str| labels.iter().position(|l| l == label).unwrap();
+    assert!(position("ToolNearby") < position("ToolFar"), "{labels:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_in_files_with_wider_indentation() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/wide.yml");
+    let text = "-   type: entity\n    id: Wide\n    components:\n    -   type: Tool\n        \n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 4, 8))).await;
+    assert_eq!(labels(response), ["speedModifier", "useSound", "verb"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_entity_locale_keys() {
+    let mut client = TestClient::start("locale").await;
+    // Abstract entities are never spawned, so they aren't named.
+    let bases = client.uri("Resources/Prototypes/Entities/bases.yml");
+    client
+        .save(
+            &bases,
+            "- type: entity\n  id: CrowbarBase\n  abstract: true\n",
+        )
+        .await;
+    let uri = client.uri("Resources/Locale/en-US/crowbars.ftl");
+    client
+        .open_text(&uri, "ent-Cro\n    .desc = Pries\ntool-\n")
+        .await;
+
+    let response = client.request::<Completion>(params(at(&uri, 0, 7))).await;
+    assert_eq!(labels(response), ["ent-Crowbar"]);
+
+    // Other keys aren't entity ids.
+    let response = client.request::<Completion>(params(at(&uri, 2, 5))).await;
+    assert_eq!(labels(response), Vec::<String>::new());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_prototypes_defined_in_several_files_once() {
+    let options = json!({ "prototypes": { "overrideRoots": ["Upstream"] } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let upstream = client.uri("Upstream/Prototypes/tools.yml");
+    client
+        .save(&upstream, "- type: entity\n  id: Crowbar\n")
+        .await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/lever.yml");
+    client
+        .open_text(&uri, "- type: entity\n  id: Lever\n  parent: Crowb\n")
+        .await;
+
+    let Some(CompletionResponse::List(list)) =
+        client.request::<Completion>(params(at(&uri, 2, 15))).await
+    else {
+        panic!("No completion list");
+    };
+    let items = list
+        .items
+        .iter()
+        .filter(|item| item.label == "Crowbar")
+        .collect::<Vec<_>>();
+    assert_eq!(items.len(), 1, "{:?}", list.items);
+    assert_eq!(
+        items[0].detail.as_deref(),
+        Some("entity, defined in 2 files")
+    );
+    // The upstream definition takes precedence, but the workspace one is the one to edit.
+    let description = items[0]
+        .label_details
+        .as_ref()
+        .unwrap()
+        .description
+        .as_deref();
+    assert_eq!(description, Some("Resources/Prototypes/Entities/tools.yml"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn completes_components_around_non_ascii_identifiers() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Content.Server/Tools/CafeSystem.cs");
+    // The identifier in the second call ends with a combining mark, which takes two bytes.
+    let text = "public sealed class CafeSystem : EntitySystem\n{\n    bool Tool(EntityUid uid) => TryComp<\n    bool Cafe(EntityUid uid) => TryComp<Cafe\u{301}, \n}\n";
+    client.open_text(&uri, text).await;
+
+    let response = client.request::<Completion>(params(at(&uri, 2, 40))).await;
+    assert!(labels(response).contains(&"ToolComponent".to_owned()));
+
+    let response = client.request::<Completion>(params(at(&uri, 3, 47))).await;
+    assert_eq!(response, None);
+    let status = client.custom_request("robust-lsp/status", ()).await;
+    assert_eq!(status["lastError"], json!(null));
+}
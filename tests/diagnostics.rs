@@ -0,0 +1,448 @@
+mod common;
+
+use common::TestClient;
+use robust_lsp::diagnostic::rules::get_rule;
+use serde_json::json;
+use tower_lsp::lsp_types::{
+    notification::LogMessage, request::CodeActionRequest, CodeActionContext, CodeActionOrCommand,
+    CodeActionParams, Diagnostic, DiagnosticSeverity, NumberOrString, Position,
+    TextDocumentIdentifier,
+};
+
+fn codes(diagnostics: &[Diagnostic]) -> Vec<(u32, &str)> {
+    diagnostics
+        .iter()
+        .map(|d| {
+            let code = match &d.code {
+                Some(NumberOrString::String(code)) => code.as_str(),
+                _ => "",
+            };
+            (d.range.start.line, code)
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn clean_file_has_no_diagnostics() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    assert_eq!(client.diagnostics(&uri).await, []);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_duplicates_and_unknown_components() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client
+        .open("Resources/Prototypes/Entities/broken.yml")
+        .await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    let mut codes = codes(&diagnostics);
+    codes.sort();
+    assert_eq!(codes, [(5, "unknown-component"), (8, "duplicate-id")]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_rules_with_their_default_severity() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client
+        .open("Resources/Prototypes/Entities/broken.yml")
+        .await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(diagnostics.len(), 2);
+    for (diagnostic, (_, code)) in diagnostics.iter().zip(codes(&diagnostics)) {
+        let rule = get_rule(code).expect("The code has no rule");
+        assert_eq!(diagnostic.severity, Some(rule.severity), "{code}");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn applies_rule_severities_from_settings() {
+    let options = json!({
+        "diagnostics": { "rules": { "unknown-component": "off", "duplicate-id": "hint" } }
+    });
+    let mut client = TestClient::start_with("basic", options).await;
+    let uri = client
+        .open("Resources/Prototypes/Entities/broken.yml")
+        .await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(codes(&diagnostics), [(8, "duplicate-id")]);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::HINT));
+    let href = &diagnostics[0].code_description.as_ref().unwrap().href;
+    assert!(href.as_str().ends_with("#diagnostic-rules"), "{href}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ignores_rules_named_in_comments() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/ignored.yml");
+    let text = "\
+- type: entity
+  id: IgnoredTool
+  components:
+  - type: Wrench # robust-lsp: ignore[duplicate-id]
+  - type: Hammer # robust-lsp: ignore[unknown-component]
+
+- type: entity # robust-lsp: ignore
+  id: IgnoredTool
+  components:
+  - type: Screwdriver
+";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    // Only the rule named on the line is ignored there, the whole prototype without the rules.
+    assert_eq!(codes(&diagnostics), [(3, "unknown-component")]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_missing_component_dependencies() {
+    let options = json!({ "diagnostics": { "componentDependencies": { "Tool": ["Welder"] } } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    // The abstract `BaseTool` is left to its children.
+    assert_eq!(codes(&diagnostics), [(15, "missing-dependency")]);
+
+    let actions = client
+        .request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: diagnostics[0].range,
+            context: CodeActionContext {
+                diagnostics,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+    let edit = actions
+        .into_iter()
+        .find_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => action.edit?.changes?.remove(&uri),
+            _ => None,
+        })
+        .expect("No quick fix for the missing dependency");
+
+    assert_eq!(edit.len(), 1);
+    assert_eq!(edit[0].range.start, Position::new(17, 23));
+    assert_eq!(edit[0].new_text, "\n  - type: Welder");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_locale_keys_redefined_with_different_text() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Locale/en-US/extra.ftl");
+    let text = "\
+tool-verb-use = Use
+tool-verb-pry = Pry open
+-brand = Robust
+-brand = { \"Robust\" }
+tool-verb-weld = Weld
+tool-verb-weld = Weld it
+";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(
+        codes(&diagnostics),
+        [
+            (1, "conflicting-duplicate"),
+            (3, "conflicting-duplicate"),
+            (5, "conflicting-duplicate")
+        ]
+    );
+    assert!(
+        diagnostics[0]
+            .message
+            .ends_with("tools.ftl with a different text."),
+        "{}",
+        diagnostics[0].message
+    );
+    assert_eq!(
+        diagnostics[2].message,
+        "`tool-verb-weld` is also defined on line 5 with a different text."
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_server_only_fields_in_client_resources() {
+    let options = json!({ "diagnostics": { "serverOnlyFields": true } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let text = "\
+- type: entity
+  id: Torch
+  components:
+  - type: Welder
+    fuelConsumption: 1
+    fuelCapacity: 50
+";
+
+    let uri = client.uri("Content.Client/Resources/Prototypes/torch.yml");
+    client.open_text(&uri, text).await;
+    assert_eq!(
+        codes(&client.diagnostics(&uri).await),
+        [(4, "server-only-field")]
+    );
+
+    // The shared resources are read by the server too.
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    client.open_text(&uri, text).await;
+    assert_eq!(client.diagnostics(&uri).await, []);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_unknown_construction_graphs_and_nodes() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/construction.yml");
+    let text = "\
+- type: entity
+  id: Lever
+  components:
+  - type: Construction
+    graph: Crowbar
+    node: crowbar
+  - type: Construction
+    graph: Crowbar
+    node: lever
+  - type: Construction
+    graph: Lever
+    node: start
+";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(
+        codes(&diagnostics),
+        [(8, "unknown-graph-node"), (10, "unknown-prototype")]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_unknown_reagents() {
+    let mut client = TestClient::start("chemistry").await;
+    let uri = client.uri("Resources/Prototypes/Recipes/Reactions/steam.yml");
+    let text = "\
+- type: reaction
+  id: Steam
+  reactants:
+    Water:
+      amount: 1
+    Heat:
+      amount: 1
+  products:
+    Steam: 1
+
+- type: entity
+  id: Sink
+  components:
+  - type: Drain
+    filters:
+      Blood: 1
+";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(
+        codes(&diagnostics),
+        [
+            (5, "unknown-prototype"),
+            (8, "unknown-prototype"),
+            (15, "unknown-prototype")
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_fields_specified_twice() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/pliers.yml");
+    let text = "\
+- type: entity
+  id: Pliers
+  name: pliers
+  description: Grips things.
+  components:
+  - type: Tool
+    speedModifier: 1
+    verb: tool-verb-pry
+    speedModifier: 2
+  name: cutters
+";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(
+        codes(&diagnostics),
+        [(9, "duplicate-field"), (8, "duplicate-field")]
+    );
+    assert_eq!(
+        diagnostics[0].message,
+        "Field `name` is already specified on line 3, this value replaces it."
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_ids_in_another_case() {
+    let options = json!({ "diagnostics": { "idCases": { "entity": "snake_case" } } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    assert_eq!(codes(&diagnostics), [(2, "id-case"), (11, "id-case")]);
+
+    let actions = client
+        .request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: diagnostics[0].range,
+            context: CodeActionContext {
+                diagnostics: vec![diagnostics[0].clone()],
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+    let edit = actions
+        .into_iter()
+        .find_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => action.edit?.changes?.remove(&uri),
+            _ => None,
+        })
+        .expect("No quick fix for the id");
+
+    // The `parent` of `Crowbar` is renamed along.
+    assert_eq!(edit.len(), 2);
+    assert_eq!(edit[0].range.start, Position::new(2, 6));
+    assert_eq!(edit[1].range.start, Position::new(10, 10));
+    assert!(edit.iter().all(|e| e.new_text == "base_tool"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skips_excluded_files() {
+    let options = json!({
+        "diagnostics": { "idCases": { "entity": "snake_case" } },
+        "prototypes": { "exclude": ["Resources/Prototypes/Entities/tools.yml"] },
+    });
+    let mut client = TestClient::start_with("basic", options).await;
+
+    let log = client
+        .wait_for::<LogMessage>(|p| p.message.starts_with("Indexed"))
+        .await;
+    assert_eq!(
+        log.message,
+        "Indexed 7 classes, 2 prototypes and 2 locale keys."
+    );
+
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    assert!(client.diagnostics(&uri).await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_tab_indentation_with_a_fix() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/tabs.yml");
+    let text = "- type: entity\n\tid: Tabbed\n\tcomponents:\n\t- type: Tool\n\t\n";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    // The blank line with a tab isn't indentation.
+    assert_eq!(
+        codes(&diagnostics),
+        [
+            (1, "tab-indentation"),
+            (2, "tab-indentation"),
+            (3, "tab-indentation")
+        ]
+    );
+    assert_eq!(
+        diagnostics[0].message,
+        "Tabs are not valid YAML indentation."
+    );
+
+    let actions = client
+        .request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: diagnostics[0].range,
+            context: CodeActionContext {
+                diagnostics: vec![diagnostics[0].clone()],
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+    let edit = actions
+        .into_iter()
+        .find_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => action.edit?.changes?.remove(&uri),
+            _ => None,
+        })
+        .expect("No quick fix for the tabs");
+
+    // Every indented line is fixed at once, not only the one of the diagnostic.
+    assert_eq!(edit.len(), 3);
+    assert_eq!(edit[2].range.start, Position::new(3, 0));
+    assert_eq!(edit[2].new_text, "  ");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_entity_locale_keys_of_no_entity() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.uri("Resources/Locale/en-US/renamed.ftl");
+    client
+        .open_text(&uri, "ent-Crowbar = crowbar\nent-Prybar = prybar\n")
+        .await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    assert_eq!(codes(&diagnostics), [(1, "unknown-entity-locale")]);
+    assert_eq!(
+        diagnostics[0].message,
+        "No entity has the id `Prybar` of `ent-Prybar`."
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_values_equal_to_the_field_defaults() {
+    let options = json!({ "diagnostics": { "redundantDefaults": true } });
+    let mut client = TestClient::start_with("basic", options).await;
+    let uri = client.uri("Resources/Prototypes/Entities/pry.yml");
+    let text = "- type: entity\n  id: Pry\n  components:\n  - type: Tool\n    speedModifier: 1.0\n    verb: \"tool-verb-use\"\n    useSound: /Audio/pry.ogg\n";
+    client.open_text(&uri, text).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+
+    let mut redundant = codes(&diagnostics);
+    redundant.retain(|(_, code)| *code == "redundant-default");
+    assert_eq!(
+        redundant,
+        [(4, "redundant-default"), (5, "redundant-default")]
+    );
+    let messages = diagnostics
+        .iter()
+        .filter(|d| d.severity == Some(DiagnosticSeverity::HINT))
+        .map(|d| d.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "`speedModifier` is already `1f` by default.",
+            "`verb` is already `\"tool-verb-use\"` by default."
+        ]
+    );
+}
@@ -0,0 +1,9 @@
+namespace Content.Server.Tools;
+
+public sealed class ToolSystem : EntitySystem
+{
+    public bool CanUse(EntityUid uid)
+    {
+        return TryComp<ToolComponent>(uid, out var tool) && tool.SpeedModifier > 0;
+    }
+}
@@ -0,0 +1,11 @@
+namespace Content.Shared.Construction.Components;
+
+[RegisterComponent]
+public sealed partial class ConstructionComponent : Component
+{
+    [DataField("graph", required: true, customTypeSerializer: typeof(PrototypeIdSerializer<ConstructionGraphPrototype>))]
+    public string Graph { get; set; } = string.Empty;
+
+    [DataField("node", required: true)]
+    public string Node { get; set; } = default!;
+}
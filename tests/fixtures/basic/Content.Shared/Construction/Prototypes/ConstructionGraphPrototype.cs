@@ -0,0 +1,14 @@
+namespace Content.Shared.Construction.Prototypes;
+
+[Prototype("constructionGraph")]
+public sealed partial class ConstructionGraphPrototype : IPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+
+    [DataField("start")]
+    public string? Start { get; private set; }
+
+    [DataField("graph", priority: 0)]
+    private List<ConstructionGraphNode> _graph = new();
+}
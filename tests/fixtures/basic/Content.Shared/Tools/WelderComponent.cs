@@ -0,0 +1,22 @@
+namespace Content.Shared.Tools;
+
+[RegisterComponent]
+public sealed partial class WelderComponent : Component
+{
+    public const string CapacityTag = "fuelCapacity";
+
+    [DataField(serverOnly: true)]
+    public float FuelConsumption = 0.1f;
+
+    [DataField(CapacityTag)]
+    public float Capacity = 100f;
+
+    [DataField(nameof(WeldingDamage))]
+    public float WeldingDamage;
+
+    public float Fuel { get; private set; }
+
+    public bool Lit => Fuel > 0;
+
+    public bool Empty { get { return Fuel == 0; } }
+}
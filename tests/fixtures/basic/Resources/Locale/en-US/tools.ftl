@@ -0,0 +1,2 @@
+tool-verb-use = Use
+tool-verb-pry = Pry
@@ -0,0 +1,11 @@
+namespace Robust.Client.GameObjects;
+
+[RegisterComponent]
+public sealed partial class SpriteComponent : Component
+{
+    [DataField("sprite")]
+    public string? BaseRSIPath;
+
+    [DataField("state")]
+    public string? State;
+}
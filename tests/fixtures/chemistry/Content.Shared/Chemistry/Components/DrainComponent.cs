@@ -0,0 +1,34 @@
+namespace Content.Shared.Chemistry.Components;
+
+[RegisterComponent]
+public sealed partial class DrainComponent : Component
+{
+    [DataField]
+    public Dictionary<ReagentId, FixedPoint2> Filters = new();
+
+    [DataField]
+    public ReagentId? Output;
+
+    [DataField]
+    public DrainSchedule Schedule = new();
+}
+
+[DataDefinition]
+public partial record struct DrainSchedule
+{
+    [DataField]
+    public float Interval = 1f;
+
+    [DataField]
+    public DrainLimit Limit;
+}
+
+[DataDefinition]
+public partial struct DrainLimit
+{
+    [DataField]
+    public FixedPoint2 Volume;
+
+    [DataField]
+    public bool Overflow;
+}
@@ -0,0 +1,24 @@
+namespace Content.Shared.Chemistry.Reaction;
+
+[Prototype("reaction")]
+public sealed partial class ReactionPrototype : IPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+
+    [DataField("reactants", customTypeSerializer: typeof(PrototypeIdDictionarySerializer<ReactantPrototype, ReagentPrototype>))]
+    public Dictionary<string, ReactantPrototype> Reactants = new();
+
+    [DataField("products", customTypeSerializer: typeof(PrototypeIdDictionarySerializer<FixedPoint2, ReagentPrototype>))]
+    public Dictionary<string, FixedPoint2> Products = new();
+}
+
+[DataDefinition]
+public sealed partial class ReactantPrototype
+{
+    [DataField("amount")]
+    private FixedPoint2 _amount = FixedPoint2.New(1);
+
+    [DataField("catalyst")]
+    private bool _catalyst;
+}
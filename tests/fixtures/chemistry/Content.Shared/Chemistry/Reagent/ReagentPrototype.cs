@@ -0,0 +1,8 @@
+namespace Content.Shared.Chemistry.Reagent;
+
+[Prototype("reagent")]
+public sealed partial class ReagentPrototype : IPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+}
@@ -0,0 +1 @@
+engine-marker = Marker
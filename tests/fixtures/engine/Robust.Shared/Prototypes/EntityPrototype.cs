@@ -0,0 +1,27 @@
+namespace Robust.Shared.Prototypes;
+
+/// <summary>
+/// Prototype that describes an entity and the components it is
+/// spawned with, see <see cref="T:Robust.Shared.GameObjects.Component"/>.
+/// </summary>
+[Prototype("entity")]
+public sealed partial class EntityPrototype : IPrototype, IInheritingPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+
+    [ParentDataField(typeof(AbstractPrototypeIdArraySerializer<EntityPrototype>))]
+    public string[]? Parents { get; private set; }
+
+    [DataField("name")]
+    public string? SetName;
+
+    [DataField("description")]
+    public string? SetDesc;
+
+    [DataField("suffix")]
+    public string? SetSuffix;
+
+    [DataField("components")]
+    public ComponentRegistry Components = new();
+}
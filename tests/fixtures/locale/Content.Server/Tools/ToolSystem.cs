@@ -0,0 +1,9 @@
+namespace Content.Server.Tools;
+
+public sealed class ToolSystem : EntitySystem
+{
+    public string Examine(EntityUid uid)
+    {
+        return Loc.GetString("tool-examine", ("tool", uid));
+    }
+}
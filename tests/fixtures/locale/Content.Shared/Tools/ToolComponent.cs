@@ -0,0 +1,14 @@
+namespace Content.Shared.Tools;
+
+[RegisterComponent]
+public sealed partial class ToolComponent : Component
+{
+    [DataField]
+    public float SpeedModifier = 1f;
+
+    [DataField("useSound")]
+    public SoundSpecifier? UseSound;
+
+    [DataField]
+    public LocId Verb = "tool-verb-use";
+}
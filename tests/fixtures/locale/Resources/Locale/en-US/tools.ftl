@@ -0,0 +1,8 @@
+ent-Crowbar = crowbar
+    .desc = Pries things open.
+
+tool-verb-use = Use
+tool-verb-pry = Pry
+tool-examine = This is { tool-name }.
+tool-name = a tool
+tool-verb-weld = Weld
@@ -0,0 +1,10 @@
+namespace Content.Server.Power;
+
+public sealed partial class BatteryComponent
+{
+    [DataField]
+    public float StartingCharge;
+
+    [DataField]
+    public float PricePerJoule = 0.0001f;
+}
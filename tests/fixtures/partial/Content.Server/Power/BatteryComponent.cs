@@ -0,0 +1,8 @@
+namespace Content.Server.Power;
+
+[RegisterComponent]
+public sealed partial class BatteryComponent : Component
+{
+    [DataField]
+    public float MaxCharge = 1000f;
+}
@@ -0,0 +1,23 @@
+namespace Robust.Shared.Prototypes;
+
+[Prototype("entity")]
+public sealed partial class EntityPrototype : IPrototype, IInheritingPrototype
+{
+    [IdDataField]
+    public string ID { get; private set; } = default!;
+
+    [ParentDataField(typeof(AbstractPrototypeIdArraySerializer<EntityPrototype>))]
+    public string[]? Parents { get; private set; }
+
+    [DataField("name")]
+    public string? SetName;
+
+    [DataField("description")]
+    public string? SetDesc;
+
+    [DataField("suffix")]
+    public string? SetSuffix;
+
+    [DataField("components")]
+    public ComponentRegistry Components = new();
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{at, TestClient};
+use tower_lsp::lsp_types::{
+    request::{GotoDefinition, GotoTypeDefinition, GotoTypeDefinitionParams},
+    GotoDefinitionParams, GotoDefinitionResponse, Location, Position, Range,
+    TextDocumentPositionParams, Url,
+};
+
+async fn goto(client: &mut TestClient, position: TextDocumentPositionParams) -> Vec<Location> {
+    let response = client
+        .request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: position,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await;
+
+    locations(response)
+}
+
+async fn goto_type(client: &mut TestClient, position: TextDocumentPositionParams) -> Vec<Location> {
+    let response = client
+        .request::<GotoTypeDefinition>(GotoTypeDefinitionParams {
+            text_document_position_params: position,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await;
+
+    locations(response)
+}
+
+fn locations(response: Option<GotoDefinitionResponse>) -> Vec<Location> {
+    match response {
+        Some(GotoDefinitionResponse::Scalar(location)) => vec![location],
+        Some(GotoDefinitionResponse::Array(locations)) => locations,
+        Some(GotoDefinitionResponse::Link(links)) => links
+            .into_iter()
+            .map(|link| Location::new(link.target_uri, link.target_selection_range))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn location(uri: Url, line: u32, start: u32, end: u32) -> Location {
+    Location::new(
+        uri,
+        Range::new(Position::new(line, start), Position::new(line, end)),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goes_to_parent() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let locations = goto(&mut client, at(&uri, 10, 12)).await;
+
+    assert_eq!(locations, [location(uri, 2, 6, 14)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goes_to_component() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let locations = goto(&mut client, at(&uri, 6, 12)).await;
+
+    let component = client.uri("Content.Shared/Tools/ToolComponent.cs");
+    assert_eq!(locations, [location(component, 3, 28, 41)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goes_to_locale() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let locations = goto(&mut client, at(&uri, 17, 15)).await;
+
+    let locale = client.uri("Resources/Locale/en-US/tools.ftl");
+    assert_eq!(locations, [location(locale, 1, 0, 13)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goes_from_id_to_class_and_locale() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let locations = goto(&mut client, at(&uri, 1, 8)).await;
+
+    let class = client.uri("RobustToolbox/Robust.Shared/Prototypes/EntityPrototype.cs");
+    let locale = client.uri("Resources/Locale/en-US/tools.ftl");
+    assert_eq!(
+        locations,
+        [location(class, 3, 28, 43), location(locale, 0, 0, 11)]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goes_to_field_type() {
+    let mut client = TestClient::start("chemistry").await;
+    let uri = client
+        .open("Resources/Prototypes/Recipes/Reactions/water.yml")
+        .await;
+    let reaction = client.uri("Content.Shared/Chemistry/Reaction/ReactionPrototype.cs");
+    let reactant = || location(reaction.clone(), 16, 28, 45);
+
+    // The field itself is the definition, its type is the type definition.
+    let locations = goto(&mut client, at(&uri, 2, 4)).await;
+    assert_eq!(locations, [location(reaction.clone(), 9, 49, 58)]);
+    assert_eq!(goto_type(&mut client, at(&uri, 2, 4)).await, [reactant()]);
+
+    // The entries of a dictionary have the type of its values.
+    assert_eq!(goto_type(&mut client, at(&uri, 3, 6)).await, [reactant()]);
+
+    // Types that aren't indexed, e.g. `FixedPoint2`, have no definition.
+    assert_eq!(goto_type(&mut client, at(&uri, 4, 8)).await, []);
+}
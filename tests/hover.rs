@@ -0,0 +1,64 @@
+mod common;
+
+use common::{at, TestClient};
+use tower_lsp::lsp_types::{request::HoverRequest, HoverContents, HoverParams};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn describes_prototype_kinds() {
+    let mut client = TestClient::start("engine").await;
+    let uri = client
+        .open("Resources/EnginePrototypes/Entities/markers.yml")
+        .await;
+
+    let hover = client
+        .request::<HoverRequest>(HoverParams {
+            text_document_position_params: at(&uri, 2, 10),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No hover over the prototype kind");
+
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("Unexpected hover contents: {:?}", hover.contents);
+    };
+    let file = client.uri("Robust.Shared/Prototypes/EntityPrototype.cs");
+    assert_eq!(
+        markup.value,
+        format!(
+            "```csharp\nclass EntityPrototype : IPrototype, IInheritingPrototype\n```\n\n\
+             Prototype that describes an entity and the components it is spawned with, \
+             see `Component`.\n\n\
+             Namespace: `Robust.Shared.Prototypes`\n\n\
+             Defined in [`Robust.Shared/Prototypes/EntityPrototype.cs`]({file})\n\n\
+             Load priority: 1"
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shows_prototype_comments() {
+    let mut client = TestClient::start("engine").await;
+    let uri = client
+        .open("Resources/EnginePrototypes/Entities/markers.yml")
+        .await;
+
+    let hover = client
+        .request::<HoverRequest>(HoverParams {
+            text_document_position_params: at(&uri, 3, 8),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No hover over the prototype id");
+
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("Unexpected hover contents: {:?}", hover.contents);
+    };
+    assert!(
+        markup.value.starts_with(
+            "**entity** `EngineMarker`\n\n\
+             A marker placed by the map editor.\nInvisible in game."
+        ),
+        "{}",
+        markup.value
+    );
+}
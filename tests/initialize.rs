@@ -0,0 +1,199 @@
+mod common;
+
+use common::{at, fixture, TestClient};
+use serde_json::json;
+use tower_lsp::lsp_types::{
+    notification::{LogMessage, Progress, ShowMessage},
+    request::{ExecuteCommand, HoverRequest, Shutdown},
+    ExecuteCommandParams, HoverParams, MessageType, NumberOrString, OneOf, ProgressParamsValue,
+    WorkDoneProgress,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn advertises_capabilities() {
+    let client = TestClient::start("basic").await;
+    let capabilities = &client.initialize_result.capabilities;
+
+    assert!(capabilities.completion_provider.is_some());
+    assert!(capabilities.hover_provider.is_some());
+    assert_eq!(capabilities.definition_provider, Some(OneOf::Left(true)));
+    assert!(capabilities.type_definition_provider.is_some());
+    assert_eq!(capabilities.references_provider, Some(OneOf::Left(true)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexes_project() {
+    let mut client = TestClient::start("basic").await;
+
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "includeAbstract": true }))
+        .await;
+    let mut ids = page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    assert_eq!(ids, ["BaseTool", "BrokenTool", "Crowbar"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexes_engine_repository() {
+    let mut client = TestClient::start("engine").await;
+
+    let log = client
+        .wait_for::<LogMessage>(|p| p.message.starts_with("Indexed"))
+        .await;
+    assert_eq!(
+        log.message,
+        "Indexed 2 classes, 1 prototypes and 1 locale keys."
+    );
+
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "includeAbstract": true }))
+        .await;
+    assert_eq!(page["items"][0]["id"], "EngineMarker");
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shuts_down() {
+    let mut client = TestClient::start("basic").await;
+    client.request::<Shutdown>(()).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sums_up_the_index() {
+    let mut client = TestClient::start("basic").await;
+    let yaml_files = NumberOrString::String("parse-1-yaml files".to_owned());
+
+    let progress = client
+        .wait_for::<Progress>(|p| {
+            p.token == yaml_files
+                && matches!(
+                    p.value,
+                    ProgressParamsValue::WorkDone(WorkDoneProgress::End(_))
+                )
+        })
+        .await;
+    let ProgressParamsValue::WorkDone(WorkDoneProgress::End(end)) = progress.value else {
+        unreachable!();
+    };
+    assert_eq!(end.message.as_deref(), Some("5 prototypes in 3 files"));
+
+    let log = client
+        .wait_for::<LogMessage>(|p| p.message.starts_with("Indexed"))
+        .await;
+    assert_eq!(
+        log.message,
+        "Indexed 7 classes, 4 prototypes and 2 locale keys."
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stays_idle_outside_of_a_project() {
+    // The resources alone lack the solution files of a project.
+    let mut client = TestClient::start("basic/Resources").await;
+
+    let message = client.wait_for::<ShowMessage>(|_| true).await;
+    assert_eq!(message.typ, MessageType::WARNING);
+    assert!(
+        message.message.contains("`SpaceStation14.sln`"),
+        "{}",
+        message.message
+    );
+
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "includeAbstract": true }))
+        .await;
+    assert_eq!(page["items"], json!([]));
+
+    client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.setProjectRoot".to_owned(),
+            arguments: vec![json!(fixture("basic"))],
+            work_done_progress_params: Default::default(),
+        })
+        .await;
+
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "includeAbstract": true }))
+        .await;
+    assert_eq!(page["items"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_index_stats() {
+    let mut client = TestClient::start("basic").await;
+
+    let stats = client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.indexStats".to_owned(),
+            arguments: vec![],
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(stats["classes"], 7);
+    assert_eq!(stats["localeKeys"], 2);
+
+    // The type names and the attribute names are shared by the fields.
+    let strings = &stats["strings"];
+    assert!(strings["strings"].as_u64().unwrap() > 0);
+    assert!(strings["references"].as_u64() > strings["strings"].as_u64());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_request_timings() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    // The `Tool` component of `BaseTool`, hovered twice.
+    for _ in 0..2 {
+        client
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: at(&uri, 6, 11),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .expect("No hover over the component");
+    }
+
+    let stats = client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.indexStats".to_owned(),
+            arguments: vec![],
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap();
+    let hover = &stats["timings"]["hover"];
+    assert_eq!(hover["count"], 2);
+    assert_eq!(hover["results"], 2);
+    // The timings are of this server alone, nothing else was requested.
+    assert!(stats["timings"]["completion"].is_null());
+    assert!(hover["maxMs"].as_f64().unwrap() >= hover["averageMs"].as_f64().unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_files_without_prototypes_as_parsed() {
+    // The reagents are next to a file of settings, which has no prototypes.
+    let mut client = TestClient::start("chemistry").await;
+    let yaml_files = NumberOrString::String("parse-1-yaml files".to_owned());
+
+    let progress = client
+        .wait_for::<Progress>(|p| {
+            p.token == yaml_files
+                && matches!(
+                    p.value,
+                    ProgressParamsValue::WorkDone(WorkDoneProgress::End(_))
+                )
+        })
+        .await;
+    let ProgressParamsValue::WorkDone(WorkDoneProgress::End(end)) = progress.value else {
+        unreachable!();
+    };
+    assert_eq!(end.message.as_deref(), Some("4 prototypes in 3 files"));
+}
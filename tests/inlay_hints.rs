@@ -0,0 +1,121 @@
+mod common;
+
+use common::TestClient;
+use tower_lsp::lsp_types::{
+    request::InlayHintRequest, InlayHintLabel, InlayHintParams, InlayHintTooltip, Position, Range,
+    TextDocumentIdentifier, Url,
+};
+
+/// The hints with their positions: the line, the character and the label.
+async fn hints(client: &mut TestClient, uri: &Url, range: Range) -> Vec<(u32, u32, String)> {
+    let hints = client
+        .request::<InlayHintRequest>(InlayHintParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range,
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+
+    hints
+        .into_iter()
+        .map(|hint| {
+            let InlayHintLabel::String(label) = hint.label else {
+                panic!("Unexpected label: {:?}", hint.label);
+            };
+            (hint.position.line, hint.position.character, label)
+        })
+        .collect()
+}
+
+/// The tooltips of the hints with the lines they are shown on.
+async fn tooltips(client: &mut TestClient, uri: &Url, range: Range) -> Vec<(u32, Option<String>)> {
+    let hints = client
+        .request::<InlayHintRequest>(InlayHintParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range,
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+
+    hints
+        .into_iter()
+        .map(|hint| {
+            let tooltip = hint.tooltip.map(|tooltip| match tooltip {
+                InlayHintTooltip::String(tooltip) => tooltip,
+                InlayHintTooltip::MarkupContent(markup) => markup.value,
+            });
+            (hint.position.line, tooltip)
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hints_yaml_names_of_data_fields() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Content.Shared/Tools/WelderComponent.cs").await;
+
+    let range = Range::new(Position::new(0, 0), Position::new(16, 0));
+    assert_eq!(
+        hints(&mut client, &uri, range).await,
+        [
+            (8, 32, "fuelConsumption".to_owned()),
+            (11, 25, "fuelCapacity".to_owned()),
+            (14, 30, "WeldingDamage".to_owned()),
+        ]
+    );
+
+    let range = Range::new(Position::new(10, 0), Position::new(12, 0));
+    assert_eq!(
+        hints(&mut client, &uri, range).await,
+        [(11, 25, "fuelCapacity".to_owned())]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn explains_inherited_and_default_values() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let range = Range::new(Position::new(15, 0), Position::new(18, 0));
+    assert_eq!(
+        tooltips(&mut client, &uri, range).await,
+        [
+            (
+                16,
+                Some("Overrides `1` inherited from `BaseTool`\nDefault: `1f`".to_owned())
+            ),
+            (17, Some("Default: `\"tool-verb-use\"`".to_owned())),
+        ]
+    );
+
+    let range = Range::new(Position::new(6, 0), Position::new(8, 0));
+    assert_eq!(
+        tooltips(&mut client, &uri, range).await,
+        [(7, Some("Default: `1f`".to_owned()))]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn skips_computed_properties() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    let text = "\
+- type: entity
+  id: Torch
+  components:
+  - type: Welder
+    fuel: 5
+    lit: true
+    empty: false
+";
+    client.open_text(&uri, text).await;
+
+    // `Fuel` has a setter, `Lit` and `Empty` are only computed from it.
+    let range = Range::new(Position::new(3, 0), Position::new(7, 0));
+    assert_eq!(
+        hints(&mut client, &uri, range).await,
+        [(4, 8, "float".to_owned())]
+    );
+}
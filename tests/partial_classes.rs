@@ -0,0 +1,96 @@
+mod common;
+
+use common::{at, labels, TestClient};
+use tower_lsp::lsp_types::{
+    request::{Completion, InlayHintRequest},
+    CompletionParams, InlayHintParams, Position, Range, TextDocumentIdentifier, Url,
+};
+
+async fn field_labels(client: &mut TestClient, uri: &Url) -> Vec<String> {
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(uri, 5, 4),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+
+    let mut labels = labels(response);
+    labels.sort();
+    labels
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn merges_the_declarations_of_a_component() {
+    let mut client = TestClient::start("partial").await;
+    let uri = client.uri("Resources/Prototypes/Entities/cells.yml");
+    let text = "\
+- type: entity
+  id: SmallCell
+  components:
+  - type: Battery
+    maxCharge: 360
+    
+";
+    client.open_text(&uri, text).await;
+
+    // `[RegisterComponent]` is only on the declaration in the other file.
+    assert_eq!(client.diagnostics(&uri).await, []);
+    assert_eq!(
+        field_labels(&mut client, &uri).await,
+        ["pricePerJoule", "startingCharge"]
+    );
+
+    // The data fields of the declaration without attributes are hinted in its own file.
+    let charge = client
+        .open("Content.Server/Power/BatteryComponent.Charge.cs")
+        .await;
+    let hints = client
+        .request::<InlayHintRequest>(InlayHintParams {
+            text_document: TextDocumentIdentifier::new(charge),
+            range: Range::new(Position::new(0, 0), Position::new(10, 0)),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap_or_default();
+    assert_eq!(hints.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keeps_the_other_declarations_on_save() {
+    let mut client = TestClient::start("partial").await;
+    let component = client
+        .open("Content.Server/Power/BatteryComponent.cs")
+        .await;
+    let saved = "\
+namespace Content.Server.Power;
+
+[RegisterComponent]
+public sealed partial class BatteryComponent : Component
+{
+    [DataField]
+    public float MaxCharge = 1000f;
+
+    [DataField]
+    public bool AutoRecharge;
+}
+";
+    client.save(&component, saved).await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/cells.yml");
+    let text = "\
+- type: entity
+  id: SmallCell
+  components:
+  - type: Battery
+    maxCharge: 360
+    
+";
+    client.open_text(&uri, text).await;
+
+    assert_eq!(
+        field_labels(&mut client, &uri).await,
+        ["autoRecharge", "pricePerJoule", "startingCharge"]
+    );
+}
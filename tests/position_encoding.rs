@@ -0,0 +1,132 @@
+mod common;
+
+use common::{at, TestClient};
+use tower_lsp::lsp_types::{
+    notification::DidSaveTextDocument,
+    request::{Completion, Rename},
+    ClientCapabilities, CompletionParams, CompletionResponse, CompletionTextEdit,
+    DidSaveTextDocumentParams, GeneralClientCapabilities, Position, PositionEncodingKind, Range,
+    RenameParams, TextDocumentIdentifier, TextEdit,
+};
+
+/// The ids take 4 bytes for the emoji, 2 UTF-16 code units and a single char.
+const DUPLICATES: &str = "\
+- type: entity
+  id: Tool😀
+
+- type: entity
+  id: Tool😀
+";
+
+fn offering(encodings: Vec<PositionEncodingKind>) -> ClientCapabilities {
+    ClientCapabilities {
+        general: Some(GeneralClientCapabilities {
+            position_encodings: Some(encodings),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_utf16_code_units_by_default() {
+    let mut client = TestClient::start("basic").await;
+    assert_eq!(
+        client.initialize_result.capabilities.position_encoding,
+        Some(PositionEncodingKind::UTF16)
+    );
+
+    let uri = client.uri("Resources/Prototypes/Entities/emoji.yml");
+    client.open_text(&uri, DUPLICATES).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    let ends = diagnostics.iter().map(|d| d.range.end).collect::<Vec<_>>();
+    assert_eq!(ends, [Position::new(4, 12)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prefers_utf8_when_offered() {
+    let capabilities = offering(vec![
+        PositionEncodingKind::UTF16,
+        PositionEncodingKind::UTF8,
+    ]);
+    let mut client = TestClient::start_with_capabilities("basic", capabilities).await;
+    assert_eq!(
+        client.initialize_result.capabilities.position_encoding,
+        Some(PositionEncodingKind::UTF8)
+    );
+
+    let uri = client.uri("Resources/Prototypes/Entities/emoji.yml");
+    client.open_text(&uri, DUPLICATES).await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    let ends = diagnostics.iter().map(|d| d.range.end).collect::<Vec<_>>();
+    assert_eq!(ends, [Position::new(4, 14)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn applies_changes_at_utf16_positions() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Prototypes/Entities/emoji.yml");
+    client.open_text(&uri, DUPLICATES).await;
+    assert_eq!(client.diagnostics(&uri).await.len(), 1);
+
+    // Right after the emoji, so the second id becomes `Tool😀s`.
+    client.insert(&uri, Position::new(4, 12), "s").await;
+    client
+        .notify::<DidSaveTextDocument>(DidSaveTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            text: None,
+        })
+        .await;
+
+    assert_eq!(client.diagnostics(&uri).await, []);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_fluent_lines_with_non_ascii_text() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.uri("Resources/Locale/ru-RU/tools.ftl");
+    // The Cyrillic letters take 2 bytes and a single UTF-16 code unit each.
+    let text = "count = Число { NU }\ntool-look = Это { tool-name }.\ntool-name = инструмент\n";
+    client.open_text(&uri, text).await;
+
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 0, 18),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+    let Some(CompletionResponse::List(list)) = response else {
+        panic!("No completion list: {response:?}");
+    };
+    assert_eq!(list.items[0].label, "NUMBER");
+    assert_eq!(
+        list.items[0].text_edit,
+        Some(CompletionTextEdit::Edit(TextEdit::new(
+            Range::new(Position::new(0, 16), Position::new(0, 18)),
+            "NUMBER".to_owned()
+        )))
+    );
+
+    let edit = client
+        .request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 1, 19),
+            new_name: "tool-title".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No rename of the reference");
+    let rename = |line, start, end| {
+        TextEdit::new(
+            Range::new(Position::new(line, start), Position::new(line, end)),
+            "tool-title".to_owned(),
+        )
+    };
+    assert_eq!(
+        edit.changes.expect("No changes")[&uri],
+        [rename(1, 18, 27), rename(2, 0, 9)]
+    );
+}
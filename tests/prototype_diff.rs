@@ -0,0 +1,32 @@
+mod common;
+
+use common::{fixture, TestClient};
+use serde_json::json;
+use tower_lsp::lsp_types::{request::ExecuteCommand, ExecuteCommandParams};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compares_prototypes_of_snapshots() {
+    let mut client = TestClient::start("basic").await;
+
+    let diff = client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.diffPrototypes".to_owned(),
+            arguments: vec![
+                json!(fixture("snapshots/old")),
+                json!(fixture("snapshots/new")),
+            ],
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap();
+
+    // Moving the screwdriver to another file doesn't change it.
+    assert_eq!(
+        diff,
+        json!({
+            "added": [{ "type": "entity", "id": "Welder" }],
+            "removed": [{ "type": "entity", "id": "Wrench" }],
+            "changed": [{ "type": "entity", "id": "Crowbar" }],
+        })
+    );
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::{at, TestClient};
+use tower_lsp::lsp_types::{
+    request::{ExecuteCommand, References},
+    ExecuteCommandParams, Location, Position, Range, ReferenceContext, ReferenceParams,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn finds_component_usages() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let references = |include_declaration| ReferenceParams {
+        text_document_position: at(&uri, 6, 12),
+        context: ReferenceContext {
+            include_declaration,
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    let usages = client
+        .request::<References>(references(false))
+        .await
+        .unwrap();
+    assert_eq!(usages.len(), 1);
+    assert_eq!(
+        usages[0].uri,
+        client.uri("Content.Server/Tools/ToolSystem.cs")
+    );
+    assert_eq!(usages[0].range.start.line, 6);
+
+    let with_declaration = client
+        .request::<References>(references(true))
+        .await
+        .unwrap();
+    assert!(with_declaration
+        .iter()
+        .any(|l| l.uri == client.uri("Content.Shared/Tools/ToolComponent.cs")));
+    assert_eq!(with_declaration.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn finds_prototype_references() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let references = |include_declaration| ReferenceParams {
+        text_document_position: at(&uri, 2, 8),
+        context: ReferenceContext {
+            include_declaration,
+        },
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+
+    // The `parent` of `Crowbar`.
+    let usages = client
+        .request::<References>(references(false))
+        .await
+        .unwrap();
+    assert_eq!(
+        usages,
+        [Location::new(
+            uri.clone(),
+            Range::new(Position::new(10, 10), Position::new(10, 18))
+        )]
+    );
+
+    let with_declaration = client
+        .request::<References>(references(true))
+        .await
+        .unwrap();
+    assert_eq!(with_declaration.len(), 2);
+    assert_eq!(with_declaration[0].range.start.line, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn finds_prototype_references_in_edited_documents() {
+    let mut client = TestClient::start("basic").await;
+    // The edit isn't indexed yet, the reference is found in the opened text.
+    let broken = client.uri("Resources/Prototypes/Entities/broken.yml");
+    client
+        .open_text(
+            &broken,
+            "- type: entity\n  id: BrokenTool\n  parent: BaseTool\n",
+        )
+        .await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let usages = client
+        .request::<References>(ReferenceParams {
+            text_document_position: at(&uri, 2, 8),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        usages,
+        [
+            Location::new(
+                broken,
+                Range::new(Position::new(2, 10), Position::new(2, 18))
+            ),
+            Location::new(
+                uri,
+                Range::new(Position::new(10, 10), Position::new(10, 18))
+            ),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lists_unused_locale_keys() {
+    let mut client = TestClient::start("locale").await;
+
+    let unused = client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "robust-lsp.unusedLocaleKeys".to_owned(),
+            arguments: vec![],
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .unwrap();
+
+    let unused = unused.as_array().unwrap();
+    assert_eq!(unused.len(), 1, "{unused:?}");
+    assert_eq!(unused[0]["key"], "tool-verb-weld");
+    assert_eq!(unused[0]["locale"], "en-US");
+
+    let location: Location = serde_json::from_value(unused[0]["location"].clone()).unwrap();
+    assert_eq!(location.uri, client.uri("Resources/Locale/en-US/tools.ftl"));
+    assert_eq!(location.range.start.line, 7);
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use common::{at, TestClient};
+use tower_lsp::lsp_types::{
+    request::{PrepareRenameRequest, Rename},
+    Position, PrepareRenameResponse, Range, RenameParams, TextEdit,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prepares_prototype_ids() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let response = client
+        .request::<PrepareRenameRequest>(at(&uri, 11, 8))
+        .await;
+
+    assert_eq!(
+        response,
+        Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: Range::new(Position::new(11, 6), Position::new(11, 13)),
+            placeholder: "Crowbar".to_owned(),
+        })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn renames_prototype_ids_with_references() {
+    let mut client = TestClient::start("basic").await;
+    let component = client.uri("Content.Shared/Tools/SpawnerComponent.cs");
+    client
+        .save(
+            &component,
+            "namespace Content.Shared.Tools;\n\n[RegisterComponent]\npublic sealed partial class SpawnerComponent : Component\n{\n    [DataField]\n    public EntProtoId Prototype;\n\n    [DataField]\n    public List<EntProtoId> Extra = new();\n}\n",
+        )
+        .await;
+    // The spawners are only opened, the rename reads them as edited.
+    let spawners = client.uri("Resources/Prototypes/Entities/spawners.yml");
+    let text = "- type: entity\n  id: Spawner\n  parent: [BaseTool]\n  components:\n  - type: Spawner\n    prototype: BaseTool\n    extra:\n    - \"BaseTool\"\n";
+    client.open_text(&spawners, text).await;
+    client.save(&spawners, text).await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let edit = client
+        .request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 2, 8),
+            new_name: "ToolBase".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No rename of the id");
+
+    let changes = edit.changes.expect("No changes");
+    let rename = |line, start, end| {
+        TextEdit::new(
+            Range::new(Position::new(line, start), Position::new(line, end)),
+            "ToolBase".to_owned(),
+        )
+    };
+    assert_eq!(changes[&uri], [rename(2, 6, 14), rename(10, 10, 18)]);
+    assert_eq!(
+        changes[&spawners],
+        [rename(2, 11, 19), rename(5, 15, 23), rename(7, 7, 15)]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_invalid_prototype_ids() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    let err = client
+        .try_request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 2, 8),
+            new_name: "Tool Base".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect_err("The id is renamed to an invalid one");
+
+    assert_eq!(err.message, "`Tool Base` isn't a valid prototype id.");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prepares_fluent_references() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Locale/en-US/tools.ftl").await;
+
+    let response = client
+        .request::<PrepareRenameRequest>(at(&uri, 5, 27))
+        .await;
+
+    assert_eq!(
+        response,
+        Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: Range::new(Position::new(5, 25), Position::new(5, 34)),
+            placeholder: "tool-name".to_owned(),
+        })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_values() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Locale/en-US/tools.ftl").await;
+
+    let err = client
+        .try_request::<PrepareRenameRequest>(at(&uri, 0, 17))
+        .await
+        .expect_err("The value of a message is renamed");
+
+    assert_eq!(
+        err.message,
+        "Only prototype ids and Fluent messages and terms can be renamed."
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn renames_fluent_messages_with_references() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Locale/en-US/tools.ftl").await;
+
+    let edit = client
+        .request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 6, 2),
+            new_name: "tool-title".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No rename of the message");
+
+    let changes = edit.changes.expect("No changes");
+    let rename = |line, start, end| {
+        TextEdit::new(
+            Range::new(Position::new(line, start), Position::new(line, end)),
+            "tool-title".to_owned(),
+        )
+    };
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[&uri], [rename(5, 25, 34), rename(6, 0, 9)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_invalid_fluent_identifiers() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Locale/en-US/tools.ftl").await;
+
+    let err = client
+        .try_request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 6, 2),
+            new_name: "tool name".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect_err("The message is renamed to an invalid identifier");
+
+    assert_eq!(err.message, "`tool name` isn't a valid Fluent identifier.");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn renames_fluent_messages_in_prototypes_and_code() {
+    let mut client = TestClient::start("locale").await;
+    let uri = client.open("Resources/Locale/en-US/tools.ftl").await;
+
+    // The translation is only in the editor, it is read as edited rather than from the disk.
+    let translation = client.uri("Resources/Locale/ru-RU/tools.ftl");
+    client
+        .open_text(&translation, "tool-verb-pry = Поддеть\n")
+        .await;
+    client.save(&translation, "tool-verb-pry = Поддеть\n").await;
+    client.insert(&translation, Position::new(0, 0), "\n").await;
+
+    let system = client.uri("Content.Server/Tools/PrySystem.cs");
+    let text = "namespace Content.Server.Tools;\n\npublic sealed class PrySystem : EntitySystem\n{\n    public string Verb() => Loc.GetString(\"tool-verb-pry\");\n}\n";
+    client.open_text(&system, text).await;
+    client.save(&system, text).await;
+
+    let edit = client
+        .request::<Rename>(RenameParams {
+            text_document_position: at(&uri, 4, 2),
+            new_name: "tool-verb-lever".to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+        .await
+        .expect("No rename of the message");
+
+    let changes = edit.changes.expect("No changes");
+    let rename = |line, start, end| {
+        TextEdit::new(
+            Range::new(Position::new(line, start), Position::new(line, end)),
+            "tool-verb-lever".to_owned(),
+        )
+    };
+    let prototypes = client.uri("Resources/Prototypes/Entities/tools.yml");
+    assert_eq!(changes.len(), 4);
+    assert_eq!(changes[&uri], [rename(4, 0, 13)]);
+    assert_eq!(changes[&translation], [rename(1, 0, 13)]);
+    assert_eq!(changes[&prototypes], [rename(4, 10, 23)]);
+    assert_eq!(changes[&system], [rename(4, 43, 56)]);
+}
@@ -0,0 +1,81 @@
+use robust_lsp::utils::resource_path;
+use std::path::{Path, PathBuf};
+
+fn expected(root: &str, segments: &[&str]) -> PathBuf {
+    segments
+        .iter()
+        .fold(PathBuf::from(root), |path, segment| path.join(segment))
+}
+
+#[test]
+fn joins_forward_slashes() {
+    let root = Path::new("/home/user/space-station-14");
+    assert_eq!(
+        resource_path(root, "Resources/Textures/Objects/Tools/crowbar.rsi"),
+        expected(
+            "/home/user/space-station-14",
+            &["Resources", "Textures", "Objects", "Tools", "crowbar.rsi"]
+        )
+    );
+}
+
+#[test]
+fn joins_backslashes() {
+    let root = Path::new("/home/user/space-station-14");
+    assert_eq!(
+        resource_path(root, r"Objects\Tools\crowbar.rsi"),
+        expected(
+            "/home/user/space-station-14",
+            &["Objects", "Tools", "crowbar.rsi"]
+        )
+    );
+}
+
+#[test]
+fn joins_mixed_separators_to_windows_root() {
+    let root = Path::new(r"C:\Users\user\space-station-14");
+    assert_eq!(
+        resource_path(root, r"Resources/Textures\Objects/Tools\crowbar.rsi"),
+        expected(
+            r"C:\Users\user\space-station-14",
+            &["Resources", "Textures", "Objects", "Tools", "crowbar.rsi"]
+        )
+    );
+}
+
+#[test]
+fn stays_inside_the_folder() {
+    let root = Path::new("/project/Resources/Textures");
+    assert_eq!(
+        resource_path(root, "/Objects/Tools/"),
+        expected("/project/Resources/Textures", &["Objects", "Tools"])
+    );
+    assert_eq!(
+        resource_path(root, r"\Objects\.\Tools\\"),
+        expected("/project/Resources/Textures", &["Objects", "Tools"])
+    );
+}
+
+#[test]
+fn clamps_parent_segments_at_the_folder() {
+    let root = Path::new("/project/Resources/Textures");
+    assert_eq!(
+        resource_path(root, "../../../etc/passwd"),
+        expected("/project/Resources/Textures", &["etc", "passwd"])
+    );
+    assert_eq!(
+        resource_path(root, r"Objects\Tools\..\..\..\crowbar.rsi"),
+        expected("/project/Resources/Textures", &["crowbar.rsi"])
+    );
+    assert_eq!(
+        resource_path(root, "Objects/Tools/../crowbar.rsi"),
+        expected("/project/Resources/Textures", &["Objects", "crowbar.rsi"])
+    );
+}
+
+#[test]
+fn empty_path_is_the_folder() {
+    let root = Path::new("/project/Resources/Textures");
+    assert_eq!(resource_path(root, ""), root);
+    assert_eq!(resource_path(root, "/"), root);
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::{at, labels, TestClient};
+use serde_json::{json, Value};
+use std::{fs, time::Duration};
+use tower_lsp::lsp_types::{
+    notification::DidCloseTextDocument, request::Completion, CompletionParams,
+    DidCloseTextDocumentParams, Position, TextDocumentIdentifier,
+};
+
+/// Searches the entities until the one with the id is found, the changes are indexed
+/// once the typing stops.
+async fn indexed(client: &mut TestClient, id: &str) -> Value {
+    let query = json!({ "query": id });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let page = client.custom_request("robust-lsp/entities", &query).await;
+            if page["items"][0]["id"] == id {
+                break page;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{id} isn't indexed"))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexes_saved_text() {
+    let mut client = TestClient::start("basic").await;
+    let path = "Resources/Prototypes/Entities/tools.yml";
+    let uri = client.open(path).await;
+    client.diagnostics(&uri).await;
+
+    let on_disk = fs::read_to_string(client.path(path)).unwrap();
+    let saved = format!("{on_disk}\n- type: entity\n  parent: BaseTool\n  id: Screwdriver\n  name: screwdriver\n  description: Turns screws.\n");
+    client.save(&uri, &saved).await;
+
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "query": "Screwdriver" }))
+        .await;
+    assert_eq!(page["items"][0]["id"], "Screwdriver");
+    assert_eq!(client.diagnostics(&uri).await, []);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn diagnoses_saved_text() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    client.diagnostics(&uri).await;
+
+    client
+        .save(
+            &uri,
+            "- type: entity\n  id: Saved\n  name: saved\n  description: Saved.\n  components:\n  - type: Wrench\n",
+        )
+        .await;
+
+    let diagnostics = client.diagnostics(&uri).await;
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range.start.line, 5);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn indexes_changes_once_idle() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    client.diagnostics(&uri).await;
+
+    client
+        .insert(&uri, Position::new(19, 0), "\n- type: entity\n  id: Scr")
+        .await;
+    client
+        .insert(
+            &uri,
+            Position::new(21, 9),
+            "ewdriver\n  name: screwdriver\n",
+        )
+        .await;
+
+    let query = json!({ "query": "Screwdriver" });
+    let page = client.custom_request("robust-lsp/entities", &query).await;
+    assert_eq!(page["items"], json!([]));
+
+    let page = indexed(&mut client, "Screwdriver").await;
+    assert_eq!(page["items"][0]["name"], "screwdriver");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_closed_documents() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    client.diagnostics(&uri).await;
+
+    client
+        .insert(
+            &uri,
+            Position::new(19, 0),
+            "\n- type: entity\n  id: Screwdriver\n",
+        )
+        .await;
+    client
+        .notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+        })
+        .await;
+
+    // The pending re-parse of the unsaved change is dropped with the document. A change typed
+    // later is indexed after it would have been.
+    let later = client.uri("Resources/Prototypes/Entities/later.yml");
+    client.open_text(&later, "").await;
+    client
+        .insert(&later, Position::new(0, 0), "- type: entity\n  id: Later\n")
+        .await;
+    indexed(&mut client, "Later").await;
+
+    let query = json!({ "query": "Screwdriver" });
+    let page = client.custom_request("robust-lsp/entities", &query).await;
+    assert_eq!(page["items"], json!([]));
+
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 8, 4),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+    assert_eq!(response, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn parses_edited_csharp_incrementally_on_save() {
+    let mut client = TestClient::start("basic").await;
+    let path = "Content.Shared/Tools/ToolComponent.cs";
+    let component = client.open(path).await;
+
+    // The field is typed between the others, the kept tree is edited along.
+    let field = "\n    [DataField]\n    public float Wear;\n";
+    client
+        .insert(&component, Position::new(7, 0), "\n    [DataField]")
+        .await;
+    client
+        .insert(
+            &component,
+            Position::new(8, 15),
+            "\n    public float Wear;\n",
+        )
+        .await;
+
+    let mut lines = fs::read_to_string(client.path(path))
+        .unwrap()
+        .split_inclusive('\n')
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    lines.insert(7, field.to_owned());
+    client.save(&component, &lines.concat()).await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/worn.yml");
+    client
+        .open_text(
+            &uri,
+            "- type: entity\n  id: Worn\n  components:\n  - type: Tool\n    \n",
+        )
+        .await;
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 4, 4),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+
+    let mut labels = labels(response);
+    labels.sort();
+    assert_eq!(labels, ["speedModifier", "useSound", "verb", "wear"]);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::TestClient;
+use tower_lsp::lsp_types::{
+    request::SemanticTokensFullRequest, Position, SemanticTokensParams, SemanticTokensResult,
+    TextDocumentIdentifier, Url,
+};
+
+const LOCALE: &str = "\
+-brand = Robust
+tool-verb-weld = Weld with { -brand } { NUMBER($fuel) }
+    .title = { tool-verb-use }
+";
+
+/// The tokens with absolute positions: the line, the start, the length and the type index.
+async fn tokens(client: &mut TestClient, uri: &Url) -> Vec<(u32, u32, u32, u32)> {
+    modified_tokens(client, uri)
+        .await
+        .into_iter()
+        .map(|(line, start, length, token_type, _)| (line, start, length, token_type))
+        .collect()
+}
+
+/// The tokens along with the bitset of their modifiers.
+async fn modified_tokens(client: &mut TestClient, uri: &Url) -> Vec<(u32, u32, u32, u32, u32)> {
+    let result = client
+        .request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await;
+    let Some(SemanticTokensResult::Tokens(tokens)) = result else {
+        panic!("No semantic tokens: {result:?}");
+    };
+
+    let (mut line, mut start) = (0, 0);
+    tokens
+        .data
+        .iter()
+        .map(|token| {
+            line += token.delta_line;
+            start = match token.delta_line {
+                0 => start + token.delta_start,
+                _ => token.delta_start,
+            };
+            (
+                line,
+                start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            )
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn highlights_fluent_ids_and_references() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Locale/en-US/weld.ftl");
+    client.open_text(&uri, LOCALE).await;
+
+    assert_eq!(
+        tokens(&mut client, &uri).await,
+        [
+            (0, 0, 6, 3),
+            (1, 0, 14, 3),
+            (1, 29, 6, 3),
+            (1, 40, 6, 4),
+            (1, 47, 5, 5),
+            (2, 5, 5, 2),
+            (2, 15, 13, 3),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn highlights_changed_fluent_files() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.uri("Resources/Locale/en-US/weld.ftl");
+    client.open_text(&uri, LOCALE).await;
+    assert_eq!(tokens(&mut client, &uri).await.len(), 7);
+
+    client
+        .insert(&uri, Position::new(3, 0), "tool-verb-cut = Cut\n")
+        .await;
+
+    let tokens = tokens(&mut client, &uri).await;
+    assert_eq!(tokens.len(), 8);
+    assert_eq!(tokens[7], (3, 0, 13, 3));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn marks_obsolete_engine_and_unknown_symbols() {
+    let mut client = TestClient::start("basic").await;
+    let component = client.uri("Content.Shared/Light/TorchComponent.cs");
+    client
+        .save(
+            &component,
+            "namespace Content.Shared.Light;
+
+[RegisterComponent, Obsolete(\"Use the expendable light.\")]
+public sealed partial class TorchComponent : Component
+{
+    [DataField(readOnly: true)]
+    public float Fuel;
+}
+",
+        )
+        .await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/torch.yml");
+    let text = "- type: entity\n  id: Torch\n  components:\n  - type: Sprite\n  - type: Torch\n    fuel: 10\n  - type: Lantern\n";
+    client.open_text(&uri, text).await;
+
+    // The bits of the modifiers in the legend.
+    let (declaration, deprecated, engine, readonly, unresolved) = (1, 2, 4, 8, 16);
+    let modifiers = modified_tokens(&mut client, &uri)
+        .await
+        .into_iter()
+        .filter(|t| t.4 != 0)
+        .map(|(line, start, _, _, modifiers)| (line, start, modifiers))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        modifiers,
+        [
+            (0, 8, engine),
+            (1, 2, engine),
+            (1, 6, declaration),
+            (2, 2, engine),
+            (3, 10, engine),
+            (4, 10, deprecated),
+            (5, 4, readonly),
+            (6, 10, unresolved),
+        ]
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::TestClient;
+use robust_lsp::status::StatusNotification;
+use std::time::Duration;
+use tower_lsp::lsp_types::Position;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_status_once_indexed() {
+    let mut client = TestClient::start("basic").await;
+
+    let status = client.wait_for::<StatusNotification>(|_| true).await;
+
+    assert_eq!(status.version, env!("CARGO_PKG_VERSION"));
+    assert!(status.indexed);
+    assert_eq!(
+        (status.classes, status.prototypes, status.locale_keys),
+        (7, 4, 2)
+    );
+    assert_eq!(status.pending_reparses, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn counts_pending_reparses_on_demand() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+    client
+        .insert(
+            &uri,
+            Position::new(19, 0),
+            "\n- type: entity\n  id: Screwdriver\n",
+        )
+        .await;
+
+    let status = client.custom_request("robust-lsp/status", ()).await;
+    assert_eq!(status["pendingReparses"], 1);
+
+    // The change is parsed once the reparse delay is over.
+    let status = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let status = client.custom_request("robust-lsp/status", ()).await;
+            if status["pendingReparses"] == 0 {
+                break status;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("The change isn't parsed");
+    assert_eq!(status["prototypes"], 5);
+}
@@ -0,0 +1,46 @@
+use robust_lsp::{status::LastError, utils::recover_in_time};
+use std::time::{Duration, Instant};
+use tower_lsp::lsp_types::{Position, Url};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gives_up_on_slow_providers() {
+    let uri = Url::parse("file:///slow.yml").unwrap();
+    let last_error = LastError::default();
+
+    let start = Instant::now();
+    let result = recover_in_time(
+        "Completion",
+        &last_error,
+        Some(Duration::from_millis(50)),
+        &uri,
+        Some(Position::new(0, 0)),
+        || {
+            std::thread::sleep(Duration::from_secs(1));
+            Some(1)
+        },
+    )
+    .await;
+    assert_eq!(result, None);
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(
+        last_error.get().as_deref(),
+        Some("Completion provider didn't finish in 50ms on file:///slow.yml:1:1.")
+    );
+
+    let result = recover_in_time(
+        "Hover",
+        &last_error,
+        Some(Duration::from_secs(5)),
+        &uri,
+        None,
+        || Some(2),
+    )
+    .await;
+    assert_eq!(result, Some(2));
+
+    // A provider that panics returns an empty result as well.
+    let result: Option<i32> =
+        recover_in_time("Hover", &last_error, None, &uri, None, || panic!("boom")).await;
+    assert_eq!(result, None);
+    assert_eq!(last_error.get().as_deref(), Some("Hover provider failed."));
+}
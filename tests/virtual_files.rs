@@ -0,0 +1,151 @@
+mod common;
+
+use common::{at, labels, TestClient};
+use robust_lsp::vfs::{InMemoryFileSystem, SharedFileSystem};
+use tower_lsp::lsp_types::{
+    request::{Completion, GotoDefinition, HoverRequest},
+    CompletionParams, GotoDefinitionParams, GotoDefinitionResponse, HoverParams, NumberOrString,
+    Position, Url,
+};
+
+const RSI_META: &str = r#"{
+    "version": 1,
+    "license": "CC0-1.0",
+    "copyright": "Drawn for the tests",
+    "size": { "x": 32, "y": 32 },
+    "states": [{ "name": "icon" }, { "name": "icon-open" }]
+}"#;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_untitled_documents() {
+    let mut client = TestClient::start("basic").await;
+    let uri = Url::parse("untitled:/unsaved.yml").unwrap();
+
+    client
+        .open_text(
+            &uri,
+            "- type: entity\n  id: Unsaved\n  components:\n  - type: Wrench\n",
+        )
+        .await;
+    let diagnostics = client.diagnostics(&uri).await;
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].code,
+        Some(NumberOrString::String("unknown-component".to_owned()))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_untitled_documents_by_language() {
+    let mut client = TestClient::start("basic").await;
+    let uri = Url::parse("untitled:Untitled-1").unwrap();
+
+    client
+        .open_in_language(
+            &uri,
+            "yaml",
+            "- type: entity\n  parent: BaseTool\n  id: Unsaved\n  components:\n  - type: Wrench\n",
+        )
+        .await;
+    let diagnostics = client.diagnostics(&uri).await;
+    assert_eq!(
+        diagnostics[0].code,
+        Some(NumberOrString::String("unknown-component".to_owned()))
+    );
+
+    let hover = client
+        .request::<HoverRequest>(HoverParams {
+            text_document_position_params: at(&uri, 0, 10),
+            work_done_progress_params: Default::default(),
+        })
+        .await;
+    assert!(hover.is_some());
+
+    let response = client
+        .request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: at(&uri, 1, 12),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+        .await;
+    let Some(GotoDefinitionResponse::Link(links)) = response else {
+        panic!("No definition of the parent: {response:?}");
+    };
+    assert_eq!(
+        links[0].target_uri,
+        client.uri("Resources/Prototypes/Entities/tools.yml")
+    );
+    assert_eq!(links[0].target_selection_range.start, Position::new(2, 6));
+
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 1, 14),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+    assert!(labels(response).contains(&"BaseTool".to_owned()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_resources_from_memory() {
+    let fs = InMemoryFileSystem::default();
+    let root = common::fixture("basic");
+    fs.insert(
+        root.join("Resources/Textures/Objects/crowbar.rsi/meta.json"),
+        RSI_META,
+    );
+
+    let mut client = TestClient::start_with_file_system("basic", SharedFileSystem::new(fs)).await;
+    let uri = client.uri("Resources/Prototypes/Entities/unsaved.yml");
+    client
+        .open_text(
+            &uri,
+            "- type: entity\n  id: Unsaved\n  components:\n  - type: Sprite\n    sprite: Objects/crowbar.rsi\n    state: \n",
+        )
+        .await;
+
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 5, 11),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+
+    assert_eq!(labels(response), ["icon", "icon-open"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keeps_sprite_paths_inside_the_textures() {
+    let fs = InMemoryFileSystem::default();
+    let root = common::fixture("basic");
+    fs.insert(
+        root.join("Resources/Textures/Objects/crowbar.rsi/meta.json"),
+        RSI_META,
+    );
+
+    let mut client = TestClient::start_with_file_system("basic", SharedFileSystem::new(fs)).await;
+    let uri = client.uri("Resources/Prototypes/Entities/unsaved.yml");
+    client
+        .open_text(
+            &uri,
+            "- type: entity\n  id: Unsaved\n  components:\n  - type: Sprite\n    sprite: ../../\n",
+        )
+        .await;
+
+    // The parent folders go up no further than the textures.
+    let response = client
+        .request::<Completion>(CompletionParams {
+            text_document_position: at(&uri, 4, 18),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        })
+        .await;
+
+    assert_eq!(labels(response), ["Objects"]);
+}
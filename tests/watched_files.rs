@@ -0,0 +1,61 @@
+mod common;
+
+use common::TestClient;
+use serde_json::json;
+use tower_lsp::lsp_types::{
+    notification::DidChangeWatchedFiles, DidChangeWatchedFilesParams, FileChangeType, FileEvent,
+    Url,
+};
+
+async fn entity_ids(client: &mut TestClient) -> Vec<String> {
+    let page = client
+        .custom_request("robust-lsp/entities", json!({ "includeAbstract": true }))
+        .await;
+    let mut ids = page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+/// Reports the deletion, the fixture itself stays on disk.
+async fn delete(client: &mut TestClient, uri: Url) {
+    client
+        .notify::<DidChangeWatchedFiles>(DidChangeWatchedFilesParams {
+            changes: vec![FileEvent::new(uri, FileChangeType::DELETED)],
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_deleted_files() {
+    let mut client = TestClient::start("basic").await;
+
+    let uri = client.uri("Resources/Prototypes/Entities/broken.yml");
+    delete(&mut client, uri).await;
+
+    assert_eq!(entity_ids(&mut client).await, ["BaseTool", "Crowbar"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_files_of_deleted_folders() {
+    let mut client = TestClient::start("basic").await;
+
+    let uri = client.uri("Resources/Prototypes/Entities");
+    delete(&mut client, uri).await;
+
+    assert!(entity_ids(&mut client).await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn drops_prototypes_of_emptied_files() {
+    let mut client = TestClient::start("basic").await;
+    let uri = client.open("Resources/Prototypes/Entities/tools.yml").await;
+
+    client.save(&uri, "").await;
+
+    assert_eq!(entity_ids(&mut client).await, ["BrokenTool"]);
+}